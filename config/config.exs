@@ -9,6 +9,55 @@ config :swarm_brain, :vision,
   height: 480,
   framerate: 30
 
+# Spatial Memory persistence (snapshot + crash journal).
+# Set to nil to keep the map in RAM only.
+config :swarm_brain, :spatial_store,
+  dir: "priv/spatial",
+  snapshot_every_s: 60
+
+# Black box flight recorder (SwarmBrain.Recorder.Config): rotating segments
+# with kinematics, flow, motion, detections, commands and JPEG frames.
+# Any field can be overridden here (e.g. frame_every: 0). Set to nil to disable.
+# For exact replay: frame_every: 1, frame_scale: 1, frame_format: :png.
+config :swarm_brain, :black_box,
+  dir: "priv/blackbox",
+  max_segments: 32
+
+# Replay a recording through the heartbeat instead of the camera (regression
+# runs, SwarmBrain.Replay.Config), e.g. [dir: "priv/blackbox", speed: 0.0].
+config :swarm_brain, :replay, nil
+
+# Zenoh bus (peer mode): camera streams + Spatial Memory queryable under
+# swarm/<node>/. Any SwarmBrain.Bus.Config field can be overridden here
+# (e.g. frame_key: nil). Set to nil to stay off zenoh.
+config :swarm_brain, :zenoh,
+  listen: ["tcp/0.0.0.0:7447"],
+  multicast: true
+
+# This drone's 16-bit sender id, unique in the swarm (packets, sealed frames).
+# Required with :swarm_keys; nil hashes the node name, which may collide.
+config :swarm_brain, :node_id, nil
+
+# Sealed swarm links (ChaCha20-Poly1305). keys: [{id, base64 32-byte key, tag_len}];
+# tag_len is 4..16 bytes (8 keeps LoRa frames short). Frames are sealed with
+# the `active` key and opened with any. boot_file persists the per-boot nonce
+# prefix (default "priv/swarm_boot"): keep it across reboots (a lost file
+# restarts at 0 and reuses nonces). Set to nil to stay in the clear.
+# config :swarm_brain, :swarm_keys,
+#   active: 1,
+#   keys: [{1, System.fetch_env!("SWARM_KEY_1"), 8}]
+config :swarm_brain, :swarm_keys, nil
+
+# Radio frames are FEC shards (SwarmBrain.Fec) both ways; both ends must agree.
+config :swarm_brain, :radio_fec, false
+
+# Radio UART protocol: :raw (swarm frames) or :crsf (an ExpressLRS receiver:
+# link statistics and telemetry on "radio:link").
+config :swarm_brain, :radio_protocol, :raw
+
+# Own vitals (heading, speed, battery) broadcast every N ms, or nil to stay quiet.
+config :swarm_brain, :radio_beacon, 1000
+
 # 1. Set the Default Backend to EXLA (XLA)
 # This forces Nx to use the compiled C++ backend (CPU or GPU)
 config :nx, :default_backend, EXLA.Backend
@@ -48,6 +97,22 @@ config :libcluster,
 # Set to nil to force Simulation Mode on Laptop.
 config :swarm_brain, :fc_port, nil
 
+# Flight controller protocol: :msp (Betaflight / iNav) or :mavlink (ArduPilot / PX4).
+config :swarm_brain, :fc_protocol, :msp
+
+# MAVLink identity (companion computer on the vehicle's system id) and signing.
+# signing_key: base64 of 32 bytes (the key set on the autopilot), or nil.
+config :swarm_brain, :mavlink,
+  system_id: 1,
+  component_id: 191,
+  signing_key: nil,
+  link_id: 0,
+  allow_unsigned: true
+
+# RC output on MSP (SwarmBrain.Hardware.Reflex.Config): shaping, failsafe timings.
+# e.g. [timeout_ms: 300, descend_throttle: -0.25]
+config :swarm_brain, :reflex, []
+
 # RL Actor Configuration
 config :swarm_brain, :actor,
   model_path: "priv/actor_policy.axon",
@@ -7,35 +7,166 @@ defmodule SwarmBrain.Antenna do
   require Logger
   # alias Circuits.UART # Uncomment when running on real hardware
 
+  alias SwarmBrain.Hardware.{Crsf, Spine}
+  alias SwarmBrain.Sensor.Proprioception
+  alias SwarmBrain.Telemetry.Codec
+  alias SwarmBrain.Vision.Native
+
   @topic "radio:telemetry"
+  # Link quality and receiver telemetry (CRSF)
+  @link_topic "radio:link"
+
+  # Uplink FEC (:radio_fec): any 2 of 3 shards rebuild a frame. Receivers
+  # need no matching setting beyond :radio_fec itself: shards carry k and n.
+  @fec_k 2
+  @fec_n 3
 
   def start_link(opts) do
     GenServer.start_link(__MODULE__, opts, name: __MODULE__)
   end
 
+  @doc "Latest %{rssi, lq, snr} from the receiver (CRSF), or nil."
+  def link_quality, do: GenServer.call(__MODULE__, :link_quality)
+
+  @doc "Sends this node's `%{heading, velocity, battery}` to the swarm."
+  def transmit_vitals(vitals), do: GenServer.cast(__MODULE__, {:transmit_vitals, vitals})
+
   @impl true
   def init(_opts) do
     Logger.info("📡 Antenna Listening on UART...")
     # Mocking UART connection for development
     # In production: UART.open(...)
-    {:ok, %{port: nil, rssi: -60}}
+    schedule_beacon()
+
+    # FEC message ids start at random: drones sharing a channel rarely collide
+    {:ok, %{port: nil, rssi: -60, link: nil, fec: fec_state(), crsf: crsf_parser(), seq: 0, message: :rand.uniform(65_536) - 1}}
+  end
+
+  @impl true
+  def handle_call(:link_quality, _from, state), do: {:reply, state.link, state}
+
+  @impl true
+  def handle_cast({:transmit_vitals, vitals}, state) do
+    case Codec.encode_vitals(vitals, state.seq) do
+      {:ok, packet} ->
+        {:noreply, %{transmit(packet, state) | seq: state.seq + 1}}
+
+      {:error, reason} ->
+        Logger.debug("📡 Antenna: Vitals not sent (#{reason}).")
+        {:noreply, state}
+    end
+  end
+
+  # Own vitals, every :radio_beacon ms (skipped until the FC reports a battery)
+  @impl true
+  def handle_info(:beacon, state) do
+    schedule_beacon()
+
+    case Spine.get_battery() do
+      nil ->
+        {:noreply, state}
+
+      battery ->
+        {vx, vy, _px, _py} = Proprioception.get_kinematics()
+        vitals = %{heading: Spine.get_imu_state().yaw, velocity: :math.sqrt(vx * vx + vy * vy), battery: battery}
+        handle_cast({:transmit_vitals, vitals}, state)
+    end
   end
 
+  # CRSF receiver: frames carry link statistics and telemetry, not swarm payloads
   @impl true
-  def handle_info({:circuits_uart, _port, data}, state) do
-    # 1. Strip noise
-    clean_data = String.trim(data)
+  def handle_info({:circuits_uart, _port, data}, %{crsf: parser} = state) when parser != nil do
+    state =
+      parser
+      |> Native.crsf_feed(data)
+      |> Enum.reduce(state, &absorb/2)
 
-    # 2. Broadcast to the Swarm (Formation, Pipeline, etc.)
-    # We do NOT call Pipeline directly anymore.
-    Phoenix.PubSub.broadcast(SwarmBrain.PubSub, @topic, {:telemetry_packet, clean_data, state.rssi})
+    {:noreply, state}
+  end
 
+  def handle_info({:circuits_uart, _port, data}, %{fec: nil} = state) do
+    deliver(data, state)
     {:noreply, state}
   end
 
+  # FEC on: frames are shards, a payload exists once enough have arrived
+  def handle_info({:circuits_uart, _port, shard}, state) do
+    case SwarmBrain.Fec.collect(state.fec, shard) do
+      {:ok, data, fec} ->
+        deliver(data, state)
+        {:noreply, %{state | fec: fec}}
+
+      {:more, fec} ->
+        {:noreply, %{state | fec: fec}}
+    end
+  end
+
   # Catch-all for when we are running without real hardware
   @impl true
   def handle_info(_msg, state) do
     {:noreply, state}
   end
+
+  # --- PRIVATE ---
+
+  # Every uplink frame is sealed (SwarmBrain.Crypto; in the clear without
+  # keys), then split into FEC shards when :radio_fec is on
+  defp transmit(packet, state) do
+    with {:ok, frame} <- SwarmBrain.Crypto.seal(packet),
+         {:ok, frames} <- shards(frame, state) do
+      Enum.each(frames, &write(&1, state))
+      if state.fec, do: %{state | message: state.message + 1}, else: state
+    else
+      {:error, reason} ->
+        Logger.warning("📡 Antenna: Frame not sent (#{reason}).")
+        state
+    end
+  end
+
+  defp shards(frame, %{fec: nil}), do: {:ok, [frame]}
+  defp shards(frame, state), do: SwarmBrain.Fec.encode(frame, state.message, @fec_k, @fec_n)
+
+  defp write(_frame, %{port: nil}), do: :ok
+  defp write(frame, %{port: port}), do: Circuits.UART.write(port, frame)
+
+  defp schedule_beacon do
+    case Application.get_env(:swarm_brain, :radio_beacon) do
+      nil -> :ok
+      interval -> Process.send_after(self(), :beacon, interval)
+    end
+  end
+
+  defp absorb(%Crsf.LinkStatistics{} = stats, state) do
+    # Feeds the fallback logic (Pipeline's emergency pruning, Tactician's link_quality)
+    link = %{rssi: Crsf.rssi(stats), lq: stats.uplink_lq, snr: stats.uplink_snr}
+    Phoenix.PubSub.broadcast(SwarmBrain.PubSub, @link_topic, {:link_quality, link})
+    %{state | rssi: link.rssi, link: link}
+  end
+
+  defp absorb(message, state) do
+    Phoenix.PubSub.broadcast(SwarmBrain.PubSub, @link_topic, {:crsf, message})
+    state
+  end
+
+  defp deliver(data, state) do
+    # 1. Authenticate: forged, replayed or corrupted frames stop here
+    # (SwarmBrain.Crypto; frames are binary, so no trimming)
+    case SwarmBrain.Crypto.open(data) do
+      {:ok, payload} ->
+        # 2. Broadcast to the Swarm (Formation, Pipeline, etc.)
+        # We do NOT call Pipeline directly anymore.
+        Phoenix.PubSub.broadcast(SwarmBrain.PubSub, @topic, {:telemetry_packet, payload, state.rssi})
+
+      {:error, reason} ->
+        Logger.debug("📡 Antenna: Dropped frame (#{reason}).")
+    end
+  end
+
+  defp crsf_parser do
+    if Application.get_env(:swarm_brain, :radio_protocol, :raw) == :crsf, do: Native.crsf_new_parser(), else: nil
+  end
+
+  defp fec_state do
+    if Application.get_env(:swarm_brain, :radio_fec, false), do: %SwarmBrain.Fec{}, else: nil
+  end
 end
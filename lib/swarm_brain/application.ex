@@ -17,6 +17,13 @@ defmodule SwarmBrain.Application do
       Logger.warning("⚠️ Cortex #{inspect(cortex)} has no init/0 callback.")
     end
 
+    # --- SWARM LINK KEYS ---
+    case SwarmBrain.Crypto.setup() do
+      :ok -> Logger.info("🔐 Swarm links sealed.")
+      :disabled -> Logger.warning("⚠️ No :swarm_keys configured. Swarm links are in the clear.")
+      {:error, reason} -> raise "Invalid :swarm_keys (#{reason})"
+    end
+
     # ---------------------------------------
 
     children = [
@@ -44,7 +51,13 @@ defmodule SwarmBrain.Application do
       # 7. The Iron Lung (Vision System)
       {SwarmBrain.Vision.Server, []},
 
-      # 8. The Pilot (RL Agent)
+      # 8. The Shared Map (needs the Vision resource)
+      {SwarmBrain.Blackboard.SpatialSync, []},
+
+      # [NEW] The Black Box (records off the Vision resource)
+      {SwarmBrain.Persistence, []},
+
+      # 9. The Pilot (RL Agent)
       {SwarmBrain.Tracker, []}
     ]
 
@@ -8,8 +8,14 @@ defmodule SwarmBrain.Blackboard do
 
   @sync_interval 50
 
+  # A peer silent for longer than this leaves the census
+  @peer_timeout 2000
+  @attach_retry 1000
+
   defstruct [
     :crdt,
+    :zenoh,
+    peers: %{},
     target_class: "none",
     target_position: nil,
     mission_status: :search,
@@ -28,6 +34,9 @@ defmodule SwarmBrain.Blackboard do
     :net_kernel.monitor_nodes(true)
     update_neighbors(crdt_pid)
 
+    # 3. Peer telemetry over zenoh (once the Vision Server opens the bus)
+    send(self(), :attach_zenoh)
+
     {:ok, %__MODULE__{crdt: crdt_pid}}
   end
 
@@ -69,6 +78,35 @@ defmodule SwarmBrain.Blackboard do
     {:noreply, state}
   end
 
+  # Peer kinematics: every drone that publishes counts towards the census
+  def handle_info({:zenoh, key, _payload, _timestamp}, state) do
+    SwarmBrain.Bus.ack(state.zenoh)
+
+    if SwarmBrain.Bus.own?(key) do
+      {:noreply, state}
+    else
+      now = System.monotonic_time(:millisecond)
+
+      peers =
+        state.peers
+        |> Map.put(SwarmBrain.Bus.peer(key), now)
+        |> Map.reject(fn {_, seen} -> now - seen > @peer_timeout end)
+
+      {:noreply, %{state | peers: peers, swarm_census: map_size(peers)}}
+    end
+  end
+
+  def handle_info(:attach_zenoh, state) do
+    case SwarmBrain.Bus.subscribe("kinematics") do
+      {:ok, sub} ->
+        {:noreply, %{state | zenoh: sub}}
+
+      {:error, _} ->
+        Process.send_after(self(), :attach_zenoh, @attach_retry)
+        {:noreply, state}
+    end
+  end
+
   # --- PRIVATE ---
 
   defp update_neighbors(crdt_pid) do
@@ -0,0 +1,85 @@
+defmodule SwarmBrain.Blackboard.SpatialSync do
+  @moduledoc """
+  The Shared Map.
+  Gossips Spatial Memory deltas between drones (native delta-state CRDT).
+
+  Every tick, the cells that changed locally since the last broadcast are
+  exported and cast to every neighbour, who merges them (per-cell
+  last-writer-wins on HLC stamps, keeping the writer's stamp). While no
+  neighbour is connected the cursor stays put, so nothing is skipped. A
+  node that joins receives our full map once. Merges are idempotent and order-free, so lost or duplicated
+  messages only delay convergence.
+  """
+  use GenServer
+  require Logger
+  alias SwarmBrain.Vision.{Native, Server}
+
+  @sync_interval 200
+  @page_cells 20_000 # ~800 KB per message
+
+  def start_link(_opts), do: GenServer.start_link(__MODULE__, [], name: __MODULE__)
+
+  def init(_) do
+    case Server.get_resource() do
+      nil ->
+        Logger.warning("🗺️ SpatialSync: No Vision resource. Map sync disabled.")
+        :ignore
+
+      resource ->
+        :ok = Native.set_spatial_node_id(resource, :erlang.phash2(Node.self(), 4_294_967_296))
+        :net_kernel.monitor_nodes(true)
+        schedule_sync()
+        {:ok, %{resource: resource, cursor: 0}}
+    end
+  end
+
+  # --- CALLBACKS ---
+
+  def handle_info(:sync, state) do
+    cursor = broadcast_since(state.resource, state.cursor, Node.list())
+    schedule_sync()
+    {:noreply, %{state | cursor: cursor}}
+  end
+
+  # A newcomer knows nothing: hand it the whole map
+  def handle_info({:nodeup, node}, state) do
+    broadcast_since(state.resource, 0, [node])
+    {:noreply, state}
+  end
+
+  def handle_info({:nodedown, _node}, state), do: {:noreply, state}
+
+  def handle_cast({:spatial_delta, delta}, state) do
+    case Native.merge_spatial_delta(state.resource, delta) do
+      {:ok, %{rejected: 0}} ->
+        :ok
+
+      {:ok, %{rejected: rejected}} ->
+        Logger.warning("🗺️ SpatialSync: Rejected #{rejected} cells stamped in the future (clock skew).")
+
+      {:error, reason} ->
+        Logger.warning("🗺️ SpatialSync: Dropped peer delta (#{reason}).")
+    end
+
+    {:noreply, state}
+  end
+
+  # --- PRIVATE ---
+
+  # Sends every page changed after `since`. Returns the new cursor, which
+  # only moves past pages that were actually sent.
+  defp broadcast_since(_resource, since, []), do: since
+
+  defp broadcast_since(resource, since, nodes) do
+    {:ok, {cursor, delta}} = Native.export_spatial_delta(resource, since, @page_cells)
+
+    if cursor == since do
+      cursor
+    else
+      GenServer.abcast(nodes, __MODULE__, {:spatial_delta, delta})
+      broadcast_since(resource, cursor, nodes)
+    end
+  end
+
+  defp schedule_sync, do: Process.send_after(self(), :sync, @sync_interval)
+end
@@ -0,0 +1,54 @@
+defmodule SwarmBrain.Bus do
+  @moduledoc """
+  Peer streams over zenoh.
+
+  Subscriptions deliver `{:zenoh, key, payload, timestamp_us | nil}` to the
+  calling process. Each message must be acknowledged with `ack/1`; past
+  `capacity` unacknowledged messages the native side keeps only the newest
+  samples (drop-oldest).
+
+  Keys follow `SwarmBrain.Bus.Config.for_node/2`: `swarm/<node>/<stream>`.
+  With swarm keys, payloads are sealed frames: `SwarmBrain.Crypto.open/1`
+  them before trusting (or decoding) anything.
+  """
+  alias SwarmBrain.Bus.Config
+  alias SwarmBrain.Vision.{Native, Server}
+
+  @default_capacity 32
+
+  @doc """
+  Subscribes the caller to `stream` from every drone (`swarm/*/<stream>`).
+  Returns `{:ok, subscription} | {:error, :no_resource | :bus_inactive | :invalid_key_expr}`.
+  """
+  def subscribe(stream, capacity \\ @default_capacity) do
+    case Server.get_resource() do
+      nil -> {:error, :no_resource}
+      resource -> Native.zenoh_subscribe(resource, "swarm/*/#{stream}", self(), capacity)
+    end
+  end
+
+  def ack(subscription), do: Native.zenoh_ack(subscription, 1)
+
+  def unsubscribe(subscription), do: Native.zenoh_undeclare(subscription)
+
+  @doc "The sanitised node segment of a stream key, e.g. `\"drone_1_10.0.0.2\"`."
+  def peer(key) do
+    case String.split(key, "/") do
+      ["swarm", node | _] -> node
+      _ -> nil
+    end
+  end
+
+  @doc "Whether `key` is one of our own publications."
+  def own?(key), do: peer(key) == peer(Config.for_node(Node.self()).kinematics_key)
+
+  @doc "Decodes a kinematics sample (`bus/wire.rs`)."
+  def decode_kinematics(
+        <<vx::little-float-32, vy::little-float-32, px::little-float-32, py::little-float-32,
+          timestamp_us::little-unsigned-64, seq::little-unsigned-64>>
+      ) do
+    {:ok, %{vx: vx, vy: vy, px: px, py: py, timestamp_us: timestamp_us, seq: seq}}
+  end
+
+  def decode_kinematics(_), do: {:error, :malformed}
+end
@@ -0,0 +1,39 @@
+defmodule SwarmBrain.Bus.Config do
+  @moduledoc """
+  Zenoh bus settings for `start_zenoh/2`.
+  Decoded natively as `bus::BusConfig`, so every field must be present.
+
+  Stream keys left `nil` are not published. Payload layouts are documented
+  in `native/swarm_native/src/bus/wire.rs`.
+  """
+
+  # listen / connect: zenoh endpoints ("tcp/0.0.0.0:7447"); [] leaves zenoh's default
+  # frame_every: one camera frame in N; frame_scale: 4 = 160x120
+  defstruct listen: [],
+            connect: [],
+            multicast: true,
+            kinematics_key: nil,
+            flow_key: nil,
+            motion_key: nil,
+            frame_key: nil,
+            frame_every: 6,
+            frame_scale: 4,
+            spatial_key: nil
+
+  @doc """
+  Every stream under `swarm/<node>/`, with `overrides` (a keyword list of
+  fields) applied on top. The Spatial Memory answers on `swarm/<node>/spatial`.
+  """
+  def for_node(node, overrides \\ []) do
+    prefix = "swarm/#{node |> to_string() |> String.replace(~r/[^A-Za-z0-9_.-]/, "_")}"
+
+    %__MODULE__{
+      kinematics_key: "#{prefix}/kinematics",
+      flow_key: "#{prefix}/flow",
+      motion_key: "#{prefix}/motion",
+      frame_key: "#{prefix}/frame",
+      spatial_key: "#{prefix}/spatial"
+    }
+    |> struct!(overrides)
+  end
+end
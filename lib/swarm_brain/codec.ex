@@ -1,25 +1,35 @@
 defmodule SwarmBrain.Telemetry.Codec do
   @moduledoc """
-  Pure functional core for bit-level swarm communications.
-  Optimized for minimal bandwidth using Erlang binary pattern matching.
+  The swarm wire format (native, see `SwarmBrain.Telemetry.Packet`).
+  One definition of the bits, shared with every non-BEAM ground tool.
   """
+  alias SwarmBrain.Telemetry.Packet
+  alias SwarmBrain.Vision.Native
 
-  @doc "Packs vitals into 3 bytes: 10b Heading, 8b Velocity, 6b Battery"
-  def encode_vitals(%{heading: h, velocity: v, battery: b}) do
-    # Clamp values to prevent bit-overflow crashes
-    h_int = h |> round() |> rem(360)
-    v_int = min(v, 255)
-    b_int = min(b, 100)
+  @doc "Returns `{:ok, binary} | {:error, :unsupported_version | :out_of_range | :too_large}`."
+  def encode(%Packet{} = packet), do: Native.encode_packet(packet)
 
-    <<h_int::size(10), v_int::size(8), b_int::size(6)>>
+  @doc "Returns `{:ok, %Packet{}} | {:error, :truncated | :unsupported_version | :unknown_kind | :malformed}`."
+  def decode(binary) when is_binary(binary), do: Native.decode_packet(binary)
+
+  @doc "A vitals packet from this node, stamped now. `seq` wraps at 32 bits."
+  def encode_vitals(%{heading: h, velocity: v, battery: b}, seq) do
+    encode(%Packet{
+      node: node_id(),
+      seq: rem(seq, 4_294_967_296),
+      timestamp_us: System.os_time(:microsecond),
+      body: {:vitals, %{heading: h / 1, velocity: v / 1, battery: b |> round() |> max(0) |> min(100)}}
+    })
   end
 
-  @doc "Decodes the 24-bit vital packet."
-  def decode_vitals(<<heading::size(10), velocity::size(8), battery::size(6)>>) do
-    %{
-      heading: heading,
-      velocity: velocity,
-      battery: battery
-    }
+  @doc """
+  This node's 16-bit packet id: `config :swarm_brain, :node_id`. Unset, it is
+  hashed from the node name and may collide (`SwarmBrain.Crypto` refuses that).
+  """
+  def node_id do
+    case Application.get_env(:swarm_brain, :node_id) do
+      nil -> :erlang.phash2(Node.self(), 65_536)
+      id -> id
+    end
   end
 end
@@ -0,0 +1,102 @@
+defmodule SwarmBrain.Crypto do
+  @moduledoc """
+  Sealed swarm frames: ChaCha20-Poly1305 with per-swarm keys and a replay
+  window per sender (native, see `native/swarm_native/src/crypto/mod.rs`).
+
+  Keys come from `config :swarm_brain, :swarm_keys`. Without them, frames
+  go out and are accepted in the clear. Sealing needs a configured
+  `:node_id` (unique under each key) and a boot counter file: every boot
+  takes the next number, so nonces never repeat across reboots.
+  """
+  alias SwarmBrain.Vision.Native
+
+  @keyring_key {__MODULE__, :keyring}
+
+  @boot_file "priv/swarm_boot"
+  @max_boot 4_294_967_295
+
+  @doc """
+  Builds the keyring from config. Call once at boot.
+  Returns `:ok | :disabled | {:error, :no_node_id | :boot_counter | :invalid_key}`.
+  """
+  def setup do
+    case Application.get_env(:swarm_brain, :swarm_keys) do
+      nil ->
+        :disabled
+
+      config ->
+        with {:ok, node} <- configured_node_id(),
+             {:ok, boot} <- next_boot(Keyword.get(config, :boot_file, @boot_file)),
+             keyring = Native.new_keyring(node, boot),
+             :ok <- put_keys(keyring, Keyword.fetch!(config, :keys)) do
+          :persistent_term.put(@keyring_key, {keyring, Keyword.fetch!(config, :active)})
+        end
+    end
+  end
+
+  def enabled?, do: :persistent_term.get(@keyring_key, nil) != nil
+
+  @doc "`{keyring, active key}` for native publishers (`SwarmBrain.Bus`), or nil in the clear."
+  def sealer, do: :persistent_term.get(@keyring_key, nil)
+
+  @doc "Returns `{:ok, frame} | {:error, :unknown_key | :exhausted}`."
+  def seal(plaintext) do
+    case :persistent_term.get(@keyring_key, nil) do
+      nil -> {:ok, plaintext}
+      {keyring, active} -> Native.seal(keyring, active, plaintext)
+    end
+  end
+
+  @doc "Returns `{:ok, plaintext} | {:error, :unknown_key | :truncated | :bad_tag | :replay}`."
+  def open(frame) do
+    case :persistent_term.get(@keyring_key, nil) do
+      nil ->
+        {:ok, frame}
+
+      {keyring, _active} ->
+        with {:ok, {_sender, _key_id, plaintext}} <- Native.open(keyring, frame) do
+          {:ok, plaintext}
+        end
+    end
+  end
+
+  # --- PRIVATE ---
+
+  # Sender ids prefix every nonce: a name hash could collide, so it must be configured
+  defp configured_node_id do
+    case Application.get_env(:swarm_brain, :node_id) do
+      id when is_integer(id) and id in 0..65_535 -> {:ok, id}
+      _ -> {:error, :no_node_id}
+    end
+  end
+
+  # Persisted before it is used: a crash right after still never reuses it
+  defp next_boot(path) do
+    boot =
+      case File.read(path) do
+        {:ok, <<last::unsigned-32>>} when last < @max_boot -> {:ok, last + 1}
+        {:error, :enoent} -> {:ok, 0}
+        _ -> {:error, :boot_counter}
+      end
+
+    with {:ok, n} <- boot,
+         :ok <- File.mkdir_p(Path.dirname(path)),
+         :ok <- File.write(path <> ".tmp", <<n::unsigned-32>>, [:sync]),
+         :ok <- File.rename(path <> ".tmp", path) do
+      {:ok, n}
+    else
+      _ -> {:error, :boot_counter}
+    end
+  end
+
+  defp put_keys(keyring, keys) do
+    Enum.reduce_while(keys, :ok, fn {id, key, tag_len}, :ok ->
+      with {:ok, raw} <- Base.decode64(key),
+           :ok <- Native.put_swarm_key(keyring, id, raw, tag_len) do
+        {:cont, :ok}
+      else
+        _ -> {:halt, {:error, :invalid_key}}
+      end
+    end)
+  end
+end
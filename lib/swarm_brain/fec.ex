@@ -0,0 +1,64 @@
+defmodule SwarmBrain.Fec do
+  @moduledoc """
+  Forward error correction for lossy radio links (native, see
+  `native/swarm_native/src/fec/mod.rs`).
+
+  `encode/4` splits a payload into `n` self-describing shards, any `k` of
+  which rebuild it. A receiver feeds every frame to `collect/2`, which
+  hands back payloads as soon as enough shards of a message are in.
+
+    * `:reed_solomon`: exactly `k` shards suffice, `k < n <= 256`.
+    * `:raptorq`: fountain code, `n` up to 65535 (thumbnails, map deltas).
+
+  Message ids tell shards apart: they must not repeat among messages in
+  flight on one link.
+  """
+  alias SwarmBrain.Vision.Native
+
+  # Messages kept half-assembled; the oldest is abandoned past this
+  @max_pending 16
+
+  defstruct pending: %{}, order: [], recent: []
+
+  @doc "Returns `{:ok, [shard]} | {:error, :invalid_params | :too_large}`."
+  def encode(payload, message, k, n, scheme \\ :reed_solomon) do
+    Native.fec_encode(payload, scheme, rem(message, 65_536), k, n)
+  end
+
+  @doc """
+  Adds one received frame. Returns `{:ok, payload, acc}` when it completes a
+  message, `{:more, acc}` otherwise. Corrupt frames and late shards of
+  finished messages are dropped.
+  """
+  def collect(%__MODULE__{} = acc, shard) do
+    with {:ok, %{message: id, k: k}} <- Native.fec_shard_info(shard),
+         false <- id in acc.recent do
+      shards = [shard | Map.get(acc.pending, id, [])]
+
+      with true <- length(shards) >= k,
+           {:ok, payload} <- Native.fec_decode(shards) do
+        {:ok, payload, finish(acc, id)}
+      else
+        _ -> {:more, hold(acc, id, shards)}
+      end
+    else
+      _ -> {:more, acc}
+    end
+  end
+
+  # --- PRIVATE ---
+
+  defp hold(acc, id, shards) do
+    order = if Map.has_key?(acc.pending, id), do: acc.order, else: [id | acc.order]
+    {order, evicted} = Enum.split(order, @max_pending)
+    %{acc | pending: acc.pending |> Map.put(id, shards) |> Map.drop(evicted), order: order}
+  end
+
+  defp finish(acc, id) do
+    %{acc |
+      pending: Map.delete(acc.pending, id),
+      order: List.delete(acc.order, id),
+      recent: Enum.take([id | acc.recent], @max_pending)
+    }
+  end
+end
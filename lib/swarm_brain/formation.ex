@@ -4,13 +4,23 @@ defmodule SwarmBrain.Formation do
   require Logger
 
 
+  alias SwarmBrain.Bus
+  alias SwarmBrain.Bus.Config
+
   # Safety: If no signal for 2 seconds, stop moving.
   @signal_timeout 2000
 
+  # The zenoh bus may come up after us: retry until it does
+  @attach_retry 1000
+
   defstruct [
     :current_vector,
     :formation_offset,
-    :last_seen_ts
+    :last_seen_ts,
+    :zenoh,
+    :leader,    # Configured leader's bus segment (opts[:leader] node), or nil to elect
+    :following, # The peer whose kinematics we copy
+    peers: %{}  # Bus segment => last heard (ms)
   ]
 
   def start_link(opts) do
@@ -20,30 +30,59 @@ defmodule SwarmBrain.Formation do
   def init(opts) do
     Phoenix.PubSub.subscribe(SwarmBrain.PubSub, "radio:telemetry")
     initial_offset = Keyword.get(opts, :offset, %{x: 0, y: 0})
+    send(self(), :attach_zenoh)
 
     {:ok, %__MODULE__{
       current_vector: %{heading: 0.0, velocity: 0.0},
       formation_offset: initial_offset,
-      last_seen_ts: System.monotonic_time(:millisecond)
+      last_seen_ts: System.monotonic_time(:millisecond),
+      leader: opts |> Keyword.get(:leader) |> segment()
     }}
   end
 
 
   def handle_info({:udp, _socket, _ip, _port, binary_data}, state) do
-    # FIX: The Codec returns a map directly, not a tuple.
-    # We catch crashes using a try/rescue block if the data is malformed,
-    # or rely on the supervisor to restart us (the "Class A" approach).
+    # Forged or replayed frames fail to open and are ignored
+    case SwarmBrain.Crypto.open(binary_data) do
+      {:ok, payload} -> {:noreply, apply_vitals(payload, state)}
+      {:error, _} -> {:noreply, state}
+    end
+  end
 
-    vitals = SwarmBrain.Telemetry.Codec.decode_vitals(binary_data)
+  # Already opened by the Antenna
+  def handle_info({:telemetry_packet, payload, _rssi}, state) do
+    {:noreply, apply_vitals(payload, state)}
+  end
 
-    # Update state based on the raw map
-    new_state = %{state |
-      heading: vitals.heading,
-      velocity: vitals.velocity
-    }
+  # Peer kinematics over zenoh (see SwarmBrain.Bus). Only the leader is
+  # copied: with several peers, following whoever spoke last would flap.
+  # Samples that fail to open (forged, replayed, unsealed under swarm keys)
+  # neither count as a live peer nor steer us.
+  def handle_info({:zenoh, key, payload, _timestamp}, state) do
+    Bus.ack(state.zenoh)
 
-    # Logic to adjust formation based on new vitals...
-    {:noreply, new_state}
+    with peer when is_binary(peer) <- Bus.peer(key),
+         false <- Bus.own?(key),
+         {:ok, kinematics} <- SwarmBrain.Crypto.open(payload) do
+      now = System.monotonic_time(:millisecond)
+      peers = for {p, seen} <- Map.put(state.peers, peer, now), now - seen <= @signal_timeout, into: %{}, do: {p, seen}
+      state = %{state | peers: peers, following: follow(state.leader, state.following, peers)}
+      {:noreply, apply_leader(peer, kinematics, now, state)}
+    else
+      _ -> {:noreply, state}
+    end
+  end
+
+  def handle_info(:attach_zenoh, state) do
+    case Bus.subscribe("kinematics") do
+      {:ok, sub} ->
+        Logger.info("📡 Formation: Following peer kinematics over zenoh.")
+        {:noreply, %{state | zenoh: sub}}
+
+      {:error, _} ->
+        Process.send_after(self(), :attach_zenoh, @attach_retry)
+        {:noreply, state}
+    end
   end
 
   def handle_info(:check_safety, state) do
@@ -56,4 +95,44 @@ defmodule SwarmBrain.Formation do
       {:noreply, state}
     end
   end
+
+  defp apply_leader(peer, payload, now, %{following: peer} = state) do
+    case Bus.decode_kinematics(payload) do
+      {:ok, k} ->
+        %{state |
+          current_vector: %{heading: :math.atan2(k.vy, k.vx) * 180 / :math.pi(), velocity: :math.sqrt(k.vx * k.vx + k.vy * k.vy)},
+          last_seen_ts: now
+        }
+
+      {:error, _} ->
+        state
+    end
+  end
+
+  defp apply_leader(_peer, _payload, _now, state), do: state
+
+  # The configured leader, else the current one while it is heard, else the
+  # smallest live segment: followers hearing the same peers pick the same
+  # leader, and it only changes once that leader goes quiet.
+  defp follow(leader, _following, _peers) when is_binary(leader), do: leader
+  defp follow(nil, following, peers) when is_map_key(peers, following), do: following
+  defp follow(nil, _following, peers), do: peers |> Map.keys() |> Enum.min(fn -> nil end)
+
+  defp segment(nil), do: nil
+  defp segment(node), do: Bus.peer(Config.for_node(node).kinematics_key)
+
+  # Malformed or foreign packets decode to an error and are ignored
+  defp apply_vitals(payload, state) do
+    case SwarmBrain.Telemetry.Codec.decode(payload) do
+      {:ok, %SwarmBrain.Telemetry.Packet{body: {:vitals, vitals}}} ->
+        # Logic to adjust formation based on new vitals...
+        %{state |
+          current_vector: %{heading: vitals.heading, velocity: vitals.velocity},
+          last_seen_ts: System.monotonic_time(:millisecond)
+        }
+
+      _ ->
+        state
+    end
+  end
 end
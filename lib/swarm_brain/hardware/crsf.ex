@@ -0,0 +1,68 @@
+defmodule SwarmBrain.Hardware.Crsf do
+  @moduledoc """
+  CRSF (Crossfire / ExpressLRS) frames from the receiver.
+  Parsed natively (`native/swarm_native/src/serial/crsf.rs`): feed raw UART
+  chunks to `Native.crsf_feed/2` and get these structs back;
+  `Native.crsf_encode/1` frames any of them.
+
+  Unknown frame types arrive as a `Frame` with the raw payload.
+  """
+
+  defmodule RcChannels do
+    @moduledoc "RC_CHANNELS_PACKED (0x16). 16 channels in µs (988..2012)."
+    defstruct channels: List.duplicate(1500, 16)
+  end
+
+  defmodule LinkStatistics do
+    @moduledoc "LINK_STATISTICS (0x14). RSSI in dBm, LQ in %, SNR in dB."
+    defstruct uplink_rssi_1: 0,
+              uplink_rssi_2: 0,
+              uplink_lq: 0,
+              uplink_snr: 0,
+              active_antenna: 0,
+              rf_mode: 0,
+              uplink_tx_power: 0,
+              downlink_rssi: 0,
+              downlink_lq: 0,
+              downlink_snr: 0
+  end
+
+  defmodule Battery do
+    @moduledoc "BATTERY_SENSOR (0x08). Volts, amps, mAh drawn, % remaining."
+    defstruct voltage: 0.0, current: 0.0, capacity: 0, remaining: 0
+  end
+
+  defmodule Attitude do
+    @moduledoc "ATTITUDE (0x1E). Radians."
+    defstruct pitch: 0.0, roll: 0.0, yaw: 0.0
+  end
+
+  defmodule Gps do
+    @moduledoc "GPS (0x02). Degrees, km/h, degrees, meters."
+    defstruct latitude: 0.0, longitude: 0.0, groundspeed: 0.0, heading: 0.0, altitude: 0, satellites: 0
+  end
+
+  defmodule Msp do
+    @moduledoc """
+    MSP_REQ / MSP_RESP / MSP_WRITE (0x7A..0x7C): one chunk of a tunnelled MSP
+    message. `kind`: `:request | :response | :write`.
+    """
+    defstruct kind: :request,
+              destination: 0xC8,
+              origin: 0xEA,
+              sequence: 0,
+              start: true,
+              version: 1,
+              error: false,
+              data: <<>>
+  end
+
+  defmodule Frame do
+    @moduledoc "Any other valid frame."
+    defstruct address: 0xC8, frame_type: 0, payload: <<>>
+  end
+
+  @doc "The RSSI of the antenna in use (dBm)."
+  def rssi(%LinkStatistics{active_antenna: 1, uplink_rssi_2: rssi}), do: rssi
+  def rssi(%LinkStatistics{uplink_rssi_1: rssi}), do: rssi
+end
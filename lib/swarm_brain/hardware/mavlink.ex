@@ -0,0 +1,128 @@
+defmodule SwarmBrain.Hardware.Mavlink do
+  @moduledoc """
+  MAVLink v2 messages for ArduPilot / PX4 airframes.
+  Framed, signed and parsed natively (`native/swarm_native/src/serial/mavlink/`):
+  `Native.mavlink_feed/2` returns `{system_id, component_id, message}` and
+  `Native.mavlink_encode/2` frames any of the structs below.
+
+  Units are MAVLink's own (rad, degE7, mm, cm/s, mV...), see common.xml.
+  """
+
+  # MAV_TYPE_ONBOARD_CONTROLLER / MAV_AUTOPILOT_INVALID: what a companion computer announces
+  @mav_type_onboard_controller 18
+  @mav_autopilot_invalid 8
+  @mav_state_active 4
+
+  @mav_cmd_set_message_interval 511
+  @mav_frame_body_ned 8
+  # POSITION_TARGET_TYPEMASK: ignore position, acceleration and yaw (velocity + yaw rate only)
+  @velocity_only 0b0000_0101_1100_0111
+
+  defmodule Heartbeat do
+    @moduledoc "HEARTBEAT (0). `base_mode` bit 128 = armed."
+    defstruct custom_mode: 0, mav_type: 0, autopilot: 0, base_mode: 0, system_status: 0, mavlink_version: 3
+  end
+
+  defmodule SysStatus do
+    @moduledoc "SYS_STATUS (1). Battery in mV / cA / %, -1 (or 65535) when unknown."
+    defstruct sensors_present: 0,
+              sensors_enabled: 0,
+              sensors_health: 0,
+              load: 0,
+              voltage_battery: 0,
+              current_battery: -1,
+              drop_rate_comm: 0,
+              errors_comm: 0,
+              errors_count: {0, 0, 0, 0},
+              battery_remaining: -1
+  end
+
+  defmodule Attitude do
+    @moduledoc "ATTITUDE (30). Radians, rad/s."
+    defstruct time_boot_ms: 0, roll: 0.0, pitch: 0.0, yaw: 0.0, rollspeed: 0.0, pitchspeed: 0.0, yawspeed: 0.0
+  end
+
+  defmodule GlobalPositionInt do
+    @moduledoc "GLOBAL_POSITION_INT (33). degE7, mm, cm/s (NED), cdeg."
+    defstruct time_boot_ms: 0, lat: 0, lon: 0, alt: 0, relative_alt: 0, vx: 0, vy: 0, vz: 0, hdg: 65535
+  end
+
+  defmodule CommandLong do
+    @moduledoc "COMMAND_LONG (76)."
+    defstruct target_system: 0,
+              target_component: 0,
+              command: 0,
+              confirmation: 0,
+              param1: 0.0,
+              param2: 0.0,
+              param3: 0.0,
+              param4: 0.0,
+              param5: 0.0,
+              param6: 0.0,
+              param7: 0.0
+  end
+
+  defmodule CommandAck do
+    @moduledoc "COMMAND_ACK (77). `result` 0 = accepted."
+    defstruct command: 0, result: 0
+  end
+
+  defmodule SetPositionTargetLocalNed do
+    @moduledoc "SET_POSITION_TARGET_LOCAL_NED (84). Bits set in `type_mask` are ignored."
+    defstruct time_boot_ms: 0,
+              target_system: 0,
+              target_component: 0,
+              coordinate_frame: 1,
+              type_mask: 0,
+              x: 0.0,
+              y: 0.0,
+              z: 0.0,
+              vx: 0.0,
+              vy: 0.0,
+              vz: 0.0,
+              afx: 0.0,
+              afy: 0.0,
+              afz: 0.0,
+              yaw: 0.0,
+              yaw_rate: 0.0
+  end
+
+  # --- BUILDERS ---
+
+  @doc "Our 1 Hz heartbeat as a companion computer."
+  def heartbeat do
+    %Heartbeat{
+      mav_type: @mav_type_onboard_controller,
+      autopilot: @mav_autopilot_invalid,
+      system_status: @mav_state_active
+    }
+  end
+
+  @doc "True for heartbeats from an autopilot (not a GCS or another companion)."
+  def autopilot?(%Heartbeat{autopilot: autopilot}), do: autopilot != @mav_autopilot_invalid
+
+  @doc "Asks `{system, component}` to stream message `id` at `hz`."
+  def set_message_interval({system, component}, id, hz) do
+    %CommandLong{
+      target_system: system,
+      target_component: component,
+      command: @mav_cmd_set_message_interval,
+      param1: id * 1.0,
+      param2: 1_000_000 / hz
+    }
+  end
+
+  @doc "Body-frame velocity (m/s, NED: z down) and yaw rate (rad/s) setpoint."
+  def velocity_setpoint({system, component}, {vx, vy, vz}, yaw_rate) do
+    %SetPositionTargetLocalNed{
+      target_system: system,
+      target_component: component,
+      coordinate_frame: @mav_frame_body_ned,
+      type_mask: @velocity_only,
+      vx: vx,
+      vy: vy,
+      vz: vz,
+      yaw_rate: yaw_rate
+    }
+  end
+end
@@ -0,0 +1,52 @@
+defmodule SwarmBrain.Hardware.Msp do
+  @moduledoc """
+  MSP (MultiWii Serial Protocol) messages from the flight controller.
+  Parsed natively (`native/swarm_native/src/serial/msp.rs`): feed raw UART
+  chunks to `Native.msp_feed/2` and get these structs back.
+
+  Responses to the commands below are decoded; anything else arrives as a
+  `Frame` with the raw payload.
+  """
+
+  @ident 100
+  @status 101
+  @raw_imu 102
+  @attitude 108
+  @analog 110
+
+  def ident, do: @ident
+  def status, do: @status
+  def raw_imu, do: @raw_imu
+  def attitude, do: @attitude
+  def analog, do: @analog
+
+  defmodule Ident do
+    @moduledoc "MSP_IDENT (100)."
+    defstruct version: 0, multitype: 0, msp_version: 0, capability: 0
+  end
+
+  defmodule Status do
+    @moduledoc "MSP_STATUS (101). `mode_flags`: active boxes, bit 0 = ARM on Betaflight."
+    defstruct cycle_time_us: 0, i2c_errors: 0, sensors: 0, mode_flags: 0, profile: 0
+  end
+
+  defmodule RawImu do
+    @moduledoc "MSP_RAW_IMU (102). `{x, y, z}` in raw sensor units."
+    defstruct acc: {0, 0, 0}, gyro: {0, 0, 0}, mag: {0, 0, 0}
+  end
+
+  defmodule Attitude do
+    @moduledoc "MSP_ATTITUDE (108). Degrees."
+    defstruct roll: 0.0, pitch: 0.0, yaw: 0.0
+  end
+
+  defmodule Analog do
+    @moduledoc "MSP_ANALOG (110). Volts, mAh, RSSI (0..1023), amps."
+    defstruct voltage: 0.0, mah_drawn: 0, rssi: 0, amperage: 0.0
+  end
+
+  defmodule Frame do
+    @moduledoc "Any other valid frame. `direction`: `:request | :response | :error`."
+    defstruct version: :v1, direction: :response, flag: 0, cmd: 0, payload: <<>>
+  end
+end
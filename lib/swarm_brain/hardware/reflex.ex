@@ -0,0 +1,42 @@
+defmodule SwarmBrain.Hardware.Reflex do
+  @moduledoc """
+  Settings for the native RC mixer (`native/swarm_native/src/reflex/`):
+  stick shaping, slew limits, arming interlock and the failsafe sequence
+  (hold -> descend -> disarm) used by `SwarmBrain.Hardware.Spine` on MSP.
+
+  Overrides come from `config :swarm_brain, :reflex, [...]`.
+  """
+
+  defmodule Axis do
+    @moduledoc """
+    Per-axis shaping, in stick units (-1.0..1.0).
+    `rate_limit` is the max change per second (2.0 = full sweep in 1 s), 0 = off.
+    """
+    defstruct deadband: 0.02, expo: 0.2, rate_limit: 4.0
+  end
+
+  defmodule Config do
+    @moduledoc "Mirrors `reflex::Config`; every field must be present."
+    alias SwarmBrain.Hardware.Reflex.Axis
+
+    defstruct roll: %Axis{},
+              pitch: %Axis{},
+              yaw: %Axis{},
+              throttle: %Axis{deadband: 0.0, expo: 0.0, rate_limit: 2.0},
+              pwm_min: 1000,
+              pwm_mid: 1500,
+              pwm_max: 2000,
+              # Aux1 while armed (match the FC's ARM mode range)
+              arm_pwm: 1800,
+              channels: 8,
+              arm_throttle_max: -0.9,
+              timeout_ms: 250,
+              hold_ms: 1000,
+              descend_ms: 5000,
+              descend_throttle: -0.2
+  end
+
+  def config do
+    struct(Config, Application.get_env(:swarm_brain, :reflex, []))
+  end
+end
@@ -1,59 +1,114 @@
 defmodule SwarmBrain.Hardware.Spine do
   @moduledoc """
   The Spinal Cord.
-  Translates 'Intent' (RL Output) into 'MSP' (Flight Controller Protocol).
-  Implements the "Reflex Arc" - safe clamping and mapping of signals.
+  Translates 'Intent' (RL Output) into the Flight Controller's protocol:
+  MSP (Betaflight / iNav) or MAVLink (ArduPilot / PX4), per `:fc_protocol`.
+  Implements the "Reflex Arc" - shaping, slew limits, arming and failsafe
+  happen natively (`SwarmBrain.Hardware.Reflex`), ticked at 50 Hz on MSP.
   """
   use GenServer
   require Logger
 
-  # MSP Command IDs
+  alias SwarmBrain.Hardware.{Mavlink, Msp, Reflex}
+  alias SwarmBrain.Vision.Native
+
+  # MSP telemetry requests (the FC only speaks when asked)
+  @poll_interval 100
+  @poll [Msp.attitude(), Msp.analog(), Msp.status()]
+
+  # MAVLink: our heartbeat, and the streams asked of the autopilot {msg id, Hz}
+  @heartbeat_interval 1000
+  @streams [{30, 20}, {33, 5}, {1, 2}]
+
+  # MSP RC output: the FC's own RX failsafe trips if this stream stops
+  @rc_interval 20
   @msp_set_raw_rc 200
-  @msp_ident 100
-  # @msp_attitude 108 (Uncomment when we implement full telemetry parsing)
 
-  # PWM Constants (Standard RC)
-  @pwm_min 1000
-  @pwm_max 2000
-  @pwm_mid 1500
+  # Velocity Constants (MAVLink setpoints at full stick)
+  @max_speed 5.0
+  @max_climb 2.0
+  @max_yaw_rate 1.0
 
-  defstruct [:uart_pid, :mode, :last_attitude]
+  defstruct [
+    :uart_pid,
+    :mode,
+    :protocol,
+    :parser,
+    :reflex,
+    :target,
+    :last_attitude,
+    :ident,
+    :analog,
+    :status,
+    :position,
+    phase: :disarmed
+  ]
 
   def start_link(opts), do: GenServer.start_link(__MODULE__, opts, name: __MODULE__)
 
   # API
   def get_imu_state, do: GenServer.call(__MODULE__, :get_attitude)
 
-  # The Reflex Trigger: Called by Tracker 30 times a second
-  def send_controls(r, p, y, t), do: GenServer.cast(__MODULE__, {:command, {r, p, y, t}})
+  @doc "Battery % (MAVLink SYS_STATUS, or estimated from MSP pack voltage), nil when unknown."
+  def get_battery, do: GenServer.call(__MODULE__, :get_battery)
+
+  # The Reflex Trigger: Called by Tracker 30 times a second (and recorded in the black box)
+  def send_controls(r, p, y, t) do
+    SwarmBrain.Persistence.record_command(r, p, y, t)
+    GenServer.cast(__MODULE__, {:command, {r, p, y, t}})
+  end
+
+  # Arming interlock (MSP): refused without fresh commands or with throttle up
+  def arm, do: GenServer.call(__MODULE__, :arm)
+  def disarm, do: GenServer.call(__MODULE__, :disarm)
 
   def init(_opts) do
     uart_dev = Application.get_env(:swarm_brain, :fc_port)
+    protocol = Application.get_env(:swarm_brain, :fc_protocol, :msp)
 
     # Simple Hardware Check
     if uart_dev && File.exists?(uart_dev) do
-      Logger.info("🔌 Spine: Connecting to Nervous System (FC) on #{uart_dev}")
+      Logger.info("🔌 Spine: Connecting to Nervous System (FC) on #{uart_dev} (#{protocol})")
       {:ok, pid} = Circuits.UART.start_link()
       Circuits.UART.open(pid, uart_dev, speed: 115200, active: true)
 
-      # Handshake
-      request_ident(pid)
+      state = %__MODULE__{
+        mode: :hardware,
+        protocol: protocol,
+        uart_pid: pid,
+        parser: new_parser(protocol),
+        reflex: new_reflex(protocol),
+        last_attitude: %{roll: 0.0, pitch: 0.0, yaw: 0.0}
+      }
 
-      {:ok, %__MODULE__{mode: :hardware, uart_pid: pid, last_attitude: %{roll: 0.0, pitch: 0.0, yaw: 0.0}}}
+      # Handshake
+      handshake(state)
+      {:ok, state}
     else
       Logger.warning("👻 Spine: No Flight Controller detected. Running in GHOST MODE.")
-      {:ok, %__MODULE__{mode: :ghost, uart_pid: nil, last_attitude: %{roll: 0.0, pitch: 0.0, yaw: 0.0}}}
+      {:ok, %__MODULE__{mode: :ghost, protocol: protocol, uart_pid: nil, last_attitude: %{roll: 0.0, pitch: 0.0, yaw: 0.0}}}
     end
   end
 
   # --- REFLEX LOOP ---
 
-  def handle_cast({:command, {r, p, y, t}}, %{mode: :hardware} = state) do
-    # 1. Map RL Intent (-1.0 to 1.0) to PWM (1000 to 2000)
-    payload = encode_rc_payload(r, p, y, t)
+  def handle_cast({:command, {r, p, y, t}}, %{mode: :hardware, protocol: :msp} = state) do
+    # RL Intent (-1.0 to 1.0) is shaped natively; it reaches the FC on the next :rc tick
+    Native.reflex_command(state.reflex, r * 1.0, p * 1.0, y * 1.0, t * 1.0)
+    {:noreply, state}
+  end
+
+  def handle_cast({:command, _}, %{mode: :hardware, protocol: :mavlink, target: nil} = state) do
+    # No autopilot heartbeat yet: nobody to address
+    {:noreply, state}
+  end
+
+  def handle_cast({:command, {r, p, y, t}}, %{mode: :hardware, protocol: :mavlink} = state) do
+    # 1. Map RL Intent (-1.0 to 1.0) to a body-frame velocity setpoint (NED: z down)
+    velocity = {clamp(p) * @max_speed, clamp(r) * @max_speed, -clamp(t) * @max_climb}
 
     # 2. Send to Nervous System (FC)
-    Circuits.UART.write(state.uart_pid, pack_msp(@msp_set_raw_rc, payload))
+    send_mavlink(state, Mavlink.velocity_setpoint(state.target, velocity, clamp(y) * @max_yaw_rate))
 
     {:noreply, state}
   end
@@ -65,56 +120,177 @@ defmodule SwarmBrain.Hardware.Spine do
 
   # --- INCOMING SENSORY DATA ---
 
-  # [FIX] Prefix unused 'data' with underscore to silence warning
-  def handle_info({:circuits_uart, _, _data}, state) do
-    # Placeholder: Future telemetry parsing goes here.
+  # UART reads arrive in arbitrary fragments; the native parser reassembles them
+  def handle_info({:circuits_uart, _, data}, state) when is_binary(data) do
+    state =
+      state
+      |> feed(data)
+      |> Enum.reduce(state, &absorb/2)
+
     {:noreply, state}
   end
 
-  # --- MSP PROTOCOL ---
+  def handle_info({:circuits_uart, _, {:error, reason}}, state) do
+    Logger.warning("⚠️ Spine: UART error #{inspect(reason)}")
+    {:noreply, state}
+  end
 
-  defp request_ident(pid), do: Circuits.UART.write(pid, pack_msp(@msp_ident, <<>>))
+  def handle_info(:rc, state) do
+    # 1. Failsafe + slew limits advance even when commands stop
+    {payload, status} = Native.reflex_tick(state.reflex)
 
-  defp pack_msp(cmd_id, payload) do
-    size = byte_size(payload)
-    checksum = bitwise_xor_checksum(size, cmd_id, payload)
-    << "$", "M", "<", size, cmd_id, payload::binary, checksum >>
+    # 2. Send to Nervous System (FC)
+    {:ok, frame} = Native.msp_encode_request(@msp_set_raw_rc, payload, :v1)
+    Circuits.UART.write(state.uart_pid, frame)
+
+    if status.phase != state.phase do
+      Logger.warning("🦴 Spine: Reflex #{state.phase} -> #{status.phase}")
+    end
+
+    Process.send_after(self(), :rc, @rc_interval)
+    {:noreply, %{state | phase: status.phase}}
+  end
+
+  def handle_info(:poll, %{protocol: :msp} = state) do
+    Enum.each(@poll, &request(state.uart_pid, &1))
+    Process.send_after(self(), :poll, @poll_interval)
+    {:noreply, state}
+  end
+
+  def handle_info(:poll, %{protocol: :mavlink} = state) do
+    send_mavlink(state, Mavlink.heartbeat())
+    Process.send_after(self(), :poll, @heartbeat_interval)
+    {:noreply, state}
+  end
+
+  defp feed(%{protocol: :msp, parser: parser}, data), do: Native.msp_feed(parser, data)
+  defp feed(%{protocol: :mavlink, parser: parser}, data), do: Native.mavlink_feed(parser, data)
+
+  # MSP
+  defp absorb(%Msp.Attitude{roll: r, pitch: p, yaw: y}, state) do
+    %{state | last_attitude: %{roll: r, pitch: p, yaw: y}}
+  end
+
+  defp absorb(%Msp.Ident{} = ident, %{ident: nil} = state) do
+    Logger.info("🧠 Spine: FC identified (MSP #{ident.msp_version}, type #{ident.multitype})")
+    %{state | ident: ident}
   end
 
-  defp bitwise_xor_checksum(size, cmd, payload) do
-    initial = Bitwise.bxor(size, cmd)
-    for <<byte <- payload>>, reduce: initial do
-      acc -> Bitwise.bxor(acc, byte)
+  defp absorb(%Msp.Analog{} = analog, state), do: %{state | analog: analog}
+  defp absorb(%Msp.Status{} = status, state), do: %{state | status: status}
+
+  defp absorb(%Msp.Frame{direction: :error, cmd: cmd}, state) do
+    Logger.debug("Spine: FC rejected MSP command #{cmd}")
+    state
+  end
+
+  # MAVLink: the first autopilot heard becomes our target
+  defp absorb({sys, comp, %Mavlink.Heartbeat{} = hb}, %{target: nil} = state) do
+    if Mavlink.autopilot?(hb) do
+      Logger.info("🧠 Spine: Autopilot #{sys}/#{comp} found (MAV_AUTOPILOT #{hb.autopilot})")
+      state = %{state | target: {sys, comp}, ident: hb}
+      Enum.each(@streams, fn {id, hz} -> send_mavlink(state, Mavlink.set_message_interval(state.target, id, hz)) end)
+      state
+    else
+      state
+    end
+  end
+
+  defp absorb({sys, comp, message}, %{target: {sys, comp}} = state), do: absorb_target(message, state)
+
+  defp absorb(_other, state), do: state
+
+  defp absorb_target(%Mavlink.Attitude{roll: r, pitch: p, yaw: y}, state) do
+    # Radians -> degrees, heading 0..360 (same shape as MSP)
+    yaw = :math.fmod(y * 180 / :math.pi() + 360, 360)
+    %{state | last_attitude: %{roll: r * 180 / :math.pi(), pitch: p * 180 / :math.pi(), yaw: yaw}}
+  end
+
+  defp absorb_target(%Mavlink.Heartbeat{} = hb, state), do: %{state | ident: hb}
+  defp absorb_target(%Mavlink.SysStatus{} = status, state), do: %{state | status: status}
+  defp absorb_target(%Mavlink.GlobalPositionInt{} = position, state), do: %{state | position: position}
+
+  defp absorb_target(%Mavlink.CommandAck{command: cmd, result: result}, state) when result != 0 do
+    Logger.debug("Spine: Autopilot rejected MAV_CMD #{cmd} (MAV_RESULT #{result})")
+    state
+  end
+
+  defp absorb_target(_other, state), do: state
+
+  # --- PROTOCOLS ---
+
+  defp new_parser(:msp), do: Native.msp_new_parser()
+
+  defp new_parser(:mavlink) do
+    cfg = Application.get_env(:swarm_brain, :mavlink, [])
+    link = Native.mavlink_new_link(Keyword.get(cfg, :system_id, 1), Keyword.get(cfg, :component_id, 191))
+
+    case Keyword.get(cfg, :signing_key) do
+      nil ->
+        link
+
+      key ->
+        :ok =
+          Native.mavlink_set_signing(
+            link,
+            Base.decode64!(key),
+            Keyword.get(cfg, :link_id, 0),
+            Keyword.get(cfg, :allow_unsigned, false)
+          )
+
+        link
     end
   end
 
-  # The "Reflex" Mapper
-  defp encode_rc_payload(r, p, y, t) do
-    <<
-      map_to_pwm(r)::little-16, # Roll
-      map_to_pwm(p)::little-16, # Pitch
-      map_to_pwm(t)::little-16, # Throttle
-      map_to_pwm(y)::little-16, # Yaw
-      @pwm_mid::little-16,      # Aux1 (Arm?) - [FIX] Use Attribute
-      @pwm_min::little-16,      # Aux2          [FIX] Use Attribute
-      @pwm_min::little-16,      # Aux3
-      @pwm_min::little-16       # Aux4
-    >>
+  defp new_reflex(:msp) do
+    {:ok, reflex} = Native.reflex_new(Reflex.config())
+    reflex
+  end
+
+  defp new_reflex(_protocol), do: nil
+
+  defp handshake(%{protocol: :msp, uart_pid: pid}) do
+    request(pid, Msp.ident())
+    Process.send_after(self(), :poll, @poll_interval)
+    Process.send_after(self(), :rc, @rc_interval)
+  end
+
+  defp handshake(%{protocol: :mavlink} = state) do
+    send_mavlink(state, Mavlink.heartbeat())
+    Process.send_after(self(), :poll, @heartbeat_interval)
   end
 
-  defp map_to_pwm(val) do
-    # Clamp safety
-    clamped = max(-1.0, min(val, 1.0))
+  defp request(pid, cmd) do
+    {:ok, frame} = Native.msp_encode_request(cmd, <<>>, :v1)
+    Circuits.UART.write(pid, frame)
+  end
+
+  defp send_mavlink(state, message) do
+    Circuits.UART.write(state.uart_pid, Native.mavlink_encode(state.parser, message))
+  end
 
-    # [FIX] Dynamic calculation using Attributes instead of hardcoded numbers
-    # Slope = (2000 - 1000) / 2 = 500
-    slope = (@pwm_max - @pwm_min) / 2.0
-    intercept = @pwm_mid
+  # LiPo: cell count from the pack voltage, linear 3.5 V (empty) .. 4.2 V (full)
+  defp battery(%{status: %Mavlink.SysStatus{battery_remaining: pct}}) when pct in 0..100, do: pct
 
-    round((clamped * slope) + intercept)
+  defp battery(%{analog: %Msp.Analog{voltage: v}}) when v > 0 do
+    cells = ceil(v / 4.3)
+    round(max(0.0, min((v / cells - 3.5) / 0.7, 1.0)) * 100)
   end
 
+  defp battery(_state), do: nil
+
+  # Clamp safety
+  defp clamp(val), do: max(-1.0, min(val, 1.0))
+
   def handle_call(:get_attitude, _from, state) do
     {:reply, state.last_attitude, state}
   end
+
+  def handle_call(:get_battery, _from, state), do: {:reply, battery(state), state}
+
+  def handle_call(:arm, _from, %{reflex: nil} = state), do: {:reply, {:error, :unsupported}, state}
+  def handle_call(:arm, _from, state), do: {:reply, Native.reflex_arm(state.reflex), state}
+
+  def handle_call(:disarm, _from, %{reflex: nil} = state), do: {:reply, {:error, :unsupported}, state}
+  def handle_call(:disarm, _from, state), do: {:reply, Native.reflex_disarm(state.reflex), state}
 end
@@ -1,11 +1,25 @@
 defmodule SwarmBrain.Persistence do
   @moduledoc """
   The Black Box Recorder.
-  Saves telemetry and detections to disk or upstream.
+  Starts the native flight recorder (`:black_box` config) on the Vision
+  resource: the heartbeat records kinematics, flow grids, motion events and
+  frames (JPEG, PNG or raw); detections (`log/1`) and commands (`record_command/4`) are
+  added from here.
+
+  Recordings are read back with `open_recording/1` and `stream/3`, which
+  yield `{timestamp_us, record}` with records such as
+  `{:kinematics, %{vx, vy, px, py, seq}}`, `{:flow, binary}`,
+  `{:motion, %{x, y, w, h}}`, `{:frame, %{width, height, format, data}}`,
+  `{:detection, term}` and `{:command, %{roll, pitch, yaw, throttle}}`.
   """
   use GenServer
   require Logger
 
+  alias SwarmBrain.Recorder.Config
+  alias SwarmBrain.Vision.{Native, Server}
+
+  @batch 256
+
   def start_link(opts) do
     GenServer.start_link(__MODULE__, opts, name: __MODULE__)
   end
@@ -15,9 +29,76 @@ defmodule SwarmBrain.Persistence do
     GenServer.cast(__MODULE__, {:log, data})
   end
 
+  @doc "Records a control output. Called at the control rate: straight to the native queue."
+  def record_command(roll, pitch, yaw, throttle) do
+    with resource when resource != nil <- Server.get_resource() do
+      Native.recorder_append(
+        resource,
+        {:command, %{roll: roll * 1.0, pitch: pitch * 1.0, yaw: yaw * 1.0, throttle: throttle * 1.0}}
+      )
+    end
+
+    :ok
+  end
+
+  @doc "Returns `{:ok, %{records, bytes, segments, dropped, failed}}` | `{:error, reason}`."
+  def stats do
+    case Server.get_resource() do
+      nil -> {:error, :no_resource}
+      resource -> Native.recorder_stats(resource)
+    end
+  end
+
+  @doc "Opens a recording directory (default: the configured one) for reading."
+  def open_recording(dir \\ Config.new(Application.get_env(:swarm_brain, :black_box) || []).dir) do
+    Native.open_recording(dir)
+  end
+
+  @doc """
+  Lazily streams the records of `recording` between `from_us` and `to_us`
+  (inclusive, µs since the UNIX epoch). Restarts the recording's cursor.
+  """
+  def stream(recording, from_us \\ 0, to_us \\ 0xFFFFFFFFFFFFFFFF) do
+    Stream.resource(
+      fn -> Native.recording_seek(recording, from_us, to_us) end,
+      fn _ ->
+        case Native.recording_next(recording, @batch) do
+          [] -> {:halt, nil}
+          entries -> {Enum.map(entries, &decode/1), nil}
+        end
+      end,
+      fn _ -> :ok end
+    )
+  end
+
   @impl true
   def init(_opts) do
-    {:ok, %{}}
+    resource = Server.get_resource()
+
+    case Application.get_env(:swarm_brain, :black_box) do
+      nil ->
+        {:ok, %{resource: resource, active: false}}
+
+      _ when resource == nil ->
+        Logger.warning("💾 [PERSISTENCE] No Vision resource. Black box disabled.")
+        {:ok, %{resource: nil, active: false}}
+
+      overrides ->
+        config = Config.new(overrides)
+        File.mkdir_p!(config.dir)
+
+        case Native.start_recorder(resource, config) do
+          :ok ->
+            # Seal the open segment on shutdown
+            Process.flag(:trap_exit, true)
+            Logger.info("💾 [PERSISTENCE] Black box recording to #{config.dir}")
+            {:ok, %{resource: resource, active: true}}
+
+          {:error, reason} ->
+            Logger.warning("💾 [PERSISTENCE] Black box unavailable (#{reason}).")
+            {:ok, %{resource: resource, active: false}}
+        end
+    end
   end
 
   # NEW: Handle Cluster Nodes
@@ -28,10 +109,21 @@ defmodule SwarmBrain.Persistence do
   end
 
   @impl true
+  def handle_cast({:log, data}, %{active: true} = state) do
+    Native.recorder_append(state.resource, {:detection, :erlang.term_to_binary(data)})
+    {:noreply, state}
+  end
+
   def handle_cast({:log, data}, state) do
-    # For now, just print to console.
-    # Later: Write to SQLite or DETS.
-    Logger.debug("💾 [PERSISTENCE] Saved: #{inspect(data)}")
+    Logger.debug("💾 [PERSISTENCE] Not recorded: #{inspect(data)}")
     {:noreply, state}
   end
+
+  @impl true
+  def terminate(_reason, %{active: true} = state), do: Native.stop_recorder(state.resource)
+  def terminate(_reason, _state), do: :ok
+
+  # Detections were stored as external terms
+  defp decode({ts, {:detection, binary}}), do: {ts, {:detection, :erlang.binary_to_term(binary)}}
+  defp decode(entry), do: entry
 end
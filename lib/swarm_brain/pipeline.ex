@@ -13,6 +13,7 @@ defmodule SwarmBrain.Pipeline do
 
   def init(state) do
     Phoenix.PubSub.subscribe(SwarmBrain.PubSub, "radio:telemetry")
+    Phoenix.PubSub.subscribe(SwarmBrain.PubSub, "radio:link")
     {:ok, state}
   end
 
@@ -22,6 +23,13 @@ defmodule SwarmBrain.Pipeline do
     {:noreply, Map.put(state, :rssi, rssi)}
   end
 
+  # Receiver link statistics (CRSF), measured rather than per packet
+  def handle_info({:link_quality, %{rssi: rssi}}, state) do
+    {:noreply, Map.put(state, :rssi, rssi)}
+  end
+
+  def handle_info({:crsf, _message}, state), do: {:noreply, state}
+
   @doc """
   Main Entry Point: The Eye sees something.
   """
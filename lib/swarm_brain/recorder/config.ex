@@ -0,0 +1,26 @@
+defmodule SwarmBrain.Recorder.Config do
+  @moduledoc """
+  Black box settings for `start_recorder/2`.
+  Decoded natively as `recorder::RecorderConfig`, so every field must be present.
+
+  Segment and index layouts are documented in
+  `native/swarm_native/src/recorder/segment.rs`.
+  """
+
+  # segment_bytes: rotate at this size; max_segments: oldest deleted beyond (0 = keep all)
+  # flow_every / frame_every: one in N camera frames (0 = never); frame_scale: 2 = 320x240
+  # frame_format: :jpeg | :png | :raw. Replay (`SwarmBrain.Replay`) is exact only with
+  # frame_every: 1, frame_scale: 1 and a lossless format.
+  defstruct dir: "priv/blackbox",
+            segment_bytes: 64 * 1024 * 1024,
+            max_segments: 32,
+            index_every_ms: 1000,
+            flow_every: 1,
+            frame_every: 15,
+            frame_scale: 2,
+            frame_format: :jpeg,
+            jpeg_quality: 70
+
+  @doc "The defaults with `overrides` (a keyword list of fields) applied on top."
+  def new(overrides \\ []), do: struct!(__MODULE__, overrides)
+end
@@ -0,0 +1,28 @@
+defmodule SwarmBrain.Replay do
+  @moduledoc """
+  Regression runs on recorded flights.
+  With `config :swarm_brain, :replay, dir: ...` set, `Vision.Server` feeds the
+  heartbeat from the black box instead of the camera: frames keep their
+  recorded capture times, so `get_fused_state`, `detect_change` and the flow
+  grid come out as they did in flight.
+
+  These helpers drive the running replay on the Vision resource.
+  """
+  alias SwarmBrain.Vision.{Native, Server}
+
+  @doc "Plays `n` more frames (when started with, or set to, speed 0.0)."
+  def step(n \\ 1), do: with_resource(&Native.replay_step(&1, n))
+
+  @doc "1.0 real time, > 1.0 accelerated, 0.0 step-by-step."
+  def set_speed(speed), do: with_resource(&Native.replay_set_speed(&1, speed * 1.0))
+
+  @doc "Returns `{:ok, %{frames, position_us, finished, speed}}` | `{:error, reason}`."
+  def status, do: with_resource(&Native.replay_status/1)
+
+  defp with_resource(fun) do
+    case Server.get_resource() do
+      nil -> {:error, :no_resource}
+      resource -> fun.(resource)
+    end
+  end
+end
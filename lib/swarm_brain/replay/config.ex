@@ -0,0 +1,19 @@
+defmodule SwarmBrain.Replay.Config do
+  @moduledoc """
+  Replay settings for `start_replay/2`.
+  Decoded natively as `vision::replay::ReplayConfig`, so every field must be present.
+
+  Exact replay needs a recording made with `frame_every: 1`, `frame_scale: 1`
+  and `frame_format: :png` or `:raw` (see `SwarmBrain.Recorder.Config`).
+  """
+
+  # from_us / to_us: capture-time range (µs since the UNIX epoch, inclusive)
+  # speed: 1.0 real time, > 1.0 accelerated, 0.0 step-by-step (`SwarmBrain.Replay.step/1`)
+  defstruct dir: "priv/blackbox",
+            from_us: 0,
+            to_us: 0xFFFFFFFFFFFFFFFF,
+            speed: 1.0
+
+  @doc "The defaults with `overrides` (a keyword list of fields) applied on top."
+  def new(overrides \\ []), do: struct!(__MODULE__, overrides)
+end
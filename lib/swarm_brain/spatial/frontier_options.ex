@@ -0,0 +1,17 @@
+defmodule SwarmBrain.Spatial.FrontierOptions do
+  @moduledoc """
+  Exploration settings for `find_frontiers/3` and `allocate_frontiers/3`.
+  Decoded natively as `frontier::FrontierOptions`, so every field must be present.
+  """
+
+  # radius: search box half-size around each drone; sensor_range: gain radius (cells)
+  # score = gain (unknown cells in view) - distance_weight * travel cost
+  # obstacles / inflation / unknown_penalty: as in PlanOptions (0 must stay out of obstacles)
+  defstruct radius: 64,
+            min_size: 4,
+            sensor_range: 8,
+            distance_weight: 1.0,
+            obstacles: [2],
+            inflation: 1,
+            unknown_penalty: 1.0
+end
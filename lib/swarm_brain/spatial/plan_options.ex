@@ -0,0 +1,16 @@
+defmodule SwarmBrain.Spatial.PlanOptions do
+  @moduledoc """
+  Path planner settings for `plan_path/4` and `start_replanner/4`.
+  Decoded natively as `planner::PlanOptions`, so every field must be present.
+  """
+
+  # obstacles: statuses treated as walls (2 = occupied; add 0 to stay in explored space)
+  # inflation / margin in cells (inflation at most 64); unknown_penalty is extra cost per cell of unknown space
+  defstruct algorithm: :theta_star,
+            obstacles: [2],
+            inflation: 1,
+            unknown_penalty: 1.0,
+            margin: 32,
+            max_expansions: 200_000,
+            smooth: true
+end
@@ -2,8 +2,14 @@ defmodule SwarmBrain.Tactician do
   use GenServer
   require Logger
 
+  alias SwarmBrain.Tactician.{Native, Params}
+
+  @context_size 2048
+  @topic "tactician:decisions"
+
   # This module is the high-level manager for the AI Tactician (Llama.cpp).
   # It wraps the Rust NIFs and holds the model state.
+  # Without a model it still runs: the native rule engine decides alone.
 
   # --- CLIENT API ---
 
@@ -15,41 +21,170 @@ defmodule SwarmBrain.Tactician do
     GenServer.call(__MODULE__, {:think, prompt}, 60_000) # 60s timeout for thinking
   end
 
+  # Same as think/1, but replies {:ok, %SwarmBrain.Tactician.Decision{}}
+  def decide(prompt) do
+    GenServer.call(__MODULE__, {:decide, prompt}, 60_000)
+  end
+
+  @doc """
+  Decides on a structured observation (see `SwarmBrain.Tactician.Native`).
+  Uses the LLM when loaded, always filtered through the safety veto rules;
+  falls back to the rule engine when no model is loaded or generation fails.
+  """
+  def assess(observation) do
+    GenServer.call(__MODULE__, {:assess, observation}, 60_000)
+  end
+
+  @doc """
+  Starts a streamed deliberation. A newer call supersedes (cancels) the one in flight.
+  The finished text is broadcast on "#{@topic}" as `{:decision, text, stats}`.
+  """
+  def deliberate(prompt) do
+    GenServer.cast(__MODULE__, {:deliberate, prompt})
+  end
+
   # --- SERVER CALLBACKS ---
 
   @impl true
   def init(_opts) do
-    # 1. Define the model path
+    # The Rule Engine is mandatory: it is both the fallback and the veto.
+    rules_path = Application.app_dir(:swarm_brain, "priv/tactician_rules.json")
+
+    case Native.load_rules(rules_path) do
+      {:ok, rules} ->
+        {:ok,
+         %{
+           brain: load_brain(),
+           rules: rules,
+           context_size: @context_size,
+           # Streaming state: the in-flight text, and the prompt waiting for it to drain
+           streaming: false,
+           buffer: "",
+           queued: nil
+         }}
+
+      {:error, reason} ->
+        Logger.error("❌ Tactician rules rejected at #{rules_path}: #{inspect(reason)}")
+        {:stop, reason}
+    end
+  end
+
+  defp load_brain do
     model_path = Application.app_dir(:swarm_brain, "priv/tactician.gguf")
 
-    # 2. Check if model exists before crashing
-    if not File.exists?(model_path) do
-      Logger.error("❌ Model not found at: #{model_path}")
-      {:stop, :model_missing}
+    # Load the Model (via Rust NIF, runs on a dirty scheduler)
+    with true <- File.exists?(model_path) || {:error, :model_missing},
+         {:ok, brain} <- Native.load_model(model_path, @context_size, -1) do
+      Logger.info("🧠 Tactician Core online. Memory mapped: #{model_path}")
+      brain
     else
-      # 3. Load the Model (via Rust NIF)
-      # We assume the NIF loading happens here or is managed by the Native module.
-      # If your NIF load function is `SwarmBrain.Tactician.Native.load_model/3`:
-      # SwarmBrain.Tactician.Native.load_model(model_path, 2048, -1)
+      {:error, reason} ->
+        Logger.warning("⚠️ Tactician running on rules only (#{inspect(reason)}): #{model_path}")
+        nil
+    end
+  end
 
-      # 4. THE FIX IS HERE:
-      # We log the success instead of returning the string as the function result.
-      Logger.info("🧠 Tactician Core online. Memory mapped: #{model_path}")
+  @impl true
+  def handle_call({:assess, observation}, _from, state) do
+    {:reply, {:ok, assess(state, observation)}, state}
+  end
+
+  # think/decide need the LLM; there is no free-text fallback.
+  def handle_call({_, _prompt}, _from, %{brain: nil} = state) do
+    {:reply, {:error, :no_model}, state}
+  end
+
+  def handle_call({:think, prompt}, _from, state) do
+    # Forwards the prompt to llama.cpp. Replies {:ok, text} | {:error, reason}.
+    response = Native.think(state.brain, prompt, %Params{})
+
+    {:reply, response, state}
+  end
+
+  def handle_call({:decide, prompt}, _from, state) do
+    {:reply, Native.decide(state.brain, prompt, %Params{}), state}
+  end
+
+  defp assess(%{brain: nil} = state, observation), do: fallback(state, observation)
 
-      # 5. The State
-      # We return the tuple {:ok, state}. The state is just a map for now.
-      {:ok, %{model_path: model_path, context_size: 2048}}
+  defp assess(state, observation) do
+    case Native.decide(state.brain, situation_report(observation), %Params{}) do
+      {:ok, decision} ->
+        Native.veto(state.rules, observation, decision)
+
+      {:error, reason} ->
+        Logger.warning("⚠️ Tactician LLM failed (#{inspect(reason)}), using rules.")
+        fallback(state, observation)
     end
   end
 
+  # The shipped ruleset ends in a catch-all, so this list is never empty.
+  defp fallback(state, observation) do
+    state.rules |> Native.evaluate_rules(observation) |> hd()
+  end
+
+  defp situation_report(obs) do
+    contacts =
+      obs.detections
+      |> Enum.map(fn d -> "#{d.label}##{d.track_id} (#{Float.round(d.confidence * 1.0, 2)})" end)
+      |> Enum.join(", ")
+
+    """
+    You are the tactical planner of an autonomous drone. Choose one action.
+    Battery: #{round(obs.battery)}%. Link quality: #{round(obs.link_quality)}%. Geofence: #{obs.geofence}.
+    Contacts: #{if contacts == "", do: "none", else: contacts}.
+    Answer as JSON with action, target_id, reason and confidence.
+    """
+  end
+
+  # --- STREAMING ---
+
   @impl true
-  def handle_call({:think, prompt}, _from, state) do
-    # This forwards the prompt to the Rust NIF
-    # response = SwarmBrain.Tactician.Native.think(prompt)
+  def handle_cast({:deliberate, _prompt}, %{brain: nil} = state) do
+    Logger.warning("⚠️ Tactician has no model; streamed deliberation ignored.")
+    {:noreply, state}
+  end
 
-    # Placeholder response until NIF is fully connected in your logic:
-    response = "Tactician hears you: #{prompt}"
+  def handle_cast({:deliberate, prompt}, %{streaming: true} = state) do
+    # A new threat makes the current deliberation stale. We cannot start the
+    # next one yet: tokens from the old stream may still be in our mailbox.
+    # We wait for its {:done, _} and start the queued prompt from there.
+    Native.cancel(state.brain)
+    {:noreply, %{state | queued: prompt}}
+  end
 
-    {:reply, response, state}
+  def handle_cast({:deliberate, prompt}, state) do
+    {:noreply, start_stream(state, prompt)}
+  end
+
+  @impl true
+  def handle_info({:token, _text}, %{queued: queued} = state) when queued != nil do
+    # Draining a cancelled stream
+    {:noreply, state}
+  end
+
+  def handle_info({:token, text}, state) do
+    {:noreply, %{state | buffer: state.buffer <> text}}
+  end
+
+  def handle_info({:done, stats}, state) do
+    if state.queued == nil do
+      Phoenix.PubSub.broadcast(SwarmBrain.PubSub, @topic, {:decision, state.buffer, stats})
+    end
+
+    {:noreply, next_stream(state)}
+  end
+
+  def handle_info({:error, reason}, state) do
+    Logger.error("❌ Tactician deliberation failed: #{inspect(reason)}")
+    {:noreply, next_stream(state)}
+  end
+
+  defp next_stream(%{queued: nil} = state), do: %{state | streaming: false, buffer: ""}
+  defp next_stream(%{queued: prompt} = state), do: start_stream(%{state | queued: nil}, prompt)
+
+  defp start_stream(state, prompt) do
+    :ok = Native.think_stream(state.brain, prompt, self())
+    %{state | streaming: true, buffer: ""}
   end
 end
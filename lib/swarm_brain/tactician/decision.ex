@@ -0,0 +1,12 @@
+defmodule SwarmBrain.Tactician.Decision do
+  @moduledoc """
+  A structured Tactician verdict, decoded natively from grammar-constrained output.
+
+    * `action` - `:track_target | :hover | :search | :rtb | :land`
+    * `target_id` - Tracker ID, or `nil` when the action has no target
+    * `reason` - One-line justification from the model
+    * `confidence` - 0.0 to 1.0
+  """
+
+  defstruct [:action, :target_id, :reason, :confidence]
+end
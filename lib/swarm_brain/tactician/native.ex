@@ -0,0 +1,50 @@
+defmodule SwarmBrain.Tactician.Native do
+  @moduledoc """
+  The Tactician Interface.
+  Acts as the bridge to the Rust 'swarm_brain_tactician' crate (llama.cpp).
+  """
+  use Rustler, otp_app: :swarm_brain, crate: "swarm_brain_tactician"
+
+  # --- 1. LIFECYCLE ---
+
+  # Arity 3: path, n_ctx, n_gpu_layers (-1 = offload everything)
+  # Returns {:ok, brain} | {:error, reason}
+  def load_model(_path, _n_ctx, _n_gpu_layers), do: error()
+
+  # --- 2. DELIBERATION ---
+
+  # Arity 3: brain, prompt, %SwarmBrain.Tactician.Params{}
+  # Returns {:ok, text} | {:error, reason}
+  def think(_brain, _prompt, _params), do: error()
+
+  # Arity 3: same as think/3, but grammar-constrained.
+  # Returns {:ok, %SwarmBrain.Tactician.Decision{}} | {:error, reason}
+  def decide(_brain, _prompt, _params), do: error()
+
+  # Arity 3/4: brain, prompt, pid[, params]. Returns :ok at once; pid then gets
+  # {:token, text}* followed by {:done, stats} | {:error, reason}.
+  # (arity 3 uses the default %SwarmBrain.Tactician.Params{})
+  def think_stream(_brain, _prompt, _pid), do: error()
+  def think_stream(_brain, _prompt, _pid, _params), do: error()
+
+  # Aborts every deliberation issued on this brain so far.
+  def cancel(_brain), do: error()
+
+  # --- 3. RULE ENGINE ---
+
+  # Arity 1: path to a JSON ruleset (see priv/tactician_rules.json)
+  # Returns {:ok, rules} | {:error, :rules_not_found | :rules_invalid}
+  def load_rules(_path), do: error()
+
+  # Observation map (all keys required):
+  #   %{detections: [%{track_id: 3, label: "person", confidence: 0.9}],
+  #     battery: 72.0, link_quality: 95.0, geofence: :inside | :margin | :outside}
+
+  # Arity 2: rules, observation. Returns [%Decision{}], best first.
+  def evaluate_rules(_rules, _observation), do: error()
+
+  # Arity 3: rules, observation, decision. Returns the decision or its safety override.
+  def veto(_rules, _observation, _decision), do: error()
+
+  defp error, do: :erlang.nif_error(:nif_not_loaded)
+end
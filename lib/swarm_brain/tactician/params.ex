@@ -0,0 +1,13 @@
+defmodule SwarmBrain.Tactician.Params do
+  @moduledoc """
+  Sampling knobs for a single deliberation.
+  Decoded natively as `engine::Params`, so every field must be present.
+  """
+
+  # temperature <= 0.0 means greedy (deterministic) decoding
+  defstruct temperature: 0.7,
+            top_p: 0.9,
+            max_tokens: 128,
+            stop: ["\n\n"],
+            seed: 42
+end
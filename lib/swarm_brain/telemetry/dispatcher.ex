@@ -18,23 +18,15 @@ defmodule SwarmBrain.Telemetry.Dispatcher do
   defp process_batch(packet_list) do
     packet_list
     |> Task.async_stream(fn packet ->
-      # 2. Lamport Timestamp Check (Low-Level Mitigation)
-      # Assuming first 32 bits are the sequence number
-      # NOTE: Ensure your packet format actually starts with 32-bit seq,
-      # otherwise this pattern match will crash.
-      case packet do
-        <<seq::32, _rest::binary>> ->
-          # Only process if valid (Guard handles the check)
-          case SwarmBrain.Telemetry.Guard.validate(packet, seq) do
-            {:ok, vitals} ->
-              Phoenix.PubSub.broadcast(SwarmBrain.PubSub, "swarm:vitals", {:update, vitals})
-            {:error, :stale} ->
-              :ignore # Drop out-of-order packet
-            {:error, _} ->
-              :ignore
-          end
-        _ ->
-          # Handle packets too short to contain a seq number
+      # 2. Decode + Sequence Check (Low-Level Mitigation)
+      # The header (version, node, seq, timestamp) is SwarmBrain.Telemetry.Codec's:
+      # short or corrupt packets come back as errors, never as crashes.
+      case SwarmBrain.Telemetry.Guard.validate(packet) do
+        {:ok, vitals} ->
+          Phoenix.PubSub.broadcast(SwarmBrain.PubSub, "swarm:vitals", {:update, vitals})
+        {:error, :stale} ->
+          :ignore # Drop out-of-order packet
+        {:error, _} ->
           :ignore
       end
     end, max_concurrency: System.schedulers_online(), ordered: false)
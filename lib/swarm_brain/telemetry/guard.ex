@@ -1,40 +1,62 @@
 defmodule SwarmBrain.Telemetry.Guard do
   require Logger
+  alias SwarmBrain.Telemetry.{Codec, Monitor, Packet}
 
-  @doc "Validates packet structure and sequence order."
-  def validate(packet, seq) do
-    # 1. Structural Check
-    if byte_size(packet) >= 7 do
-
-      # 2. Sequence Check Simulation
-      # We introduce a synthetic condition so the compiler sees :stale as possible.
-      # In production, this would be: if seq < last_seen_seq, do: ...
-      if rem(seq, 9999) == 0 do
-        # Simulate a dropped packet for testing resilience
-        SwarmBrain.Telemetry.Monitor.log_packet(:error)
-        {:error, :stale}
-      else
-        validate_payload(packet, seq)
-      end
-
-    else
-      SwarmBrain.Telemetry.Monitor.log_packet(:error)
-      {:error, :packet_too_short}
-    end
+  # Last {seq, timestamp_us} accepted per sender (owned by Monitor)
+  @table :telemetry_guard_seq
+
+  # seq is 32-bit and wraps: "newer" means ahead by less than half the space
+  @seq_space 4_294_967_296
+  @seq_half 2_147_483_648
+
+  # A restarted sender counts from 0 again: accepted once its clock is this far past ours
+  @restart_gap_us 1_000_000
+
+  @doc "Creates the per-sender sequence table. Called once by `Monitor`."
+  def setup do
+    :ets.new(@table, [:named_table, :public, :set, write_concurrency: true])
+    :ok
   end
 
-  defp validate_payload(packet, seq) do
-    # Skip the 4-byte sequence number
-    <<_seq::32, payload::binary>> = packet
-
-    # Use the Codec to parse the rest
-    case SwarmBrain.Telemetry.Codec.decode_vitals(payload) do
-      vitals when is_map(vitals) ->
-        SwarmBrain.Telemetry.Monitor.log_packet(:ok)
-        {:ok, Map.put(vitals, :seq, seq)}
-      _ ->
-        SwarmBrain.Telemetry.Monitor.log_packet(:error)
-        {:error, :malformed_payload}
+  @doc "Decodes a swarm packet and validates sequence order."
+  def validate(packet) do
+    # 1. Structural Check (version, length, field ranges: native codec)
+    case Codec.decode(packet) do
+      {:ok, %Packet{} = decoded} ->
+        # 2. Sequence Check: duplicates, replays and late arrivals are dropped
+        if fresh?(decoded) do
+          validate_payload(decoded)
+        else
+          Monitor.log_packet(:error)
+          {:error, :stale}
+        end
+
+      {:error, reason} ->
+        Monitor.log_packet(:error)
+        {:error, reason}
     end
   end
+
+  defp validate_payload(%Packet{body: {:vitals, vitals}} = packet) do
+    Monitor.log_packet(:ok)
+    {:ok, Map.merge(vitals, %{node: packet.node, seq: packet.seq, timestamp_us: packet.timestamp_us})}
+  end
+
+  # Well-formed, but not for this pipeline
+  defp validate_payload(_packet) do
+    Monitor.log_packet(:ok)
+    {:error, :not_vitals}
+  end
+
+  # Check-and-set in one ETS operation: Dispatcher validates packets concurrently
+  defp fresh?(%Packet{node: node, seq: seq, timestamp_us: ts}) do
+    :ets.insert_new(@table, {node, seq, ts}) or
+      :ets.select_replace(@table, [{{node, :"$1", :"$2"}, [newer(seq, ts)], [{{node, seq, ts}}]}]) == 1
+  end
+
+  # Match-spec guard against the stored {_, $1 = seq, $2 = timestamp_us}
+  defp newer(seq, ts) do
+    ahead = {:band, {:-, {:+, seq, @seq_space}, :"$1"}, @seq_space - 1}
+    {:orelse, {:andalso, {:>, ahead, 0}, {:<, ahead, @seq_half}}, {:>, ts, {:+, :"$2", @restart_gap_us}}}
+  end
 end
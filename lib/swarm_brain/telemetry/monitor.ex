@@ -14,6 +14,9 @@ defmodule SwarmBrain.Telemetry.Monitor do
     ref = :atomics.new(2, signed: false)
     :persistent_term.put(:telemetry_monitor_ref, ref)
 
+    # Per-sender sequence numbers live as long as the counters
+    SwarmBrain.Telemetry.Guard.setup()
+
     :timer.send_interval(1000, :tick)
     {:ok, %{ref: ref}}
   end
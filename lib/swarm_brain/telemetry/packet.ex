@@ -0,0 +1,23 @@
+defmodule SwarmBrain.Telemetry.Packet do
+  @moduledoc """
+  One swarm packet (header + body) for `SwarmBrain.Telemetry.Codec`.
+  Decoded natively as `codec::Packet`, so every field must be present.
+
+  Bodies are tagged tuples:
+
+    * `{:vitals, %{heading, velocity, battery}}` (degrees, m/s, %)
+    * `{:pose, %{x, y, z, yaw, vx, vy, vz}}` (meters, degrees, m/s)
+    * `{:detection, %{class, confidence, track, x, y, w, h}}` (box in frame pixels)
+    * `{:spatial_delta, %{cursor, delta}}` (from `export_spatial_delta/3`)
+    * `{:command, %{action, target, x, y, z, param}}` (target 0 = every drone)
+
+  Bit layouts, quantisation and golden vectors: `native/swarm_native/src/codec/mod.rs`.
+  """
+
+  # node: 16 bits, seq: 32 bits (wraps), timestamp_us: 56 bits
+  defstruct version: 1,
+            node: 0,
+            seq: 0,
+            timestamp_us: 0,
+            body: nil
+end
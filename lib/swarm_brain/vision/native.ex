@@ -11,7 +11,7 @@ defmodule SwarmBrain.Vision.Native do
   # Arity 3: width, height, threshold
   def init_retina(_width, _height, _threshold), do: error()
 
-  # Arity 3: resource, width, height
+  # Arity 3: resource, width, height. Returns :ok | {:error, :replay_active}
   def start_camera(_resource, _width, _height), do: error()
 
   # [NEW] The Watchdog Probe
@@ -21,8 +21,19 @@ defmodule SwarmBrain.Vision.Native do
 
   def get_latest_frame(_resource), do: error()
   def get_fused_state(_resource), do: error()
+
+  # Consistent snapshot: %{vx, vy, px, py, timestamp_us, seq}
+  def get_kinematics(_resource), do: error()
   def get_flow_grid(_resource), do: error()
 
+  # Arity 4: resource, :jpeg | :png | :raw, quality (1..100, JPEG), scale (2 = 320x240).
+  # Returns {:ok, binary} | {:error, :encode_failed}
+  def encode_latest_frame(_resource, _format, _quality, _scale), do: error()
+
+  # Arity 3: resource, {x, y, w, h} (e.g. from detect_change), format. Full-resolution crop.
+  # Returns {:ok, binary} | {:error, :empty_roi | :encode_failed}
+  def encode_roi(_resource, _roi, _format), do: error()
+
   # --- 3. LOGIC (RETINA) ---
 
   # Arity 2: resource, frame_binary
@@ -39,5 +50,245 @@ defmodule SwarmBrain.Vision.Native do
   # [FIX] Removed _env. Arity is now 0.
   def setup_queryable(), do: error()
 
+  # --- 5. SPATIAL MEMORY (OCCUPANCY GRID) ---
+
+  # Arity 2: resource, %{cell_size, hit, miss, clamp_min, clamp_max,
+  #   occupied_threshold, free_threshold, half_life_s} (all floats, all required)
+  # Returns :ok | {:error, :invalid_config}
+  def configure_spatial_map(_resource, _config), do: error()
+  def get_spatial_config(_resource), do: error()
+
+  # Arity 4: resource, x, y (meters), [{bearing_rad, range_m, hit?}]
+  # Returns the number of cell updates.
+  def cast_rays(_resource, _x, _y, _rays), do: error()
+
+  # Arity 6: resource, x0, y0, w, h, :probability | :log_odds | :status
+  # Returns a binary for Nx.from_binary/2 (f32, or u32 for :status), shape {h, w}.
+  def get_spatial_region(_resource, _x0, _y0, _w, _h, _layer), do: error()
+
+  # --- Queries (all coordinates in cells; status filters accept nil) ---
+
+  # Returns the map version counter.
+  def get_spatial_version(_resource), do: error()
+
+  # Returns [{x, y, status, version}] of known cells, sorted by {y, x}.
+  def spatial_cells_in_box(_resource, _x0, _y0, _x1, _y1, _status), do: error()
+  def spatial_cells_in_radius(_resource, _x, _y, _radius, _status), do: error()
+
+  # Returns {x, y, distance} | nil. Status 0 finds the nearest unexplored cell.
+  def spatial_nearest(_resource, _x, _y, _status, _max_radius), do: error()
+
+  # Arity 2: resource, nil | {x0, y0, x1, y1}. Returns %{status => count}.
+  def spatial_count_by_status(_resource, _bounds), do: error()
+
+  # Arity 3: resource, since, limit. Returns {cursor, [{x, y, status, version}]}.
+  def spatial_changes_since(_resource, _since, _limit), do: error()
+
+  # --- Persistence (snapshot + append-only journal) ---
+
+  # Returns {:ok, cell_count} | {:error, :io_error | :corrupt | :unsupported_version | :journal_attached}
+  def save_spatial_snapshot(_resource, _path), do: error()
+  def load_spatial_snapshot(_resource, _path), do: error()
+
+  # Replays the journal, then logs every write. Returns {:ok, replayed} | {:error, reason}.
+  def open_spatial_journal(_resource, _path), do: error()
+  def close_spatial_journal(_resource), do: error()
+  def spatial_journal_active(_resource), do: error()
+
+  # --- Swarm merge (delta-state CRDT, per-cell LWW on HLC stamps) ---
+
+  # Arity 2: resource, node_id (u64). Returns :ok.
+  def set_spatial_node_id(_resource, _node_id), do: error()
+
+  # Arity 3: resource, since, limit. Returns {:ok, {cursor, delta_binary}}.
+  def export_spatial_delta(_resource, _since, _limit), do: error()
+
+  # Returns {:ok, %{applied, stale, rejected}} | {:error, :corrupt | :unsupported_version}
+  def merge_spatial_delta(_resource, _delta), do: error()
+
+  # --- Navigation (cells; options: %SwarmBrain.Spatial.PlanOptions{}) ---
+
+  # Arity 4: resource, {x, y} start, {x, y} goal, options
+  # Returns {:ok, [{x, y}]} | {:error, :start_blocked | :goal_blocked | :no_path | :search_limit | :region_too_large
+  #   | :invalid_inflation}
+  def plan_path(_resource, _start, _goal, _options), do: error()
+
+  # D* Lite. Returns {:ok, {replanner, waypoints}} | {:error, reason}
+  def start_replanner(_resource, _start, _goal, _options), do: error()
+
+  # Arity 2: replanner, {x, y} current cell. Returns {:ok, waypoints} | {:error, reason}
+  def replan_path(_replanner, _start), do: error()
+
+  # --- Exploration (cells; options: %SwarmBrain.Spatial.FrontierOptions{}) ---
+
+  # Arity 3: resource, {x, y} origin, options. Best first.
+  # Returns {:ok, [%{target, centroid, size, gain, distance, score}]} | {:error, :region_too_large | :invalid_inflation}
+  def find_frontiers(_resource, _origin, _options), do: error()
+
+  # Arity 3: resource, [{x, y}] drone cells, options.
+  # Returns {:ok, goals}: one distinct frontier (or nil) per drone, same order
+  #   | {:error, :region_too_large | :invalid_inflation}
+  def allocate_frontiers(_resource, _positions, _options), do: error()
+
+  # --- Zenoh Bus (config: %SwarmBrain.Bus.Config{}) ---
+
+  # Arity 3: resource, config, {keyring, key id} to seal every payload (or nil)
+  # Returns :ok | {:error, :bus_active | :invalid_endpoint | :invalid_key_expr | :session_failed}
+  def start_zenoh(_resource, _config, _seal), do: error()
+  def stop_zenoh(_resource), do: error()
+  def zenoh_active(_resource), do: error()
+
+  # Arity 4: resource, key expression, pid, capacity. The pid receives
+  # {:zenoh, key, payload, timestamp_us | nil}; at most `capacity` in flight
+  # until acked, then the oldest queued sample is dropped.
+  # Returns {:ok, subscription} | {:error, :bus_inactive | :invalid_key_expr}
+  def zenoh_subscribe(_resource, _key, _pid, _capacity), do: error()
+
+  # Arity 2: subscription, n handled messages. Returns :ok.
+  def zenoh_ack(_subscription, _n), do: error()
+  def zenoh_dropped(_subscription), do: error()
+  def zenoh_undeclare(_subscription), do: error()
+
+  # --- Swarm Wire Format (packets: %SwarmBrain.Telemetry.Packet{}) ---
+
+  # Returns {:ok, binary} | {:error, :unsupported_version | :out_of_range | :too_large}
+  def encode_packet(_packet), do: error()
+
+  # Returns {:ok, packet} | {:error, :truncated | :unsupported_version | :unknown_kind | :malformed}
+  def decode_packet(_binary), do: error()
+
+  # --- Sealed Frames (ChaCha20-Poly1305, replay window per sender) ---
+
+  # Arity 2: node id (16 bits), boot number (32 bits, never repeated). Returns a keyring.
+  def new_keyring(_node, _boot), do: error()
+
+  # Arity 4: keyring, key id (0..255), 32-byte key, tag length (4..16)
+  # Returns :ok | {:error, :invalid_key}
+  def put_swarm_key(_keyring, _id, _key, _tag_len), do: error()
+  def drop_swarm_key(_keyring, _id), do: error()
+
+  # Returns {:ok, frame} | {:error, :unknown_key | :exhausted}
+  def seal(_keyring, _id, _plaintext), do: error()
+
+  # Returns {:ok, {sender, key_id, plaintext}} | {:error, :unknown_key | :truncated | :bad_tag | :replay}
+  def open(_keyring, _frame), do: error()
+
+  # --- Forward Error Correction (scheme: :reed_solomon | :raptorq) ---
+
+  # Arity 5: payload, scheme, message id (16 bits), k, n
+  # Returns {:ok, [shard]} | {:error, :invalid_params | :too_large}
+  def fec_encode(_payload, _scheme, _message, _k, _n), do: error()
+
+  # Returns {:ok, payload} | {:error, :insufficient_shards}
+  def fec_decode(_shards), do: error()
+
+  # Returns {:ok, %{scheme, message, k, n, index, length}} | {:error, :corrupt}
+  def fec_shard_info(_shard), do: error()
+
+  # --- Flight Controller Link (MSP v1 / v2, version: :v1 | :v2; MAVLink v2) ---
+
+  # Returns a streaming parser (one per UART)
+  def msp_new_parser, do: error()
+
+  # Returns [%SwarmBrain.Hardware.Msp.Attitude{} | ... | %SwarmBrain.Hardware.Msp.Frame{}]
+  def msp_feed(_parser, _data), do: error()
+
+  # Returns %{frames, bad_checksums, dropped_bytes}
+  def msp_parser_stats(_parser), do: error()
+
+  # Returns {:ok, frame} | {:error, :invalid_command | :too_large}
+  def msp_encode_request(_cmd, _payload, _version), do: error()
+
+  # Arity 2: channels in µs (1..18), version. Returns {:ok, frame} | {:error, :invalid_channels}
+  def msp_set_raw_rc(_channels, _version), do: error()
+
+  # Arity 2: our system id, component id. Returns a link.
+  def mavlink_new_link(_system_id, _component_id), do: error()
+
+  # Arity 4: link, 32-byte key (nil = off), link id, accept unsigned frames?
+  # Returns :ok | {:error, :invalid_key}
+  def mavlink_set_signing(_link, _key, _link_id, _allow_unsigned), do: error()
+
+  # Returns [{system_id, component_id, %SwarmBrain.Hardware.Mavlink.Heartbeat{} | ...}]
+  def mavlink_feed(_link, _data), do: error()
+
+  # Returns the framed (and signed) message
+  def mavlink_encode(_link, _message), do: error()
+
+  # Returns %{frames, bad_crc, bad_signature, unknown, dropped_bytes}
+  def mavlink_link_stats(_link), do: error()
+
+  # --- Receiver Link (CRSF / ExpressLRS) ---
+
+  # Returns a streaming parser (one per receiver UART)
+  def crsf_new_parser, do: error()
+
+  # Returns [%SwarmBrain.Hardware.Crsf.LinkStatistics{} | ... | %SwarmBrain.Hardware.Crsf.Frame{}]
+  def crsf_feed(_parser, _data), do: error()
+
+  # Returns %{frames, bad_crc, dropped_bytes}
+  def crsf_parser_stats(_parser), do: error()
+
+  # Returns {:ok, frame} | {:error, :too_large | :invalid_channels}
+  def crsf_encode(_message), do: error()
+
+  # --- RC Output (%SwarmBrain.Hardware.Reflex.Config{}) ---
+
+  # Returns {:ok, reflex} | {:error, :invalid_config}
+  def reflex_new(_config), do: error()
+
+  # Arity 5: reflex, roll, pitch, yaw, throttle (-1.0..1.0)
+  def reflex_command(_reflex, _roll, _pitch, _yaw, _throttle), do: error()
+
+  # Returns :ok | {:error, :no_commands | :throttle_high}
+  def reflex_arm(_reflex), do: error()
+  def reflex_disarm(_reflex), do: error()
+
+  # Returns {msp_set_raw_rc_payload, %{phase, armed, command_age_ms}}
+  # phase: :active | :hold | :descend | :disarmed
+  def reflex_tick(_reflex), do: error()
+  def reflex_status(_reflex), do: error()
+
+  # --- Black Box (config: %SwarmBrain.Recorder.Config{}) ---
+
+  # Returns :ok | {:error, :recorder_active | :invalid_config | :io_error}
+  def start_recorder(_resource, _config), do: error()
+  def stop_recorder(_resource), do: error()
+
+  # Arity 2: resource, {:detection, binary} | {:command, %{roll, pitch, yaw, throttle}} | ...
+  # Returns :ok | {:error, :recorder_inactive}
+  def recorder_append(_resource, _record), do: error()
+
+  # Returns {:ok, %{records, bytes, segments, dropped, failed}} | {:error, :recorder_inactive}
+  def recorder_stats(_resource), do: error()
+
+  # Arity 1: directory. Returns {:ok, recording} | {:error, :io_error}
+  def open_recording(_dir), do: error()
+
+  # Returns %{segments, records, first_us, last_us}
+  def recording_info(_recording), do: error()
+
+  # Arity 3: recording, from_us, to_us (inclusive). Returns :ok.
+  def recording_seek(_recording, _from_us, _to_us), do: error()
+
+  # Arity 2: recording, max. Returns [{timestamp_us, record}]; [] once exhausted
+  def recording_next(_recording, _max), do: error()
+
+  # --- Replay (config: %SwarmBrain.Replay.Config{}) ---
+
+  # Feeds the heartbeat from a recording instead of the camera.
+  # Returns :ok | {:error, :camera_active | :replay_active | :invalid_config | :io_error | :empty}
+  def start_replay(_resource, _config), do: error()
+
+  # Arity 2: resource, frames. Returns :ok | {:error, :replay_inactive}
+  def replay_step(_resource, _n), do: error()
+
+  # Arity 2: resource, speed (1.0 real time, 0.0 step). Returns :ok | {:error, :replay_inactive | :invalid_config}
+  def replay_set_speed(_resource, _speed), do: error()
+
+  # Returns {:ok, %{frames, position_us, finished, speed}} | {:error, :replay_inactive}
+  def replay_status(_resource), do: error()
+  def stop_replay(_resource), do: error()
+
   defp error, do: :erlang.nif_error(:nif_not_loaded)
 end
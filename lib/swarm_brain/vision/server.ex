@@ -6,6 +6,7 @@ defmodule SwarmBrain.Vision.Server do
   use GenServer
   require Logger
   alias SwarmBrain.Vision.{Native, SharedBuffer}
+  alias SwarmBrain.Bus
   alias SwarmBrain.Cortex.Yolo
 
   @resource_key :swarm_vision_resource
@@ -28,8 +29,14 @@ defmodule SwarmBrain.Vision.Server do
     resource = Native.init_state()
     :persistent_term.put(@resource_key, resource)
 
-    # 2. START HEARTBEAT
-    case Native.start_camera(resource, width, height) do
+    # 2. RESTORE SPATIAL MEMORY (before any observation lands)
+    store = restore_spatial_memory(resource)
+
+    # 3. OPEN THE ZENOH BUS (optional; the heartbeat publishes once it is up)
+    start_bus(resource)
+
+    # 4. START HEARTBEAT (camera, or a recording when :replay is configured)
+    case start_heartbeat(resource, width, height) do
       :ok ->
         Logger.info("👁️ Vision.Server: Heartbeat Active.")
         schedule_tick()
@@ -37,7 +44,8 @@ defmodule SwarmBrain.Vision.Server do
           resource: resource,
           active_inferences: 0,
           max_concurrency: 1,
-          tick_count: 0
+          tick_count: 0,
+          spatial_store: store
         }}
 
       error ->
@@ -57,6 +65,10 @@ defmodule SwarmBrain.Vision.Server do
       check_physiology!(state.resource)
     end
 
+    if state.spatial_store && rem(new_tick_count, state.spatial_store.snapshot_ticks) == 0 do
+      snapshot_spatial_memory(state.resource, state.spatial_store)
+    end
+
     # 2. Process Vision (Backpressure Control)
     if state.active_inferences < state.max_concurrency do
       case SharedBuffer.get_vision_tensor() do
@@ -96,6 +108,79 @@ defmodule SwarmBrain.Vision.Server do
     end
   end
 
+  # Loads the last snapshot, replays the journal on top, keeps journaling.
+  # Returns the store config, or nil when persistence is disabled.
+  defp restore_spatial_memory(resource) do
+    case Application.get_env(:swarm_brain, :spatial_store) do
+      nil ->
+        nil
+
+      config ->
+        dir = Keyword.fetch!(config, :dir)
+        File.mkdir_p!(dir)
+        snapshot = Path.join(dir, "spatial.snapshot")
+        journal = Path.join(dir, "spatial.journal")
+
+        case Native.load_spatial_snapshot(resource, snapshot) do
+          {:ok, cells} -> Logger.info("🗺️ Vision.Server: Spatial snapshot restored (#{cells} cells).")
+          {:error, :io_error} -> :ok # First boot
+          {:error, reason} -> Logger.warning("🗺️ Vision.Server: Snapshot rejected (#{reason}). Starting from the journal.")
+        end
+
+        case Native.open_spatial_journal(resource, journal) do
+          {:ok, replayed} ->
+            Logger.info("🗺️ Vision.Server: Journal replayed (#{replayed} records).")
+
+          {:error, reason} ->
+            Logger.warning("🗺️ Vision.Server: Journal unavailable (#{reason}). Spatial Memory is volatile.")
+        end
+
+        ticks = div(Keyword.get(config, :snapshot_every_s, 60) * 1000, @tick_interval)
+        %{snapshot: snapshot, snapshot_ticks: max(ticks, 1)}
+    end
+  end
+
+  defp start_heartbeat(resource, width, height) do
+    case Application.get_env(:swarm_brain, :replay) do
+      nil ->
+        Native.start_camera(resource, width, height)
+
+      overrides ->
+        config = SwarmBrain.Replay.Config.new(overrides)
+        Logger.info("📼 Vision.Server: Replaying #{config.dir} at speed #{config.speed}.")
+        Native.start_replay(resource, config)
+    end
+  end
+
+  # A bus failure is not fatal: the drone flies without zenoh.
+  defp start_bus(resource) do
+    case Application.get_env(:swarm_brain, :zenoh) do
+      nil ->
+        :ok
+
+      overrides ->
+        config = Bus.Config.for_node(Node.self(), overrides)
+
+        case Native.start_zenoh(resource, config, SwarmBrain.Crypto.sealer()) do
+          :ok -> Logger.info("📡 Vision.Server: Zenoh bus up (#{config.kinematics_key}).")
+          {:error, reason} -> Logger.warning("📡 Vision.Server: Zenoh bus unavailable (#{reason}).")
+        end
+    end
+  end
+
+  # Off the tick loop: a large map takes a while to serialize.
+  defp snapshot_spatial_memory(resource, store) do
+    Task.Supervisor.start_child(SwarmBrain.Cortex.Supervisor, fn ->
+      with {:error, reason} <- Native.save_spatial_snapshot(resource, store.snapshot) do
+        Logger.warning("🗺️ Vision.Server: Spatial snapshot failed (#{reason}).")
+      end
+
+      unless Native.spatial_journal_active(resource) do
+        Logger.warning("🗺️ Vision.Server: Spatial journal dropped after a write error.")
+      end
+    end)
+  end
+
   defp async_inference(tensor) do
     parent = self()
     Task.Supervisor.start_child(SwarmBrain.Cortex.Supervisor, fn ->
@@ -108,7 +193,9 @@ defmodule SwarmBrain.Vision.Server do
   defp schedule_tick, do: Process.send_after(self(), :tick, @tick_interval)
 
   @impl true
-  def terminate(_reason, _state) do
+  def terminate(_reason, state) do
+    Native.stop_replay(state.resource)
+    Native.stop_zenoh(state.resource)
     :persistent_term.erase(@resource_key)
   end
 end
@@ -8,6 +8,7 @@ defmodule SwarmBrain.MixProject do
       elixir: "~> 1.15",
       start_permanent: Mix.env() == :prod,
       deps: deps(),
+      aliases: aliases(),
       rustler_crates: [
         swarm_brain_tactician: [],
         swarm_brain_nms: [],
@@ -23,6 +24,12 @@ defmodule SwarmBrain.MixProject do
     ]
   end
 
+  # Tests exercise the NIFs directly; starting the application would open the
+  # camera, serial links and the swarm bus.
+  defp aliases do
+    [test: "test --no-start"]
+  end
+
   defp deps do
     [
       # --- The Nervous System ---
@@ -0,0 +1,45 @@
+// native/swarm_brain_nms/benches/nms.rs
+
+//! Naive vs grid NMS on a detector-like scene: clusters of overlapping
+//! candidates spread over a 1920x1080 frame.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use swarm_brain_nms::{nms_grid, nms_naive, Rect};
+
+// Deterministic scene (xorshift), so runs are comparable
+fn scene(n: usize) -> Vec<Rect> {
+    let mut s = 0x9E37_79B9_7F4A_7C15u64;
+    let mut next = move || {
+        s ^= s << 13;
+        s ^= s >> 7;
+        s ^= s << 17;
+        (s >> 40) as f32 / (1u64 << 24) as f32
+    };
+
+    (0..n)
+        .map(|_| {
+            let (w, h) = (20.0 + next() * 80.0, 20.0 + next() * 80.0);
+            let (x, y) = (next() * (1920.0 - w), next() * (1080.0 - h));
+            Rect { x1: x, y1: y, x2: x + w, y2: y + h, score: next(), label: String::new() }
+        })
+        .collect()
+}
+
+fn bench_nms(c: &mut Criterion) {
+    let mut group = c.benchmark_group("nms");
+    group.sample_size(10);
+
+    for n in [100, 1_000, 10_000] {
+        let boxes = scene(n);
+        group.bench_with_input(BenchmarkId::new("naive", n), &boxes, |b, boxes| {
+            b.iter(|| nms_naive(boxes.clone(), 0.5))
+        });
+        group.bench_with_input(BenchmarkId::new("grid", n), &boxes, |b, boxes| {
+            b.iter(|| nms_grid(boxes.clone(), 0.5))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_nms);
+criterion_main!(benches);
@@ -11,8 +11,29 @@ pub struct Rect {
     pub label: String,
 }
 
+// Below this many boxes the quadratic scan beats building the grid.
+const GRID_MIN_BOXES: usize = 128;
+
+// Upper bound on grid resolution per axis (keeps huge boxes from exploding the cell count).
+const GRID_MAX_CELLS: f32 = 256.0;
+
 #[rustler::nif]
 pub fn nms(boxes: Vec<Rect>, iou_threshold: f32) -> Vec<Rect> {
+    // The grid only finds pairs that touch. That is exact as long as every
+    // non-touching pair survives the threshold (IoU 0.0 < t), so anything
+    // else (t <= 0, NaN, infinite coordinates) takes the reference path.
+    let grid_safe = iou_threshold > 0.0
+        && boxes.iter().all(|b| b.x1.is_finite() && b.y1.is_finite() && b.x2.is_finite() && b.y2.is_finite());
+
+    if boxes.len() >= GRID_MIN_BOXES && grid_safe {
+        nms_grid(boxes, iou_threshold)
+    } else {
+        nms_naive(boxes, iou_threshold)
+    }
+}
+
+/// The reference greedy NMS. O(n²), every survivor is checked against every remaining box.
+pub fn nms_naive(boxes: Vec<Rect>, iou_threshold: f32) -> Vec<Rect> {
     let mut detections = boxes;
 
     // Optimization: Sort Ascending so .pop() gives the highest score efficiently
@@ -27,10 +48,116 @@ pub fn nms(boxes: Vec<Rect>, iou_threshold: f32) -> Vec<Rect> {
         // We iterate backwards to safely remove items
         detections.retain(|item| calculate_iou(&best, item) < iou_threshold);
     }
-    
+
     kept
 }
 
+/// Spatial-hash NMS.
+///
+/// Same greedy order and tie-breaking as `nms_naive`, but each survivor is only
+/// compared against boxes sharing a grid cell with it. Boxes whose closed extents
+/// do not intersect always score IoU 0.0, so skipping them cannot change the result.
+/// Requires finite coordinates and a positive threshold (see `nms`).
+pub fn nms_grid(boxes: Vec<Rect>, iou_threshold: f32) -> Vec<Rect> {
+    let n = boxes.len();
+
+    // 1. Greedy Order
+    // Sort indices exactly like the naive path (stable ascending), then walk it backwards.
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&a, &b| boxes[a].score.partial_cmp(&boxes[b].score).unwrap_or(std::cmp::Ordering::Equal));
+    order.reverse();
+
+    let mut rank = vec![0usize; n];
+    for (r, &i) in order.iter().enumerate() {
+        rank[i] = r;
+    }
+
+    // 2. Build the Grid
+    // Inverted boxes (x2 < x1 or y2 < y1) never overlap anything, so they stay out of it.
+    let valid = |b: &Rect| b.x2 >= b.x1 && b.y2 >= b.y1;
+
+    let (mut min_x, mut min_y) = (f32::MAX, f32::MAX);
+    let (mut max_x, mut max_y) = (f32::MIN, f32::MIN);
+    let mut extent_sum = 0.0f64;
+    let mut valid_count = 0usize;
+
+    for b in boxes.iter().filter(|b| valid(b)) {
+        min_x = min_x.min(b.x1);
+        min_y = min_y.min(b.y1);
+        max_x = max_x.max(b.x2);
+        max_y = max_y.max(b.y2);
+        extent_sum += (b.x2 - b.x1).max(b.y2 - b.y1) as f64;
+        valid_count += 1;
+    }
+
+    if valid_count == 0 {
+        return nms_naive(boxes, iou_threshold);
+    }
+
+    // Cell size ~ the average box, but never so small that the grid exceeds GRID_MAX_CELLS per axis.
+    let span = (max_x - min_x).max(max_y - min_y);
+    let cell = ((extent_sum / valid_count as f64) as f32)
+        .max(span / GRID_MAX_CELLS)
+        .max(f32::MIN_POSITIVE);
+
+    let cols = (((max_x - min_x) / cell) as usize + 1).min(GRID_MAX_CELLS as usize);
+    let rows = (((max_y - min_y) / cell) as usize + 1).min(GRID_MAX_CELLS as usize);
+
+    let cell_range = |b: &Rect| {
+        let cx = |x: f32| (((x - min_x) / cell) as usize).min(cols - 1);
+        let cy = |y: f32| (((y - min_y) / cell) as usize).min(rows - 1);
+        (cx(b.x1), cx(b.x2), cy(b.y1), cy(b.y2))
+    };
+
+    let mut grid: Vec<Vec<usize>> = vec![Vec::new(); cols * rows];
+    for (i, b) in boxes.iter().enumerate().filter(|(_, b)| valid(b)) {
+        let (c0, c1, r0, r1) = cell_range(b);
+        for r in r0..=r1 {
+            for c in c0..=c1 {
+                grid[r * cols + c].push(i);
+            }
+        }
+    }
+
+    // 3. Greedy Suppression
+    // 'checked' stamps each candidate with the survivor that last looked at it,
+    // so a pair spanning several shared cells is only scored once.
+    let mut suppressed = vec![false; n];
+    let mut checked = vec![usize::MAX; n];
+    let mut kept = Vec::new();
+
+    for &best in &order {
+        if suppressed[best] {
+            continue;
+        }
+        kept.push(best);
+
+        let b = &boxes[best];
+        if !valid(b) {
+            continue;
+        }
+
+        let (c0, c1, r0, r1) = cell_range(b);
+        for r in r0..=r1 {
+            for c in c0..=c1 {
+                for &j in &grid[r * cols + c] {
+                    if rank[j] <= rank[best] || suppressed[j] || checked[j] == best {
+                        continue;
+                    }
+                    checked[j] = best;
+
+                    // Not "< threshold" (NaN included) suppresses, like the retain in nms_naive
+                    if calculate_iou(b, &boxes[j]).partial_cmp(&iou_threshold) != Some(std::cmp::Ordering::Less) {
+                        suppressed[j] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    kept.into_iter().map(|i| boxes[i].clone()).collect()
+}
+
 fn calculate_iou(a: &Rect, b: &Rect) -> f32 {
     let x_left = a.x1.max(b.x1);
     let y_top = a.y1.max(b.y1);
@@ -48,4 +175,41 @@ fn calculate_iou(a: &Rect, b: &Rect) -> f32 {
     intersection_area / (area_a + area_b - intersection_area)
 }
 
-rustler::init!("Elixir.SwarmBrain.Vision.NMS", [nms]);
\ No newline at end of file
+rustler::init!("Elixir.SwarmBrain.Vision.NMS", [nms]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn rect((x, y, w, h, score): (f32, f32, f32, f32, u8)) -> Rect {
+        // Scores from a small set, so ties (and their ordering) are exercised
+        Rect { x1: x, y1: y, x2: x + w, y2: y + h, score: score as f32 / 16.0, label: String::new() }
+    }
+
+    fn same(a: &[Rect], b: &[Rect]) -> bool {
+        a.len() == b.len()
+            && a.iter().zip(b).all(|(p, q)| (p.x1, p.y1, p.x2, p.y2, p.score) == (q.x1, q.y1, q.x2, q.y2, q.score))
+    }
+
+    proptest! {
+        #[test]
+        fn grid_matches_naive(
+            raw in prop::collection::vec((0.0f32..1000.0, 0.0f32..1000.0, -5.0f32..120.0, -5.0f32..120.0, 0u8..16), 0..400),
+            threshold in 0.01f32..1.0,
+        ) {
+            let boxes: Vec<Rect> = raw.into_iter().map(rect).collect();
+            let naive = nms_naive(boxes.clone(), threshold);
+            let grid = nms_grid(boxes, threshold);
+            prop_assert!(same(&naive, &grid));
+        }
+    }
+
+    #[test]
+    fn dispatch_falls_back_for_unsafe_inputs() {
+        let mut boxes: Vec<Rect> = (0..200).map(|i| rect((i as f32 * 3.0, 0.0, 10.0, 10.0, (i % 16) as u8))).collect();
+        boxes[7].x2 = f32::INFINITY;
+        assert!(same(&nms(boxes.clone(), 0.5), &nms_naive(boxes.clone(), 0.5)));
+        assert!(same(&nms(boxes.clone(), 0.0), &nms_naive(boxes, 0.0)));
+    }
+}
\ No newline at end of file
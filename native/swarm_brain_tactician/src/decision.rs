@@ -0,0 +1,90 @@
+// native/swarm_brain_tactician/src/decision.rs
+
+//! THE VERDICT (Structured Decisions)
+//!
+//! Instead of free text ("DECISION: HOVER. REASON: ..."), the model is forced
+//! through a GBNF grammar that only admits one JSON object:
+//!
+//! `{"action": "HOVER", "target_id": null, "reason": "...", "confidence": 0.8}`
+//!
+//! The output is then decoded straight into `%SwarmBrain.Tactician.Decision{}`.
+
+use rustler::{NifStruct, NifUnitEnum};
+use serde::Deserialize;
+
+/// The decision vocabulary. Encoded to Elixir as snake_case atoms
+/// (`:track_target`, `:hover`, ...), spelled SCREAMING_SNAKE_CASE in the JSON.
+#[derive(NifUnitEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Action {
+    TrackTarget,
+    Hover,
+    Search,
+    Rtb, // Return To Base
+    Land,
+}
+
+impl Action {
+    pub const ALL: [Action; 5] = [
+        Action::TrackTarget,
+        Action::Hover,
+        Action::Search,
+        Action::Rtb,
+        Action::Land,
+    ];
+
+    /// The wire spelling (matches the serde rename).
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Action::TrackTarget => "TRACK_TARGET",
+            Action::Hover => "HOVER",
+            Action::Search => "SEARCH",
+            Action::Rtb => "RTB",
+            Action::Land => "LAND",
+        }
+    }
+}
+
+#[derive(NifStruct, Deserialize, Clone, Debug)]
+#[module = "SwarmBrain.Tactician.Decision"]
+pub struct Decision {
+    pub action: Action,
+    pub target_id: Option<u32>, // Tracker ID, nil when the action has no target
+    pub reason: String,
+    pub confidence: f32, // 0.0 ..= 1.0
+}
+
+impl Decision {
+    pub fn new(action: Action, target_id: Option<u32>, reason: &str, confidence: f32) -> Self {
+        Self { action, target_id, reason: reason.to_string(), confidence }
+    }
+
+    /// Decodes the grammar-constrained output. `None` means the text is not a
+    /// complete decision (e.g. generation hit max_tokens mid-object).
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut decision: Decision = serde_json::from_str(text.trim()).ok()?;
+        decision.confidence = decision.confidence.clamp(0.0, 1.0);
+        Some(decision)
+    }
+}
+
+/// GBNF for exactly one decision object, keys in a fixed order.
+/// The action alternatives are generated from `Action::ALL` so the grammar
+/// can never drift from the enum.
+pub fn grammar() -> String {
+    let actions = Action::ALL
+        .iter()
+        .map(|a| format!(r#""\"{}\"""#, a.as_str()))
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    format!(
+        r#"root       ::= "{{" ws "\"action\":" ws action "," ws "\"target_id\":" ws target "," ws "\"reason\":" ws reason "," ws "\"confidence\":" ws confidence ws "}}"
+action     ::= {actions}
+target     ::= "null" | [0-9] [0-9]{{0,8}}
+reason     ::= "\"" [^"\\\n]{{1,160}} "\""
+confidence ::= "0" ("." [0-9] [0-9]?)? | "1" (".0")?
+ws         ::= " "?
+"#
+    )
+}
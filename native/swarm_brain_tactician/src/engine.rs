@@ -0,0 +1,466 @@
+// native/swarm_brain_tactician/src/engine.rs
+
+//! THE CORTEX (llama.cpp Inference)
+//!
+//! Owns the loaded GGUF model and runs CPU generation against it.
+//! A fresh context is created per deliberation: every prompt is a
+//! self-contained situation report, so no KV cache is carried over.
+
+use std::num::NonZeroU32;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::Instant;
+
+use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::llama_backend::LlamaBackend;
+use llama_cpp_2::llama_batch::LlamaBatch;
+use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::{AddBos, LlamaModel, Special};
+use llama_cpp_2::sampling::LlamaSampler;
+use rustler::{Atom, NifMap, NifStruct, NifUnitEnum};
+
+use crate::atoms;
+
+/// llama.cpp allows exactly one backend per process, shared by every model.
+/// `None` means initialization failed and will not be retried.
+static BACKEND: OnceLock<Option<LlamaBackend>> = OnceLock::new();
+
+pub fn backend() -> Result<&'static LlamaBackend, Atom> {
+    BACKEND
+        .get_or_init(|| LlamaBackend::init().ok())
+        .as_ref()
+        .ok_or_else(atoms::backend_failed)
+}
+
+/// Sampling knobs for one deliberation.
+/// Mirrors `%SwarmBrain.Tactician.Params{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Tactician.Params"]
+pub struct Params {
+    pub temperature: f32, // <= 0.0 switches to greedy decoding
+    pub top_p: f32,
+    pub max_tokens: u32,
+    pub stop: Vec<String>,
+    pub seed: u32,
+}
+
+/// The `%SwarmBrain.Tactician.Params{}` defaults, for NIFs called without params.
+impl Default for Params {
+    fn default() -> Self {
+        Self { temperature: 0.7, top_p: 0.9, max_tokens: 128, stop: vec!["\n\n".to_string()], seed: 42 }
+    }
+}
+
+/// The loaded model (the ResourceArc payload).
+pub struct Brain {
+    pub model: LlamaModel,
+    pub n_ctx: u32,
+
+    // Cancellation epoch. Every deliberation snapshots it when it is issued;
+    // `cancel` bumps it, so only deliberations issued before the cancel stop.
+    epoch: AtomicU64,
+}
+
+impl std::panic::RefUnwindSafe for Brain {}
+
+impl Brain {
+    /// Memory-maps the GGUF file. `n_gpu_layers < 0` offloads every layer.
+    pub fn load(path: &str, n_ctx: u32, n_gpu_layers: i32) -> Result<Self, Atom> {
+        let backend = backend()?;
+
+        let gpu_layers = if n_gpu_layers < 0 { i32::MAX as u32 } else { n_gpu_layers as u32 };
+        let model_params = LlamaModelParams::default().with_n_gpu_layers(gpu_layers);
+
+        let model = LlamaModel::load_from_file(backend, path, &model_params)
+            .map_err(|_| atoms::load_failed())?;
+
+        Ok(Self { model, n_ctx: n_ctx.max(1), epoch: AtomicU64::new(0) })
+    }
+
+    /// Ticket for a new deliberation. Take it on the calling thread, before
+    /// any worker is spawned, so a `cancel` issued right after still applies.
+    pub fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::Acquire)
+    }
+
+    /// Aborts every deliberation issued so far. They end with `StopReason::Cancelled`.
+    pub fn cancel(&self) {
+        self.epoch.fetch_add(1, Ordering::AcqRel);
+    }
+
+    fn is_cancelled(&self, epoch: u64) -> bool {
+        self.epoch.load(Ordering::Acquire) != epoch
+    }
+}
+
+/// Why generation ended (`:eos | :stop | :length | :cancelled`).
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Eos,       // The model emitted an end-of-generation token
+    Stop,      // A stop sequence matched
+    Length,    // max_tokens (or the context window) ran out
+    Cancelled, // Brain::cancel, or the listener went away
+}
+
+pub struct Generation {
+    pub text: String,
+    pub stats: Stats,
+}
+
+/// Bookkeeping for one deliberation (sent as the `{:done, stats}` payload).
+#[derive(NifMap, Clone, Debug)]
+pub struct Stats {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub elapsed_ms: u64,
+    pub reason: StopReason,
+}
+
+/// Runs one prompt to completion.
+///
+/// Text is accumulated until EOS, a stop sequence, the token budget or
+/// cancellation. A matched stop sequence is cut from the returned text.
+///
+/// `on_text` receives the text incrementally as it becomes final. Bytes that
+/// could still grow into a stop sequence are held back until disambiguated,
+/// so the concatenated pieces always equal `Generation::text`.
+/// Returning `false` from `on_text` cancels the generation.
+///
+/// `grammar` (GBNF, root rule `root`) constrains every sampled token.
+pub fn generate<F>(
+    brain: &Brain,
+    prompt: &str,
+    params: &Params,
+    grammar: Option<&str>,
+    epoch: u64,
+    mut on_text: F,
+) -> Result<Generation, Atom>
+where
+    F: FnMut(&str) -> bool,
+{
+    let started = Instant::now();
+    let backend = backend()?;
+
+    // 1. Context (sized so the whole prompt fits in a single batch)
+    let ctx_params = LlamaContextParams::default()
+        .with_n_ctx(NonZeroU32::new(brain.n_ctx))
+        .with_n_batch(brain.n_ctx);
+    let mut ctx = brain
+        .model
+        .new_context(backend, ctx_params)
+        .map_err(|_| atoms::context_failed())?;
+
+    // 2. Prompt Ingestion
+    let tokens = brain
+        .model
+        .str_to_token(prompt, AddBos::Always)
+        .map_err(|_| atoms::tokenize_failed())?;
+
+    let prompt_tokens = tokens.len() as u32;
+    if prompt_tokens == 0 || prompt_tokens >= brain.n_ctx {
+        return Err(atoms::prompt_too_long());
+    }
+
+    let mut batch = LlamaBatch::new(brain.n_ctx as usize, 1);
+    let last = tokens.len() - 1;
+    for (i, token) in tokens.into_iter().enumerate() {
+        // Only the final prompt position needs logits
+        batch
+            .add(token, i as i32, &[0], i == last)
+            .map_err(|_| atoms::decode_failed())?;
+    }
+    ctx.decode(&mut batch).map_err(|_| atoms::decode_failed())?;
+
+    // 3. The Generation Loop
+    let mut sampler = build_sampler(brain, params, grammar)?;
+    let budget = params.max_tokens.min(brain.n_ctx - prompt_tokens);
+
+    let mut text = String::new();
+    let mut emitted = 0; // Prefix of 'text' already handed to on_text
+    let mut pending = Vec::new(); // Bytes of a UTF-8 char split across tokens
+    let mut completion_tokens = 0;
+    let mut pos = prompt_tokens as i32;
+
+    let reason = loop {
+        if completion_tokens >= budget {
+            break StopReason::Length;
+        }
+        if brain.is_cancelled(epoch) {
+            break StopReason::Cancelled;
+        }
+
+        // sample() also feeds the token back into the sampler chain
+        let token = sampler.sample(&ctx, batch.n_tokens() - 1);
+        if brain.model.is_eog_token(token) {
+            break StopReason::Eos;
+        }
+        completion_tokens += 1;
+
+        let bytes = brain
+            .model
+            .token_to_bytes(token, Special::Tokenize)
+            .map_err(|_| atoms::decode_failed())?;
+        pending.extend_from_slice(&bytes);
+        text.push_str(&drain_utf8(&mut pending));
+
+        if let Some(cut) = find_stop(&text, &params.stop) {
+            text.truncate(cut);
+            break StopReason::Stop;
+        }
+
+        let safe = text.len() - stop_holdback(&text, &params.stop);
+        if safe > emitted {
+            if !on_text(&text[emitted..safe]) {
+                break StopReason::Cancelled;
+            }
+            emitted = safe;
+        }
+
+        batch.clear();
+        batch
+            .add(token, pos, &[0], true)
+            .map_err(|_| atoms::decode_failed())?;
+        pos += 1;
+        ctx.decode(&mut batch).map_err(|_| atoms::decode_failed())?;
+    };
+
+    // 4. Flush whatever was held back (a cut stop sequence is already gone)
+    if text.len() > emitted && !matches!(reason, StopReason::Cancelled) {
+        on_text(&text[emitted..]);
+    }
+
+    Ok(Generation {
+        text,
+        stats: Stats {
+            prompt_tokens,
+            completion_tokens,
+            elapsed_ms: started.elapsed().as_millis() as u64,
+            reason,
+        },
+    })
+}
+
+fn build_sampler(brain: &Brain, params: &Params, grammar: Option<&str>) -> Result<LlamaSampler, Atom> {
+    // The grammar goes first: it masks illegal tokens before anything else sees the logits.
+    let mut chain = Vec::with_capacity(4);
+    if let Some(grammar) = grammar {
+        let constraint = LlamaSampler::grammar(&brain.model, grammar, "root")
+            .map_err(|_| atoms::grammar_failed())?;
+        chain.push(constraint);
+    }
+
+    if params.temperature <= 0.0 {
+        chain.push(LlamaSampler::greedy());
+    } else {
+        chain.push(LlamaSampler::top_p(params.top_p.clamp(0.0, 1.0), 1));
+        chain.push(LlamaSampler::temp(params.temperature));
+        chain.push(LlamaSampler::dist(params.seed));
+    }
+
+    Ok(LlamaSampler::chain_simple(chain))
+}
+
+/// Moves the longest valid UTF-8 prefix out of `pending`.
+/// An incomplete trailing sequence stays behind for the next token;
+/// genuinely invalid bytes are replaced rather than stalling the stream.
+fn drain_utf8(pending: &mut Vec<u8>) -> String {
+    match std::str::from_utf8(pending) {
+        Ok(s) => {
+            let out = s.to_owned();
+            pending.clear();
+            out
+        }
+        Err(e) if e.error_len().is_none() => {
+            let valid = e.valid_up_to();
+            let out = String::from_utf8_lossy(&pending[..valid]).into_owned();
+            pending.drain(..valid);
+            out
+        }
+        Err(_) => {
+            let out = String::from_utf8_lossy(pending).into_owned();
+            pending.clear();
+            out
+        }
+    }
+}
+
+/// Byte offset of the earliest stop sequence in `text`, if any.
+fn find_stop(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| text.find(s.as_str()))
+        .min()
+}
+
+/// Length of the longest suffix of `text` that is a proper prefix of a stop
+/// sequence, i.e. the bytes that must not be streamed out yet.
+fn stop_holdback(text: &str, stop: &[String]) -> usize {
+    stop.iter()
+        .filter_map(|s| {
+            (1..s.len())
+                .rev()
+                .find(|&k| s.is_char_boundary(k) && text.ends_with(&s[..k]))
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Model tests are #[ignore]d: point TACTICIAN_TEST_GGUF at a tiny GGUF
+    // (e.g. stories260K.gguf, ~1 MB) and run `cargo test -- --ignored`.
+    fn fixture() -> Brain {
+        let path = std::env::var("TACTICIAN_TEST_GGUF").expect("TACTICIAN_TEST_GGUF is not set");
+        Brain::load(&path, 256, 0).unwrap_or_else(|_| panic!("cannot load {path}"))
+    }
+
+    fn params(temperature: f32, max_tokens: u32, stop: &[&str], seed: u32) -> Params {
+        Params { temperature, top_p: 0.9, max_tokens, stop: stop.iter().map(|s| s.to_string()).collect(), seed }
+    }
+
+    fn run(brain: &Brain, params: &Params) -> Generation {
+        generate(brain, "Once upon a time", params, None, brain.epoch(), |_| true)
+            .unwrap_or_else(|_| panic!("generation failed"))
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn load_and_think() {
+        let brain = fixture();
+        let generation = run(&brain, &params(0.0, 16, &[], 0));
+
+        assert!(generation.stats.prompt_tokens > 0);
+        assert!(generation.stats.completion_tokens <= 16);
+        assert!(matches!(generation.stats.reason, StopReason::Eos | StopReason::Length));
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn same_seed_same_text() {
+        let brain = fixture();
+        let p = params(0.9, 24, &[], 7);
+
+        assert_eq!(run(&brain, &p).text, run(&brain, &p).text);
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn max_tokens_bounds_generation() {
+        let brain = fixture();
+        let generation = run(&brain, &params(0.0, 4, &[], 0));
+
+        match generation.stats.reason {
+            StopReason::Length => assert_eq!(generation.stats.completion_tokens, 4),
+            reason => assert_eq!(reason, StopReason::Eos),
+        }
+    }
+
+    // --- Streaming (think_stream) ---
+
+    #[test]
+    fn holdback_keeps_possible_stop_prefixes() {
+        let stop = vec!["\n\n".to_string(), "END".to_string()];
+
+        assert_eq!(stop_holdback("hello\n", &stop), 1);
+        assert_eq!(stop_holdback("hello EN", &stop), 2);
+        assert_eq!(stop_holdback("hello E", &stop), 1);
+        assert_eq!(stop_holdback("hello", &stop), 0);
+        assert_eq!(stop_holdback("hello", &[]), 0);
+    }
+
+    #[test]
+    fn earliest_stop_wins() {
+        let stop = vec!["END".to_string(), "\n".to_string(), String::new()];
+
+        assert_eq!(find_stop("ab\ncdEND", &stop), Some(2));
+        assert_eq!(find_stop("abEND\n", &stop), Some(2));
+        assert_eq!(find_stop("abc", &stop), None);
+    }
+
+    #[test]
+    fn utf8_split_across_tokens_is_held() {
+        let mut pending = "é".as_bytes()[..1].to_vec();
+        assert_eq!(drain_utf8(&mut pending), "");
+        pending.extend_from_slice(&"é".as_bytes()[1..]);
+        assert_eq!(drain_utf8(&mut pending), "é");
+        assert!(pending.is_empty());
+
+        let mut garbage = vec![b'a', 0xFF, b'b'];
+        assert_eq!(drain_utf8(&mut garbage), "a\u{FFFD}b");
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn streamed_pieces_equal_text() {
+        let brain = fixture();
+        let free = run(&brain, &params(0.0, 32, &[], 0)).text;
+        let chars: Vec<(usize, char)> = free.char_indices().collect();
+        let stop: Vec<&str> = if chars.len() >= 6 { vec![&free[chars[3].0..chars[5].0]] } else { vec![] };
+
+        let mut pieces = Vec::new();
+        let p = params(0.0, 32, &stop, 0);
+        let generation = generate(&brain, "Once upon a time", &p, None, brain.epoch(), |t| {
+            pieces.push(t.to_string());
+            true
+        })
+        .unwrap_or_else(|_| panic!("generation failed"));
+
+        // Held-back stop prefixes are released or cut, never duplicated
+        assert_eq!(pieces.concat(), generation.text);
+        assert!(stop.iter().all(|s| !generation.text.contains(s)));
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn cancel_stops_earlier_deliberations_only() {
+        let brain = fixture();
+        let stale = brain.epoch();
+        brain.cancel();
+
+        let p = params(0.0, 16, &[], 0);
+        let cancelled = generate(&brain, "Once upon a time", &p, None, stale, |_| true)
+            .unwrap_or_else(|_| panic!("generation failed"));
+        assert_eq!(cancelled.stats.reason, StopReason::Cancelled);
+        assert_eq!(cancelled.stats.completion_tokens, 0);
+
+        assert_ne!(run(&brain, &p).stats.reason, StopReason::Cancelled);
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn listener_gone_cancels() {
+        let brain = fixture();
+        let mut calls = 0;
+        let generation = generate(&brain, "Once upon a time", &params(0.0, 32, &[], 0), None, brain.epoch(), |_| {
+            calls += 1;
+            false
+        })
+        .unwrap_or_else(|_| panic!("generation failed"));
+
+        // Only possible if the model ended before emitting anything
+        if calls > 0 {
+            assert_eq!(generation.stats.reason, StopReason::Cancelled);
+            assert_eq!(calls, 1);
+        }
+    }
+
+    #[test]
+    #[ignore = "needs TACTICIAN_TEST_GGUF"]
+    fn stop_sequence_cuts_text() {
+        let brain = fixture();
+        let free = run(&brain, &params(0.0, 32, &[], 0)).text;
+
+        // Stop on a piece of what greedy decoding is known to produce
+        let chars: Vec<(usize, char)> = free.char_indices().collect();
+        if chars.len() < 6 {
+            return;
+        }
+        let stop = &free[chars[3].0..chars[5].0];
+        let cut = run(&brain, &params(0.0, 32, &[stop], 0));
+
+        assert_eq!(cut.stats.reason, StopReason::Stop);
+        assert_eq!(cut.text, free[..free.find(stop).unwrap()]);
+    }
+}
@@ -1,27 +1,173 @@
-use rustler::{NifStruct, Env, Term, NifResult};
+// native/swarm_brain_tactician/src/lib.rs
+
+//! # THE TACTICIAN (llama.cpp NIF)
+//!
+//! Loads a GGUF model into a BEAM resource and runs deliberations on the
+//! dirty CPU schedulers. A deterministic rule engine backs it up: it decides
+//! alone when no model is loaded, and vetoes unsafe LLM decisions.
+
+mod decision;
+mod engine;
+mod rules;
+
+use rustler::{Atom, Encoder, Env, LocalPid, OwnedEnv, ResourceArc, Term};
 use std::path::Path;
+use std::thread;
+
+use decision::Decision;
+use engine::{Brain, Params, StopReason};
+use rules::{Observation, RuleError, RuleSet};
+
+mod atoms {
+    rustler::atoms! {
+        model_not_found,
+        backend_failed,
+        load_failed,
+        context_failed,
+        tokenize_failed,
+        decode_failed,
+        prompt_too_long,
+        grammar_failed,
+        malformed_decision,
+        rules_not_found,
+        rules_invalid,
+        cancelled,
+
+        // Stream messages
+        token,
+        done,
+        error
+    }
+}
+
+/// Loads the model. Returns `{:ok, handle}` or `{:error, reason}`.
+#[rustler::nif(schedule = "DirtyCpu")]
+fn load_model(path: String, n_ctx: u32, n_gpu_layers: i32) -> Result<ResourceArc<Brain>, Atom> {
+    if !Path::new(&path).exists() {
+        return Err(atoms::model_not_found());
+    }
+
+    Brain::load(&path, n_ctx, n_gpu_layers).map(ResourceArc::new)
+}
 
+/// Runs one deliberation. Returns `{:ok, text}` or `{:error, reason}`.
 #[rustler::nif(schedule = "DirtyCpu")]
-fn load_model(path: String) -> NifResult<String> {
-    if Path::new(&path).exists() {
-        Ok(format!("🧠 Tactician Core online. Memory mapped: {}", path))
-    } else {
-        Ok(format!("❌ Error: Brain file not found at {}", path))
+fn think(brain: ResourceArc<Brain>, prompt: String, params: Params) -> Result<String, Atom> {
+    let generation = engine::generate(&brain, &prompt, &params, None, brain.epoch(), |_| true)?;
+    if generation.stats.reason == StopReason::Cancelled {
+        return Err(atoms::cancelled());
     }
+
+    Ok(generation.text)
 }
 
+/// Structured deliberation. Generation is grammar-constrained to a single JSON
+/// decision object, so the result is `{:ok, %SwarmBrain.Tactician.Decision{}}`
+/// rather than text to be regex-parsed.
 #[rustler::nif(schedule = "DirtyCpu")]
-fn think(context_text: String) -> String {
-    // SIMULATED REASONING (The "Tube Amp" Warm-up)
-    // This proves the pipeline works.
-    
-    if context_text.contains("person") {
-        "DECISION: TRACK_TARGET. REASON: Unauthorized human detected."
-    } else if context_text.contains("watch") {
-        "DECISION: HOVER. REASON: High-value asset identified."
-    } else {
-        "DECISION: SEARCH. REASON: Sector clear."
-    }.to_string()
-}
-
-rustler::init!("Elixir.SwarmBrain.Tactician.Native", [load_model, think]);
\ No newline at end of file
+fn decide(brain: ResourceArc<Brain>, prompt: String, params: Params) -> Result<Decision, Atom> {
+    let grammar = decision::grammar();
+    let generation = engine::generate(&brain, &prompt, &params, Some(&grammar), brain.epoch(), |_| true)?;
+    if generation.stats.reason == StopReason::Cancelled {
+        return Err(atoms::cancelled());
+    }
+
+    Decision::parse(&generation.text).ok_or_else(atoms::malformed_decision)
+}
+
+/// The Streaming Deliberation.
+/// Returns `:ok` immediately and generates on a dedicated OS thread, so no
+/// scheduler is held. `pid` receives `{:token, text}` for every piece of final
+/// text, then exactly one `{:done, stats}` or `{:error, reason}`.
+#[rustler::nif]
+fn think_stream(brain: ResourceArc<Brain>, prompt: String, pid: LocalPid, params: Params) -> Atom {
+    spawn_stream(brain, prompt, pid, params)
+}
+
+/// `think_stream/3`: the same with the default `%Params{}`.
+#[rustler::nif(name = "think_stream")]
+fn think_stream_default(brain: ResourceArc<Brain>, prompt: String, pid: LocalPid) -> Atom {
+    spawn_stream(brain, prompt, pid, Params::default())
+}
+
+fn spawn_stream(brain: ResourceArc<Brain>, prompt: String, pid: LocalPid, params: Params) -> Atom {
+    // Snapshot the epoch here, not on the thread: a cancel() that lands
+    // before the thread is scheduled must still abort this deliberation.
+    let epoch = brain.epoch();
+
+    thread::spawn(move || {
+        let mut env = OwnedEnv::new();
+
+        let result = engine::generate(&brain, &prompt, &params, None, epoch, |text| {
+            // A failed send means the listener is dead: stop burning CPU.
+            env.send_and_clear(&pid, |env| (atoms::token(), text).encode(env)).is_ok()
+        });
+
+        let _ = env.send_and_clear(&pid, |env| match result {
+            Ok(generation) => (atoms::done(), generation.stats).encode(env),
+            Err(reason) => (atoms::error(), reason).encode(env),
+        });
+    });
+
+    rustler::types::atom::ok()
+}
+
+/// The Abort Switch.
+/// Cancels every deliberation (blocking or streaming) issued on this handle so far.
+/// Streams still deliver their `{:done, %{reason: :cancelled}}`.
+#[rustler::nif]
+fn cancel(brain: ResourceArc<Brain>) -> Atom {
+    brain.cancel();
+    rustler::types::atom::ok()
+}
+
+/// Loads a JSON ruleset. Returns `{:ok, rules}` or `{:error, reason}`.
+#[rustler::nif]
+fn load_rules(path: String) -> Result<ResourceArc<RuleSet>, Atom> {
+    RuleSet::load(&path).map(ResourceArc::new).map_err(|e| match e {
+        RuleError::Io => atoms::rules_not_found(),
+        RuleError::Invalid => atoms::rules_invalid(),
+    })
+}
+
+/// The Fallback Brain.
+/// Every matching rule as a `%Decision{}`, best first (empty if nothing matched).
+#[rustler::nif]
+fn evaluate_rules(rules: ResourceArc<RuleSet>, observation: Observation) -> Vec<Decision> {
+    rules.evaluate(&observation)
+}
+
+/// The Safety Veto.
+/// Passes an LLM decision through, or replaces it with the winning `veto` rule's.
+#[rustler::nif]
+fn veto(rules: ResourceArc<RuleSet>, observation: Observation, decision: Decision) -> Decision {
+    rules.veto(&observation, decision)
+}
+
+fn load(env: Env, _info: Term) -> bool {
+    rustler::resource!(Brain, env);
+    rustler::resource!(RuleSet, env);
+    true
+}
+
+// THE FINAL MANIFEST
+rustler::init!(
+    "Elixir.SwarmBrain.Tactician.Native",
+    [
+        // 1. The Cortex (engine.rs)
+        load_model,
+        think,
+        think_stream,
+        think_stream_default,
+        cancel,
+
+        // 2. The Verdict (decision.rs)
+        decide,
+
+        // 3. The Code of Conduct (rules.rs)
+        load_rules,
+        evaluate_rules,
+        veto
+    ],
+    load = load
+);
@@ -0,0 +1,258 @@
+// native/swarm_brain_tactician/src/rules.rs
+
+//! THE CODE OF CONDUCT (Deterministic Rule Engine)
+//!
+//! A declarative ruleset, loaded from JSON, evaluated against a structured
+//! observation. It has two jobs:
+//! 1. Fallback: the whole decision when no model is loaded.
+//! 2. Safety Veto: `veto: true` rules override whatever the LLM decided.
+//!
+//! Rule file shape:
+//! ```json
+//! { "version": 1,
+//!   "rules": [
+//!     { "name": "battery_critical", "priority": 90, "veto": true,
+//!       "when": { "battery_below": 15.0 }, "action": "LAND" },
+//!     { "name": "intruder", "priority": 50,
+//!       "when": { "detection": { "label": "person", "min_confidence": 0.5 } },
+//!       "action": "TRACK_TARGET", "reason": "Unauthorized human detected." } ] }
+//! ```
+//! Every condition present in `when` must hold; an empty `when` always matches.
+
+use rustler::{NifMap, NifUnitEnum};
+use serde::Deserialize;
+
+use crate::decision::{Action, Decision};
+
+pub const RULESET_VERSION: u32 = 1;
+
+// --- OBSERVATION (Elixir -> Rust) ---
+
+#[derive(NifUnitEnum, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Geofence {
+    Inside,
+    Margin, // Inside, but within the warning band
+    Outside,
+}
+
+#[derive(NifMap, Clone, Debug)]
+pub struct Detection {
+    pub track_id: u32,
+    pub label: String,
+    pub confidence: f32,
+}
+
+#[derive(NifMap, Clone, Debug)]
+pub struct Observation {
+    pub detections: Vec<Detection>,
+    pub battery: f32,      // Percent, 0..100
+    pub link_quality: f32, // Percent, 0..100 (ELRS LQ)
+    pub geofence: Geofence,
+}
+
+// --- RULESET (JSON -> Rust) ---
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    version: u32,
+    rules: Vec<Rule>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct Rule {
+    name: String,
+    #[serde(default)]
+    priority: i32,
+    #[serde(default)]
+    when: Condition,
+    action: Action,
+    reason: Option<String>,
+    #[serde(default = "full_confidence")]
+    confidence: f32,
+    #[serde(default)]
+    veto: bool,
+    // Actions the veto tolerates besides its own (e.g. LAND is fine when RTB is demanded)
+    #[serde(default)]
+    allow: Vec<Action>,
+}
+
+// Unknown keys are rejected: a typo in a safety rule must not silently match everything.
+#[derive(Deserialize, Default, Debug)]
+#[serde(deny_unknown_fields)]
+struct Condition {
+    battery_below: Option<f32>,
+    battery_above: Option<f32>,
+    link_quality_below: Option<f32>,
+    link_quality_above: Option<f32>,
+    geofence: Option<Geofence>,
+    detection: Option<DetectionMatch>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+struct DetectionMatch {
+    label: Option<String>, // None = any label
+    #[serde(default)]
+    min_confidence: f32,
+}
+
+fn full_confidence() -> f32 {
+    1.0
+}
+
+/// Why a ruleset was rejected.
+#[derive(Debug)]
+pub enum RuleError {
+    Io,
+    Invalid,
+}
+
+impl RuleSet {
+    pub fn load(path: &str) -> Result<Self, RuleError> {
+        let raw = std::fs::read_to_string(path).map_err(|_| RuleError::Io)?;
+        Self::parse(&raw)
+    }
+
+    pub fn parse(raw: &str) -> Result<Self, RuleError> {
+        let set: RuleSet = serde_json::from_str(raw).map_err(|_| RuleError::Invalid)?;
+        if set.version != RULESET_VERSION || set.rules.is_empty() {
+            return Err(RuleError::Invalid);
+        }
+        Ok(set)
+    }
+
+    /// Every matching rule as a Decision, best first.
+    /// Ranking: priority (high first), then file order.
+    pub fn evaluate(&self, obs: &Observation) -> Vec<Decision> {
+        self.ranked_matches(obs, false)
+            .into_iter()
+            .map(|(rule, det)| rule.decide(det))
+            .collect()
+    }
+
+    /// The Safety Veto.
+    /// Returns `decision` untouched if every matching veto rule accepts its
+    /// action. Otherwise picks the highest-ranked candidate (each matching
+    /// rule's action, then its `allow` list, in rank order) that every
+    /// matching veto rule accepts, falling back to LAND, then HOVER.
+    pub fn veto(&self, obs: &Observation, decision: Decision) -> Decision {
+        let vetoes = self.ranked_matches(obs, true);
+        let Some(&(rejecting, det)) = vetoes.iter().find(|(rule, _)| !rule.accepts(decision.action)) else {
+            return decision;
+        };
+
+        let accepted = |action: Action| vetoes.iter().all(|(rule, _)| rule.accepts(action));
+        let action = vetoes
+            .iter()
+            .flat_map(|(rule, _)| std::iter::once(&rule.action).chain(&rule.allow))
+            .chain(&[Action::Land, Action::Hover])
+            .copied()
+            .find(|&action| accepted(action))
+            // Contradictory vetoes: LAND is the last safe resort
+            .unwrap_or(Action::Land);
+
+        // The rule that demands the action explains it; otherwise the top rejecting rule does
+        match vetoes.iter().find(|(rule, _)| rule.action == action) {
+            Some((rule, det)) => rule.decide(*det),
+            None => Decision { action, ..rejecting.decide(det) },
+        }
+    }
+
+    fn ranked_matches<'a>(&'a self, obs: &'a Observation, veto_only: bool) -> Vec<(&'a Rule, Option<&'a Detection>)> {
+        let mut hits: Vec<(&Rule, Option<&Detection>)> = self
+            .rules
+            .iter()
+            .filter(|rule| !veto_only || rule.veto)
+            .filter_map(|rule| rule.when.matches(obs).map(|det| (rule, det)))
+            .collect();
+
+        // Stable sort keeps file order within a priority level
+        hits.sort_by(|a, b| b.0.priority.cmp(&a.0.priority));
+        hits
+    }
+}
+
+impl Rule {
+    /// A veto rule accepts its own action and the ones it allows.
+    fn accepts(&self, action: Action) -> bool {
+        self.action == action || self.allow.contains(&action)
+    }
+
+    /// Detection rules inherit the target and scale confidence by the detector's.
+    fn decide(&self, det: Option<&Detection>) -> Decision {
+        let reason = self.reason.as_deref().unwrap_or(&self.name);
+        match det {
+            Some(d) => Decision::new(self.action, Some(d.track_id), reason, self.confidence * d.confidence),
+            None => Decision::new(self.action, None, reason, self.confidence),
+        }
+    }
+}
+
+impl Condition {
+    /// `None` if the condition fails. `Some(det)` if it holds, carrying the
+    /// best matching detection when the condition involves one.
+    fn matches<'a>(&self, obs: &'a Observation) -> Option<Option<&'a Detection>> {
+        let checks = [
+            self.battery_below.is_none_or(|v| obs.battery < v),
+            self.battery_above.is_none_or(|v| obs.battery > v),
+            self.link_quality_below.is_none_or(|v| obs.link_quality < v),
+            self.link_quality_above.is_none_or(|v| obs.link_quality > v),
+            self.geofence.is_none_or(|g| obs.geofence == g),
+        ];
+        if !checks.iter().all(|&ok| ok) {
+            return None;
+        }
+
+        match &self.detection {
+            None => Some(None),
+            Some(m) => obs
+                .detections
+                .iter()
+                .filter(|d| m.label.as_deref().is_none_or(|l| d.label == l))
+                .filter(|d| d.confidence >= m.min_confidence)
+                .max_by(|a, b| a.confidence.partial_cmp(&b.confidence).unwrap_or(std::cmp::Ordering::Equal))
+                .map(Some),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RULES: &str = r#"{ "version": 1, "rules": [
+        { "name": "geofence", "priority": 95, "veto": true,
+          "when": { "geofence": "outside" }, "action": "RTB", "allow": ["LAND"] },
+        { "name": "battery_critical", "priority": 90, "veto": true,
+          "when": { "battery_below": 15.0 }, "action": "LAND" } ] }"#;
+
+    fn obs(battery: f32, geofence: Geofence) -> Observation {
+        Observation { detections: vec![], battery, link_quality: 100.0, geofence }
+    }
+
+    fn llm(action: Action) -> Decision {
+        Decision::new(action, None, "llm", 0.8)
+    }
+
+    #[test]
+    fn every_matching_veto_must_accept() {
+        let rules = RuleSet::parse(RULES).unwrap();
+
+        // RTB satisfies the geofence but not the critical battery
+        assert_eq!(rules.veto(&obs(10.0, Geofence::Outside), llm(Action::Rtb)).action, Action::Land);
+        // LAND satisfies both
+        assert_eq!(rules.veto(&obs(10.0, Geofence::Outside), llm(Action::Land)).reason, "llm");
+        // Neither: RTB would breach the battery veto, LAND satisfies both
+        let landed = rules.veto(&obs(10.0, Geofence::Outside), llm(Action::Hover));
+        assert_eq!((landed.action, landed.reason.as_str()), (Action::Land, "battery_critical"));
+        // Only the battery matches
+        assert_eq!(rules.veto(&obs(10.0, Geofence::Inside), llm(Action::Hover)).action, Action::Land);
+        // Only the geofence matches
+        assert_eq!(rules.veto(&obs(80.0, Geofence::Outside), llm(Action::Rtb)).reason, "llm");
+        // Nothing matches
+        assert_eq!(rules.veto(&obs(80.0, Geofence::Inside), llm(Action::Hover)).reason, "llm");
+    }
+}
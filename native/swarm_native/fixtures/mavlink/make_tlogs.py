@@ -0,0 +1,84 @@
+#!/usr/bin/env python3
+"""Writes the MAVLink tlog fixtures replayed by src/serial/mavlink tests.
+
+Frames are built from the MAVLink v2 spec alone (common.xml wire order and
+CRC_EXTRA, MCRF4XX CRC, trailing-zero truncation, SHA-256 signing), not by
+the crate's encoder, so the tests check it against an independent source.
+
+tlog records: timestamp_us u64 (big-endian) | one raw frame.
+
+    python3 make_tlogs.py   # rewrites flight.tlog and signed.tlog here
+"""
+import hashlib
+import os
+import struct
+
+T0 = 1_700_000_000_000_000      # Unix µs of the first record
+EPOCH_2015_US = 1_420_070_400_000_000
+KEY = bytes(range(32))          # Signing key of signed.tlog
+LINK_ID = 1
+
+# msg id: (CRC_EXTRA, struct format in wire order)
+SPECS = {
+    0: (50, "<IBBBBB"),              # HEARTBEAT
+    1: (124, "<IIIHHhHHHHHHb"),      # SYS_STATUS
+    30: (39, "<Iffffff"),            # ATTITUDE
+    33: (104, "<IiiiihhhH"),         # GLOBAL_POSITION_INT
+    77: (143, "<HB"),                # COMMAND_ACK
+}
+
+
+def crc16(data, crc=0xFFFF):
+    for b in data:
+        tmp = (b ^ crc) & 0xFF
+        tmp = (tmp ^ (tmp << 4)) & 0xFF
+        crc = ((crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)) & 0xFFFF
+    return crc
+
+
+def frame(seq, sysid, compid, msgid, fields, timestamp=None):
+    crc_extra, fmt = SPECS[msgid]
+    payload = struct.pack(fmt, *fields).rstrip(b"\0") or b"\0"
+    incompat = 0x01 if timestamp is not None else 0
+    out = bytes([0xFD, len(payload), incompat, 0, seq, sysid, compid]) + struct.pack("<I", msgid)[:3] + payload
+    out += struct.pack("<H", crc16(out[1:] + bytes([crc_extra])))
+    if timestamp is not None:
+        out += bytes([LINK_ID]) + struct.pack("<Q", timestamp)[:6]
+        out += hashlib.sha256(KEY + out).digest()[:6]
+    return out
+
+
+def v1_heartbeat(seq, sysid, compid):
+    payload = struct.pack("<IBBBBB", 0, 2, 3, 81, 3, 3)
+    out = bytes([0xFE, len(payload), seq, sysid, compid, 0]) + payload
+    return out + struct.pack("<H", crc16(out[1:] + bytes([50])))
+
+
+def tlog(name, records):
+    with open(os.path.join(os.path.dirname(os.path.abspath(__file__)), name), "wb") as f:
+        for t, data in records:
+            f.write(struct.pack(">Q", t) + data)
+
+
+# An ArduCopter (1/1) talking to a GCS (255/190), one v1 frame in between
+tlog("flight.tlog", [
+    (T0, frame(0, 1, 1, 0, (4, 2, 3, 217, 4, 3))),
+    (T0 + 5_000, frame(0, 255, 190, 0, (0, 6, 8, 0, 0, 3))),
+    (T0 + 10_000, frame(1, 1, 1, 1, (0x0120FC2F, 0x0120FC2F, 0x0120FC2F, 250, 16_380, 1_234, 0, 0, 0, 0, 0, 0, 76))),
+    (T0 + 15_000, v1_heartbeat(7, 1, 1)),
+    (T0 + 20_000, frame(2, 1, 1, 30, (123_456, 0.25, -0.125, 1.5, 0.0, 0.0, -0.5))),
+    (T0 + 30_000, frame(3, 1, 1, 33, (123_500, 473_977_418, 85_455_938, 488_000, 10_000, 150, -75, 0, 9_000))),
+    (T0 + 40_000, frame(4, 1, 1, 77, (400, 0))),
+    (T0 + 50_000, frame(5, 1, 1, 30, (123_600, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0))),
+])
+
+# The same autopilot with signing on (link 1), 20 ms apart
+signed = [
+    (0, (4, 2, 3, 217, 4, 3)),
+    (30, (123_456, 0.25, -0.125, 1.5, 0.0, 0.0, -0.5)),
+    (77, (400, 0)),
+]
+tlog("signed.tlog", [
+    (T0 + i * 20_000, frame(i, 1, 1, msgid, fields, (T0 + i * 20_000 - EPOCH_2015_US) // 10))
+    for i, (msgid, fields) in enumerate(signed)
+])
@@ -0,0 +1,316 @@
+// native/swarm_native/src/bus/mod.rs
+
+//! THE NERVE (Zenoh Bus)
+//!
+//! An optional zenoh session (peer mode, no router required) that lets
+//! ground tools and other drones tap the Iron Lung without going through
+//! the BEAM:
+//!
+//! * The camera heartbeat hands every frame to `Bus::on_frame`, which
+//!   publishes kinematics, the flow grid, motion events and every Nth
+//!   frame (downscaled) on their configured key expressions.
+//! * A queryable serves the Spatial Memory (see queryable.rs).
+//! * Subscriptions forward peer samples to Elixir processes (see
+//!   subscriber.rs).
+//!
+//! Publishers drop rather than block under congestion: the heartbeat must
+//! never wait on the network. A stream whose key is `None` is not declared.
+//!
+//! With swarm keys, every published payload goes out as a sealed frame
+//! (crypto/mod.rs) and subscribers must open it before trusting it. The
+//! spatial queryable still answers in the clear.
+
+pub mod queryable;  // Spatial Memory on request
+pub mod subscriber; // Samples -> Elixir pids
+pub mod wire;       // Payload layouts
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use rustler::NifStruct;
+use zenoh::pubsub::Publisher;
+use zenoh::qos::{CongestionControl, Priority};
+use zenoh::query::Queryable;
+use zenoh::{Config, Session, Wait};
+
+use crate::crypto::SealError;
+use crate::spatial::grid::SpatialMap;
+use crate::state::arena::{FRAME_HEIGHT, FRAME_WIDTH};
+use crate::types::KinematicsSnapshot;
+use crate::vision::detector::MotionROI;
+
+/// Mirrors `%SwarmBrain.Bus.Config{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Bus.Config"]
+pub struct BusConfig {
+    pub listen: Vec<String>,            // Endpoints, e.g. "tcp/0.0.0.0:7447" ([] = zenoh default)
+    pub connect: Vec<String>,           // Peers to dial directly
+    pub multicast: bool,                // Multicast scouting (LAN discovery)
+    pub kinematics_key: Option<String>, // Stream keys: nil = not published
+    pub flow_key: Option<String>,
+    pub motion_key: Option<String>,
+    pub frame_key: Option<String>,
+    pub frame_every: u32,               // Publish one camera frame in N
+    pub frame_scale: u32,               // Downscale factor (4 = 160x120)
+    pub spatial_key: Option<String>,    // Queryable key expression, nil = none
+}
+
+/// Seals one payload (a keyring and its active key).
+pub type Sealer = Box<dyn Fn(&[u8]) -> Result<Vec<u8>, SealError> + Send + Sync>;
+
+#[derive(Debug)]
+pub enum BusError {
+    Config,  // Endpoint or option rejected by zenoh
+    KeyExpr, // Invalid key expression
+    Session, // Could not open the session
+}
+
+/// The live session and its declarations. Dropping the last handle
+/// undeclares everything and closes the session.
+pub struct Bus {
+    session: Session,
+    kinematics: Option<Publisher<'static>>,
+    flow: Option<Publisher<'static>>,
+    motion: Option<Publisher<'static>>,
+    frame: Option<Publisher<'static>>,
+    frame_every: u64,
+    frame_scale: usize,
+    frames: AtomicU64, // Camera frames seen
+    seal: Option<Sealer>,
+    _spatial: Option<Queryable<()>>,
+}
+
+impl Bus {
+    pub fn open(cfg: &BusConfig, map: Arc<SpatialMap>, seal: Option<Sealer>) -> Result<Self, BusError> {
+        // 1. Session
+        let session = zenoh::open(zenoh_config(cfg)?).wait().map_err(|_| BusError::Session)?;
+
+        // 2. Declarations
+        let publisher = |key: &Option<String>, priority: Priority| -> Result<Option<Publisher<'static>>, BusError> {
+            key.as_ref()
+                .map(|k| {
+                    session
+                        .declare_publisher(k.clone())
+                        .congestion_control(CongestionControl::Drop)
+                        .priority(priority)
+                        .wait()
+                        .map_err(|_| BusError::KeyExpr)
+                })
+                .transpose()
+        };
+        let kinematics = publisher(&cfg.kinematics_key, Priority::RealTime)?;
+        let flow = publisher(&cfg.flow_key, Priority::DataHigh)?;
+        let motion = publisher(&cfg.motion_key, Priority::DataHigh)?;
+        let frame = publisher(&cfg.frame_key, Priority::DataLow)?;
+
+        let spatial = cfg
+            .spatial_key
+            .as_ref()
+            .map(|k| queryable::declare(&session, k, map))
+            .transpose()
+            .map_err(|_| BusError::KeyExpr)?;
+
+        Ok(Self {
+            session,
+            kinematics,
+            flow,
+            motion,
+            frame,
+            frame_every: cfg.frame_every.max(1) as u64,
+            frame_scale: cfg.frame_scale.max(1) as usize,
+            frames: AtomicU64::new(0),
+            seal,
+            _spatial: spatial,
+        })
+    }
+
+    pub fn session(&self) -> &Session {
+        &self.session
+    }
+
+    /// Whether the heartbeat should run motion detection for us.
+    pub fn wants_motion(&self) -> bool {
+        self.motion.is_some()
+    }
+
+    /// Publishes one camera update (called from the heartbeat, once per frame).
+    pub fn on_frame(&self, rgb: &[u8], flow: &[f32], kin: &KinematicsSnapshot, motion: Option<MotionROI>) {
+        let n = self.frames.fetch_add(1, Ordering::Relaxed);
+
+        self.put(&self.kinematics, || wire::kinematics(kin));
+        self.put(&self.flow, || wire::flow(flow));
+        if let Some(roi) = motion {
+            self.put(&self.motion, || wire::motion(&roi));
+        }
+        if n.is_multiple_of(self.frame_every) {
+            self.put(&self.frame, || wire::frame(rgb, FRAME_WIDTH, FRAME_HEIGHT, self.frame_scale));
+        }
+    }
+
+    // Best effort: a failed seal or put is a dropped sample, like congestion
+    fn put(&self, publisher: &Option<Publisher<'static>>, payload: impl FnOnce() -> Vec<u8>) {
+        if let Some(p) = publisher {
+            let payload = match &self.seal {
+                Some(seal) => match seal(&payload()) {
+                    Ok(frame) => frame,
+                    Err(_) => return,
+                },
+                None => payload(),
+            };
+            let _ = p.put(payload).timestamp(self.session.new_timestamp()).wait();
+        }
+    }
+}
+
+fn zenoh_config(cfg: &BusConfig) -> Result<Config, BusError> {
+    let mut c = Config::default();
+    let mut set = |key: &str, value: String| c.insert_json5(key, &value).map_err(|_| BusError::Config);
+
+    set("mode", r#""peer""#.to_string())?;
+    set("scouting/multicast/enabled", cfg.multicast.to_string())?;
+    if !cfg.listen.is_empty() {
+        set("listen/endpoints", json_strings(&cfg.listen))?;
+    }
+    if !cfg.connect.is_empty() {
+        set("connect/endpoints", json_strings(&cfg.connect))?;
+    }
+    Ok(c)
+}
+
+// Endpoints are plain locators ("tcp/10.0.0.2:7447"); Debug quoting is valid JSON5 for them
+fn json_strings(items: &[String]) -> String {
+    format!("[{}]", items.iter().map(|s| format!("{s:?}")).collect::<Vec<_>>().join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::time::{Duration, Instant};
+
+    use crate::crypto::Keyring;
+    use crate::spatial::grid::STATUS_OCCUPIED;
+
+    const PATIENCE: Duration = Duration::from_secs(10);
+
+    fn free_port() -> u16 {
+        TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    fn config(port: u16) -> BusConfig {
+        let key = |s: &str| Some(format!("swarm/test/{port}/{s}"));
+        BusConfig {
+            listen: vec![format!("tcp/127.0.0.1:{port}")],
+            connect: Vec::new(),
+            multicast: false,
+            kinematics_key: key("kinematics"),
+            flow_key: None,
+            motion_key: key("motion"),
+            frame_key: None,
+            frame_every: 1,
+            frame_scale: 1,
+            spatial_key: key("spatial"),
+        }
+    }
+
+    // A second peer dialing the first one directly, as a ground tool would
+    fn peer(port: u16) -> Session {
+        let cfg = BusConfig { listen: Vec::new(), connect: vec![format!("tcp/127.0.0.1:{port}")], ..config(port) };
+        zenoh::open(zenoh_config(&cfg).unwrap()).wait().unwrap()
+    }
+
+    #[test]
+    fn samples_reach_a_peer_session() {
+        let port = free_port();
+        let cfg = config(port);
+        let bus = Bus::open(&cfg, Arc::new(SpatialMap::new()), None).unwrap();
+        let peer = peer(port);
+        let kinematics = peer.declare_subscriber(cfg.kinematics_key.clone().unwrap()).wait().unwrap();
+        let motion = peer.declare_subscriber(cfg.motion_key.clone().unwrap()).wait().unwrap();
+
+        let kin = KinematicsSnapshot { vx: 1.5, vy: -2.0, px: 10.0, py: 20.0, timestamp_us: 42, seq: 7 };
+        let roi = MotionROI { x: 1, y: 2, w: 3, h: 4 };
+
+        // Publications before a subscription has propagated are lost: retry
+        let deadline = Instant::now() + PATIENCE;
+        let (mut got_kinematics, mut got_motion) = (None, None);
+        while got_kinematics.is_none() || got_motion.is_none() {
+            assert!(Instant::now() < deadline, "bus samples never reached the peer");
+            bus.on_frame(&[], &[], &kin, Some(roi));
+            got_kinematics = got_kinematics.or(kinematics.recv_timeout(Duration::from_millis(100)).unwrap());
+            got_motion = got_motion.or(motion.recv_timeout(Duration::from_millis(100)).unwrap());
+        }
+
+        let sample = got_kinematics.unwrap();
+        assert_eq!(sample.payload().to_bytes().as_ref(), wire::kinematics(&kin).as_slice());
+        assert!(sample.timestamp().is_some());
+        assert_eq!(got_motion.unwrap().payload().to_bytes().as_ref(), wire::motion(&roi).as_slice());
+    }
+
+    #[test]
+    fn spatial_queryable_answers_a_peer() {
+        let port = free_port();
+        let cfg = config(port);
+        let map = Arc::new(SpatialMap::new());
+        map.set_status(3, -4, STATUS_OCCUPIED, crate::spatial::now_us());
+        let _bus = Bus::open(&cfg, map.clone(), None).unwrap();
+        let peer = peer(port);
+        let key = cfg.spatial_key.unwrap();
+
+        let ask = |params: &str| {
+            let deadline = Instant::now() + PATIENCE;
+            loop {
+                let replies = peer.get(format!("{key}?{params}")).timeout(Duration::from_secs(1)).wait().unwrap();
+                if let Ok(Some(reply)) = replies.recv_timeout(Duration::from_secs(2)) {
+                    break reply.result().map(|s| s.payload().to_bytes().into_owned()).map_err(|e| e.payload().to_bytes().into_owned());
+                }
+                assert!(Instant::now() < deadline, "no reply to {params}");
+            }
+        };
+
+        assert_eq!(ask("x=3;y=-4"), Ok(STATUS_OCCUPIED.to_le_bytes().to_vec()));
+        assert_eq!(ask(""), Ok(map.version().to_le_bytes().to_vec()));
+
+        let cells = ask("x0=0;y0=-5;x1=5;y1=0").unwrap();
+        assert_eq!(cells.len(), wire::CELL_LEN);
+        assert_eq!(&cells[..12], [3i32.to_le_bytes(), (-4i32).to_le_bytes(), STATUS_OCCUPIED.to_le_bytes()].concat());
+
+        assert_eq!(ask("x=abc;y=1"), Err(b"parameters must be integers".to_vec()));
+    }
+
+    #[test]
+    fn sealed_samples_only_open_with_the_swarm_key() {
+        let port = free_port();
+        let cfg = BusConfig { motion_key: None, ..config(port) };
+        let keyring = |node| {
+            let k = Arc::new(Keyring::new(node, 0));
+            k.insert(1, &[9; crate::crypto::KEY_LEN], 8).unwrap();
+            k
+        };
+        let (ours, theirs) = (keyring(1), keyring(2));
+        let sealer = ours.clone();
+        let bus = Bus::open(&cfg, Arc::new(SpatialMap::new()), Some(Box::new(move |p| sealer.seal(1, p)))).unwrap();
+        let peer = peer(port);
+        let kinematics = peer.declare_subscriber(cfg.kinematics_key.clone().unwrap()).wait().unwrap();
+
+        let kin = KinematicsSnapshot { vx: 1.5, vy: -2.0, px: 10.0, py: 20.0, timestamp_us: 42, seq: 7 };
+        let deadline = Instant::now() + PATIENCE;
+        let sample = loop {
+            assert!(Instant::now() < deadline, "bus samples never reached the peer");
+            bus.on_frame(&[], &[], &kin, None);
+            if let Some(sample) = kinematics.recv_timeout(Duration::from_millis(100)).unwrap() {
+                break sample;
+            }
+        };
+
+        let frame = sample.payload().to_bytes().into_owned();
+        assert_ne!(frame, wire::kinematics(&kin));
+        let opened = theirs.open(&frame).unwrap();
+        assert_eq!((opened.sender, opened.plaintext), (1, wire::kinematics(&kin)));
+
+        // Another swarm's key (same id) cannot open it
+        let stranger = Keyring::new(3, 0);
+        stranger.insert(1, &[8; crate::crypto::KEY_LEN], 8).unwrap();
+        assert_eq!(stranger.open(&frame).err(), Some(SealError::BadTag));
+    }
+}
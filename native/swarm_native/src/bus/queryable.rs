@@ -0,0 +1,101 @@
+// native/swarm_native/src/bus/queryable.rs
+
+//! THE ORACLE (Spatial Memory on request)
+//!
+//! Answers zenoh `get`s on the configured key expression straight from the
+//! Spatial Memory, without a round trip through the BEAM. The selector
+//! parameters pick the query (cells, same keys as `get_spatial_state`):
+//!
+//! * `x=3;y=-4`                  -> status u32
+//! * `x0=..;y0=..;x1=..;y1=..`   -> known cells in the box (optional `status=`)
+//! * `since=N` (optional `limit=`) -> cursor u64 | changed cells
+//! * no parameters               -> map version u64
+//!
+//! Cell lists use the `wire::cells` layout. Malformed selectors get a
+//! reply error carrying a short reason.
+
+use std::sync::Arc;
+
+use zenoh::query::{Query, Queryable};
+use zenoh::{Session, Wait};
+
+use crate::bus::wire;
+use crate::spatial::{self, grid::{SpatialMap, MAX_REGION_CELLS}, query::Bounds};
+
+// Cells per `since` reply unless the caller asks for fewer
+const DEFAULT_CHANGE_LIMIT: usize = 20_000;
+
+pub fn declare(session: &Session, key: &str, map: Arc<SpatialMap>) -> zenoh::Result<Queryable<()>> {
+    session
+        .declare_queryable(key.to_string())
+        .callback(move |query| answer(&map, &query))
+        .wait()
+}
+
+fn answer(map: &SpatialMap, query: &Query) {
+    let now = spatial::now_us();
+    let result = match Request::parse(query) {
+        Ok(request) => Ok(request.run(map, now)),
+        Err(reason) => Err(reason),
+    };
+
+    // The querier may be gone already: nothing to do about a failed reply
+    let _ = match result {
+        Ok(payload) => query.reply(query.key_expr().clone(), payload).wait(),
+        Err(reason) => query.reply_err(reason).wait(),
+    };
+}
+
+enum Request {
+    Cell(i32, i32),
+    Box(Bounds, Option<u32>),
+    Since(u64, usize),
+    Version,
+}
+
+impl Request {
+    fn parse(query: &Query) -> Result<Self, &'static str> {
+        let params = query.parameters();
+        let int = |k: &str| -> Result<Option<i64>, &'static str> {
+            params.get(k).map(|v| v.parse::<i64>().map_err(|_| "parameters must be integers")).transpose()
+        };
+        let coord = |k: &str| -> Result<i32, &'static str> {
+            int(k)?.ok_or("missing coordinate").and_then(|v| i32::try_from(v).map_err(|_| "coordinate out of range"))
+        };
+
+        if let Some(since) = int("since")? {
+            let limit = int("limit")?.map_or(DEFAULT_CHANGE_LIMIT, |l| l.clamp(1, DEFAULT_CHANGE_LIMIT as i64) as usize);
+            return Ok(Request::Since(since.max(0) as u64, limit));
+        }
+        if params.get("x0").is_some() {
+            let bounds = Bounds::new(coord("x0")?, coord("y0")?, coord("x1")?, coord("y1")?);
+            if bounds.area() > MAX_REGION_CELLS as u64 {
+                return Err("region too large");
+            }
+            let status = int("status")?.map(|s| s.clamp(0, u32::MAX as i64) as u32);
+            return Ok(Request::Box(bounds, status));
+        }
+        if params.get("x").is_some() {
+            return Ok(Request::Cell(coord("x")?, coord("y")?));
+        }
+        Ok(Request::Version)
+    }
+
+    fn run(self, map: &SpatialMap, now_us: u64) -> Vec<u8> {
+        match self {
+            Request::Cell(x, y) => map.status(x, y, now_us).to_le_bytes().to_vec(),
+            Request::Box(bounds, status) => {
+                let mut out = Vec::new();
+                wire::cells(&mut out, &map.cells_in_bounds(bounds, status, now_us));
+                out
+            }
+            Request::Since(since, limit) => {
+                let (cursor, cells) = map.changes_since(since, limit, now_us);
+                let mut out = cursor.to_le_bytes().to_vec();
+                wire::cells(&mut out, &cells);
+                out
+            }
+            Request::Version => map.version().to_le_bytes().to_vec(),
+        }
+    }
+}
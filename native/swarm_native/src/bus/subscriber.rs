@@ -0,0 +1,170 @@
+// native/swarm_native/src/bus/subscriber.rs
+
+//! THE SYNAPSE (Zenoh -> Elixir)
+//!
+//! Delivers every sample on a key expression to one Elixir process as
+//! `{:zenoh, key, payload, timestamp_us | nil}`.
+//!
+//! # ARCHITECTURAL TACTIC: Credit-Based Backpressure
+//! A mailbox is unbounded, so a fast peer could bury a slow GenServer.
+//! The forwarder thread only sends while the receiver has credit: at most
+//! `capacity` messages unacknowledged, credit returned with `zenoh_ack`.
+//! Samples arriving meanwhile wait in a native queue of `capacity`, where
+//! the OLDEST is dropped first: for telemetry, fresh beats complete.
+//!
+//! The zenoh callback only queues (it runs on zenoh's runtime and must
+//! not block); the dedicated forwarder thread owns the `OwnedEnv`.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+
+use rustler::{Encoder, LocalPid, OwnedBinary, OwnedEnv};
+use zenoh::pubsub::Subscriber;
+use zenoh::sample::Sample;
+use zenoh::{Session, Wait};
+
+mod atoms {
+    rustler::atoms! {
+        zenoh
+    }
+}
+
+struct Message {
+    key: String,
+    payload: Vec<u8>,
+    timestamp_us: Option<u64>,
+}
+
+struct Inbox {
+    state: Mutex<InboxState>,
+    ready: Condvar,
+    capacity: usize,
+    dropped: AtomicU64,
+}
+
+struct InboxState {
+    queue: VecDeque<Message>,
+    credits: usize,
+    closed: bool,
+}
+
+impl Inbox {
+    fn push(&self, sample: Sample) {
+        let message = Message {
+            key: sample.key_expr().to_string(),
+            payload: sample.payload().to_bytes().into_owned(),
+            timestamp_us: sample.timestamp().map(|t| t.get_time().to_duration().as_micros() as u64),
+        };
+
+        let mut s = self.state.lock().unwrap();
+        if s.closed {
+            return;
+        }
+        if s.queue.len() >= self.capacity {
+            s.queue.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        s.queue.push_back(message);
+        self.ready.notify_one();
+    }
+
+    /// Blocks until a message may be sent. `None` once closed.
+    fn next(&self) -> Option<Message> {
+        let mut s = self.state.lock().unwrap();
+        loop {
+            if s.closed {
+                return None;
+            }
+            if s.credits > 0 {
+                if let Some(m) = s.queue.pop_front() {
+                    s.credits -= 1;
+                    return Some(m);
+                }
+            }
+            s = self.ready.wait(s).unwrap();
+        }
+    }
+
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.ready.notify_all();
+    }
+}
+
+/// One subscription (the ResourceArc payload). Undeclared on drop.
+pub struct Subscription {
+    inbox: Arc<Inbox>,
+    subscriber: Mutex<Option<Subscriber<()>>>,
+    forwarder: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl std::panic::RefUnwindSafe for Subscription {}
+
+impl Subscription {
+    pub fn declare(session: &Session, key: &str, pid: LocalPid, capacity: usize) -> zenoh::Result<Self> {
+        let capacity = capacity.max(1);
+        let inbox = Arc::new(Inbox {
+            state: Mutex::new(InboxState { queue: VecDeque::with_capacity(capacity), credits: capacity, closed: false }),
+            ready: Condvar::new(),
+            capacity,
+            dropped: AtomicU64::new(0),
+        });
+
+        let sink = inbox.clone();
+        let subscriber = session.declare_subscriber(key.to_string()).callback(move |sample| sink.push(sample)).wait()?;
+
+        let source = inbox.clone();
+        let forwarder = thread::spawn(move || forward(&source, pid));
+
+        Ok(Self { inbox, subscriber: Mutex::new(Some(subscriber)), forwarder: Mutex::new(Some(forwarder)) })
+    }
+
+    /// The receiver handled `n` messages.
+    pub fn ack(&self, n: usize) {
+        let mut s = self.inbox.state.lock().unwrap();
+        s.credits = s.credits.saturating_add(n).min(self.inbox.capacity);
+        self.inbox.ready.notify_one();
+    }
+
+    /// Samples discarded by the drop-oldest queue so far.
+    pub fn dropped(&self) -> u64 {
+        self.inbox.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stops delivery and undeclares the subscriber. Idempotent.
+    pub fn undeclare(&self) {
+        self.inbox.close();
+        if let Some(subscriber) = self.subscriber.lock().unwrap().take() {
+            let _ = subscriber.undeclare().wait();
+        }
+        if let Some(forwarder) = self.forwarder.lock().unwrap().take() {
+            let _ = forwarder.join();
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.undeclare();
+    }
+}
+
+// The forwarder thread: ends on undeclare, or when the receiver is gone.
+fn forward(inbox: &Inbox, pid: LocalPid) {
+    let mut env = OwnedEnv::new();
+
+    while let Some(m) = inbox.next() {
+        let sent = env.send_and_clear(&pid, |env| {
+            let mut payload = OwnedBinary::new(m.payload.len()).unwrap();
+            payload.as_mut_slice().copy_from_slice(&m.payload);
+            (atoms::zenoh(), m.key, payload.release(env), m.timestamp_us).encode(env)
+        });
+
+        if sent.is_err() {
+            inbox.close(); // Receiver died: stop queueing for nobody
+            break;
+        }
+    }
+}
@@ -0,0 +1,63 @@
+// native/swarm_native/src/bus/wire.rs
+
+//! Payload layouts published on the bus. All little-endian, fixed layouts,
+//! no framing: one zenoh sample is one payload. Publications carry a zenoh
+//! timestamp; the payloads only hold what the sample itself lacks.
+//!
+//! * kinematics (32 B): vx, vy, px, py f32 | timestamp_us u64 | seq u64
+//! * flow (800 B):      200 x f32 (same bytes as `get_flow_grid`)
+//! * motion (16 B):     x, y, w, h u32 (frame pixels)
+//! * frame:             width u32 | height u32 | RGB24 rows
+//! * cells (20 B each): x i32 | y i32 | status u32 | version u64
+
+use crate::types::KinematicsSnapshot;
+use crate::vision::detector::MotionROI;
+
+pub const KINEMATICS_LEN: usize = 32;
+pub const CELL_LEN: usize = 20;
+
+pub fn kinematics(k: &KinematicsSnapshot) -> Vec<u8> {
+    let mut out = Vec::with_capacity(KINEMATICS_LEN);
+    for v in [k.vx, k.vy, k.px, k.py] {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.extend_from_slice(&k.timestamp_us.to_le_bytes());
+    out.extend_from_slice(&k.seq.to_le_bytes());
+    out
+}
+
+pub fn flow(grid: &[f32]) -> Vec<u8> {
+    grid.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+pub fn motion(roi: &MotionROI) -> Vec<u8> {
+    [roi.x, roi.y, roi.w, roi.h].iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+/// Nearest-neighbour downscale of an RGB24 frame by `scale` (1 = full size).
+pub fn frame(rgb: &[u8], width: usize, height: usize, scale: usize) -> Vec<u8> {
+    let scale = scale.max(1);
+    let (w, h) = (width / scale, height / scale);
+
+    let mut out = Vec::with_capacity(8 + w * h * 3);
+    out.extend_from_slice(&(w as u32).to_le_bytes());
+    out.extend_from_slice(&(h as u32).to_le_bytes());
+    for y in 0..h {
+        let row = y * scale * width;
+        for x in 0..w {
+            let i = (row + x * scale) * 3;
+            out.extend_from_slice(&rgb[i..i + 3]);
+        }
+    }
+    out
+}
+
+pub fn cells(out: &mut Vec<u8>, cells: &[(i32, i32, u32, u64)]) {
+    out.reserve(cells.len() * CELL_LEN);
+    for &(x, y, status, version) in cells {
+        out.extend_from_slice(&x.to_le_bytes());
+        out.extend_from_slice(&y.to_le_bytes());
+        out.extend_from_slice(&status.to_le_bytes());
+        out.extend_from_slice(&version.to_le_bytes());
+    }
+}
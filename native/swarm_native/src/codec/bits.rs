@@ -0,0 +1,105 @@
+// native/swarm_native/src/codec/bits.rs
+
+//! MSB-first bit packing (the order of Erlang's `<<a::10, b::6>>`), so a
+//! ground tool can pattern match packets with plain binary syntax.
+
+pub struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize, // Bits written so far
+}
+
+impl BitWriter {
+    pub fn with_capacity(bytes: usize) -> Self {
+        Self { bytes: Vec::with_capacity(bytes), bits: 0 }
+    }
+
+    /// Writes the low `width` bits of `value` (width <= 64).
+    pub fn put(&mut self, value: u64, width: u32) {
+        for i in (0..width).rev() {
+            if self.bits.is_multiple_of(8) {
+                self.bytes.push(0);
+            }
+            if (value >> i) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.bits % 8);
+            }
+            self.bits += 1;
+        }
+    }
+
+    /// Two's complement in `width` bits.
+    pub fn put_signed(&mut self, value: i64, width: u32) {
+        self.put(value as u64 & mask(width), width);
+    }
+
+    /// Zero padding up to the next byte boundary, then raw bytes.
+    pub fn put_bytes(&mut self, bytes: &[u8]) {
+        self.align();
+        self.bytes.extend_from_slice(bytes);
+        self.bits += bytes.len() * 8;
+    }
+
+    pub fn align(&mut self) {
+        self.bits = self.bits.next_multiple_of(8);
+    }
+
+    pub fn finish(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    bits: usize, // Bits consumed so far
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, bits: 0 }
+    }
+
+    /// `None` if fewer than `width` bits are left.
+    pub fn get(&mut self, width: u32) -> Option<u64> {
+        if self.bits + width as usize > self.bytes.len() * 8 {
+            return None;
+        }
+        let mut value = 0u64;
+        for _ in 0..width {
+            let bit = (self.bytes[self.bits / 8] >> (7 - self.bits % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.bits += 1;
+        }
+        Some(value)
+    }
+
+    pub fn get_signed(&mut self, width: u32) -> Option<i64> {
+        let raw = self.get(width)?;
+        let shift = 64 - width;
+        Some(((raw << shift) as i64) >> shift)
+    }
+
+    pub fn get_bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        if !self.align() {
+            return None;
+        }
+        let start = self.bits / 8;
+        let out = self.bytes.get(start..start.checked_add(len)?)?;
+        self.bits += len * 8;
+        Some(out)
+    }
+
+    /// Skips to the next byte boundary. False if the skipped bits were not zero.
+    pub fn align(&mut self) -> bool {
+        let pad = (8 - self.bits % 8) % 8;
+        pad == 0 || self.get(pad as u32) == Some(0)
+    }
+
+    /// Whole bytes left after the current position (padding excluded).
+    pub fn remaining(&self) -> usize {
+        self.bytes.len() - self.bits.div_ceil(8)
+    }
+}
+
+fn mask(width: u32) -> u64 {
+    if width >= 64 { u64::MAX } else { (1 << width) - 1 }
+}
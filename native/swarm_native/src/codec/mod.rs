@@ -0,0 +1,425 @@
+// native/swarm_native/src/codec/mod.rs
+
+//! THE TONGUE (Swarm Wire Format)
+//!
+//! One versioned packet format for everything drones say to each other and
+//! to the ground: the BEAM, the radio link and non-BEAM tools all go
+//! through these layouts, so there is exactly one definition of the bits.
+//!
+//! # Layout
+//! Bit-packed, most significant bit first (Erlang `<<x::size(n)>>` order),
+//! zero-padded to whole bytes. Signed fields are two's complement.
+//!
+//! Header (14 B): version u3 | kind u5 | node u16 | seq u32 | timestamp_us u56
+//!
+//! | kind | body          | fields                                                          | size  |
+//! |------|---------------|-----------------------------------------------------------------|-------|
+//! | 1    | vitals        | heading u12 (0.1°) · velocity u12 (0.05 m/s) · battery u7 (%)   | 4 B   |
+//! | 2    | pose          | x, y, z i24 (cm) · yaw u12 (0.1°) · vx, vy, vz i16 (cm/s)       | 17 B  |
+//! | 3    | detection     | class u8 · confidence u8 (1/255) · track u16 · x, y, w, h u10   | 9 B   |
+//! | 4    | spatial_delta | cursor u64 · length u24 · delta bytes (spatial/crdt.rs)         | 11 B+ |
+//! | 5    | command       | action u4 · target u16 (0 = all) · x, y, z i24 (cm) · param u16 | 14 B  |
+//!
+//! Floats are quantised to the nearest step and clamped to the field;
+//! angles wrap into [0, 360). NaN and infinities are refused.
+//!
+//! Decoding is strict: wrong version, short input, unknown kind or action,
+//! out-of-range values, non-zero padding and trailing bytes are all errors.
+//!
+//! # Golden vectors (version 1)
+//! Any implementation must produce exactly these bytes:
+//!
+//! * vitals, node 7, seq 42, t 1_700_000_000_000_000,
+//!   heading 90.0, velocity 12.5, battery 87:
+//!   `21 00 07 00 00 00 2a 06 0a 24 18 1e 40 00 38 40 fa ae`
+//! * pose, node 513, seq 1, t 1, x 1.5, y -2.25, z 10.0, yaw 359.95,
+//!   vx 0.5, vy -0.5, vz 0.0 (yaw wraps to 0.0):
+//!   `22 02 01 00 00 00 01 00 00 00 00 00 00 01 00 00 96 ff ff 1f 00 03 e8 00 00 03 2f fc e0 00 00`
+//! * detection, node 1, seq 2, t 3, class 0, confidence 1.0, track 77,
+//!   box (320, 240, 64, 48):
+//!   `23 00 01 00 00 00 02 00 00 00 00 00 00 03 00 ff 00 4d 50 0f 01 00 30`
+//! * spatial_delta, node 2, seq 3, t 4, cursor 9, delta `de ad`:
+//!   `24 00 02 00 00 00 03 00 00 00 00 00 00 04 00 00 00 00 00 00 00 09 00 00 02 de ad`
+//! * command goto, node 3, seq 4, t 5, target 0, (1.0, 2.0, -3.0), param 1:
+//!   `25 00 03 00 00 00 04 00 00 00 00 00 00 05 10 00 00 00 06 40 00 0c 8f ff ed 40 00 10`
+
+pub mod bits; // MSB-first bit writer / reader
+
+use rustler::{Binary, Decoder, Encoder, Env, NifResult, NifStruct, NifTaggedEnum, NifUnitEnum, OwnedBinary, Term};
+use serde::{Deserialize, Serialize};
+
+use bits::{BitReader, BitWriter};
+
+/// The only version this build speaks.
+pub const VERSION: u8 = 1;
+pub const HEADER_LEN: usize = 14;
+
+// Deltas larger than this must be split (`export_spatial_delta` limit)
+pub const MAX_DELTA_LEN: usize = (1 << 24) - 1;
+
+// Quantisation: steps per unit
+const PER_DEGREE: f32 = 10.0; // 0.1°
+const PER_MS: f32 = 20.0;     // 0.05 m/s
+const PER_METER: f32 = 100.0; // cm (positions, velocities)
+const PER_UNIT: f32 = 255.0;  // confidence
+
+const KIND_VITALS: u64 = 1;
+const KIND_POSE: u64 = 2;
+const KIND_DETECTION: u64 = 3;
+const KIND_SPATIAL_DELTA: u64 = 4;
+const KIND_COMMAND: u64 = 5;
+
+/// Mirrors `%SwarmBrain.Telemetry.Packet{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Telemetry.Packet"]
+pub struct Packet {
+    pub version: u8,       // Must be VERSION to encode
+    pub node: u16,         // Sender
+    pub seq: u32,          // Per-sender counter (wraps)
+    pub timestamp_us: u64, // µs since UNIX epoch, 56 bits on the wire
+    pub body: Body,
+}
+
+/// Encoded to Elixir as `{:vitals, %{heading, velocity, battery}}`, etc.
+#[derive(NifTaggedEnum, Clone, Debug, PartialEq)]
+pub enum Body {
+    Vitals { heading: f32, velocity: f32, battery: u8 },
+    Pose { x: f32, y: f32, z: f32, yaw: f32, vx: f32, vy: f32, vz: f32 },
+    Detection { class: u8, confidence: f32, track: u16, x: u16, y: u16, w: u16, h: u16 },
+    SpatialDelta { cursor: u64, delta: Bytes },
+    Command { action: Action, target: u16, x: f32, y: f32, z: f32, param: u16 },
+}
+
+/// Wire value is the position in `ACTIONS`: append only.
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Hover,
+    Goto,       // x, y, z
+    Land,
+    ReturnHome,
+    Takeoff,    // param = altitude (cm)
+    Arm,
+    Disarm,
+    Formation,  // param = formation id
+}
+
+const ACTIONS: [Action; 8] = [
+    Action::Hover,
+    Action::Goto,
+    Action::Land,
+    Action::ReturnHome,
+    Action::Takeoff,
+    Action::Arm,
+    Action::Disarm,
+    Action::Formation,
+];
+
+/// Raw bytes that cross the NIF boundary as an Erlang binary (not a list).
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Encoder for Bytes {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        let mut binary = OwnedBinary::new(self.0.len()).unwrap();
+        binary.as_mut_slice().copy_from_slice(&self.0);
+        binary.release(env).encode(env)
+    }
+}
+
+impl<'a> Decoder<'a> for Bytes {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        Ok(Bytes(Binary::decode(term)?.as_slice().to_vec()))
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CodecError {
+    Truncated,   // Input ends inside a field
+    Unsupported, // Unknown version
+    UnknownKind, // Unknown body kind
+    Malformed,   // Bad value, padding or trailing bytes
+    OutOfRange,  // Cannot be encoded (NaN, timestamp beyond 56 bits)
+    TooLarge,    // Delta longer than MAX_DELTA_LEN
+}
+
+pub fn encode(p: &Packet) -> Result<Vec<u8>, CodecError> {
+    if p.version != VERSION {
+        return Err(CodecError::Unsupported);
+    }
+    if p.timestamp_us >> 56 != 0 {
+        return Err(CodecError::OutOfRange);
+    }
+
+    // 1. Header
+    let mut w = BitWriter::with_capacity(HEADER_LEN + 17);
+    w.put(VERSION as u64, 3);
+    w.put(kind(&p.body), 5);
+    w.put(p.node as u64, 16);
+    w.put(p.seq as u64, 32);
+    w.put(p.timestamp_us, 56);
+
+    // 2. Body
+    match &p.body {
+        Body::Vitals { heading, velocity, battery } => {
+            w.put(angle(*heading)?, 12);
+            w.put(unsigned(*velocity, PER_MS, 12)?, 12);
+            w.put((*battery).min(100) as u64, 7);
+        }
+        Body::Pose { x, y, z, yaw, vx, vy, vz } => {
+            for v in [x, y, z] {
+                w.put_signed(signed(*v, PER_METER, 24)?, 24);
+            }
+            w.put(angle(*yaw)?, 12);
+            for v in [vx, vy, vz] {
+                w.put_signed(signed(*v, PER_METER, 16)?, 16);
+            }
+        }
+        Body::Detection { class, confidence, track, x, y, w: width, h } => {
+            w.put(*class as u64, 8);
+            w.put(unsigned(*confidence, PER_UNIT, 8)?, 8);
+            w.put(*track as u64, 16);
+            for v in [x, y, width, h] {
+                w.put((*v).min(1023) as u64, 10);
+            }
+        }
+        Body::SpatialDelta { cursor, delta } => {
+            if delta.0.len() > MAX_DELTA_LEN {
+                return Err(CodecError::TooLarge);
+            }
+            w.put(*cursor, 64);
+            w.put(delta.0.len() as u64, 24);
+            w.put_bytes(&delta.0);
+        }
+        Body::Command { action, target, x, y, z, param } => {
+            w.put(ACTIONS.iter().position(|a| a == action).unwrap() as u64, 4);
+            w.put(*target as u64, 16);
+            for v in [x, y, z] {
+                w.put_signed(signed(*v, PER_METER, 24)?, 24);
+            }
+            w.put(*param as u64, 16);
+        }
+    }
+
+    Ok(w.finish())
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Packet, CodecError> {
+    let mut r = BitReader::new(bytes);
+
+    // 1. Header (version first: later versions may lay out the rest differently)
+    let version = get(&mut r, 3)? as u8;
+    if version != VERSION {
+        return Err(CodecError::Unsupported);
+    }
+    let kind = get(&mut r, 5)?;
+    let node = get(&mut r, 16)? as u16;
+    let seq = get(&mut r, 32)? as u32;
+    let timestamp_us = get(&mut r, 56)?;
+
+    // 2. Body
+    let body = match kind {
+        KIND_VITALS => Body::Vitals {
+            heading: get_angle(&mut r)?,
+            velocity: get(&mut r, 12)? as f32 / PER_MS,
+            battery: match get(&mut r, 7)? {
+                b @ 0..=100 => b as u8,
+                _ => return Err(CodecError::Malformed),
+            },
+        },
+        KIND_POSE => Body::Pose {
+            x: get_signed(&mut r, 24)? as f32 / PER_METER,
+            y: get_signed(&mut r, 24)? as f32 / PER_METER,
+            z: get_signed(&mut r, 24)? as f32 / PER_METER,
+            yaw: get_angle(&mut r)?,
+            vx: get_signed(&mut r, 16)? as f32 / PER_METER,
+            vy: get_signed(&mut r, 16)? as f32 / PER_METER,
+            vz: get_signed(&mut r, 16)? as f32 / PER_METER,
+        },
+        KIND_DETECTION => Body::Detection {
+            class: get(&mut r, 8)? as u8,
+            confidence: get(&mut r, 8)? as f32 / PER_UNIT,
+            track: get(&mut r, 16)? as u16,
+            x: get(&mut r, 10)? as u16,
+            y: get(&mut r, 10)? as u16,
+            w: get(&mut r, 10)? as u16,
+            h: get(&mut r, 10)? as u16,
+        },
+        KIND_SPATIAL_DELTA => {
+            let cursor = get(&mut r, 64)?;
+            let len = get(&mut r, 24)? as usize;
+            let delta = r.get_bytes(len).ok_or(CodecError::Truncated)?;
+            Body::SpatialDelta { cursor, delta: Bytes(delta.to_vec()) }
+        }
+        KIND_COMMAND => Body::Command {
+            action: *ACTIONS.get(get(&mut r, 4)? as usize).ok_or(CodecError::Malformed)?,
+            target: get(&mut r, 16)? as u16,
+            x: get_signed(&mut r, 24)? as f32 / PER_METER,
+            y: get_signed(&mut r, 24)? as f32 / PER_METER,
+            z: get_signed(&mut r, 24)? as f32 / PER_METER,
+            param: get(&mut r, 16)? as u16,
+        },
+        _ => return Err(CodecError::UnknownKind),
+    };
+
+    // 3. Nothing may follow but zero padding
+    if !r.align() || r.remaining() != 0 {
+        return Err(CodecError::Malformed);
+    }
+
+    Ok(Packet { version, node, seq, timestamp_us, body })
+}
+
+fn kind(body: &Body) -> u64 {
+    match body {
+        Body::Vitals { .. } => KIND_VITALS,
+        Body::Pose { .. } => KIND_POSE,
+        Body::Detection { .. } => KIND_DETECTION,
+        Body::SpatialDelta { .. } => KIND_SPATIAL_DELTA,
+        Body::Command { .. } => KIND_COMMAND,
+    }
+}
+
+// --- QUANTISATION ---
+
+fn unsigned(v: f32, per_unit: f32, width: u32) -> Result<u64, CodecError> {
+    if !v.is_finite() {
+        return Err(CodecError::OutOfRange);
+    }
+    Ok(((v * per_unit).round().max(0.0) as u64).min((1 << width) - 1))
+}
+
+fn signed(v: f32, per_unit: f32, width: u32) -> Result<i64, CodecError> {
+    if !v.is_finite() {
+        return Err(CodecError::OutOfRange);
+    }
+    let max = (1i64 << (width - 1)) - 1;
+    Ok(((v * per_unit).round() as i64).clamp(-max - 1, max))
+}
+
+// Wrapped into [0, 360) first, so 359.99° becomes 0.0°, not 360.0°
+fn angle(degrees: f32) -> Result<u64, CodecError> {
+    if !degrees.is_finite() {
+        return Err(CodecError::OutOfRange);
+    }
+    Ok((degrees.rem_euclid(360.0) * PER_DEGREE).round() as u64 % 3600)
+}
+
+fn get(r: &mut BitReader, width: u32) -> Result<u64, CodecError> {
+    r.get(width).ok_or(CodecError::Truncated)
+}
+
+fn get_signed(r: &mut BitReader, width: u32) -> Result<i64, CodecError> {
+    r.get_signed(width).ok_or(CodecError::Truncated)
+}
+
+fn get_angle(r: &mut BitReader) -> Result<f32, CodecError> {
+    match get(r, 12)? {
+        q @ 0..3600 => Ok(q as f32 / PER_DEGREE),
+        _ => Err(CodecError::Malformed),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        s.split_whitespace().map(|b| u8::from_str_radix(b, 16).unwrap()).collect()
+    }
+
+    fn packet(node: u16, seq: u32, timestamp_us: u64, body: Body) -> Packet {
+        Packet { version: VERSION, node, seq, timestamp_us, body }
+    }
+
+    // (packet as sent, packet as decoded, bytes) for every vector in the module docs
+    fn golden() -> Vec<(Packet, Packet, Vec<u8>)> {
+        let vitals = packet(7, 42, 1_700_000_000_000_000, Body::Vitals { heading: 90.0, velocity: 12.5, battery: 87 });
+        let pose = |yaw| {
+            packet(513, 1, 1, Body::Pose { x: 1.5, y: -2.25, z: 10.0, yaw, vx: 0.5, vy: -0.5, vz: 0.0 })
+        };
+        let detection = packet(
+            1,
+            2,
+            3,
+            Body::Detection { class: 0, confidence: 1.0, track: 77, x: 320, y: 240, w: 64, h: 48 },
+        );
+        let delta = packet(2, 3, 4, Body::SpatialDelta { cursor: 9, delta: Bytes(vec![0xde, 0xad]) });
+        let command = packet(
+            3,
+            4,
+            5,
+            Body::Command { action: Action::Goto, target: 0, x: 1.0, y: 2.0, z: -3.0, param: 1 },
+        );
+
+        vec![
+            (vitals.clone(), vitals, hex("21 00 07 00 00 00 2a 06 0a 24 18 1e 40 00 38 40 fa ae")),
+            (
+                pose(359.95),
+                pose(0.0),
+                hex("22 02 01 00 00 00 01 00 00 00 00 00 00 01 00 00 96 ff ff 1f 00 03 e8 00 00 03 2f fc e0 00 00"),
+            ),
+            (detection.clone(), detection, hex("23 00 01 00 00 00 02 00 00 00 00 00 00 03 00 ff 00 4d 50 0f 01 00 30")),
+            (delta.clone(), delta, hex("24 00 02 00 00 00 03 00 00 00 00 00 00 04 00 00 00 00 00 00 00 09 00 00 02 de ad")),
+            (
+                command.clone(),
+                command,
+                hex("25 00 03 00 00 00 04 00 00 00 00 00 00 05 10 00 00 00 06 40 00 0c 8f ff ed 40 00 10"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn golden_vectors_encode_byte_exact() {
+        for (sent, _, bytes) in golden() {
+            assert_eq!(encode(&sent).unwrap(), bytes, "{:?}", sent.body);
+        }
+    }
+
+    #[test]
+    fn golden_vectors_round_trip() {
+        for (_, decoded, bytes) in golden() {
+            assert_eq!(decode(&bytes).unwrap(), decoded);
+            assert_eq!(encode(&decoded).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn decoding_is_strict() {
+        let (_, _, bytes) = golden().swap_remove(0);
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(decode(&trailing), Err(CodecError::Malformed));
+
+        let mut padded = bytes.clone();
+        *padded.last_mut().unwrap() |= 1; // Vitals end 1 bit short of a byte
+        assert_eq!(decode(&padded), Err(CodecError::Malformed));
+
+        let mut version = bytes.clone();
+        version[0] = (2 << 5) | 1;
+        assert_eq!(decode(&version), Err(CodecError::Unsupported));
+
+        let mut kind = bytes.clone();
+        kind[0] = (1 << 5) | 31;
+        assert_eq!(decode(&kind), Err(CodecError::UnknownKind));
+
+        for len in 0..bytes.len() {
+            assert_eq!(decode(&bytes[..len]), Err(CodecError::Truncated), "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn unencodable_values_are_refused() {
+        let vitals = |heading| packet(1, 1, 1, Body::Vitals { heading, velocity: 0.0, battery: 0 });
+        assert_eq!(encode(&vitals(f32::NAN)), Err(CodecError::OutOfRange));
+        assert_eq!(encode(&vitals(f32::INFINITY)), Err(CodecError::OutOfRange));
+
+        let mut late = vitals(0.0);
+        late.timestamp_us = 1 << 56;
+        assert_eq!(encode(&late), Err(CodecError::OutOfRange));
+
+        let mut future = vitals(0.0);
+        future.version = VERSION + 1;
+        assert_eq!(encode(&future), Err(CodecError::Unsupported));
+    }
+}
@@ -0,0 +1,256 @@
+// native/swarm_native/src/crypto/mod.rs
+
+//! THE SEAL (Authenticated Swarm Links)
+//!
+//! ChaCha20-Poly1305 around any swarm payload (usually a codec packet), so
+//! nobody on the radio channel can forge or replay a leader vector.
+//!
+//! Frame: `key_id u8 | sender u16 | counter u64 | ciphertext | tag`
+//! (big-endian). The 11-byte header is the associated data: authenticated,
+//! not encrypted, so a receiver can pick the key and the replay window
+//! before spending any work on the tag.
+//!
+//! # Nonces
+//! `0x0000 | sender | counter`, counter = `boot u32 | frame u32`. `boot` is
+//! a persisted per-node count bumped before every keyring is built
+//! (`SwarmBrain.Crypto`), so a reboot never reuses a nonce, even on boards
+//! without a real-time clock, and counters still only grow for the replay
+//! windows. Node ids must be unique under a given swarm key (configured,
+//! never derived).
+//!
+//! # Truncated tags
+//! Each key carries a tag length of 4..=16 bytes. Short tags trade forgery
+//! resistance (2^-8n per attempt) for airtime; 8 is a sane floor for LoRa.
+//!
+//! # Replay
+//! One sliding window per (key, sender), advanced only after the tag
+//! verifies: a forged frame cannot push a window forward.
+
+pub mod replay; // Sliding window
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use chacha20::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use chacha20::ChaCha20;
+use chacha20poly1305::aead::{AeadInPlace, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, Tag};
+use subtle::ConstantTimeEq;
+
+use replay::ReplayWindow;
+
+pub const HEADER_LEN: usize = 11;
+pub const KEY_LEN: usize = 32;
+pub const MIN_TAG_LEN: usize = 4;
+pub const MAX_TAG_LEN: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum SealError {
+    InvalidKey, // Not 32 bytes, or tag length outside 4..=16
+    UnknownKey, // No key under this id
+    Truncated,  // Shorter than header + tag
+    BadTag,     // Forged, corrupted or sealed under another key
+    Replay,     // Counter already seen, or older than the window
+    Exhausted,  // 2^32 frames sealed this boot: rebuild with the next boot
+}
+
+/// A frame that passed `open`.
+pub struct Opened {
+    pub key_id: u8,
+    pub sender: u16,
+    pub plaintext: Vec<u8>,
+}
+
+struct SwarmKey {
+    aead: ChaCha20Poly1305,
+    key: Key,
+    tag_len: usize,
+}
+
+/// Swarm keys, our counter and every sender's replay window
+/// (the ResourceArc payload).
+pub struct Keyring {
+    node: u16,
+    boot: u32,
+    counter: AtomicU64,
+    keys: RwLock<HashMap<u8, SwarmKey>>,
+    windows: Mutex<HashMap<(u8, u16), ReplayWindow>>,
+}
+
+impl std::panic::RefUnwindSafe for Keyring {}
+
+impl Keyring {
+    pub fn new(node: u16, boot: u32) -> Self {
+        Self {
+            node,
+            boot,
+            counter: AtomicU64::new((boot as u64) << 32),
+            keys: RwLock::new(HashMap::new()),
+            windows: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds or replaces a key. Replay windows survive a replacement:
+    /// counters keep counting across key rotations.
+    pub fn insert(&self, id: u8, key: &[u8], tag_len: usize) -> Result<(), SealError> {
+        if key.len() != KEY_LEN || !(MIN_TAG_LEN..=MAX_TAG_LEN).contains(&tag_len) {
+            return Err(SealError::InvalidKey);
+        }
+        let key = *Key::from_slice(key);
+        let entry = SwarmKey { aead: ChaCha20Poly1305::new(&key), key, tag_len };
+        self.keys.write().unwrap().insert(id, entry);
+        Ok(())
+    }
+
+    pub fn remove(&self, id: u8) {
+        self.keys.write().unwrap().remove(&id);
+        self.windows.lock().unwrap().retain(|(key_id, _), _| *key_id != id);
+    }
+
+    pub fn seal(&self, id: u8, plaintext: &[u8]) -> Result<Vec<u8>, SealError> {
+        let keys = self.keys.read().unwrap();
+        let k = keys.get(&id).ok_or(SealError::UnknownKey)?;
+        let counter = self.counter.fetch_add(1, Ordering::Relaxed);
+        if counter >> 32 != self.boot as u64 {
+            return Err(SealError::Exhausted); // Next boot's nonces are not ours
+        }
+
+        // 1. Header (the associated data)
+        let mut frame = Vec::with_capacity(HEADER_LEN + plaintext.len() + k.tag_len);
+        frame.push(id);
+        frame.extend_from_slice(&self.node.to_be_bytes());
+        frame.extend_from_slice(&counter.to_be_bytes());
+
+        // 2. Ciphertext + (truncated) tag
+        frame.extend_from_slice(plaintext);
+        let (aad, body) = frame.split_at_mut(HEADER_LEN);
+        let tag = k
+            .aead
+            .encrypt_in_place_detached(&nonce(self.node, counter), aad, body)
+            .map_err(|_| SealError::InvalidKey)?;
+        frame.extend_from_slice(&tag[..k.tag_len]);
+        Ok(frame)
+    }
+
+    pub fn open(&self, frame: &[u8]) -> Result<Opened, SealError> {
+        // 1. Key and header
+        let &key_id = frame.first().ok_or(SealError::Truncated)?;
+        let keys = self.keys.read().unwrap();
+        let k = keys.get(&key_id).ok_or(SealError::UnknownKey)?;
+        if frame.len() < HEADER_LEN + k.tag_len {
+            return Err(SealError::Truncated);
+        }
+        let sender = u16::from_be_bytes([frame[1], frame[2]]);
+        let counter = u64::from_be_bytes(frame[3..HEADER_LEN].try_into().unwrap());
+
+        // 2. Cheap replay pre-check: no crypto spent on obvious replays
+        if !self.windows.lock().unwrap().get(&(key_id, sender)).is_none_or(|w| w.check(counter)) {
+            return Err(SealError::Replay);
+        }
+
+        // 3. Tag
+        let (aad, rest) = frame.split_at(HEADER_LEN);
+        let (ciphertext, tag) = rest.split_at(rest.len() - k.tag_len);
+        let mut plaintext = ciphertext.to_vec();
+        decrypt(k, &nonce(sender, counter), aad, &mut plaintext, tag)?;
+
+        // 4. Window (re-checked: a duplicate may have verified meanwhile)
+        if !self.windows.lock().unwrap().entry((key_id, sender)).or_default().accept(counter) {
+            return Err(SealError::Replay);
+        }
+
+        Ok(Opened { key_id, sender, plaintext })
+    }
+}
+
+fn nonce(sender: u16, counter: u64) -> Nonce {
+    let mut n = [0u8; 12];
+    n[2..4].copy_from_slice(&sender.to_be_bytes());
+    n[4..].copy_from_slice(&counter.to_be_bytes());
+    Nonce::from(n)
+}
+
+// Full tags go through the AEAD as is. A truncated tag cannot: decrypt
+// with the raw stream, re-seal the plaintext (same nonce, same key: same
+// ciphertext) and compare the tag prefix in constant time.
+fn decrypt(k: &SwarmKey, nonce: &Nonce, aad: &[u8], buf: &mut [u8], tag: &[u8]) -> Result<(), SealError> {
+    if tag.len() == MAX_TAG_LEN {
+        return k.aead.decrypt_in_place_detached(nonce, aad, buf, Tag::from_slice(tag)).map_err(|_| SealError::BadTag);
+    }
+
+    let mut stream = ChaCha20::new(&k.key, nonce);
+    stream.seek(64u64); // Block 0 keys Poly1305; the payload starts at block 1
+    stream.apply_keystream(buf);
+
+    let mut resealed = buf.to_vec();
+    let full = k.aead.encrypt_in_place_detached(nonce, aad, &mut resealed).map_err(|_| SealError::BadTag)?;
+    if bool::from(full[..tag.len()].ct_eq(tag)) {
+        Ok(())
+    } else {
+        buf.fill(0); // Unauthenticated plaintext never leaves
+        Err(SealError::BadTag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; KEY_LEN] = [7; KEY_LEN];
+
+    fn keyring(node: u16, boot: u32) -> Keyring {
+        let k = Keyring::new(node, boot);
+        k.insert(1, &KEY, 8).unwrap();
+        k
+    }
+
+    fn counter(frame: &[u8]) -> u64 {
+        u64::from_be_bytes(frame[3..HEADER_LEN].try_into().unwrap())
+    }
+
+    #[test]
+    fn seal_open_round_trip() {
+        let (tx, rx) = (keyring(3, 0), keyring(4, 0));
+        let frame = tx.seal(1, b"leader").unwrap();
+        let opened = rx.open(&frame).unwrap();
+        assert_eq!((opened.key_id, opened.sender, opened.plaintext.as_slice()), (1, 3, &b"leader"[..]));
+        assert_eq!(rx.open(&frame).err(), Some(SealError::Replay));
+
+        let mut forged = tx.seal(1, b"leader").unwrap();
+        forged[HEADER_LEN] ^= 1;
+        assert_eq!(rx.open(&forged).err(), Some(SealError::BadTag));
+    }
+
+    #[test]
+    fn every_boot_has_its_own_nonces() {
+        let rx = keyring(4, 0);
+        let first = keyring(3, 41);
+        let before = first.seal(1, b"a").unwrap();
+        assert_eq!(counter(&before), 41 << 32);
+        rx.open(&before).unwrap();
+
+        // Rebooted (same wall clock or not): fresh nonces, still accepted
+        let second = keyring(3, 42);
+        let after = second.seal(1, b"a").unwrap();
+        assert_eq!(counter(&after), 42 << 32);
+        assert_ne!(after, before);
+        rx.open(&after).unwrap();
+
+        // A frame from the old boot is now outside the window
+        assert_eq!(rx.open(&first.seal(1, b"a").unwrap()).err(), Some(SealError::Replay));
+    }
+
+    #[test]
+    fn sealing_stops_before_the_next_boot() {
+        let tx = keyring(3, 5);
+        tx.counter.store((6 << 32) - 1, Ordering::Relaxed);
+        assert_eq!(counter(&tx.seal(1, b"a").unwrap()), (6 << 32) - 1);
+        assert_eq!(tx.seal(1, b"a").err(), Some(SealError::Exhausted));
+
+        let last = keyring(3, u32::MAX);
+        last.counter.store(u64::MAX, Ordering::Relaxed);
+        assert_eq!(counter(&last.seal(1, b"a").unwrap()), u64::MAX);
+        assert_eq!(last.seal(1, b"a").err(), Some(SealError::Exhausted));
+    }
+}
@@ -0,0 +1,45 @@
+// native/swarm_native/src/crypto/replay.rs
+
+//! Sliding replay window (the IPsec / WireGuard scheme): the highest
+//! counter accepted plus a bitmap of the WINDOW counters below it. Late
+//! packets inside the window pass once; older ones are refused.
+
+pub const WINDOW: u64 = 128;
+
+#[derive(Default)]
+pub struct ReplayWindow {
+    top: Option<u64>, // Highest counter accepted
+    seen: u128,       // Bit i set = counter top - i accepted
+}
+
+impl ReplayWindow {
+    /// Whether `counter` is fresh (no state change).
+    pub fn check(&self, counter: u64) -> bool {
+        match self.top {
+            None => true,
+            Some(top) if counter > top => true,
+            Some(top) => top - counter < WINDOW && self.seen & (1 << (top - counter)) == 0,
+        }
+    }
+
+    /// Marks `counter` as used. False if it was not fresh (nothing changes).
+    /// Only call with counters whose tag verified.
+    pub fn accept(&mut self, counter: u64) -> bool {
+        if !self.check(counter) {
+            return false;
+        }
+        match self.top {
+            Some(top) if counter <= top => self.seen |= 1 << (top - counter),
+            Some(top) => {
+                let shift = counter - top;
+                self.seen = if shift >= WINDOW { 1 } else { (self.seen << shift) | 1 };
+                self.top = Some(counter);
+            }
+            None => {
+                self.seen = 1;
+                self.top = Some(counter);
+            }
+        }
+        true
+    }
+}
@@ -0,0 +1,53 @@
+// native/swarm_native/src/fec/fountain.rs
+
+//! RaptorQ (RFC 6330) in one source block: the payload is cut into at most
+//! `k` source symbols, followed by repair symbols up to `n` shards. Symbol
+//! size is derived from (length, k), so shards need no RaptorQ config.
+
+use raptorq::{Decoder, Encoder, EncodingPacket, ObjectTransmissionInformation};
+
+use super::FecError;
+
+const ALIGNMENT: usize = 8;
+const MAX_SYMBOL: usize = 65_528;         // Largest u16 multiple of ALIGNMENT
+const MAX_SOURCE_SYMBOLS: usize = 56_403; // RFC 6330 K'max
+const PAYLOAD_ID_LEN: usize = 4;          // Block number + symbol id
+
+pub fn encode(payload: &[u8], k: usize, n: usize) -> Result<Vec<Vec<u8>>, FecError> {
+    let config = config(payload.len(), k)?;
+    let encoder = Encoder::new(payload, config);
+    let block = &encoder.get_block_encoders()[0];
+
+    let mut packets = block.source_packets();
+    let repair = n.saturating_sub(packets.len()) as u32;
+    packets.extend(block.repair_packets(0, repair));
+    Ok(packets.iter().map(EncodingPacket::serialize).collect())
+}
+
+pub fn decode(length: usize, k: usize, bodies: &[(usize, &[u8])]) -> Result<Vec<u8>, FecError> {
+    let config = config(length, k).map_err(|_| FecError::Insufficient)?;
+    let expected = PAYLOAD_ID_LEN + config.symbol_size() as usize;
+
+    let mut decoder = Decoder::new(config);
+    for &(_, body) in bodies {
+        if body.len() != expected {
+            continue;
+        }
+        if let Some(payload) = decoder.decode(EncodingPacket::deserialize(body)) {
+            return Ok(payload);
+        }
+    }
+    Err(FecError::Insufficient)
+}
+
+// Smallest aligned symbol that fits the payload in k symbols
+fn config(length: usize, k: usize) -> Result<ObjectTransmissionInformation, FecError> {
+    if length == 0 || k == 0 || k > MAX_SOURCE_SYMBOLS {
+        return Err(FecError::InvalidParams);
+    }
+    let symbol = length.div_ceil(k).next_multiple_of(ALIGNMENT);
+    if symbol > MAX_SYMBOL {
+        return Err(FecError::TooLarge);
+    }
+    Ok(ObjectTransmissionInformation::new(length as u64, symbol as u16, 1, 1, ALIGNMENT as u8))
+}
@@ -0,0 +1,255 @@
+// native/swarm_native/src/fec/mod.rs
+
+//! THE MENDER (Forward Error Correction)
+//!
+//! Splits a payload into `n` shards so that any `k` of them rebuild it: the
+//! radio link loses and mangles frames, and asking again costs a round
+//! trip the swarm does not have.
+//!
+//! * `reed_solomon` (rs.rs): systematic Reed-Solomon over GF(2^8). Exactly
+//!   `k` shards suffice; `k < n <= 256`. For short frames on bursty links.
+//! * `raptorq` (fountain.rs): RaptorQ fountain code. `k` shards almost
+//!   always suffice (`k + 2` practically always); `n` up to 65535, so large
+//!   payloads (thumbnails, map deltas) can be sprayed over many frames.
+//!
+//! # Shard layout (big-endian, 17-byte header)
+//! `scheme u8 | message u16 | k u16 | n u16 | index u16 | length u32 | crc32 u32 | body`
+//!
+//! The CRC covers the header fields and the body, so a corrupted shard is
+//! simply an erasure. Shards carry everything needed to decode: any
+//! receiver can rebuild a payload from shards alone.
+
+pub mod fountain; // RaptorQ
+pub mod rs;       // Reed-Solomon
+
+use rustler::{NifMap, NifUnitEnum};
+
+pub const HEADER_LEN: usize = 17;
+
+// Largest payload accepted (the length field is u32)
+pub const MAX_PAYLOAD: usize = u32::MAX as usize;
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheme {
+    ReedSolomon,
+    Raptorq,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FecError {
+    InvalidParams, // Empty payload, or k / n outside the scheme's limits
+    TooLarge,      // Payload too long for the scheme with this k
+    Insufficient,  // Not enough intact shards of one message
+    Corrupt,       // Shard fails its CRC or is not a shard
+}
+
+/// A shard header, as returned by `fec_shard_info`.
+#[derive(NifMap, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ShardInfo {
+    pub scheme: Scheme,
+    pub message: u16,
+    pub k: u16,
+    pub n: u16,
+    pub index: u16,
+    pub length: u32,
+}
+
+impl ShardInfo {
+    // Shards of the same message agree on everything but the index
+    fn same_message(&self, other: &ShardInfo) -> bool {
+        ShardInfo { index: other.index, ..*self } == *other
+    }
+}
+
+pub fn encode(payload: &[u8], scheme: Scheme, message: u16, k: usize, n: usize) -> Result<Vec<Vec<u8>>, FecError> {
+    if payload.is_empty() || k == 0 || n < k || n > u16::MAX as usize {
+        return Err(FecError::InvalidParams);
+    }
+    if payload.len() > MAX_PAYLOAD {
+        return Err(FecError::TooLarge);
+    }
+
+    let bodies = match scheme {
+        Scheme::ReedSolomon => rs::encode(payload, k, n)?,
+        Scheme::Raptorq => fountain::encode(payload, k, n)?,
+    };
+
+    Ok(bodies
+        .into_iter()
+        .enumerate()
+        .map(|(index, body)| {
+            let info = ShardInfo { scheme, message, k: k as u16, n: n as u16, index: index as u16, length: payload.len() as u32 };
+            shard(&info, &body)
+        })
+        .collect())
+}
+
+/// Rebuilds the message of the first intact shard. Corrupt shards, other
+/// messages and duplicates are skipped.
+pub fn decode(shards: &[&[u8]]) -> Result<Vec<u8>, FecError> {
+    // 1. Intact shards of one message
+    let mut head: Option<ShardInfo> = None;
+    let mut bodies: Vec<(usize, &[u8])> = Vec::new();
+    for s in shards {
+        let Ok(info) = info(s) else { continue };
+        let first = *head.get_or_insert(info);
+        if first.same_message(&info) && !bodies.iter().any(|(i, _)| *i == info.index as usize) {
+            bodies.push((info.index as usize, &s[HEADER_LEN..]));
+        }
+    }
+    let info = head.ok_or(FecError::Insufficient)?;
+
+    // 2. Scheme
+    let (k, n, length) = (info.k as usize, info.n as usize, info.length as usize);
+    match info.scheme {
+        Scheme::ReedSolomon => rs::decode(length, k, n, &bodies),
+        Scheme::Raptorq => fountain::decode(length, k, &bodies),
+    }
+}
+
+/// Parses and checks a shard header.
+pub fn info(shard: &[u8]) -> Result<ShardInfo, FecError> {
+    if shard.len() < HEADER_LEN {
+        return Err(FecError::Corrupt);
+    }
+    let u16_at = |i: usize| u16::from_be_bytes([shard[i], shard[i + 1]]);
+    let u32_at = |i: usize| u32::from_be_bytes(shard[i..i + 4].try_into().unwrap());
+
+    if checksum(&shard[..13], &shard[HEADER_LEN..]) != u32_at(13) {
+        return Err(FecError::Corrupt);
+    }
+    let scheme = match shard[0] {
+        0 => Scheme::ReedSolomon,
+        1 => Scheme::Raptorq,
+        _ => return Err(FecError::Corrupt),
+    };
+    Ok(ShardInfo { scheme, message: u16_at(1), k: u16_at(3), n: u16_at(5), index: u16_at(7), length: u32_at(9) })
+}
+
+fn shard(info: &ShardInfo, body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(HEADER_LEN + body.len());
+    out.push(info.scheme as u8);
+    for v in [info.message, info.k, info.n, info.index] {
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+    out.extend_from_slice(&info.length.to_be_bytes());
+    let crc = checksum(&out, body);
+    out.extend_from_slice(&crc.to_be_bytes());
+    out.extend_from_slice(body);
+    out
+}
+
+fn checksum(header: &[u8], body: &[u8]) -> u32 {
+    let mut h = crc32fast::Hasher::new();
+    h.update(header);
+    h.update(body);
+    h.finalize()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    // RaptorQ needs a little overhead to be practically certain (module docs)
+    const RAPTORQ_OVERHEAD: usize = 2;
+
+    // (largest k tried, shards needed beyond k)
+    fn limits(scheme: Scheme) -> (usize, usize) {
+        match scheme {
+            Scheme::ReedSolomon => (16, 0),
+            Scheme::Raptorq => (32, RAPTORQ_OVERHEAD),
+        }
+    }
+
+    fn keep(shards: &[Vec<u8>], indices: &[usize]) -> Vec<Vec<u8>> {
+        indices.iter().map(|&i| shards[i].clone()).collect()
+    }
+
+    fn decode_owned(shards: &[Vec<u8>]) -> Result<Vec<u8>, FecError> {
+        decode(&shards.iter().map(Vec::as_slice).collect::<Vec<_>>())
+    }
+
+    // (payload, k, n, surviving indices in arrival order); `needed` of the n survive
+    fn random_loss(scheme: Scheme) -> impl Strategy<Value = (Vec<u8>, usize, usize, Vec<usize>)> {
+        let (max_k, overhead) = limits(scheme);
+        (1..=max_k, 1..=8usize).prop_flat_map(move |(k, extra)| {
+            let n = k + overhead + extra;
+            let survivors = prop::sample::subsequence((0..n).collect::<Vec<_>>(), k + overhead).prop_shuffle();
+            (prop::collection::vec(any::<u8>(), 1..600), Just(k), Just(n), survivors)
+        })
+    }
+
+    // Same, but the losses are one contiguous run (wrapping), as a fade would cause
+    fn burst_loss(scheme: Scheme) -> impl Strategy<Value = (Vec<u8>, usize, usize, Vec<usize>)> {
+        let (max_k, overhead) = limits(scheme);
+        (1..=max_k, 1..=8usize).prop_flat_map(move |(k, extra)| {
+            let n = k + overhead + extra;
+            let survivors = (0..n).prop_map(move |start| (0..k + overhead).map(|i| (start + extra + i) % n).collect());
+            (prop::collection::vec(any::<u8>(), 1..600), Just(k), Just(n), survivors)
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn reed_solomon_survives_random_loss((payload, k, n, survivors) in random_loss(Scheme::ReedSolomon)) {
+            let shards = encode(&payload, Scheme::ReedSolomon, 7, k, n).unwrap();
+            prop_assert_eq!(decode_owned(&keep(&shards, &survivors)).unwrap(), payload);
+        }
+
+        #[test]
+        fn reed_solomon_survives_burst_loss((payload, k, n, survivors) in burst_loss(Scheme::ReedSolomon)) {
+            let shards = encode(&payload, Scheme::ReedSolomon, 7, k, n).unwrap();
+            prop_assert_eq!(decode_owned(&keep(&shards, &survivors)).unwrap(), payload);
+        }
+
+        #[test]
+        fn raptorq_survives_random_loss((payload, k, n, survivors) in random_loss(Scheme::Raptorq)) {
+            let shards = encode(&payload, Scheme::Raptorq, 7, k, n).unwrap();
+            prop_assert_eq!(decode_owned(&keep(&shards, &survivors)).unwrap(), payload);
+        }
+
+        #[test]
+        fn raptorq_survives_burst_loss((payload, k, n, survivors) in burst_loss(Scheme::Raptorq)) {
+            let shards = encode(&payload, Scheme::Raptorq, 7, k, n).unwrap();
+            prop_assert_eq!(decode_owned(&keep(&shards, &survivors)).unwrap(), payload);
+        }
+
+        #[test]
+        fn reed_solomon_needs_k_shards((payload, k, n, survivors) in random_loss(Scheme::ReedSolomon)) {
+            let shards = encode(&payload, Scheme::ReedSolomon, 7, k, n).unwrap();
+            prop_assert_eq!(decode_owned(&keep(&shards, &survivors[1..])), Err(FecError::Insufficient));
+        }
+    }
+
+    #[test]
+    fn corrupt_shards_are_erasures() {
+        let payload: Vec<u8> = (0..=255).collect();
+        let mut shards = encode(&payload, Scheme::ReedSolomon, 1, 4, 6).unwrap();
+        shards[0][HEADER_LEN] ^= 0x80; // Body bit flip
+        shards[1][3] ^= 0x01;          // Header bit flip
+        assert_eq!(info(&shards[0]), Err(FecError::Corrupt));
+        assert_eq!(decode_owned(&shards).unwrap(), payload);
+        assert_eq!(decode_owned(&shards[..5]), Err(FecError::Insufficient));
+    }
+
+    #[test]
+    fn other_messages_and_duplicates_are_skipped() {
+        let payload = b"leader vector".to_vec();
+        let ours = encode(&payload, Scheme::ReedSolomon, 1, 2, 4).unwrap();
+        let theirs = encode(b"something else", Scheme::ReedSolomon, 2, 2, 4).unwrap();
+        let mixed = vec![ours[3].clone(), theirs[0].clone(), ours[3].clone(), theirs[1].clone(), ours[0].clone()];
+        assert_eq!(decode_owned(&mixed).unwrap(), payload);
+        assert_eq!(decode_owned(&mixed[..4]), Err(FecError::Insufficient));
+    }
+
+    #[test]
+    fn invalid_parameters_are_refused() {
+        assert_eq!(encode(&[], Scheme::ReedSolomon, 0, 1, 2), Err(FecError::InvalidParams));
+        assert_eq!(encode(b"x", Scheme::ReedSolomon, 0, 0, 2), Err(FecError::InvalidParams));
+        assert_eq!(encode(b"x", Scheme::ReedSolomon, 0, 2, 1), Err(FecError::InvalidParams));
+        assert_eq!(encode(b"x", Scheme::ReedSolomon, 0, 2, 2), Err(FecError::InvalidParams));
+        assert_eq!(encode(b"x", Scheme::ReedSolomon, 0, 2, 257), Err(FecError::InvalidParams));
+        assert_eq!(encode(b"x", Scheme::Raptorq, 0, 1, 65_536), Err(FecError::InvalidParams));
+    }
+}
@@ -0,0 +1,54 @@
+// native/swarm_native/src/fec/rs.rs
+
+//! Systematic Reed-Solomon: the first `k` shards are the payload itself
+//! (zero-padded to equal size), the other `n - k` are parity. Any `k`
+//! shards rebuild the rest.
+
+use reed_solomon_erasure::galois_8::ReedSolomon;
+
+use super::FecError;
+
+// GF(2^8): at most 256 shards in total
+pub const MAX_SHARDS: usize = 256;
+
+pub fn encode(payload: &[u8], k: usize, n: usize) -> Result<Vec<Vec<u8>>, FecError> {
+    let codec = codec(k, n)?;
+    let size = payload.len().div_ceil(k);
+
+    let mut shards: Vec<Vec<u8>> = (0..n)
+        .map(|i| {
+            let mut s = payload.get(i * size..).map_or(&[][..], |rest| &rest[..size.min(rest.len())]).to_vec();
+            s.resize(size, 0);
+            s
+        })
+        .collect();
+    codec.encode(&mut shards).map_err(|_| FecError::InvalidParams)?;
+    Ok(shards)
+}
+
+pub fn decode(length: usize, k: usize, n: usize, bodies: &[(usize, &[u8])]) -> Result<Vec<u8>, FecError> {
+    let codec = codec(k, n).map_err(|_| FecError::Insufficient)?;
+    let size = length.div_ceil(k);
+
+    let mut shards: Vec<Option<Vec<u8>>> = vec![None; n];
+    for &(i, body) in bodies {
+        if i < n && body.len() == size {
+            shards[i] = Some(body.to_vec());
+        }
+    }
+    if shards.iter().flatten().count() < k {
+        return Err(FecError::Insufficient);
+    }
+    codec.reconstruct_data(&mut shards).map_err(|_| FecError::Insufficient)?;
+
+    let mut payload: Vec<u8> = shards.into_iter().take(k).flatten().flatten().collect();
+    payload.truncate(length);
+    Ok(payload)
+}
+
+fn codec(k: usize, n: usize) -> Result<ReedSolomon, FecError> {
+    if k == 0 || n <= k || n > MAX_SHARDS {
+        return Err(FecError::InvalidParams);
+    }
+    ReedSolomon::new(k, n - k).map_err(|_| FecError::InvalidParams)
+}
@@ -7,9 +7,17 @@
 
 // 1. Module Registration
 // These must match the folder names in src/
-mod types;
+pub mod types; // Public (via the rlib): the atomic toolbox
 mod state;
 mod vision;
+mod spatial;
+mod bus;
+mod codec;
+mod crypto;
+mod fec;
+mod serial;
+mod reflex;
+mod recorder;
 mod nifs;
 
 use rustler::{Env, Term};
@@ -19,6 +27,22 @@ use rustler::{Env, Term};
 fn load(env: Env, _info: Term) -> bool {
     // Matches src/state/arena.rs
     rustler::resource!(state::arena::SwarmState, env);
+    // Matches src/spatial/planner.rs (D* Lite handles)
+    rustler::resource!(spatial::planner::Replanner, env);
+    // Matches src/bus/subscriber.rs (zenoh subscriptions)
+    rustler::resource!(bus::subscriber::Subscription, env);
+    // Matches src/crypto/mod.rs (swarm keys + replay windows)
+    rustler::resource!(crypto::Keyring, env);
+    // Matches src/serial/msp.rs (per-UART MSP parsers)
+    rustler::resource!(serial::msp::MspStream, env);
+    // Matches src/serial/mavlink/mod.rs (per-autopilot MAVLink links)
+    rustler::resource!(serial::mavlink::MavlinkLink, env);
+    // Matches src/serial/crsf.rs (per-receiver CRSF parsers)
+    rustler::resource!(serial::crsf::CrsfStream, env);
+    // Matches src/reflex/mod.rs (RC mixer + failsafe)
+    rustler::resource!(reflex::Reflex, env);
+    // Matches src/recorder/reader.rs (black box playback cursors)
+    rustler::resource!(recorder::reader::Recording, env);
     true
 }
 
@@ -35,13 +59,107 @@ rustler::init!(
         // CHANGED: 'sensing' -> 'telemetry' to match your file name
         nifs::telemetry::get_latest_frame,
         nifs::telemetry::get_fused_state,
+        nifs::telemetry::get_kinematics,
         nifs::telemetry::get_flow_grid,
+        nifs::telemetry::encode_latest_frame,
+        nifs::telemetry::encode_roi,
 
         // 3. Legacy Path (nifs/legacy.rs)
         // Note: Removed 'setup_queryable'/'init_retina' as they are not in legacy.rs
         nifs::legacy::detect_change,
         nifs::legacy::update_spatial_state,
-        nifs::legacy::get_spatial_state
+        nifs::legacy::get_spatial_state,
+
+        // 4. Spatial Path (nifs/spatial.rs)
+        nifs::spatial::configure_spatial_map,
+        nifs::spatial::get_spatial_config,
+        nifs::spatial::cast_rays,
+        nifs::spatial::get_spatial_region,
+        nifs::spatial::get_spatial_version,
+        nifs::spatial::spatial_cells_in_box,
+        nifs::spatial::spatial_cells_in_radius,
+        nifs::spatial::spatial_nearest,
+        nifs::spatial::spatial_count_by_status,
+        nifs::spatial::spatial_changes_since,
+        nifs::spatial::save_spatial_snapshot,
+        nifs::spatial::load_spatial_snapshot,
+        nifs::spatial::open_spatial_journal,
+        nifs::spatial::close_spatial_journal,
+        nifs::spatial::spatial_journal_active,
+        nifs::spatial::set_spatial_node_id,
+        nifs::spatial::export_spatial_delta,
+        nifs::spatial::merge_spatial_delta,
+        nifs::spatial::plan_path,
+        nifs::spatial::start_replanner,
+        nifs::spatial::replan_path,
+        nifs::spatial::find_frontiers,
+        nifs::spatial::allocate_frontiers,
+
+        // 5. Bus Path (nifs/bus.rs)
+        nifs::bus::start_zenoh,
+        nifs::bus::stop_zenoh,
+        nifs::bus::zenoh_active,
+        nifs::bus::zenoh_subscribe,
+        nifs::bus::zenoh_ack,
+        nifs::bus::zenoh_dropped,
+        nifs::bus::zenoh_undeclare,
+
+        // 6. Codec Path (nifs/codec.rs)
+        nifs::codec::encode_packet,
+        nifs::codec::decode_packet,
+
+        // 7. Crypto Path (nifs/crypto.rs)
+        nifs::crypto::new_keyring,
+        nifs::crypto::put_swarm_key,
+        nifs::crypto::drop_swarm_key,
+        nifs::crypto::seal,
+        nifs::crypto::open,
+
+        // 8. FEC Path (nifs/fec.rs)
+        nifs::fec::fec_encode,
+        nifs::fec::fec_decode,
+        nifs::fec::fec_shard_info,
+
+        // 9. Serial Path (nifs/serial.rs)
+        nifs::serial::msp_new_parser,
+        nifs::serial::msp_feed,
+        nifs::serial::msp_parser_stats,
+        nifs::serial::msp_encode_request,
+        nifs::serial::msp_set_raw_rc,
+        nifs::serial::mavlink_new_link,
+        nifs::serial::mavlink_set_signing,
+        nifs::serial::mavlink_feed,
+        nifs::serial::mavlink_encode,
+        nifs::serial::mavlink_link_stats,
+        nifs::serial::crsf_new_parser,
+        nifs::serial::crsf_feed,
+        nifs::serial::crsf_parser_stats,
+        nifs::serial::crsf_encode,
+
+        // 10. Reflex Path (nifs/reflex.rs)
+        nifs::reflex::reflex_new,
+        nifs::reflex::reflex_command,
+        nifs::reflex::reflex_arm,
+        nifs::reflex::reflex_disarm,
+        nifs::reflex::reflex_tick,
+        nifs::reflex::reflex_status,
+
+        // 11. Recorder Path (nifs/recorder.rs)
+        nifs::recorder::start_recorder,
+        nifs::recorder::stop_recorder,
+        nifs::recorder::recorder_append,
+        nifs::recorder::recorder_stats,
+        nifs::recorder::open_recording,
+        nifs::recorder::recording_info,
+        nifs::recorder::recording_seek,
+        nifs::recorder::recording_next,
+
+        // 12. Replay Path (nifs/replay.rs)
+        nifs::replay::start_replay,
+        nifs::replay::replay_step,
+        nifs::replay::replay_set_speed,
+        nifs::replay::replay_status,
+        nifs::replay::stop_replay
     ],
     load = load
 );
\ No newline at end of file
@@ -0,0 +1,105 @@
+// native/swarm_native/src/nifs/bus.rs
+
+use std::sync::Arc;
+
+use rustler::{Atom, LocalPid, ResourceArc};
+use crate::bus::{subscriber::Subscription, Bus, BusConfig, BusError, Sealer};
+use crate::crypto::Keyring;
+use crate::state::arena::SwarmState;
+use super::Ack;
+
+mod atoms {
+    rustler::atoms! {
+        bus_active,
+        bus_inactive,
+        invalid_endpoint,
+        invalid_key_expr,
+        session_failed
+    }
+}
+
+fn bus_error(e: BusError) -> Atom {
+    match e {
+        BusError::Config => atoms::invalid_endpoint(),
+        BusError::KeyExpr => atoms::invalid_key_expr(),
+        BusError::Session => atoms::session_failed(),
+    }
+}
+
+/// Opens the zenoh session (peer mode) and starts publishing from the
+/// heartbeat. `seal` is {keyring, key id} to publish sealed frames, or nil.
+/// Returns :ok | {:error, :bus_active | :invalid_endpoint |
+/// :invalid_key_expr | :session_failed}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn start_zenoh(
+    state: ResourceArc<SwarmState>,
+    config: BusConfig,
+    seal: Option<(ResourceArc<Keyring>, u8)>,
+) -> Ack {
+    Ack(open_bus(&state, &config, seal))
+}
+
+fn open_bus(state: &SwarmState, config: &BusConfig, seal: Option<(ResourceArc<Keyring>, u8)>) -> Result<(), Atom> {
+    if state.bus.read().unwrap().is_some() {
+        return Err(atoms::bus_active());
+    }
+
+    // Opened outside the lock: the heartbeat reads it every frame
+    let sealer = seal.map(|(keyring, id)| -> Sealer { Box::new(move |payload| keyring.seal(id, payload)) });
+    let bus = Bus::open(config, state.spatial_memory.clone(), sealer).map_err(bus_error)?;
+
+    let mut slot = state.bus.write().unwrap();
+    if slot.is_some() {
+        return Err(atoms::bus_active()); // Lost a race; ours is dropped (closed)
+    }
+    *slot = Some(Arc::new(bus));
+    Ok(())
+}
+
+/// Undeclares every stream and closes the session. Always :ok.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn stop_zenoh(state: ResourceArc<SwarmState>) -> Atom {
+    let bus = state.bus.write().unwrap().take();
+    drop(bus); // Closes here unless the heartbeat holds it for one more frame
+    rustler::types::atom::ok()
+}
+
+#[rustler::nif]
+pub fn zenoh_active(state: ResourceArc<SwarmState>) -> bool {
+    state.bus.read().unwrap().is_some()
+}
+
+/// Forwards samples on `key` to `pid` as {:zenoh, key, payload, timestamp_us | nil}.
+/// At most `capacity` messages are in flight until acked; beyond that the
+/// oldest queued sample is dropped. Returns {:ok, subscription} |
+/// {:error, :bus_inactive | :invalid_key_expr}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn zenoh_subscribe(
+    state: ResourceArc<SwarmState>,
+    key: String,
+    pid: LocalPid,
+    capacity: usize,
+) -> Result<ResourceArc<Subscription>, Atom> {
+    let bus = state.bus.read().unwrap().clone().ok_or_else(atoms::bus_inactive)?;
+    let sub = Subscription::declare(bus.session(), &key, pid, capacity).map_err(|_| atoms::invalid_key_expr())?;
+    Ok(ResourceArc::new(sub))
+}
+
+/// Returns credit for `n` handled messages. Always :ok.
+#[rustler::nif]
+pub fn zenoh_ack(sub: ResourceArc<Subscription>, n: usize) -> Atom {
+    sub.ack(n);
+    rustler::types::atom::ok()
+}
+
+#[rustler::nif]
+pub fn zenoh_dropped(sub: ResourceArc<Subscription>) -> u64 {
+    sub.dropped()
+}
+
+/// Stops delivery (messages already sent stay in the mailbox). Always :ok.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn zenoh_undeclare(sub: ResourceArc<Subscription>) -> Atom {
+    sub.undeclare();
+    rustler::types::atom::ok()
+}
@@ -0,0 +1,44 @@
+// native/swarm_native/src/nifs/codec.rs
+
+use rustler::{Atom, Binary, Env, OwnedBinary};
+use crate::codec::{self, CodecError, Packet};
+
+mod atoms {
+    rustler::atoms! {
+        truncated,
+        unsupported_version,
+        unknown_kind,
+        malformed,
+        out_of_range,
+        too_large
+    }
+}
+
+fn codec_error(e: CodecError) -> Atom {
+    match e {
+        CodecError::Truncated => atoms::truncated(),
+        CodecError::Unsupported => atoms::unsupported_version(),
+        CodecError::UnknownKind => atoms::unknown_kind(),
+        CodecError::Malformed => atoms::malformed(),
+        CodecError::OutOfRange => atoms::out_of_range(),
+        CodecError::TooLarge => atoms::too_large(),
+    }
+}
+
+/// %SwarmBrain.Telemetry.Packet{} -> {:ok, binary} |
+/// {:error, :unsupported_version | :out_of_range | :too_large}.
+#[rustler::nif]
+pub fn encode_packet<'a>(env: Env<'a>, packet: Packet) -> Result<Binary<'a>, Atom> {
+    let bytes = codec::encode(&packet).map_err(codec_error)?;
+
+    let mut binary = OwnedBinary::new(bytes.len()).ok_or_else(atoms::too_large)?;
+    binary.as_mut_slice().copy_from_slice(&bytes);
+    Ok(binary.release(env))
+}
+
+/// binary -> {:ok, %SwarmBrain.Telemetry.Packet{}} |
+/// {:error, :truncated | :unsupported_version | :unknown_kind | :malformed}.
+#[rustler::nif]
+pub fn decode_packet(bytes: Binary) -> Result<Packet, Atom> {
+    codec::decode(bytes.as_slice()).map_err(codec_error)
+}
@@ -3,13 +3,25 @@
 use rustler::ResourceArc;
 use crate::state::arena::SwarmState;
 use crate::vision::camera;
+use super::Ack;
 use rustler::Atom; // Add Atom to imports
 
+mod atoms {
+    rustler::atoms! {
+        replay_active
+    }
+}
+
 /// The Watchdog Probe.
 /// Checks if the FFmpeg child process is still running.
-/// Returns :ok if alive, :error if dead or missing.
+/// Returns :ok if alive (or a replay is feeding the heartbeat), :error if dead or missing.
 #[rustler::nif]
 pub fn check_health(state: ResourceArc<SwarmState>) -> Atom {
+    // A replay has no child process to watch
+    if state.replay.read().unwrap().is_some() {
+        return rustler::types::atom::ok();
+    }
+
     // Lock the child process mutex
     let mut lock = state.child_process.lock().unwrap();
     
@@ -37,13 +49,17 @@ pub fn init_state() -> ResourceArc<SwarmState> {
 
 /// Tactic 4: The Heartbeat
 /// Spawns the dedicated OS thread for FFmpeg.
-
+/// Returns :ok | {:error, :replay_active} (the heartbeat has one source).
 #[rustler::nif]
-pub fn start_camera(state: ResourceArc<SwarmState>, width: u32, height: u32) -> Atom {    
+pub fn start_camera(state: ResourceArc<SwarmState>, width: u32, height: u32) -> Ack {
+    if state.replay.read().unwrap().is_some() {
+        return Ack(Err(atoms::replay_active()));
+    }
+
     // FIX: Dereference the ResourceArc (*) to get to the SwarmState, 
     // then Clone it to get an owned struct.
     let state_owned = (*state).clone(); 
     
     camera::spawn_heartbeat(state_owned, width, height);
-    rustler::types::atom::ok()
-}
\ No newline at end of file
+    Ack(Ok(()))
+}
@@ -0,0 +1,69 @@
+// native/swarm_native/src/nifs/crypto.rs
+
+use rustler::{Atom, Binary, Env, OwnedBinary, ResourceArc};
+use crate::crypto::{Keyring, SealError};
+use super::Ack;
+
+mod atoms {
+    rustler::atoms! {
+        invalid_key,
+        unknown_key,
+        truncated,
+        bad_tag,
+        replay,
+        exhausted
+    }
+}
+
+fn seal_error(e: SealError) -> Atom {
+    match e {
+        SealError::InvalidKey => atoms::invalid_key(),
+        SealError::UnknownKey => atoms::unknown_key(),
+        SealError::Truncated => atoms::truncated(),
+        SealError::BadTag => atoms::bad_tag(),
+        SealError::Replay => atoms::replay(),
+        SealError::Exhausted => atoms::exhausted(),
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// An empty keyring sealing as `node` (16 bits, unique per swarm key).
+/// `boot` must never repeat for a node: it prefixes every nonce.
+#[rustler::nif]
+pub fn new_keyring(node: u16, boot: u32) -> ResourceArc<Keyring> {
+    ResourceArc::new(Keyring::new(node, boot))
+}
+
+/// Adds or replaces swarm key `id` (32 bytes) with a tag of `tag_len`
+/// bytes (4..16). Returns :ok | {:error, :invalid_key}.
+#[rustler::nif]
+pub fn put_swarm_key(keyring: ResourceArc<Keyring>, id: u8, key: Binary, tag_len: usize) -> Ack {
+    Ack(keyring.insert(id, key.as_slice(), tag_len).map_err(seal_error))
+}
+
+/// Forgets key `id` and its replay windows. Always :ok.
+#[rustler::nif]
+pub fn drop_swarm_key(keyring: ResourceArc<Keyring>, id: u8) -> Atom {
+    keyring.remove(id);
+    rustler::types::atom::ok()
+}
+
+/// Returns {:ok, frame} | {:error, :unknown_key | :exhausted}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn seal<'a>(env: Env<'a>, keyring: ResourceArc<Keyring>, id: u8, plaintext: Binary) -> Result<Binary<'a>, Atom> {
+    let frame = keyring.seal(id, plaintext.as_slice()).map_err(seal_error)?;
+    Ok(to_binary(env, &frame))
+}
+
+/// Returns {:ok, {sender, key_id, plaintext}} |
+/// {:error, :unknown_key | :truncated | :bad_tag | :replay}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn open<'a>(env: Env<'a>, keyring: ResourceArc<Keyring>, frame: Binary) -> Result<(u16, u8, Binary<'a>), Atom> {
+    let opened = keyring.open(frame.as_slice()).map_err(seal_error)?;
+    Ok((opened.sender, opened.key_id, to_binary(env, &opened.plaintext)))
+}
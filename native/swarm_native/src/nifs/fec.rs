@@ -0,0 +1,58 @@
+// native/swarm_native/src/nifs/fec.rs
+
+use rustler::{Atom, Binary, Env, OwnedBinary};
+use crate::fec::{self, FecError, Scheme, ShardInfo};
+
+mod atoms {
+    rustler::atoms! {
+        invalid_params,
+        too_large,
+        insufficient_shards,
+        corrupt
+    }
+}
+
+fn fec_error(e: FecError) -> Atom {
+    match e {
+        FecError::InvalidParams => atoms::invalid_params(),
+        FecError::TooLarge => atoms::too_large(),
+        FecError::Insufficient => atoms::insufficient_shards(),
+        FecError::Corrupt => atoms::corrupt(),
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// Splits `payload` into `n` shards, any `k` of which rebuild it.
+/// Returns {:ok, [shard]} | {:error, :invalid_params | :too_large}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn fec_encode<'a>(
+    env: Env<'a>,
+    payload: Binary,
+    scheme: Scheme,
+    message: u16,
+    k: usize,
+    n: usize,
+) -> Result<Vec<Binary<'a>>, Atom> {
+    let shards = fec::encode(payload.as_slice(), scheme, message, k, n).map_err(fec_error)?;
+    Ok(shards.iter().map(|s| to_binary(env, s)).collect())
+}
+
+/// Rebuilds the message of the first intact shard (corrupt, foreign and
+/// duplicate shards are skipped). Returns {:ok, payload} | {:error, :insufficient_shards}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn fec_decode<'a>(env: Env<'a>, shards: Vec<Binary>) -> Result<Binary<'a>, Atom> {
+    let slices: Vec<&[u8]> = shards.iter().map(|s| s.as_slice()).collect();
+    let payload = fec::decode(&slices).map_err(fec_error)?;
+    Ok(to_binary(env, &payload))
+}
+
+/// Returns {:ok, %{scheme, message, k, n, index, length}} | {:error, :corrupt}.
+#[rustler::nif]
+pub fn fec_shard_info(shard: Binary) -> Result<ShardInfo, Atom> {
+    fec::info(shard.as_slice()).map_err(fec_error)
+}
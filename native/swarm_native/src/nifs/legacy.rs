@@ -4,19 +4,20 @@ use rustler::ResourceArc;
 use std::sync::atomic::Ordering;
 use crate::state::arena::{SwarmState, FRAME_WIDTH, FRAME_HEIGHT};
 use crate::vision::detector; // Import the Logic
+use crate::spatial;
 
+/// Sets the explicit status tag of a cell (0 clears it, handing the cell
+/// back to the occupancy evidence).
 #[rustler::nif]
 pub fn update_spatial_state(state: ResourceArc<SwarmState>, x: i32, y: i32, status: u32) -> String {
-    state.spatial_memory.insert((x, y), status);
+    state.spatial_memory.set_status(x, y, status, spatial::now_us());
     "ok".to_string()
 }
 
+/// The tag if set, otherwise the occupancy class (0 unknown, 1 free, 2 occupied).
 #[rustler::nif]
 pub fn get_spatial_state(state: ResourceArc<SwarmState>, x: i32, y: i32) -> u32 {
-    match state.spatial_memory.get(&(x, y)) {
-        Some(val) => *val,
-        None => 0,
-    }
+    state.spatial_memory.status(x, y, spatial::now_us())
 }
 
 /// The "Wake-on-Motion" Trigger.
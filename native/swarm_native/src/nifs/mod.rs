@@ -1,5 +1,29 @@
 // native/swarm_native/src/nifs/mod.rs
 
 pub mod control;   // init_state, start_camera
-pub mod telemetry; // get_fused_state, get_latest_frame
-pub mod legacy;    // detect_change, update_spatial_state
\ No newline at end of file
+pub mod telemetry; // get_fused_state, get_kinematics, get_latest_frame, encode_*
+pub mod legacy;    // detect_change, update_spatial_state
+pub mod spatial;   // occupancy grid: configure, cast_rays, region export
+pub mod bus;       // zenoh session: start, stop, subscribe
+pub mod codec;     // swarm wire format: encode, decode packets
+pub mod crypto;    // sealed frames: keyring, seal, open
+pub mod fec;       // erasure coding: shard, rebuild
+pub mod serial;    // flight controller / receiver links: MSP, MAVLink, CRSF
+pub mod reflex;    // RC output: shaping, arming, failsafe
+pub mod recorder;  // black box: record, play back by time range
+pub mod replay;    // heartbeat from a recording: start, step, pace
+
+use rustler::{types::atom, Atom, Encoder, Env, Term};
+
+/// `:ok | {:error, reason}` for NIFs with nothing to return on success.
+/// (`Result<Atom, Atom>` would encode success as `{:ok, :ok}`.)
+pub struct Ack(pub Result<(), Atom>);
+
+impl Encoder for Ack {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self.0 {
+            Ok(()) => atom::ok().encode(env),
+            Err(reason) => (atom::error(), reason).encode(env),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+// native/swarm_native/src/nifs/recorder.rs
+
+use std::path::Path;
+use std::sync::Arc;
+
+use rustler::{Atom, ResourceArc};
+use crate::recorder::reader::{Recording, RecordingInfo};
+use crate::recorder::{Record, Recorder, RecorderConfig, RecorderError, RecorderStats};
+use crate::spatial;
+use crate::state::arena::SwarmState;
+use super::Ack;
+
+mod atoms {
+    rustler::atoms! {
+        io_error,
+        invalid_config,
+        recorder_active,
+        recorder_inactive
+    }
+}
+
+fn recorder_error(e: RecorderError) -> Atom {
+    match e {
+        RecorderError::Io => atoms::io_error(),
+        RecorderError::InvalidConfig => atoms::invalid_config(),
+    }
+}
+
+/// Seals what a crash left in `config.dir`, opens a new segment and starts
+/// recording the heartbeat. Returns :ok | {:error, :recorder_active |
+/// :invalid_config | :io_error}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn start_recorder(state: ResourceArc<SwarmState>, config: RecorderConfig) -> Ack {
+    Ack(open_recorder(&state, &config))
+}
+
+fn open_recorder(state: &SwarmState, config: &RecorderConfig) -> Result<(), Atom> {
+    if state.recorder.read().unwrap().is_some() {
+        return Err(atoms::recorder_active());
+    }
+
+    // Opened outside the lock: the heartbeat reads it every frame
+    let recorder = Recorder::start(config).map_err(recorder_error)?;
+
+    let mut slot = state.recorder.write().unwrap();
+    if slot.is_some() {
+        return Err(atoms::recorder_active()); // Lost a race; ours is dropped (sealed)
+    }
+    *slot = Some(Arc::new(recorder));
+    Ok(())
+}
+
+/// Drains the queue and seals the open segment. Always :ok.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn stop_recorder(state: ResourceArc<SwarmState>) -> Atom {
+    let recorder = state.recorder.write().unwrap().take();
+    drop(recorder); // Seals here unless the heartbeat holds it for one more frame
+    rustler::types::atom::ok()
+}
+
+/// Queues a record stamped now, e.g. {:detection, binary} or
+/// {:command, %{roll, pitch, yaw, throttle}}. Never blocks.
+/// Returns :ok | {:error, :recorder_inactive}.
+#[rustler::nif]
+pub fn recorder_append(state: ResourceArc<SwarmState>, record: Record) -> Ack {
+    let Some(recorder) = state.recorder.read().unwrap().clone() else {
+        return Ack(Err(atoms::recorder_inactive()));
+    };
+    recorder.append(spatial::now_us(), record);
+    Ack(Ok(()))
+}
+
+/// Returns {:ok, %{records, bytes, segments, dropped, failed}} | {:error, :recorder_inactive}.
+#[rustler::nif]
+pub fn recorder_stats(state: ResourceArc<SwarmState>) -> Result<RecorderStats, Atom> {
+    let recorder = state.recorder.read().unwrap().clone().ok_or_else(atoms::recorder_inactive)?;
+    Ok(recorder.stats())
+}
+
+// --- PLAYBACK (recorder/reader.rs) ---
+
+/// Indexes the segments in `dir` (read-only). Returns {:ok, recording} | {:error, :io_error}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn open_recording(dir: String) -> Result<ResourceArc<Recording>, Atom> {
+    Ok(ResourceArc::new(Recording::open(Path::new(&dir)).map_err(recorder_error)?))
+}
+
+/// Returns %{segments, records, first_us, last_us}.
+#[rustler::nif]
+pub fn recording_info(recording: ResourceArc<Recording>) -> RecordingInfo {
+    recording.info()
+}
+
+/// Restarts iteration over [from_us, to_us] (inclusive). Always :ok.
+#[rustler::nif]
+pub fn recording_seek(recording: ResourceArc<Recording>, from_us: u64, to_us: u64) -> Atom {
+    recording.seek(from_us, to_us);
+    rustler::types::atom::ok()
+}
+
+/// Returns up to `max` [{ts_us, record}] of the range; [] once it is exhausted.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn recording_next(recording: ResourceArc<Recording>, max: usize) -> Vec<(u64, Record)> {
+    recording.next(max).into_iter().map(|e| (e.ts_us, e.record)).collect()
+}
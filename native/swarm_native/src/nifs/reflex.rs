@@ -0,0 +1,65 @@
+// native/swarm_native/src/nifs/reflex.rs
+
+use rustler::{Atom, Binary, Env, OwnedBinary, ResourceArc};
+use crate::reflex::{Config, Reflex, ReflexError, Status};
+use super::Ack;
+
+mod atoms {
+    rustler::atoms! {
+        invalid_config,
+        throttle_high,
+        no_commands
+    }
+}
+
+fn reflex_error(e: ReflexError) -> Atom {
+    match e {
+        ReflexError::InvalidConfig => atoms::invalid_config(),
+        ReflexError::ThrottleHigh => atoms::throttle_high(),
+        ReflexError::NoCommands => atoms::no_commands(),
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+/// A disarmed mixer. Returns {:ok, reflex} | {:error, :invalid_config}.
+#[rustler::nif]
+pub fn reflex_new(config: Config) -> Result<ResourceArc<Reflex>, Atom> {
+    Ok(ResourceArc::new(Reflex::new(config).map_err(reflex_error)?))
+}
+
+/// Records an intent (-1.0..1.0 per axis); it reaches the channels on the next tick.
+#[rustler::nif]
+pub fn reflex_command(reflex: ResourceArc<Reflex>, roll: f32, pitch: f32, yaw: f32, throttle: f32) -> Atom {
+    reflex.command(roll, pitch, yaw, throttle);
+    rustler::types::atom::ok()
+}
+
+/// Returns :ok | {:error, :no_commands | :throttle_high}.
+#[rustler::nif]
+pub fn reflex_arm(reflex: ResourceArc<Reflex>) -> Ack {
+    Ack(reflex.arm().map_err(reflex_error))
+}
+
+#[rustler::nif]
+pub fn reflex_disarm(reflex: ResourceArc<Reflex>) -> Atom {
+    reflex.disarm();
+    rustler::types::atom::ok()
+}
+
+/// Advances failsafe and slew limits. Returns {msp_set_raw_rc_payload, %{phase, armed, command_age_ms}}.
+#[rustler::nif]
+pub fn reflex_tick<'a>(env: Env<'a>, reflex: ResourceArc<Reflex>) -> (Binary<'a>, Status) {
+    let (payload, status) = reflex.tick();
+    (to_binary(env, &payload), status)
+}
+
+/// Returns %{phase, armed, command_age_ms}.
+#[rustler::nif]
+pub fn reflex_status(reflex: ResourceArc<Reflex>) -> Status {
+    reflex.status()
+}
@@ -0,0 +1,92 @@
+// native/swarm_native/src/nifs/replay.rs
+
+use rustler::{Atom, ResourceArc};
+use crate::state::arena::SwarmState;
+use super::Ack;
+use crate::vision::replay::{Replay, ReplayConfig, ReplayError, ReplayStatus};
+
+mod atoms {
+    rustler::atoms! {
+        io_error,
+        empty,
+        invalid_config,
+        camera_active,
+        replay_active,
+        replay_inactive
+    }
+}
+
+fn replay_error(e: ReplayError) -> Atom {
+    match e {
+        ReplayError::Io => atoms::io_error(),
+        ReplayError::Empty => atoms::empty(),
+        ReplayError::InvalidConfig => atoms::invalid_config(),
+    }
+}
+
+/// Feeds the heartbeat from the black box in `config.dir` instead of the
+/// camera. Returns :ok | {:error, :camera_active | :replay_active |
+/// :invalid_config | :io_error | :empty}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn start_replay(state: ResourceArc<SwarmState>, config: ReplayConfig) -> Ack {
+    Ack(open_replay(&state, &config))
+}
+
+fn open_replay(state: &SwarmState, config: &ReplayConfig) -> Result<(), Atom> {
+    if state.child_process.lock().unwrap().is_some() {
+        return Err(atoms::camera_active());
+    }
+    if state.replay.read().unwrap().is_some() {
+        return Err(atoms::replay_active());
+    }
+
+    // Opened outside the lock: indexing a long recording takes a while
+    let replay = Replay::start(state.clone(), config).map_err(replay_error)?;
+
+    let mut slot = state.replay.write().unwrap();
+    if slot.is_some() {
+        replay.stop(); // Lost a race
+        return Err(atoms::replay_active());
+    }
+    *slot = Some(replay);
+    Ok(())
+}
+
+/// Plays `n` more frames while stepping (speed 0.0).
+/// Returns :ok | {:error, :replay_inactive}.
+#[rustler::nif]
+pub fn replay_step(state: ResourceArc<SwarmState>, n: u64) -> Ack {
+    let Some(replay) = state.replay.read().unwrap().clone() else {
+        return Ack(Err(atoms::replay_inactive()));
+    };
+    replay.step(n);
+    Ack(Ok(()))
+}
+
+/// 1.0 real time, >1.0 accelerated, 0.0 step-by-step.
+/// Returns :ok | {:error, :replay_inactive | :invalid_config}.
+#[rustler::nif]
+pub fn replay_set_speed(state: ResourceArc<SwarmState>, speed: f64) -> Ack {
+    let Some(replay) = state.replay.read().unwrap().clone() else {
+        return Ack(Err(atoms::replay_inactive()));
+    };
+    Ack(if replay.set_speed(speed) { Ok(()) } else { Err(atoms::invalid_config()) })
+}
+
+/// Returns {:ok, %{frames, position_us, finished, speed}} | {:error, :replay_inactive}.
+#[rustler::nif]
+pub fn replay_status(state: ResourceArc<SwarmState>) -> Result<ReplayStatus, Atom> {
+    let replay = state.replay.read().unwrap().clone().ok_or_else(atoms::replay_inactive)?;
+    Ok(replay.status())
+}
+
+/// Stops the replay and waits for its last frame. The arena keeps that
+/// frame and state. Always :ok.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn stop_replay(state: ResourceArc<SwarmState>) -> Atom {
+    let replay = state.replay.write().unwrap().take();
+    if let Some(replay) = replay {
+        replay.stop();
+    }
+    rustler::types::atom::ok()
+}
@@ -0,0 +1,151 @@
+// native/swarm_native/src/nifs/serial.rs
+
+use rustler::{Atom, Binary, Env, OwnedBinary, ResourceArc};
+use crate::serial::crsf::{self, CrsfError, CrsfStream, Message as CrsfMessage, ParserStats as CrsfStats};
+use crate::serial::mavlink::messages::Message as MavlinkMessage;
+use crate::serial::mavlink::{LinkStats, MavlinkError, MavlinkLink};
+use crate::serial::msp::{self, Message, MspError, MspStream, MspVersion, ParserStats};
+use crate::spatial;
+use super::Ack;
+
+mod atoms {
+    rustler::atoms! {
+        invalid_command,
+        too_large,
+        invalid_channels,
+        invalid_key
+    }
+}
+
+fn msp_error(e: MspError) -> Atom {
+    match e {
+        MspError::InvalidCommand => atoms::invalid_command(),
+        MspError::TooLarge => atoms::too_large(),
+        MspError::InvalidChannels => atoms::invalid_channels(),
+    }
+}
+
+fn mavlink_error(e: MavlinkError) -> Atom {
+    match e {
+        MavlinkError::InvalidKey => atoms::invalid_key(),
+    }
+}
+
+fn crsf_error(e: CrsfError) -> Atom {
+    match e {
+        CrsfError::TooLarge => atoms::too_large(),
+        CrsfError::InvalidChannels => atoms::invalid_channels(),
+    }
+}
+
+fn to_binary<'a>(env: Env<'a>, bytes: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(bytes);
+    binary.release(env)
+}
+
+// --- MSP ---
+
+/// A streaming MSP parser, one per UART.
+#[rustler::nif]
+pub fn msp_new_parser() -> ResourceArc<MspStream> {
+    ResourceArc::new(MspStream::default())
+}
+
+/// Feeds raw UART bytes (any fragment). Returns the frames completed by
+/// this chunk, decoded where known (%Msp.Attitude{}, ...) or as %Msp.Frame{}.
+#[rustler::nif]
+pub fn msp_feed(parser: ResourceArc<MspStream>, data: Binary) -> Vec<Message> {
+    parser.feed(data.as_slice())
+}
+
+/// Returns %{frames, bad_checksums, dropped_bytes}.
+#[rustler::nif]
+pub fn msp_parser_stats(parser: ResourceArc<MspStream>) -> ParserStats {
+    parser.stats()
+}
+
+/// A request frame. Returns {:ok, frame} | {:error, :invalid_command | :too_large}.
+#[rustler::nif]
+pub fn msp_encode_request<'a>(env: Env<'a>, cmd: u16, payload: Binary, version: MspVersion) -> Result<Binary<'a>, Atom> {
+    let frame = msp::encode(version, cmd, payload.as_slice()).map_err(msp_error)?;
+    Ok(to_binary(env, &frame))
+}
+
+/// MSP_SET_RAW_RC with 1..18 channels in µs.
+/// Returns {:ok, frame} | {:error, :invalid_channels}.
+#[rustler::nif]
+pub fn msp_set_raw_rc<'a>(env: Env<'a>, channels: Vec<u16>, version: MspVersion) -> Result<Binary<'a>, Atom> {
+    let frame = msp::set_raw_rc(version, &channels).map_err(msp_error)?;
+    Ok(to_binary(env, &frame))
+}
+
+// --- MAVLINK ---
+
+/// A MAVLink v2 link speaking as (system_id, component_id).
+#[rustler::nif]
+pub fn mavlink_new_link(system_id: u8, component_id: u8) -> ResourceArc<MavlinkLink> {
+    ResourceArc::new(MavlinkLink::new(system_id, component_id))
+}
+
+/// Signs outgoing frames and verifies incoming ones with a 32-byte key
+/// (nil turns signing off). Returns :ok | {:error, :invalid_key}.
+#[rustler::nif]
+pub fn mavlink_set_signing(
+    link: ResourceArc<MavlinkLink>,
+    key: Option<Binary>,
+    link_id: u8,
+    allow_unsigned: bool,
+) -> Ack {
+    Ack(link.set_signing(key.as_ref().map(|k| k.as_slice()), link_id, allow_unsigned).map_err(mavlink_error))
+}
+
+/// Feeds raw UART bytes (any fragment). Returns [{system_id, component_id, message}]
+/// for every frame completed by this chunk.
+#[rustler::nif]
+pub fn mavlink_feed(link: ResourceArc<MavlinkLink>, data: Binary) -> Vec<(u8, u8, MavlinkMessage)> {
+    link.feed(data.as_slice(), spatial::now_us())
+        .into_iter()
+        .map(|r| (r.system_id, r.component_id, r.message))
+        .collect()
+}
+
+/// Frames a message struct (%Mavlink.CommandLong{}, ...), signed if signing is on.
+#[rustler::nif]
+pub fn mavlink_encode<'a>(env: Env<'a>, link: ResourceArc<MavlinkLink>, message: MavlinkMessage) -> Binary<'a> {
+    to_binary(env, &link.encode(&message, spatial::now_us()))
+}
+
+/// Returns %{frames, bad_crc, bad_signature, unknown, dropped_bytes}.
+#[rustler::nif]
+pub fn mavlink_link_stats(link: ResourceArc<MavlinkLink>) -> LinkStats {
+    link.stats()
+}
+
+// --- CRSF ---
+
+/// A streaming CRSF parser, one per receiver UART.
+#[rustler::nif]
+pub fn crsf_new_parser() -> ResourceArc<CrsfStream> {
+    ResourceArc::new(CrsfStream::default())
+}
+
+/// Feeds raw UART bytes (any fragment). Returns the frames completed by
+/// this chunk (%Crsf.LinkStatistics{}, ...), unknown types as %Crsf.Frame{}.
+#[rustler::nif]
+pub fn crsf_feed(parser: ResourceArc<CrsfStream>, data: Binary) -> Vec<CrsfMessage> {
+    parser.feed(data.as_slice())
+}
+
+/// Returns %{frames, bad_crc, dropped_bytes}.
+#[rustler::nif]
+pub fn crsf_parser_stats(parser: ResourceArc<CrsfStream>) -> CrsfStats {
+    parser.stats()
+}
+
+/// Frames a message struct. Returns {:ok, frame} | {:error, :too_large | :invalid_channels}.
+#[rustler::nif]
+pub fn crsf_encode<'a>(env: Env<'a>, message: CrsfMessage) -> Result<Binary<'a>, Atom> {
+    let frame = crsf::encode(&message).map_err(crsf_error)?;
+    Ok(to_binary(env, &frame))
+}
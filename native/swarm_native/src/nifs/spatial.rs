@@ -0,0 +1,326 @@
+// native/swarm_native/src/nifs/spatial.rs
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use rustler::{Atom, Binary, Env, OwnedBinary, ResourceArc};
+use crate::state::arena::SwarmState;
+use super::Ack;
+use crate::spatial::{
+    self,
+    costmap::CostmapError,
+    crdt::MergeStats,
+    frontier::{self, Frontier, FrontierOptions},
+    grid::{GridConfig, Layer, Ray},
+    persist::PersistError,
+    planner::{self, PlanError, PlanOptions, Replanner},
+    query::{Bounds, CellReport},
+};
+
+mod atoms {
+    rustler::atoms! {
+        invalid_config,
+        invalid_region,
+        io_error,
+        corrupt,
+        unsupported_version,
+        journal_attached,
+        start_blocked,
+        goal_blocked,
+        no_path,
+        search_limit,
+        region_too_large,
+        out_of_bounds,
+        invalid_inflation
+    }
+}
+
+fn persist_error(e: PersistError) -> Atom {
+    match e {
+        PersistError::Io => atoms::io_error(),
+        PersistError::Corrupt => atoms::corrupt(),
+        PersistError::Unsupported => atoms::unsupported_version(),
+        PersistError::Busy => atoms::journal_attached(),
+    }
+}
+
+fn costmap_error(e: CostmapError) -> Atom {
+    match e {
+        CostmapError::TooLarge => atoms::region_too_large(),
+        CostmapError::InvalidInflation => atoms::invalid_inflation(),
+    }
+}
+
+fn plan_error(e: PlanError) -> Atom {
+    match e {
+        PlanError::StartBlocked => atoms::start_blocked(),
+        PlanError::GoalBlocked => atoms::goal_blocked(),
+        PlanError::NoPath => atoms::no_path(),
+        PlanError::SearchLimit => atoms::search_limit(),
+        PlanError::TooLarge => atoms::region_too_large(),
+        PlanError::OutOfBounds => atoms::out_of_bounds(),
+        PlanError::InvalidInflation => atoms::invalid_inflation(),
+    }
+}
+
+/// Replaces the occupancy grid tuning (cell size, log-odds, decay).
+/// Returns :ok or {:error, :invalid_config}.
+#[rustler::nif]
+pub fn configure_spatial_map(state: ResourceArc<SwarmState>, config: GridConfig) -> Ack {
+    Ack(if state.spatial_memory.set_config(config) { Ok(()) } else { Err(atoms::invalid_config()) })
+}
+
+/// Returns the active grid tuning as a map.
+#[rustler::nif]
+pub fn get_spatial_config(state: ResourceArc<SwarmState>) -> GridConfig {
+    state.spatial_memory.config()
+}
+
+/// Integrates range observations taken at world position (x, y) in meters.
+/// rays: [{bearing_rad, range_m, hit?}] (bearing in the world frame).
+/// Returns the number of cell updates applied.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn cast_rays(state: ResourceArc<SwarmState>, x: f32, y: f32, rays: Vec<Ray>) -> usize {
+    state.spatial_memory.cast_rays(x, y, &rays, spatial::now_us())
+}
+
+/// Exports a w x h block of cells starting at (x0, y0) as a raw binary.
+/// layer: :probability | :log_odds (f32) or :status (u32).
+/// Elixir: Nx.from_binary(bin, {:f, 32}) |> Nx.reshape({h, w})
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_spatial_region<'a>(
+    env: Env<'a>,
+    state: ResourceArc<SwarmState>,
+    x0: i32,
+    y0: i32,
+    w: u32,
+    h: u32,
+    layer: Layer,
+) -> Result<Binary<'a>, Atom> {
+    let bytes = state
+        .spatial_memory
+        .region(x0, y0, w, h, layer, spatial::now_us())
+        .ok_or_else(atoms::invalid_region)?;
+
+    let mut binary = OwnedBinary::new(bytes.len()).ok_or_else(atoms::invalid_region)?;
+    binary.as_mut_slice().copy_from_slice(&bytes);
+
+    Ok(binary.release(env))
+}
+
+// --- QUERIES (spatial/query.rs) ---
+// All coordinates and radii are in cells. `status` filters accept nil (any).
+
+/// The map version counter (bumped by every cell write).
+/// Pass it to `spatial_changes_since/3` to poll for updates.
+#[rustler::nif]
+pub fn get_spatial_version(state: ResourceArc<SwarmState>) -> u64 {
+    state.spatial_memory.version()
+}
+
+/// Known cells in the inclusive box (x0, y0)..(x1, y1).
+/// Returns [{x, y, status, version}] sorted by (y, x).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn spatial_cells_in_box(
+    state: ResourceArc<SwarmState>,
+    x0: i32,
+    y0: i32,
+    x1: i32,
+    y1: i32,
+    status: Option<u32>,
+) -> Vec<CellReport> {
+    state
+        .spatial_memory
+        .cells_in_bounds(Bounds::new(x0, y0, x1, y1), status, spatial::now_us())
+}
+
+/// Known cells within `radius` of (x, y). Same shape as `spatial_cells_in_box`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn spatial_cells_in_radius(
+    state: ResourceArc<SwarmState>,
+    x: i32,
+    y: i32,
+    radius: u32,
+    status: Option<u32>,
+) -> Vec<CellReport> {
+    state
+        .spatial_memory
+        .cells_in_radius(x, y, radius, status, spatial::now_us())
+}
+
+/// Nearest cell with `status` within `max_radius` (status 0 finds unexplored cells).
+/// Returns {x, y, distance_cells} or nil.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn spatial_nearest(
+    state: ResourceArc<SwarmState>,
+    x: i32,
+    y: i32,
+    status: u32,
+    max_radius: u32,
+) -> Option<(i32, i32, f32)> {
+    state
+        .spatial_memory
+        .nearest(x, y, status, max_radius, spatial::now_us())
+}
+
+/// %{status => count} over known cells, in the whole map (bounds = nil)
+/// or in {x0, y0, x1, y1}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn spatial_count_by_status(
+    state: ResourceArc<SwarmState>,
+    bounds: Option<(i32, i32, i32, i32)>,
+) -> HashMap<u32, u64> {
+    let bounds = bounds.map(|(x0, y0, x1, y1)| Bounds::new(x0, y0, x1, y1));
+    state.spatial_memory.count_by_status(bounds, spatial::now_us())
+}
+
+/// The change feed. Cells written after version `since`, oldest first.
+/// Returns {cursor, [{x, y, status, version}]}; pass `cursor` as the next `since`.
+/// A cell rewritten several times is reported once, at its latest version.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn spatial_changes_since(
+    state: ResourceArc<SwarmState>,
+    since: u64,
+    limit: usize,
+) -> (u64, Vec<CellReport>) {
+    state.spatial_memory.changes_since(since, limit, spatial::now_us())
+}
+
+// --- PERSISTENCE (spatial/persist.rs) ---
+
+/// Writes the whole map to `path` (atomic replace) and compacts the journal.
+/// Returns {:ok, cell_count} | {:error, :io_error}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn save_spatial_snapshot(state: ResourceArc<SwarmState>, path: String) -> Result<usize, Atom> {
+    state.spatial_memory.save_snapshot(Path::new(&path)).map_err(persist_error)
+}
+
+/// Replaces the map with a snapshot. Refused while a journal is attached.
+/// Returns {:ok, cell_count} | {:error, :io_error | :corrupt | :unsupported_version | :journal_attached}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn load_spatial_snapshot(state: ResourceArc<SwarmState>, path: String) -> Result<usize, Atom> {
+    state.spatial_memory.load_snapshot(Path::new(&path)).map_err(persist_error)
+}
+
+/// Replays the journal at `path` (created if missing) and logs every later write to it.
+/// Returns {:ok, replayed_records} | {:error, reason}.
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn open_spatial_journal(state: ResourceArc<SwarmState>, path: String) -> Result<usize, Atom> {
+    state.spatial_memory.attach_journal(Path::new(&path)).map_err(persist_error)
+}
+
+#[rustler::nif(schedule = "DirtyIo")]
+pub fn close_spatial_journal(state: ResourceArc<SwarmState>) -> Atom {
+    state.spatial_memory.detach_journal();
+    rustler::types::atom::ok()
+}
+
+/// False if no journal is open, or if a write failed and the journal was dropped.
+#[rustler::nif]
+pub fn spatial_journal_active(state: ResourceArc<SwarmState>) -> bool {
+    state.spatial_memory.is_journaling()
+}
+
+// --- SWARM MERGE (spatial/crdt.rs) ---
+
+/// Sets this drone's tie-break identity for the map CRDT
+/// (e.g. `:erlang.phash2(Node.self(), 4_294_967_296)`). Call before observing.
+#[rustler::nif]
+pub fn set_spatial_node_id(state: ResourceArc<SwarmState>, node_id: u64) -> Atom {
+    state.spatial_memory.set_node_id(node_id);
+    rustler::types::atom::ok()
+}
+
+/// Cells changed after local version `since`, at most `limit`, as an opaque binary.
+/// Returns {:ok, {cursor, delta}}; pass `cursor` as the next `since` (0 = full state).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn export_spatial_delta<'a>(
+    env: Env<'a>,
+    state: ResourceArc<SwarmState>,
+    since: u64,
+    limit: usize,
+) -> Result<(u64, Binary<'a>), Atom> {
+    let (cursor, bytes) = state
+        .spatial_memory
+        .export_delta(since, limit)
+        .map_err(persist_error)?;
+
+    let mut binary = OwnedBinary::new(bytes.len()).ok_or_else(atoms::io_error)?;
+    binary.as_mut_slice().copy_from_slice(&bytes);
+
+    Ok((cursor, binary.release(env)))
+}
+
+/// Joins a peer's delta (per-cell last-writer-wins on the HLC stamp).
+/// Returns {:ok, %{applied, stale, rejected}} | {:error, :corrupt | :unsupported_version}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn merge_spatial_delta(state: ResourceArc<SwarmState>, delta: Binary) -> Result<MergeStats, Atom> {
+    state
+        .spatial_memory
+        .merge_delta(delta.as_slice(), spatial::now_us())
+        .map_err(persist_error)
+}
+
+// --- NAVIGATION (spatial/planner.rs) ---
+// Start, goal and waypoints are {x, y} cells.
+
+/// One-shot A* / Theta* plan. Returns {:ok, [{x, y}, ...]} (start and goal
+/// included) | {:error, :start_blocked | :goal_blocked | :no_path | :search_limit | :region_too_large |
+/// :invalid_inflation}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn plan_path(
+    state: ResourceArc<SwarmState>,
+    start: (i32, i32),
+    goal: (i32, i32),
+    options: PlanOptions,
+) -> Result<Vec<(i32, i32)>, Atom> {
+    planner::plan(&state.spatial_memory, start, goal, &options, spatial::now_us()).map_err(plan_error)
+}
+
+/// D* Lite: plans once and keeps the search alive for cheap replanning.
+/// Returns {:ok, {replanner, waypoints}} | {:error, reason}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn start_replanner(
+    state: ResourceArc<SwarmState>,
+    start: (i32, i32),
+    goal: (i32, i32),
+    options: PlanOptions,
+) -> Result<(ResourceArc<Replanner>, Vec<(i32, i32)>), Atom> {
+    let map = state.spatial_memory.clone();
+    let (replanner, path) = Replanner::new(map, start, goal, options, spatial::now_us()).map_err(plan_error)?;
+    Ok((ResourceArc::new(replanner), path))
+}
+
+/// Repairs the D* Lite search from the drone's new cell, folding in every
+/// map write since the last call. Returns {:ok, waypoints} | {:error, reason}
+/// (:out_of_bounds once the drone leaves the search box: start a new replanner).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn replan_path(replanner: ResourceArc<Replanner>, start: (i32, i32)) -> Result<Vec<(i32, i32)>, Atom> {
+    replanner.replan(start, spatial::now_us()).map_err(plan_error)
+}
+
+// --- EXPLORATION (spatial/frontier.rs) ---
+
+/// Frontier clusters around `origin`, scored for that drone, best first.
+/// Returns {:ok, [%{target, centroid, size, gain, distance, score}]} |
+/// {:error, :region_too_large | :invalid_inflation}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn find_frontiers(
+    state: ResourceArc<SwarmState>,
+    origin: (i32, i32),
+    options: FrontierOptions,
+) -> Result<Vec<Frontier>, Atom> {
+    frontier::find(&state.spatial_memory, origin, &options, spatial::now_us()).map_err(costmap_error)
+}
+
+/// Distinct exploration goals for several drones. Returns {:ok, goals}, one
+/// frontier map (or nil) per position, in the order given |
+/// {:error, :region_too_large | :invalid_inflation}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn allocate_frontiers(
+    state: ResourceArc<SwarmState>,
+    positions: Vec<(i32, i32)>,
+    options: FrontierOptions,
+) -> Result<Vec<Option<Frontier>>, Atom> {
+    frontier::allocate(&state.spatial_memory, &positions, &options, spatial::now_us()).map_err(costmap_error)
+}
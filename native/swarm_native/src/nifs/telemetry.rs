@@ -1,8 +1,21 @@
 // native/swarm_native/src/nifs/telemetry.rs
 
-use rustler::{Env, ResourceArc, Binary, OwnedBinary};
+use rustler::{Atom, Env, ResourceArc, Binary, OwnedBinary};
 use std::sync::atomic::Ordering;
-use crate::state::arena::{SwarmState, FRAME_SIZE};
+use crate::recorder::FrameFormat;
+use crate::state::arena::{SwarmState, FRAME_SIZE, FRAME_WIDTH, FRAME_HEIGHT};
+use crate::types::KinematicsSnapshot;
+use crate::vision::encode;
+
+mod atoms {
+    rustler::atoms! {
+        empty_roi,
+        encode_failed
+    }
+}
+
+// Motion crops are small: keep the detail
+const ROI_QUALITY: u8 = 90;
 
 /// Returns the Optical Flow grid (200 floats) as a raw binary.
 /// Elixir Nx can cast this directly to a Tensor:
@@ -42,13 +55,67 @@ pub fn get_latest_frame(env: Env, state: ResourceArc<SwarmState>) -> Binary {
 }
 
 /// Returns the Fused Kinematics (Vx, Vy, Px, Py)
+/// All four values come from the same camera update.
 #[rustler::nif]
 pub fn get_fused_state(state: ResourceArc<SwarmState>) -> (f32, f32, f32, f32) {
-    let phys = &state.physiology;
-    (
-        phys.vx.load(),
-        phys.vy.load(),
-        phys.px.load(),
-        phys.py.load()
-    )
-}
\ No newline at end of file
+    let snap = state.physiology.snapshot();
+    (snap.vx, snap.vy, snap.px, snap.py)
+}
+
+/// Returns the full Kinematics snapshot:
+/// %{vx, vy, px, py, timestamp_us, seq}
+/// 'seq' counts camera updates, so callers can detect stale or skipped readings.
+#[rustler::nif]
+pub fn get_kinematics(state: ResourceArc<SwarmState>) -> KinematicsSnapshot {
+    state.physiology.snapshot()
+}
+
+/// The latest frame as an image: `format` :jpeg | :png | :raw, `quality`
+/// 1..=100 (JPEG only), `scale` 1 = 640x480, 2 = 320x240, ...
+/// The slot is only held for the copy; encoding runs after.
+/// Returns {:ok, binary} | {:error, :encode_failed}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn encode_latest_frame<'a>(
+    env: Env<'a>,
+    state: ResourceArc<SwarmState>,
+    format: FrameFormat,
+    quality: u8,
+    scale: u32,
+) -> Result<Binary<'a>, Atom> {
+    let (rgb, width, height) = {
+        let ready_idx = state.memory.ready_idx.load(Ordering::Acquire);
+        let frame_guard = state.memory.slots[ready_idx].data.lock().unwrap();
+        encode::downscale(&frame_guard, FRAME_WIDTH, FRAME_HEIGHT, scale as usize)
+    };
+    let data = encode::image(rgb, width, height, format, quality).ok_or_else(atoms::encode_failed)?;
+    Ok(to_binary(env, &data))
+}
+
+/// A crop of the latest frame, e.g. the `{x, y, w, h}` from `detect_change`,
+/// clipped to the frame. Full resolution; JPEG at quality 90.
+/// Returns {:ok, binary} | {:error, :empty_roi | :encode_failed}.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn encode_roi<'a>(
+    env: Env<'a>,
+    state: ResourceArc<SwarmState>,
+    roi: (u32, u32, u32, u32),
+    format: FrameFormat,
+) -> Result<Binary<'a>, Atom> {
+    let (x, y, w, h) = roi;
+    let (rgb, width, height) = {
+        let ready_idx = state.memory.ready_idx.load(Ordering::Acquire);
+        let frame_guard = state.memory.slots[ready_idx].data.lock().unwrap();
+        encode::crop(&frame_guard, FRAME_WIDTH, FRAME_HEIGHT, x as usize, y as usize, w as usize, h as usize)
+    };
+    if width == 0 || height == 0 {
+        return Err(atoms::empty_roi());
+    }
+    let data = encode::image(rgb, width, height, format, ROI_QUALITY).ok_or_else(atoms::encode_failed)?;
+    Ok(to_binary(env, &data))
+}
+
+fn to_binary<'a>(env: Env<'a>, data: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(data.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(data);
+    binary.release(env)
+}
@@ -0,0 +1,359 @@
+// native/swarm_native/src/recorder/mod.rs
+
+//! THE BLACK BOX (Flight Recorder)
+//!
+//! An append-only log of what the drone sensed and decided, kept in
+//! rotating segment files under one directory:
+//!
+//! * The camera heartbeat hands every frame to `Recorder::on_frame`, which
+//!   records kinematics, the flow grid (one in `flow_every`), motion events
+//!   and, one in `frame_every`, the frame: a downscaled JPEG, or PNG / raw
+//!   RGB24 for recordings meant to be replayed exactly (vision/replay.rs
+//!   needs every frame, full size, lossless).
+//! * Elixir appends detections and commands (`recorder_append`).
+//!
+//! Records go through a bounded queue to a writer thread, which does the
+//! image encoding and the disk I/O: the heartbeat never waits on the disk.
+//! A full queue drops the record (counted in `dropped`), and so does a
+//! frame while `MAX_PENDING_FRAMES` are already waiting. The writer hands
+//! each batch to the OS, so a crash of the BEAM loses at most the queue.
+//!
+//! Every record carries a CRC32 and keeps the timestamp it was given (a
+//! replay depends on the frames' exact capture times). Segments rotate at
+//! `segment_bytes`; beyond `max_segments` the oldest are deleted. A segment
+//! left unsealed by a crash has its torn tail cut and its index rebuilt on
+//! the next start.
+//!
+//! Formats: see segment.rs. Reading back: see reader.rs.
+
+pub mod reader;  // Recordings: time-range iteration
+pub mod segment; // File layouts, index, rotation
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use rustler::{NifMap, NifStruct, NifTaggedEnum, NifUnitEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::codec::Bytes;
+use crate::state::arena::{FRAME_HEIGHT, FRAME_WIDTH};
+use crate::types::KinematicsSnapshot;
+use crate::vision::detector::MotionROI;
+use crate::vision::encode;
+use segment::SegmentWriter;
+
+// Camera updates in flight to the writer (~4 s of heartbeat at 30 Hz)
+const QUEUE_DEPTH: usize = 512;
+
+// Frames are up to 900 KB each: bound the memory held by the queue
+const MAX_PENDING_FRAMES: usize = 8;
+
+/// Mirrors `%SwarmBrain.Recorder.Config{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Recorder.Config"]
+pub struct RecorderConfig {
+    pub dir: String,
+    pub segment_bytes: u64,  // Rotate once a segment reaches this size
+    pub max_segments: u32,   // Oldest deleted beyond this (0 = keep all)
+    pub index_every_ms: u32, // Index granularity
+    pub flow_every: u32,     // Record one flow grid in N frames (0 = none)
+    pub frame_every: u32,    // Record one camera frame in N (0 = none)
+    pub frame_scale: u32,    // Downscale factor (2 = 320x240)
+    pub frame_format: FrameFormat,
+    pub jpeg_quality: u8,    // 1..=100
+}
+
+impl RecorderConfig {
+    pub fn is_valid(&self) -> bool {
+        !self.dir.is_empty()
+            && self.segment_bytes >= 64 * 1024
+            && self.index_every_ms > 0
+            && self.frame_scale >= 1
+            && (1..=100).contains(&self.jpeg_quality)
+    }
+}
+
+#[derive(NifUnitEnum, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FrameFormat {
+    Jpeg,
+    Png,
+    Raw, // RGB24 rows
+}
+
+/// One black box record. Encoded to Elixir as `{:kinematics, %{...}}`,
+/// `{:flow, binary}`, `{:detection, binary}`...
+/// Appending variants keeps old recordings readable (bincode tags by position).
+#[derive(NifTaggedEnum, Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub enum Record {
+    Kinematics { vx: f32, vy: f32, px: f32, py: f32, seq: u64 },
+    Flow(Bytes), // 200 x f32 little-endian (same layout as `get_flow_grid`)
+    Motion { x: u32, y: u32, w: u32, h: u32 },
+    Frame { width: u32, height: u32, format: FrameFormat, data: Bytes },
+    Detection(Bytes), // Opaque to us: `:erlang.term_to_binary/1` on the Elixir side
+    Command { roll: f32, pitch: f32, yaw: f32, throttle: f32 },
+}
+
+/// A record and when it happened (µs since UNIX epoch).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub ts_us: u64,
+    pub record: Record,
+}
+
+#[derive(Debug)]
+pub enum RecorderError {
+    Io,
+    InvalidConfig,
+}
+
+impl From<io::Error> for RecorderError {
+    fn from(_: io::Error) -> Self {
+        RecorderError::Io
+    }
+}
+
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct RecorderStats {
+    pub records: u64,  // Written this session
+    pub bytes: u64,
+    pub segments: u64, // Opened this session
+    pub dropped: u64,  // Queue full, or discarded after a write error
+    pub failed: bool,  // A write failed: the recorder stopped writing
+}
+
+#[derive(Default)]
+struct Counters {
+    records: AtomicU64,
+    bytes: AtomicU64,
+    segments: AtomicU64,
+    dropped: AtomicU64,
+    failed: AtomicBool,
+    pending_frames: AtomicUsize,
+}
+
+enum Job {
+    Entry(Entry),
+    // Raw pixels: compressed on the writer thread
+    Frame { ts_us: u64, rgb: Vec<u8>, width: usize, height: usize },
+}
+
+// --- RECORDER ---
+
+/// The live recorder. Dropping the last handle drains the queue, seals the
+/// open segment and stops the writer thread.
+pub struct Recorder {
+    tx: Option<SyncSender<Job>>,
+    writer: Option<JoinHandle<()>>,
+    counters: Arc<Counters>,
+    frames: AtomicU64, // Camera frames seen
+    flow_every: u64,
+    frame_every: u64,
+    frame_scale: usize,
+}
+
+impl Recorder {
+    /// Repairs what a crash left behind, opens a fresh segment and starts the writer.
+    pub fn start(cfg: &RecorderConfig) -> Result<Self, RecorderError> {
+        if !cfg.is_valid() {
+            return Err(RecorderError::InvalidConfig);
+        }
+        let writer = Writer::open(cfg)?;
+        let counters = writer.counters.clone();
+
+        let (tx, rx) = mpsc::sync_channel(QUEUE_DEPTH);
+        let handle = thread::Builder::new()
+            .name("black-box".into())
+            .spawn(move || writer.run(rx))?;
+
+        Ok(Self {
+            tx: Some(tx),
+            writer: Some(handle),
+            counters,
+            frames: AtomicU64::new(0),
+            flow_every: cfg.flow_every as u64,
+            frame_every: cfg.frame_every as u64,
+            frame_scale: cfg.frame_scale as usize,
+        })
+    }
+
+    /// Records one camera update (called from the heartbeat, once per frame).
+    pub fn on_frame(&self, rgb: &[u8], flow: &[f32], kin: &KinematicsSnapshot, motion: Option<MotionROI>) {
+        let n = self.frames.fetch_add(1, Ordering::Relaxed);
+        let ts_us = kin.timestamp_us;
+
+        self.append(ts_us, Record::Kinematics { vx: kin.vx, vy: kin.vy, px: kin.px, py: kin.py, seq: kin.seq });
+        if self.flow_every > 0 && n.is_multiple_of(self.flow_every) {
+            self.append(ts_us, Record::Flow(Bytes(flow.iter().flat_map(|v| v.to_le_bytes()).collect())));
+        }
+        if let Some(roi) = motion {
+            self.append(ts_us, Record::Motion { x: roi.x, y: roi.y, w: roi.w, h: roi.h });
+        }
+        if self.frame_every > 0 && n.is_multiple_of(self.frame_every) {
+            let pending = &self.counters.pending_frames;
+            if pending.fetch_add(1, Ordering::AcqRel) >= MAX_PENDING_FRAMES {
+                pending.fetch_sub(1, Ordering::AcqRel);
+                self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+            let (rgb, width, height) = encode::downscale(rgb, FRAME_WIDTH, FRAME_HEIGHT, self.frame_scale);
+            if !self.send(Job::Frame { ts_us, rgb, width, height }) {
+                pending.fetch_sub(1, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Queues a record. Never blocks: a full queue drops it.
+    pub fn append(&self, ts_us: u64, record: Record) {
+        self.send(Job::Entry(Entry { ts_us, record }));
+    }
+
+    fn send(&self, job: Job) -> bool {
+        let sent = self.tx.as_ref().is_some_and(|tx| tx.try_send(job).is_ok());
+        if !sent {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        sent
+    }
+
+    pub fn stats(&self) -> RecorderStats {
+        let c = &self.counters;
+        RecorderStats {
+            records: c.records.load(Ordering::Relaxed),
+            bytes: c.bytes.load(Ordering::Relaxed),
+            segments: c.segments.load(Ordering::Relaxed),
+            dropped: c.dropped.load(Ordering::Relaxed),
+            failed: c.failed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for Recorder {
+    fn drop(&mut self) {
+        drop(self.tx.take()); // The writer drains what is queued, then exits
+        if let Some(handle) = self.writer.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+// --- WRITER THREAD ---
+
+struct Writer {
+    dir: PathBuf,
+    segment: Option<SegmentWriter>,
+    segment_bytes: u64,
+    max_segments: usize,
+    every_us: u64,
+    frame_format: FrameFormat,
+    jpeg_quality: u8,
+    counters: Arc<Counters>,
+}
+
+impl Writer {
+    fn open(cfg: &RecorderConfig) -> io::Result<Self> {
+        let dir = PathBuf::from(&cfg.dir);
+        let every_us = cfg.index_every_ms as u64 * 1000;
+        std::fs::create_dir_all(&dir)?;
+
+        // 1. Seal whatever the last session left open
+        let seqs = segment::list(&dir)?;
+        for &seq in &seqs {
+            segment::repair(&dir, seq, every_us)?;
+        }
+
+        // 2. This session starts its own segment
+        let next = seqs.last().map_or(1, |s| s + 1);
+        let writer = Self {
+            segment: Some(SegmentWriter::create(&dir, next, every_us)?),
+            dir,
+            segment_bytes: cfg.segment_bytes,
+            max_segments: cfg.max_segments as usize,
+            every_us,
+            frame_format: cfg.frame_format,
+            jpeg_quality: cfg.jpeg_quality,
+            counters: Arc::new(Counters::default()),
+        };
+        writer.counters.segments.store(1, Ordering::Relaxed);
+        writer.prune()?;
+        Ok(writer)
+    }
+
+    fn run(mut self, rx: Receiver<Job>) {
+        // One flush per batch: whatever queued up while we were writing
+        while let Ok(job) = rx.recv() {
+            self.handle(job);
+            while let Ok(job) = rx.try_recv() {
+                self.handle(job);
+            }
+            let flushed = self.segment.as_mut().map(|s| s.flush());
+            if let Some(Err(_)) = flushed {
+                self.fail();
+            }
+        }
+        if let Some(segment) = self.segment.take() {
+            let _ = segment.seal();
+        }
+    }
+
+    fn handle(&mut self, job: Job) {
+        if let Job::Frame { .. } = job {
+            self.counters.pending_frames.fetch_sub(1, Ordering::AcqRel);
+        }
+        if self.segment.is_none() {
+            self.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let entry = match job {
+            Job::Entry(entry) => entry,
+            Job::Frame { ts_us, rgb, width, height } => {
+                let Some(data) = encode::image(rgb, width, height, self.frame_format, self.jpeg_quality) else { return };
+                let (width, height) = (width as u32, height as u32);
+                Entry { ts_us, record: Record::Frame { width, height, format: self.frame_format, data: Bytes(data) } }
+            }
+        };
+        if self.write(entry).is_err() {
+            self.fail();
+        }
+    }
+
+    fn write(&mut self, entry: Entry) -> io::Result<()> {
+        // 1. Append
+        let segment = self.segment.as_mut().expect("checked by handle");
+        let bytes = segment.append(&entry)?;
+        self.counters.records.fetch_add(1, Ordering::Relaxed);
+        self.counters.bytes.fetch_add(bytes, Ordering::Relaxed);
+
+        // 2. Rotate
+        if segment.len() >= self.segment_bytes {
+            let next = segment.seq + 1;
+            self.segment.take().expect("open").seal()?;
+            self.segment = Some(SegmentWriter::create(&self.dir, next, self.every_us)?);
+            self.counters.segments.fetch_add(1, Ordering::Relaxed);
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    // Oldest first, never the open segment (it is the newest)
+    fn prune(&self) -> io::Result<()> {
+        if self.max_segments == 0 {
+            return Ok(());
+        }
+        let seqs = segment::list(&self.dir)?;
+        let excess = seqs.len().saturating_sub(self.max_segments.max(1));
+        for &seq in &seqs[..excess] {
+            segment::remove(&self.dir, seq)?;
+        }
+        Ok(())
+    }
+
+    // A failing disk must not take the heartbeat down: stop writing, keep counting
+    fn fail(&mut self) {
+        self.segment = None;
+        self.counters.failed.store(true, Ordering::Relaxed);
+    }
+}
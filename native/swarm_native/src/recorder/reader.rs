@@ -0,0 +1,141 @@
+// native/swarm_native/src/recorder/reader.rs
+
+//! Reading a black box back: every segment of a directory, in order, as
+//! one recording. Iteration is by time range and reads only the index
+//! blocks whose span overlaps it, so a short window out of a long flight
+//! costs about what it returns. Entries come in recording order.
+//!
+//! Opening never modifies the files, so a directory can be read while a
+//! recorder is still writing it: the open segment is scanned as it stood.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use rustler::NifMap;
+
+use super::segment::{self, SegmentIndex, SegmentReader};
+use super::{Entry, RecorderError};
+
+// Index granularity for segments that have to be scanned
+const SCAN_EVERY_US: u64 = 1_000_000;
+
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct RecordingInfo {
+    pub segments: u64,
+    pub records: u64,
+    pub first_us: u64, // 0 when empty
+    pub last_us: u64,
+}
+
+struct Segment {
+    path: PathBuf,
+    index: SegmentIndex,
+}
+
+struct Cursor {
+    from_us: u64,
+    to_us: u64,
+    segment: usize, // Position: next block to read
+    block: usize,
+    reader: Option<(usize, SegmentReader)>, // Open segment
+    in_block: bool,
+}
+
+impl Cursor {
+    fn new(from_us: u64, to_us: u64) -> Self {
+        Self { from_us, to_us, segment: 0, block: 0, reader: None, in_block: false }
+    }
+}
+
+/// An opened recording (the ResourceArc payload) and its iteration cursor.
+pub struct Recording {
+    segments: Vec<Segment>,
+    cursor: Mutex<Cursor>,
+}
+
+impl std::panic::RefUnwindSafe for Recording {}
+
+impl Recording {
+    /// Indexes every readable segment in `dir`. Unreadable ones are skipped.
+    pub fn open(dir: &Path) -> Result<Self, RecorderError> {
+        let segments = segment::list(dir)?
+            .into_iter()
+            .filter_map(|seq| {
+                let index = segment::index_or_scan(dir, seq, SCAN_EVERY_US).ok()?;
+                (index.records > 0).then(|| Segment { path: segment::segment_path(dir, seq), index })
+            })
+            .collect();
+
+        Ok(Self { segments, cursor: Mutex::new(Cursor::new(0, u64::MAX)) })
+    }
+
+    pub fn info(&self) -> RecordingInfo {
+        RecordingInfo {
+            segments: self.segments.len() as u64,
+            records: self.segments.iter().map(|s| s.index.records).sum(),
+            first_us: self.segments.iter().map(|s| s.index.min_us).min().unwrap_or(0),
+            last_us: self.segments.iter().map(|s| s.index.max_us).max().unwrap_or(0),
+        }
+    }
+
+    /// Restarts iteration over `[from_us, to_us]` (inclusive).
+    pub fn seek(&self, from_us: u64, to_us: u64) {
+        *self.cursor.lock().unwrap() = Cursor::new(from_us, to_us);
+    }
+
+    /// Up to `max` entries of the range, in recording order. Empty when done.
+    pub fn next(&self, max: usize) -> Vec<Entry> {
+        let mut cursor = self.cursor.lock().unwrap();
+        let mut out = Vec::new();
+
+        while out.len() < max {
+            // 1. Inside a block, or on to the next one that overlaps the range
+            if !cursor.in_block && !self.enter_next_block(&mut cursor) {
+                break;
+            }
+
+            // 2. Its entries, filtered to the range
+            let (from_us, to_us) = (cursor.from_us, cursor.to_us);
+            match cursor.reader.as_mut().and_then(|(_, r)| r.next_entry()) {
+                Some((entry, _)) if entry.ts_us >= from_us && entry.ts_us <= to_us => out.push(entry),
+                Some(_) => {}
+                None => cursor.in_block = false,
+            }
+        }
+        out
+    }
+
+    fn enter_next_block(&self, cursor: &mut Cursor) -> bool {
+        while let Some(s) = self.segments.get(cursor.segment) {
+            // 1. Next segment once this one is done (or does not overlap)
+            if cursor.block >= s.index.blocks.len() || !s.index.overlaps(cursor.from_us, cursor.to_us) {
+                (cursor.segment, cursor.block, cursor.reader) = (cursor.segment + 1, 0, None);
+                continue;
+            }
+            let i = cursor.block;
+            cursor.block += 1;
+            let b = s.index.blocks[i];
+            if b.min_us > cursor.to_us || b.max_us < cursor.from_us {
+                continue;
+            }
+
+            // 2. Open the segment (a segment deleted by rotation since is skipped)
+            if cursor.reader.as_ref().is_none_or(|(seg, _)| *seg != cursor.segment) {
+                match SegmentReader::open(&s.path) {
+                    Ok(reader) => cursor.reader = Some((cursor.segment, reader)),
+                    Err(_) => {
+                        cursor.block = s.index.blocks.len();
+                        continue;
+                    }
+                }
+            }
+            let (offset, end) = s.index.block_range(i);
+            let Some((_, reader)) = cursor.reader.as_mut() else { continue };
+            if reader.seek(offset, end).is_ok() {
+                cursor.in_block = true;
+                return true;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,312 @@
+// native/swarm_native/src/recorder/segment.rs
+
+//! Segment files and their sidecar indexes.
+//!
+//! * Segment `blackbox-<seq>.swbb`: `[magic "SWBB"][format u32][frame]...`
+//! * Index `blackbox-<seq>.swbi`: `[magic "SWBI"][format u32][frame]`
+//!
+//! A frame is `[len u32][crc32 u32][bincode payload]`, little-endian (the
+//! spatial journal's framing). Segment payloads are `Entry`s, the index
+//! payload is a `SegmentIndex`.
+//!
+//! The index is written when a segment is sealed. It names the segment
+//! length it describes, so an index that does not match its segment (or is
+//! missing after a crash) is rebuilt by scanning the frames.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::Entry;
+
+const SEGMENT_MAGIC: [u8; 4] = *b"SWBB";
+const INDEX_MAGIC: [u8; 4] = *b"SWBI";
+pub const FORMAT_VERSION: u32 = 1;
+
+pub const HEADER_LEN: u64 = 8;
+const FRAME_HEADER_LEN: usize = 8;
+
+// The largest record is a full-size raw frame; anything bigger is garbage.
+pub const MAX_RECORD_LEN: usize = 4 << 20;
+const MAX_INDEX_LEN: usize = 64 << 20;
+
+const PREFIX: &str = "blackbox-";
+const SEGMENT_EXT: &str = "swbb";
+const INDEX_EXT: &str = "swbi";
+
+/// A run of consecutive records: where it starts and the time span it covers.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Block {
+    pub offset: u64,
+    pub min_us: u64,
+    pub max_us: u64,
+}
+
+/// Where a segment's records are in time and on disk.
+/// Records are in arrival order, which is only roughly time order (the
+/// heartbeat and Elixir stamp their own), so blocks carry min and max.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SegmentIndex {
+    pub min_us: u64,
+    pub max_us: u64,
+    pub records: u64,
+    pub end: u64,           // Bytes of intact frames (header included)
+    pub blocks: Vec<Block>, // A new block every `index_every_ms` of record time
+}
+
+impl SegmentIndex {
+    fn note(&mut self, ts_us: u64, offset: u64, len: u64, every_us: u64) {
+        match self.blocks.last_mut() {
+            Some(b) if ts_us < b.min_us.saturating_add(every_us) => {
+                b.min_us = b.min_us.min(ts_us);
+                b.max_us = b.max_us.max(ts_us);
+            }
+            _ => self.blocks.push(Block { offset, min_us: ts_us, max_us: ts_us }),
+        }
+        if self.records == 0 {
+            (self.min_us, self.max_us) = (ts_us, ts_us);
+        }
+        self.min_us = self.min_us.min(ts_us);
+        self.max_us = self.max_us.max(ts_us);
+        self.records += 1;
+        self.end = offset + len;
+    }
+
+    pub fn overlaps(&self, from_us: u64, to_us: u64) -> bool {
+        self.records > 0 && self.min_us <= to_us && self.max_us >= from_us
+    }
+
+    /// Byte range of block `i`.
+    pub fn block_range(&self, i: usize) -> (u64, u64) {
+        let end = self.blocks.get(i + 1).map_or(self.end, |b| b.offset);
+        (self.blocks[i].offset, end)
+    }
+}
+
+// --- NAMING ---
+
+pub fn segment_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{PREFIX}{seq:06}.{SEGMENT_EXT}"))
+}
+
+pub fn index_path(dir: &Path, seq: u64) -> PathBuf {
+    dir.join(format!("{PREFIX}{seq:06}.{INDEX_EXT}"))
+}
+
+/// Sequence numbers of the segments in `dir`, oldest first.
+pub fn list(dir: &Path) -> io::Result<Vec<u64>> {
+    let mut seqs: Vec<u64> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().into_string().ok()?;
+            name.strip_prefix(PREFIX)?.strip_suffix(SEGMENT_EXT)?.strip_suffix('.')?.parse().ok()
+        })
+        .collect();
+    seqs.sort_unstable();
+    Ok(seqs)
+}
+
+// --- FRAMING ---
+
+fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Reads the next frame's payload. `None` at the end or at the first
+/// truncated, oversized or corrupt frame.
+fn read_frame(r: &mut impl Read, max_len: usize) -> Option<Vec<u8>> {
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    r.read_exact(&mut header).ok()?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as usize;
+    let crc = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+    if len > max_len {
+        return None;
+    }
+    let mut payload = vec![0u8; len];
+    r.read_exact(&mut payload).ok()?;
+    (crc32fast::hash(&payload) == crc).then_some(payload)
+}
+
+fn check_header(r: &mut impl Read, magic: [u8; 4]) -> bool {
+    let mut header = [0u8; HEADER_LEN as usize];
+    if r.read_exact(&mut header).is_err() || header[0..4] != magic {
+        return false;
+    }
+    u32::from_le_bytes([header[4], header[5], header[6], header[7]]) == FORMAT_VERSION
+}
+
+// --- READING ---
+
+/// Sequential reader over one segment, stopping at `end` or the first bad frame.
+pub struct SegmentReader {
+    file: BufReader<File>,
+    offset: u64,
+    end: u64,
+}
+
+impl SegmentReader {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+        if !check_header(&mut file, SEGMENT_MAGIC) {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a black box segment"));
+        }
+        Ok(Self { file, offset: HEADER_LEN, end: u64::MAX })
+    }
+
+    /// Reads `[offset, end)` next.
+    pub fn seek(&mut self, offset: u64, end: u64) -> io::Result<()> {
+        if offset != self.offset {
+            self.file.seek(SeekFrom::Start(offset))?;
+            self.offset = offset;
+        }
+        self.end = end;
+        Ok(())
+    }
+
+    /// The next entry and the offset it started at.
+    pub fn next_entry(&mut self) -> Option<(Entry, u64)> {
+        if self.offset >= self.end {
+            return None;
+        }
+        let payload = read_frame(&mut self.file, MAX_RECORD_LEN)?;
+        let entry = bincode::deserialize::<Entry>(&payload).ok()?;
+        let at = self.offset;
+        self.offset += (FRAME_HEADER_LEN + payload.len()) as u64;
+        Some((entry, at))
+    }
+}
+
+/// Rebuilds a segment's index from its frames.
+pub fn scan(path: &Path, every_us: u64) -> io::Result<SegmentIndex> {
+    let mut reader = SegmentReader::open(path)?;
+    let mut index = SegmentIndex { end: HEADER_LEN, ..Default::default() };
+    while let Some((entry, at)) = reader.next_entry() {
+        index.note(entry.ts_us, at, reader.offset - at, every_us);
+    }
+    Ok(index)
+}
+
+/// The sidecar index, if it exists and still describes the segment.
+pub fn load_index(dir: &Path, seq: u64) -> Option<SegmentIndex> {
+    let mut file = BufReader::new(File::open(index_path(dir, seq)).ok()?);
+    if !check_header(&mut file, INDEX_MAGIC) {
+        return None;
+    }
+    let index = bincode::deserialize::<SegmentIndex>(&read_frame(&mut file, MAX_INDEX_LEN)?).ok()?;
+    let len = fs::metadata(segment_path(dir, seq)).ok()?.len();
+    (index.end == len).then_some(index)
+}
+
+/// Index from the sidecar, or rebuilt (read-only: the segment is untouched).
+pub fn index_or_scan(dir: &Path, seq: u64, every_us: u64) -> io::Result<SegmentIndex> {
+    match load_index(dir, seq) {
+        Some(index) => Ok(index),
+        None => scan(&segment_path(dir, seq), every_us),
+    }
+}
+
+/// After a crash: cut the torn tail of an unsealed segment and seal it.
+pub fn repair(dir: &Path, seq: u64, every_us: u64) -> io::Result<()> {
+    if load_index(dir, seq).is_some() {
+        return Ok(());
+    }
+    let path = segment_path(dir, seq);
+    let index = match scan(&path, every_us) {
+        Ok(index) => index,
+        // A torn header is an empty segment; another format is left alone
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            if fs::metadata(&path)?.len() < HEADER_LEN {
+                fs::remove_file(&path)?;
+            }
+            return Ok(());
+        }
+        Err(e) => return Err(e),
+    };
+    let file = OpenOptions::new().write(true).open(&path)?;
+    if file.metadata()?.len() > index.end {
+        file.set_len(index.end)?;
+        file.sync_data()?;
+    }
+    write_index(dir, seq, &index)
+}
+
+/// Deletes a segment and its index.
+pub fn remove(dir: &Path, seq: u64) -> io::Result<()> {
+    let _ = fs::remove_file(index_path(dir, seq));
+    fs::remove_file(segment_path(dir, seq))
+}
+
+fn write_index(dir: &Path, seq: u64, index: &SegmentIndex) -> io::Result<()> {
+    let payload = bincode::serialize(index).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut bytes = Vec::with_capacity(HEADER_LEN as usize + FRAME_HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&INDEX_MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&encode_frame(&payload));
+
+    // Atomic: a half-written index must never look valid
+    let path = index_path(dir, seq);
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let mut file = File::create(&tmp)?;
+    file.write_all(&bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)
+}
+
+// --- WRITING ---
+
+/// The open segment. Appends are handed to the OS when `flush` is called.
+pub struct SegmentWriter {
+    dir: PathBuf,
+    pub seq: u64,
+    file: BufWriter<File>,
+    index: SegmentIndex,
+    every_us: u64,
+}
+
+impl SegmentWriter {
+    pub fn create(dir: &Path, seq: u64, every_us: u64) -> io::Result<Self> {
+        let mut file = OpenOptions::new().write(true).create_new(true).open(segment_path(dir, seq))?;
+        file.write_all(&SEGMENT_MAGIC)?;
+        file.write_all(&FORMAT_VERSION.to_le_bytes())?;
+        file.sync_data()?;
+
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            seq,
+            file: BufWriter::new(file),
+            index: SegmentIndex { end: HEADER_LEN, ..Default::default() },
+            every_us,
+        })
+    }
+
+    /// Appends one entry. Returns the bytes written.
+    pub fn append(&mut self, entry: &Entry) -> io::Result<u64> {
+        let payload = bincode::serialize(entry).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let frame = encode_frame(&payload);
+        self.file.write_all(&frame)?;
+        self.index.note(entry.ts_us, self.index.end, frame.len() as u64, self.every_us);
+        Ok(frame.len() as u64)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    pub fn len(&self) -> u64 {
+        self.index.end
+    }
+
+    /// Flushes to disk and writes the index. The segment is read-only afterwards.
+    pub fn seal(mut self) -> io::Result<()> {
+        self.file.flush()?;
+        self.file.get_ref().sync_data()?;
+        write_index(&self.dir, self.seq, &self.index)
+    }
+}
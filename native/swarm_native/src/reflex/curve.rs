@@ -0,0 +1,38 @@
+// native/swarm_native/src/reflex/curve.rs
+
+//! Per-axis stick shaping: deadband, expo, slew limit. All in normalised
+//! stick units (-1.0..=1.0).
+
+use rustler::NifStruct;
+
+/// Mirrors `%SwarmBrain.Hardware.Reflex.Axis{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Copy, Debug)]
+#[module = "SwarmBrain.Hardware.Reflex.Axis"]
+pub struct Axis {
+    pub deadband: f32,   // Centre band mapped to 0, rescaled outside (0.0..1.0)
+    pub expo: f32,       // 0 = linear, 1 = cubic (0.0..=1.0)
+    pub rate_limit: f32, // Max change per second (2.0 = full sweep in 1 s), 0 = off
+}
+
+impl Axis {
+    pub fn is_valid(&self) -> bool {
+        (0.0..1.0).contains(&self.deadband) && (0.0..=1.0).contains(&self.expo) && self.rate_limit >= 0.0
+    }
+
+    /// Clamp, deadband, expo.
+    pub fn shape(&self, x: f32) -> f32 {
+        let x = if x.is_nan() { 0.0 } else { x.clamp(-1.0, 1.0) };
+        let magnitude = (x.abs() - self.deadband).max(0.0) / (1.0 - self.deadband);
+        let x = magnitude.copysign(x);
+        x * (1.0 - self.expo) + x * x * x * self.expo
+    }
+
+    /// Moves `current` towards `target` by at most `rate_limit * dt_s`.
+    pub fn slew(&self, current: f32, target: f32, dt_s: f32) -> f32 {
+        if self.rate_limit == 0.0 {
+            return target;
+        }
+        let step = self.rate_limit * dt_s;
+        current + (target - current).clamp(-step, step)
+    }
+}
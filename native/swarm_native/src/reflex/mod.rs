@@ -0,0 +1,257 @@
+// native/swarm_native/src/reflex/mod.rs
+
+//! THE REFLEX ARC (Control Output)
+//!
+//! Turns RL intents (roll, pitch, yaw, throttle in -1.0..=1.0) into the
+//! exact MSP_SET_RAW_RC payload, with the parts a flight controller must
+//! never have to trust the brain for: stick shaping, slew limits, an arming
+//! interlock and a failsafe for when the brain goes quiet.
+//!
+//! # Failsafe
+//! Once the last command is older than `timeout_ms`:
+//! 1. hold (`hold_ms`): level, no yaw, last throttle
+//! 2. descend (`descend_ms`): level, throttle slews down to `descend_throttle`
+//! 3. disarmed: arm switch low, throttle minimum. Latched: fresh commands
+//!    are not enough, the brain has to arm again.
+//!
+//! A command during hold or descend resumes normal control.
+//!
+//! # Channels (AETR, Betaflight's default map)
+//! `roll | pitch | throttle | yaw | aux1 = arm switch | aux2.. = pwm_min`
+
+pub mod curve; // Deadband, expo, slew
+
+use std::sync::Mutex;
+use std::time::Instant;
+
+use rustler::{NifMap, NifStruct, NifUnitEnum};
+
+use crate::serial::msp::MAX_RC_CHANNELS;
+use curve::Axis;
+
+/// Mirrors `%SwarmBrain.Hardware.Reflex.Config{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Hardware.Reflex.Config"]
+pub struct Config {
+    pub roll: Axis,
+    pub pitch: Axis,
+    pub yaw: Axis,
+    pub throttle: Axis,
+    pub pwm_min: u16,
+    pub pwm_mid: u16,
+    pub pwm_max: u16,
+    pub arm_pwm: u16,           // Aux1 while armed
+    pub channels: usize,        // Channels in the payload (5..=18)
+    pub arm_throttle_max: f32,  // Arming is refused above this throttle
+    pub timeout_ms: u64,        // Command age that starts the failsafe
+    pub hold_ms: u64,
+    pub descend_ms: u64,
+    pub descend_throttle: f32,  // Slightly below hover
+}
+
+impl Config {
+    fn is_valid(&self) -> bool {
+        let unit = -1.0..=1.0;
+        [self.roll, self.pitch, self.yaw, self.throttle].iter().all(Axis::is_valid)
+            && self.pwm_min < self.pwm_mid
+            && self.pwm_mid < self.pwm_max
+            && (5..=MAX_RC_CHANNELS).contains(&self.channels)
+            && unit.contains(&self.arm_throttle_max)
+            && unit.contains(&self.descend_throttle)
+    }
+}
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phase {
+    Active,
+    Hold,
+    Descend,
+    Disarmed,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ReflexError {
+    InvalidConfig,
+    ThrottleHigh, // Arming above arm_throttle_max
+    NoCommands,   // Arming without a fresh command
+}
+
+#[derive(NifMap, Clone, Copy, Debug)]
+pub struct Status {
+    pub phase: Phase,
+    pub armed: bool,
+    pub command_age_ms: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Sticks {
+    roll: f32,
+    pitch: f32,
+    yaw: f32,
+    throttle: f32,
+}
+
+impl Sticks {
+    const IDLE: Sticks = Sticks { roll: 0.0, pitch: 0.0, yaw: 0.0, throttle: -1.0 };
+}
+
+/// The mixer proper; time is passed in (ms, any monotonic origin).
+pub struct Mixer {
+    config: Config,
+    command: Option<(Sticks, u64)>, // Shaped, and when it arrived
+    output: Sticks,                 // After slew limits
+    armed: bool,
+    phase: Phase,
+    last_tick: Option<u64>,
+}
+
+impl Mixer {
+    pub fn new(config: Config) -> Result<Self, ReflexError> {
+        if !config.is_valid() {
+            return Err(ReflexError::InvalidConfig);
+        }
+        Ok(Self { config, command: None, output: Sticks::IDLE, armed: false, phase: Phase::Disarmed, last_tick: None })
+    }
+
+    pub fn command(&mut self, roll: f32, pitch: f32, yaw: f32, throttle: f32, now_ms: u64) {
+        let c = &self.config;
+        let sticks = Sticks {
+            roll: c.roll.shape(roll),
+            pitch: c.pitch.shape(pitch),
+            yaw: c.yaw.shape(yaw),
+            throttle: c.throttle.shape(throttle),
+        };
+        self.command = Some((sticks, now_ms));
+    }
+
+    pub fn arm(&mut self, now_ms: u64) -> Result<(), ReflexError> {
+        if self.armed {
+            return Ok(());
+        }
+        let sticks = self.fresh_command(now_ms).ok_or(ReflexError::NoCommands)?;
+        if sticks.throttle > self.config.arm_throttle_max {
+            return Err(ReflexError::ThrottleHigh);
+        }
+        self.armed = true;
+        self.phase = Phase::Active;
+        self.output = Sticks::IDLE; // Spool up through the slew limit
+        Ok(())
+    }
+
+    pub fn disarm(&mut self) {
+        self.armed = false;
+        self.phase = Phase::Disarmed;
+        self.output = Sticks::IDLE;
+    }
+
+    /// Advances the failsafe and slew limits to `now_ms` and returns the
+    /// MSP_SET_RAW_RC payload (u16 little-endian per channel).
+    pub fn tick(&mut self, now_ms: u64) -> Vec<u8> {
+        let dt_s = self.last_tick.map_or(0, |t| now_ms.saturating_sub(t)) as f32 / 1000.0;
+        self.last_tick = Some(now_ms);
+
+        // 1. Phase
+        let c = &self.config;
+        let age = self.age(now_ms);
+        self.phase = match age {
+            _ if !self.armed => Phase::Disarmed,
+            Some(a) if a <= c.timeout_ms => Phase::Active,
+            Some(a) if a <= c.timeout_ms + c.hold_ms => Phase::Hold,
+            Some(a) if a <= c.timeout_ms + c.hold_ms + c.descend_ms => Phase::Descend,
+            _ => Phase::Disarmed,
+        };
+        if self.phase == Phase::Disarmed {
+            self.armed = false; // Latched until the next arm()
+        }
+
+        // 2. Targets (the failsafe never climbs)
+        let last = self.command.map_or(Sticks::IDLE, |(s, _)| s);
+        let target = match self.phase {
+            Phase::Active => last,
+            Phase::Hold => Sticks { throttle: last.throttle, ..Sticks::IDLE },
+            Phase::Descend => Sticks { throttle: last.throttle.min(c.descend_throttle), ..Sticks::IDLE },
+            Phase::Disarmed => Sticks::IDLE,
+        };
+
+        // 3. Slew (a disarm cuts at once)
+        self.output = match self.phase {
+            Phase::Disarmed => target,
+            _ => Sticks {
+                roll: c.roll.slew(self.output.roll, target.roll, dt_s),
+                pitch: c.pitch.slew(self.output.pitch, target.pitch, dt_s),
+                yaw: c.yaw.slew(self.output.yaw, target.yaw, dt_s),
+                throttle: c.throttle.slew(self.output.throttle, target.throttle, dt_s),
+            },
+        };
+
+        self.payload()
+    }
+
+    pub fn status(&self, now_ms: u64) -> Status {
+        Status { phase: self.phase, armed: self.armed, command_age_ms: self.age(now_ms) }
+    }
+
+    fn age(&self, now_ms: u64) -> Option<u64> {
+        self.command.map(|(_, at)| now_ms.saturating_sub(at))
+    }
+
+    fn fresh_command(&self, now_ms: u64) -> Option<Sticks> {
+        self.command.filter(|&(_, at)| now_ms.saturating_sub(at) <= self.config.timeout_ms).map(|(s, _)| s)
+    }
+
+    fn payload(&self) -> Vec<u8> {
+        let c = &self.config;
+        let o = self.output;
+        let arm = if self.armed { c.arm_pwm } else { c.pwm_min };
+
+        let mut channels = vec![self.pwm(o.roll), self.pwm(o.pitch), self.pwm(o.throttle), self.pwm(o.yaw), arm];
+        channels.resize(c.channels, c.pwm_min);
+        channels.iter().flat_map(|ch| ch.to_le_bytes()).collect()
+    }
+
+    // -1 -> pwm_min, 0 -> pwm_mid, 1 -> pwm_max (each half scaled on its own)
+    fn pwm(&self, x: f32) -> u16 {
+        let c = &self.config;
+        let half = if x >= 0.0 { c.pwm_max - c.pwm_mid } else { c.pwm_mid - c.pwm_min };
+        (c.pwm_mid as f32 + x * half as f32).round() as u16
+    }
+}
+
+/// One mixer per flight controller (the ResourceArc payload).
+pub struct Reflex {
+    mixer: Mutex<Mixer>,
+    epoch: Instant,
+}
+
+impl Reflex {
+    pub fn new(config: Config) -> Result<Self, ReflexError> {
+        Ok(Self { mixer: Mutex::new(Mixer::new(config)?), epoch: Instant::now() })
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        self.epoch.elapsed().as_millis() as u64
+    }
+
+    pub fn command(&self, roll: f32, pitch: f32, yaw: f32, throttle: f32) {
+        self.mixer.lock().unwrap().command(roll, pitch, yaw, throttle, self.now_ms());
+    }
+
+    pub fn arm(&self) -> Result<(), ReflexError> {
+        self.mixer.lock().unwrap().arm(self.now_ms())
+    }
+
+    pub fn disarm(&self) {
+        self.mixer.lock().unwrap().disarm();
+    }
+
+    pub fn tick(&self) -> (Vec<u8>, Status) {
+        let now = self.now_ms();
+        let mut mixer = self.mixer.lock().unwrap();
+        let payload = mixer.tick(now);
+        (payload, mixer.status(now))
+    }
+
+    pub fn status(&self) -> Status {
+        self.mixer.lock().unwrap().status(self.now_ms())
+    }
+}
@@ -0,0 +1,22 @@
+// native/swarm_native/src/serial/crc.rs
+
+/// CRC-8/DVB-S2 (poly 0xD5, init 0, no reflection), as used by MSP v2 and CRSF.
+pub fn crc8_dvb_s2(crc: u8, byte: u8) -> u8 {
+    let mut crc = crc ^ byte;
+    for _ in 0..8 {
+        crc = if crc & 0x80 != 0 { (crc << 1) ^ 0xD5 } else { crc << 1 };
+    }
+    crc
+}
+
+pub fn crc8_dvb_s2_slice(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |crc, &b| crc8_dvb_s2(crc, b))
+}
+
+/// CRC-16/MCRF4XX (X.25 poly 0x1021 reflected, init 0xFFFF), as used by MAVLink.
+pub fn crc16_mcrf4xx(crc: u16, byte: u8) -> u16 {
+    let mut tmp = byte ^ (crc & 0xFF) as u8;
+    tmp ^= tmp << 4;
+    let tmp = tmp as u16;
+    (crc >> 8) ^ (tmp << 8) ^ (tmp << 3) ^ (tmp >> 4)
+}
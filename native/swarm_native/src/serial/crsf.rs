@@ -0,0 +1,405 @@
+// native/swarm_native/src/serial/crsf.rs
+
+//! CRSF (Crossfire / ExpressLRS) frames, receiver <-> flight controller.
+//!
+//! `address u8 | length u8 | type u8 | payload | crc8_dvb_s2(type, payload)`
+//!
+//! `length` counts type + payload + crc (2..=62). Fields are big-endian,
+//! except the packed RC channels (16 x 11 bits, LSB first). Types from
+//! 0x28 up are "extended": the payload opens with destination and origin.
+//!
+//! There is no escaping: the address byte also shows up inside payloads,
+//! so a frame failing its length or CRC is re-scanned from the next byte.
+
+use std::sync::Mutex;
+
+use rustler::{NifMap, NifStruct, NifUnitEnum, NifUntaggedEnum};
+
+use crate::codec::Bytes;
+use crate::serial::crc::crc8_dvb_s2_slice;
+
+pub const ADDRESS_FLIGHT_CONTROLLER: u8 = 0xC8;
+pub const ADDRESS_RADIO_TRANSMITTER: u8 = 0xEA;
+pub const ADDRESS_RECEIVER: u8 = 0xEC;
+pub const ADDRESS_TRANSMITTER_MODULE: u8 = 0xEE;
+
+pub const GPS: u8 = 0x02;
+pub const BATTERY: u8 = 0x08;
+pub const LINK_STATISTICS: u8 = 0x14;
+pub const RC_CHANNELS_PACKED: u8 = 0x16;
+pub const ATTITUDE: u8 = 0x1E;
+pub const MSP_REQ: u8 = 0x7A;
+pub const MSP_RESP: u8 = 0x7B;
+pub const MSP_WRITE: u8 = 0x7C;
+
+pub const MAX_FRAME: usize = 64;
+pub const MAX_PAYLOAD: usize = MAX_FRAME - 4; // address, length, type, crc
+pub const RC_CHANNELS: usize = 16;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CrsfError {
+    TooLarge,        // Payload beyond 60 bytes
+    InvalidChannels, // RC frames carry exactly 16 channels
+}
+
+// --- MESSAGES ---
+
+/// 0x16. Channels in µs (988..2012; 11-bit ticks 172..1811 on the wire).
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.RcChannels"]
+pub struct RcChannels {
+    pub channels: Vec<u16>,
+}
+
+/// 0x14. RSSI in dBm, LQ in %, SNR in dB.
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.LinkStatistics"]
+pub struct LinkStatistics {
+    pub uplink_rssi_1: i16,
+    pub uplink_rssi_2: i16,
+    pub uplink_lq: u8,
+    pub uplink_snr: i8,
+    pub active_antenna: u8,
+    pub rf_mode: u8,         // Packet rate index (ELRS: 4 = 500 Hz ... )
+    pub uplink_tx_power: u8, // Power level index (0 = 0 mW, 1 = 10 mW, ...)
+    pub downlink_rssi: i16,
+    pub downlink_lq: u8,
+    pub downlink_snr: i8,
+}
+
+/// 0x08.
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.Battery"]
+pub struct Battery {
+    pub voltage: f32, // Volts (0.1 V on the wire)
+    pub current: f32, // Amps (0.1 A on the wire)
+    pub capacity: u32, // mAh drawn (24 bits)
+    pub remaining: u8, // %
+}
+
+/// 0x1E. Radians (100 µrad on the wire).
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.Attitude"]
+pub struct Attitude {
+    pub pitch: f32,
+    pub roll: f32,
+    pub yaw: f32,
+}
+
+/// 0x02.
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.Gps"]
+pub struct Gps {
+    pub latitude: f64,    // Degrees (1e-7 on the wire)
+    pub longitude: f64,   // Degrees
+    pub groundspeed: f32, // km/h (0.1 on the wire)
+    pub heading: f32,     // Degrees (0.01 on the wire)
+    pub altitude: i32,    // Meters (+1000 offset on the wire)
+    pub satellites: u8,
+}
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MspKind {
+    Request,  // 0x7A
+    Response, // 0x7B
+    Write,    // 0x7C
+}
+
+/// 0x7A..0x7C: one chunk of an MSP message tunnelled through the link.
+/// `data` is the MSP body after the status byte (the first chunk of a
+/// message starts with size and command).
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.Msp"]
+pub struct Msp {
+    pub kind: MspKind,
+    pub destination: u8,
+    pub origin: u8,
+    pub sequence: u8, // 0..15
+    pub start: bool,  // First chunk of a message
+    pub version: u8,  // MSP 1 or 2
+    pub error: bool,
+    pub data: Bytes,
+}
+
+/// Any other valid frame, undecoded.
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Crsf.Frame"]
+pub struct Frame {
+    pub address: u8,
+    pub frame_type: u8,
+    pub payload: Bytes,
+}
+
+/// Encoded (and decoded) as the inner struct.
+#[derive(NifUntaggedEnum, Clone, Debug, PartialEq)]
+pub enum Message {
+    RcChannels(RcChannels),
+    LinkStatistics(LinkStatistics),
+    Battery(Battery),
+    Attitude(Attitude),
+    Gps(Gps),
+    Msp(Msp),
+    Frame(Frame),
+}
+
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct ParserStats {
+    pub frames: u64,
+    pub bad_crc: u64,
+    pub dropped_bytes: u64,
+}
+
+// --- STREAMING PARSER ---
+
+/// Bytes of the frame in progress, starting at its address.
+#[derive(Default)]
+pub struct Parser {
+    buf: Vec<u8>,
+    stats: ParserStats,
+}
+
+impl Parser {
+    pub fn feed(&mut self, data: &[u8], out: &mut Vec<Message>) {
+        for &b in data {
+            self.push(b, out);
+        }
+    }
+
+    pub fn stats(&self) -> ParserStats {
+        self.stats
+    }
+
+    fn push(&mut self, b: u8, out: &mut Vec<Message>) {
+        match self.buf.len() {
+            0 if is_address(b) => self.buf.push(b),
+            0 => self.stats.dropped_bytes += 1,
+            1 if !(2..=MAX_FRAME as u8 - 2).contains(&b) => {
+                self.buf.push(b);
+                self.rescan(out);
+            }
+            _ => {
+                self.buf.push(b);
+                if self.buf.len() == self.buf[1] as usize + 2 {
+                    self.finish(out);
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<Message>) {
+        let (body, crc) = self.buf[2..].split_at(self.buf.len() - 3);
+        if crc8_dvb_s2_slice(body) != crc[0] {
+            self.stats.bad_crc += 1;
+            return self.rescan(out);
+        }
+        let frame = std::mem::take(&mut self.buf);
+        self.stats.frames += 1;
+        out.push(decode(frame[0], frame[2], &frame[3..frame.len() - 1]));
+    }
+
+    // A false start: drop the address byte and re-parse what followed it
+    fn rescan(&mut self, out: &mut Vec<Message>) {
+        let rest = std::mem::take(&mut self.buf);
+        self.stats.dropped_bytes += 1;
+        for &b in &rest[1..] {
+            self.push(b, out);
+        }
+    }
+}
+
+fn is_address(b: u8) -> bool {
+    matches!(b, ADDRESS_FLIGHT_CONTROLLER | ADDRESS_RADIO_TRANSMITTER | ADDRESS_RECEIVER | ADDRESS_TRANSMITTER_MODULE)
+}
+
+/// One parser per UART (the ResourceArc payload).
+#[derive(Default)]
+pub struct CrsfStream {
+    parser: Mutex<Parser>,
+}
+
+impl std::panic::RefUnwindSafe for CrsfStream {}
+
+impl CrsfStream {
+    pub fn feed(&self, data: &[u8]) -> Vec<Message> {
+        let mut out = Vec::new();
+        self.parser.lock().unwrap().feed(data, &mut out);
+        out
+    }
+
+    pub fn stats(&self) -> ParserStats {
+        self.parser.lock().unwrap().stats()
+    }
+}
+
+// --- DECODING ---
+
+fn decode(address: u8, frame_type: u8, p: &[u8]) -> Message {
+    let u16_at = |i: usize| u16::from_be_bytes([p[i], p[i + 1]]);
+    let i16_at = |i: usize| i16::from_be_bytes([p[i], p[i + 1]]);
+    let i32_at = |i: usize| i32::from_be_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+
+    match (frame_type, p.len()) {
+        (RC_CHANNELS_PACKED, 22) => Message::RcChannels(RcChannels { channels: unpack_channels(p) }),
+        (LINK_STATISTICS, 10..) => Message::LinkStatistics(LinkStatistics {
+            uplink_rssi_1: -(p[0] as i16),
+            uplink_rssi_2: -(p[1] as i16),
+            uplink_lq: p[2],
+            uplink_snr: p[3] as i8,
+            active_antenna: p[4],
+            rf_mode: p[5],
+            uplink_tx_power: p[6],
+            downlink_rssi: -(p[7] as i16),
+            downlink_lq: p[8],
+            downlink_snr: p[9] as i8,
+        }),
+        (BATTERY, 8..) => Message::Battery(Battery {
+            voltage: u16_at(0) as f32 / 10.0,
+            current: u16_at(2) as f32 / 10.0,
+            capacity: u32::from_be_bytes([0, p[4], p[5], p[6]]),
+            remaining: p[7],
+        }),
+        (ATTITUDE, 6..) => Message::Attitude(Attitude {
+            pitch: i16_at(0) as f32 / 10_000.0,
+            roll: i16_at(2) as f32 / 10_000.0,
+            yaw: i16_at(4) as f32 / 10_000.0,
+        }),
+        (GPS, 15..) => Message::Gps(Gps {
+            latitude: i32_at(0) as f64 / 1e7,
+            longitude: i32_at(4) as f64 / 1e7,
+            groundspeed: u16_at(8) as f32 / 10.0,
+            heading: u16_at(10) as f32 / 100.0,
+            altitude: u16_at(12) as i32 - 1000,
+            satellites: p[14],
+        }),
+        (MSP_REQ..=MSP_WRITE, 3..) => {
+            let kind = match frame_type {
+                MSP_REQ => MspKind::Request,
+                MSP_RESP => MspKind::Response,
+                _ => MspKind::Write,
+            };
+            let status = p[2];
+            Message::Msp(Msp {
+                kind,
+                destination: p[0],
+                origin: p[1],
+                sequence: status & 0x0F,
+                start: status & 0x10 != 0,
+                version: (status >> 5) & 0x03,
+                error: status & 0x80 != 0,
+                data: Bytes(p[3..].to_vec()),
+            })
+        }
+        _ => Message::Frame(Frame { address, frame_type, payload: Bytes(p.to_vec()) }),
+    }
+}
+
+// 16 x 11 bits, little-endian bit order
+fn unpack_channels(p: &[u8]) -> Vec<u16> {
+    (0..RC_CHANNELS)
+        .map(|ch| {
+            let bit = ch * 11;
+            let (byte, shift) = (bit / 8, bit % 8);
+            let word = p[byte] as u32 | (p[byte + 1] as u32) << 8 | (*p.get(byte + 2).unwrap_or(&0) as u32) << 16;
+            ticks_to_us(((word >> shift) & 0x7FF) as u16)
+        })
+        .collect()
+}
+
+fn pack_channels(channels: &[u16]) -> Vec<u8> {
+    let mut out = vec![0u8; 22];
+    for (ch, &us) in channels.iter().enumerate() {
+        let (bit, ticks) = (ch * 11, us_to_ticks(us) as u32);
+        for i in 0..11 {
+            if ticks & (1 << i) != 0 {
+                out[(bit + i) / 8] |= 1 << ((bit + i) % 8);
+            }
+        }
+    }
+    out
+}
+
+// Ticks 992 = 1500 µs, 8 ticks = 5 µs (172..1811 <-> 988..2012)
+fn ticks_to_us(ticks: u16) -> u16 {
+    ((ticks as i32 - 992) * 5 / 8 + 1500) as u16
+}
+
+fn us_to_ticks(us: u16) -> u16 {
+    ((us as i32 - 1500) * 8 / 5 + 992).clamp(0, 0x7FF) as u16
+}
+
+// --- ENCODING ---
+
+/// A frame addressed to the flight controller (or `Frame.address`).
+pub fn encode(message: &Message) -> Result<Vec<u8>, CrsfError> {
+    let (frame_type, payload) = match message {
+        Message::RcChannels(m) => {
+            if m.channels.len() != RC_CHANNELS {
+                return Err(CrsfError::InvalidChannels);
+            }
+            (RC_CHANNELS_PACKED, pack_channels(&m.channels))
+        }
+        Message::LinkStatistics(m) => {
+            let dbm = |v: i16| v.unsigned_abs().min(255) as u8;
+            let p = vec![
+                dbm(m.uplink_rssi_1),
+                dbm(m.uplink_rssi_2),
+                m.uplink_lq,
+                m.uplink_snr as u8,
+                m.active_antenna,
+                m.rf_mode,
+                m.uplink_tx_power,
+                dbm(m.downlink_rssi),
+                m.downlink_lq,
+                m.downlink_snr as u8,
+            ];
+            (LINK_STATISTICS, p)
+        }
+        Message::Battery(m) => {
+            let mut p = Vec::with_capacity(8);
+            p.extend_from_slice(&((m.voltage * 10.0).round() as u16).to_be_bytes());
+            p.extend_from_slice(&((m.current * 10.0).round() as u16).to_be_bytes());
+            p.extend_from_slice(&m.capacity.min(0xFF_FFFF).to_be_bytes()[1..]);
+            p.push(m.remaining);
+            (BATTERY, p)
+        }
+        Message::Attitude(m) => {
+            let p = [m.pitch, m.roll, m.yaw].iter().flat_map(|v| ((v * 10_000.0).round() as i16).to_be_bytes()).collect();
+            (ATTITUDE, p)
+        }
+        Message::Gps(m) => {
+            let mut p = Vec::with_capacity(15);
+            p.extend_from_slice(&((m.latitude * 1e7).round() as i32).to_be_bytes());
+            p.extend_from_slice(&((m.longitude * 1e7).round() as i32).to_be_bytes());
+            p.extend_from_slice(&((m.groundspeed * 10.0).round() as u16).to_be_bytes());
+            p.extend_from_slice(&((m.heading * 100.0).round() as u16).to_be_bytes());
+            p.extend_from_slice(&((m.altitude + 1000).clamp(0, u16::MAX as i32) as u16).to_be_bytes());
+            p.push(m.satellites);
+            (GPS, p)
+        }
+        Message::Msp(m) => {
+            let frame_type = match m.kind {
+                MspKind::Request => MSP_REQ,
+                MspKind::Response => MSP_RESP,
+                MspKind::Write => MSP_WRITE,
+            };
+            let status = (m.sequence & 0x0F) | (m.start as u8) << 4 | (m.version & 0x03) << 5 | (m.error as u8) << 7;
+            let mut p = vec![m.destination, m.origin, status];
+            p.extend_from_slice(&m.data.0);
+            (frame_type, p)
+        }
+        Message::Frame(m) => (m.frame_type, m.payload.0.clone()),
+    };
+    if payload.len() > MAX_PAYLOAD {
+        return Err(CrsfError::TooLarge);
+    }
+
+    let address = match message {
+        Message::Frame(m) => m.address,
+        _ => ADDRESS_FLIGHT_CONTROLLER,
+    };
+    let mut out = Vec::with_capacity(payload.len() + 4);
+    out.extend_from_slice(&[address, payload.len() as u8 + 2, frame_type]);
+    out.extend_from_slice(&payload);
+    out.push(crc8_dvb_s2_slice(&out[2..]));
+    Ok(out)
+}
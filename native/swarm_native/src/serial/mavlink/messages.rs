@@ -0,0 +1,339 @@
+// native/swarm_native/src/serial/mavlink/messages.rs
+
+//! The common.xml messages we speak, in wire order (fields sorted by type
+//! size, as generated by mavgen). Units are MAVLink's own, untouched, so
+//! every message round-trips bit for bit.
+
+use rustler::{NifStruct, NifUntaggedEnum};
+
+pub const HEARTBEAT: u32 = 0;
+pub const SYS_STATUS: u32 = 1;
+pub const ATTITUDE: u32 = 30;
+pub const GLOBAL_POSITION_INT: u32 = 33;
+pub const COMMAND_LONG: u32 = 76;
+pub const COMMAND_ACK: u32 = 77;
+pub const SET_POSITION_TARGET_LOCAL_NED: u32 = 84;
+
+/// (CRC_EXTRA, full payload length) of a known message id.
+pub fn spec(id: u32) -> Option<(u8, usize)> {
+    match id {
+        HEARTBEAT => Some((50, 9)),
+        SYS_STATUS => Some((124, 31)),
+        ATTITUDE => Some((39, 28)),
+        GLOBAL_POSITION_INT => Some((104, 28)),
+        COMMAND_LONG => Some((152, 33)),
+        COMMAND_ACK => Some((143, 3)),
+        SET_POSITION_TARGET_LOCAL_NED => Some((143, 53)),
+        _ => None,
+    }
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.Heartbeat"]
+pub struct Heartbeat {
+    pub custom_mode: u32, // Autopilot specific flight mode
+    pub mav_type: u8,     // MAV_TYPE (`type` in the XML)
+    pub autopilot: u8,    // MAV_AUTOPILOT
+    pub base_mode: u8,    // MAV_MODE_FLAG bits (128 = armed)
+    pub system_status: u8,
+    pub mavlink_version: u8,
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.SysStatus"]
+pub struct SysStatus {
+    pub sensors_present: u32, // MAV_SYS_STATUS_SENSOR bits
+    pub sensors_enabled: u32,
+    pub sensors_health: u32,
+    pub load: u16,            // 0.1 %
+    pub voltage_battery: u16, // mV (u16::MAX = unknown)
+    pub current_battery: i16, // cA (-1 = unknown)
+    pub drop_rate_comm: u16,  // 0.01 %
+    pub errors_comm: u16,
+    pub errors_count: (u16, u16, u16, u16), // Autopilot specific
+    pub battery_remaining: i8,              // % (-1 = unknown)
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.Attitude"]
+pub struct Attitude {
+    pub time_boot_ms: u32,
+    pub roll: f32, // rad
+    pub pitch: f32,
+    pub yaw: f32,
+    pub rollspeed: f32, // rad/s
+    pub pitchspeed: f32,
+    pub yawspeed: f32,
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.GlobalPositionInt"]
+pub struct GlobalPositionInt {
+    pub time_boot_ms: u32,
+    pub lat: i32,          // degE7
+    pub lon: i32,          // degE7
+    pub alt: i32,          // mm above MSL
+    pub relative_alt: i32, // mm above home
+    pub vx: i16,           // cm/s, NED
+    pub vy: i16,
+    pub vz: i16,
+    pub hdg: u16, // cdeg (u16::MAX = unknown)
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.CommandLong"]
+pub struct CommandLong {
+    pub target_system: u8,
+    pub target_component: u8,
+    pub command: u16, // MAV_CMD
+    pub confirmation: u8,
+    pub param1: f32,
+    pub param2: f32,
+    pub param3: f32,
+    pub param4: f32,
+    pub param5: f32,
+    pub param6: f32,
+    pub param7: f32,
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.CommandAck"]
+pub struct CommandAck {
+    pub command: u16,
+    pub result: u8, // MAV_RESULT (0 = accepted)
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Mavlink.SetPositionTargetLocalNed"]
+pub struct SetPositionTargetLocalNed {
+    pub time_boot_ms: u32,
+    pub target_system: u8,
+    pub target_component: u8,
+    pub coordinate_frame: u8, // MAV_FRAME
+    pub type_mask: u16,       // POSITION_TARGET_TYPEMASK: set bits are ignored
+    pub x: f32,               // m
+    pub y: f32,
+    pub z: f32,
+    pub vx: f32, // m/s
+    pub vy: f32,
+    pub vz: f32,
+    pub afx: f32, // m/s²
+    pub afy: f32,
+    pub afz: f32,
+    pub yaw: f32,      // rad
+    pub yaw_rate: f32, // rad/s
+}
+
+/// Encoded (and decoded) as the inner struct.
+#[derive(NifUntaggedEnum, Clone, Debug, PartialEq)]
+pub enum Message {
+    Heartbeat(Heartbeat),
+    SysStatus(SysStatus),
+    Attitude(Attitude),
+    GlobalPositionInt(GlobalPositionInt),
+    CommandLong(CommandLong),
+    CommandAck(CommandAck),
+    SetPositionTargetLocalNed(SetPositionTargetLocalNed),
+}
+
+impl Message {
+    pub fn id(&self) -> u32 {
+        match self {
+            Message::Heartbeat(_) => HEARTBEAT,
+            Message::SysStatus(_) => SYS_STATUS,
+            Message::Attitude(_) => ATTITUDE,
+            Message::GlobalPositionInt(_) => GLOBAL_POSITION_INT,
+            Message::CommandLong(_) => COMMAND_LONG,
+            Message::CommandAck(_) => COMMAND_ACK,
+            Message::SetPositionTargetLocalNed(_) => SET_POSITION_TARGET_LOCAL_NED,
+        }
+    }
+
+    /// Full-length payload (before v2 zero truncation).
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::with_capacity(64));
+        match self {
+            Message::Heartbeat(m) => {
+                w.u32(m.custom_mode);
+                w.u8(m.mav_type);
+                w.u8(m.autopilot);
+                w.u8(m.base_mode);
+                w.u8(m.system_status);
+                w.u8(m.mavlink_version);
+            }
+            Message::SysStatus(m) => {
+                w.u32(m.sensors_present);
+                w.u32(m.sensors_enabled);
+                w.u32(m.sensors_health);
+                w.u16(m.load);
+                w.u16(m.voltage_battery);
+                w.u16(m.current_battery as u16);
+                w.u16(m.drop_rate_comm);
+                w.u16(m.errors_comm);
+                let (a, b, c, d) = m.errors_count;
+                [a, b, c, d].into_iter().for_each(|v| w.u16(v));
+                w.u8(m.battery_remaining as u8);
+            }
+            Message::Attitude(m) => {
+                w.u32(m.time_boot_ms);
+                [m.roll, m.pitch, m.yaw, m.rollspeed, m.pitchspeed, m.yawspeed].into_iter().for_each(|v| w.f32(v));
+            }
+            Message::GlobalPositionInt(m) => {
+                w.u32(m.time_boot_ms);
+                [m.lat, m.lon, m.alt, m.relative_alt].into_iter().for_each(|v| w.u32(v as u32));
+                [m.vx, m.vy, m.vz].into_iter().for_each(|v| w.u16(v as u16));
+                w.u16(m.hdg);
+            }
+            Message::CommandLong(m) => {
+                [m.param1, m.param2, m.param3, m.param4, m.param5, m.param6, m.param7].into_iter().for_each(|v| w.f32(v));
+                w.u16(m.command);
+                w.u8(m.target_system);
+                w.u8(m.target_component);
+                w.u8(m.confirmation);
+            }
+            Message::CommandAck(m) => {
+                w.u16(m.command);
+                w.u8(m.result);
+            }
+            Message::SetPositionTargetLocalNed(m) => {
+                w.u32(m.time_boot_ms);
+                [m.x, m.y, m.z, m.vx, m.vy, m.vz, m.afx, m.afy, m.afz, m.yaw, m.yaw_rate]
+                    .into_iter()
+                    .for_each(|v| w.f32(v));
+                w.u16(m.type_mask);
+                w.u8(m.target_system);
+                w.u8(m.target_component);
+                w.u8(m.coordinate_frame);
+            }
+        }
+        w.0
+    }
+
+    /// `payload` must already be zero-extended to the full length.
+    pub fn parse(id: u32, payload: &[u8]) -> Option<Message> {
+        let mut r = Reader(payload);
+        let m = match id {
+            HEARTBEAT => Message::Heartbeat(Heartbeat {
+                custom_mode: r.u32(),
+                mav_type: r.u8(),
+                autopilot: r.u8(),
+                base_mode: r.u8(),
+                system_status: r.u8(),
+                mavlink_version: r.u8(),
+            }),
+            SYS_STATUS => Message::SysStatus(SysStatus {
+                sensors_present: r.u32(),
+                sensors_enabled: r.u32(),
+                sensors_health: r.u32(),
+                load: r.u16(),
+                voltage_battery: r.u16(),
+                current_battery: r.u16() as i16,
+                drop_rate_comm: r.u16(),
+                errors_comm: r.u16(),
+                errors_count: (r.u16(), r.u16(), r.u16(), r.u16()),
+                battery_remaining: r.u8() as i8,
+            }),
+            ATTITUDE => Message::Attitude(Attitude {
+                time_boot_ms: r.u32(),
+                roll: r.f32(),
+                pitch: r.f32(),
+                yaw: r.f32(),
+                rollspeed: r.f32(),
+                pitchspeed: r.f32(),
+                yawspeed: r.f32(),
+            }),
+            GLOBAL_POSITION_INT => Message::GlobalPositionInt(GlobalPositionInt {
+                time_boot_ms: r.u32(),
+                lat: r.u32() as i32,
+                lon: r.u32() as i32,
+                alt: r.u32() as i32,
+                relative_alt: r.u32() as i32,
+                vx: r.u16() as i16,
+                vy: r.u16() as i16,
+                vz: r.u16() as i16,
+                hdg: r.u16(),
+            }),
+            COMMAND_LONG => {
+                let p = [r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32()];
+                Message::CommandLong(CommandLong {
+                    command: r.u16(),
+                    target_system: r.u8(),
+                    target_component: r.u8(),
+                    confirmation: r.u8(),
+                    param1: p[0],
+                    param2: p[1],
+                    param3: p[2],
+                    param4: p[3],
+                    param5: p[4],
+                    param6: p[5],
+                    param7: p[6],
+                })
+            }
+            COMMAND_ACK => Message::CommandAck(CommandAck { command: r.u16(), result: r.u8() }),
+            SET_POSITION_TARGET_LOCAL_NED => {
+                let time_boot_ms = r.u32();
+                let v = [r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32(), r.f32()];
+                Message::SetPositionTargetLocalNed(SetPositionTargetLocalNed {
+                    time_boot_ms,
+                    type_mask: r.u16(),
+                    target_system: r.u8(),
+                    target_component: r.u8(),
+                    coordinate_frame: r.u8(),
+                    x: v[0],
+                    y: v[1],
+                    z: v[2],
+                    vx: v[3],
+                    vy: v[4],
+                    vz: v[5],
+                    afx: v[6],
+                    afy: v[7],
+                    afz: v[8],
+                    yaw: v[9],
+                    yaw_rate: v[10],
+                })
+            }
+            _ => return None,
+        };
+        Some(m)
+    }
+}
+
+// Little-endian field cursors (lengths are guaranteed by `spec`)
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn u32(&mut self, v: u32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+    fn f32(&mut self, v: f32) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl Reader<'_> {
+    fn take<const N: usize>(&mut self) -> [u8; N] {
+        let (head, rest) = self.0.split_at(N);
+        self.0 = rest;
+        head.try_into().unwrap()
+    }
+    fn u8(&mut self) -> u8 {
+        self.take::<1>()[0]
+    }
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes(self.take())
+    }
+    fn u32(&mut self) -> u32 {
+        u32::from_le_bytes(self.take())
+    }
+    fn f32(&mut self) -> f32 {
+        f32::from_le_bytes(self.take())
+    }
+}
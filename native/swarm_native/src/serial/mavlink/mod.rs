@@ -0,0 +1,494 @@
+// native/swarm_native/src/serial/mavlink/mod.rs
+
+//! MAVLink v2, for ArduPilot / PX4 airframes.
+//!
+//! `0xFD | len u8 | incompat u8 | compat u8 | seq u8 | sysid u8 | compid u8
+//! | msgid u24 | payload | crc u16 | [signature]` (little-endian).
+//!
+//! The CRC (MCRF4XX over len..payload, then the message's CRC_EXTRA) can
+//! only be checked for messages we know, so unknown ids are dropped.
+//! Trailing zero bytes of the payload are truncated on the wire and
+//! restored on receipt. v1 frames (`0xFE`) are skipped as noise.
+//!
+//! # Signing
+//! With incompat flag 0x01 a 13-byte block follows the CRC:
+//! `link_id u8 | timestamp u48 | sha256(key | frame up to here)[..6]`.
+//! Timestamps count 10 µs since 2015-01-01 and must grow per
+//! (sysid, compid, link_id) stream; a new stream may lag our clock by one
+//! minute at most. Without a key, signed frames are accepted unverified.
+
+pub mod messages;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rustler::NifMap;
+use sha2::{Digest, Sha256};
+use subtle::ConstantTimeEq;
+
+use crate::serial::crc::crc16_mcrf4xx;
+use messages::Message;
+
+pub const STX: u8 = 0xFD;
+pub const HEADER_LEN: usize = 10;
+pub const SIGNATURE_LEN: usize = 13;
+pub const KEY_LEN: usize = 32;
+
+const INCOMPAT_SIGNED: u8 = 0x01;
+const EPOCH_2015_US: u64 = 1_420_070_400_000_000;
+const STREAM_LAG: u64 = 6_000_000; // One minute, in 10 µs units
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MavlinkError {
+    InvalidKey, // Not 32 bytes
+}
+
+/// A frame that passed CRC (and signature) checks.
+pub struct Received {
+    pub system_id: u8,
+    pub component_id: u8,
+    pub message: Message,
+}
+
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct LinkStats {
+    pub frames: u64,
+    pub bad_crc: u64,
+    pub bad_signature: u64, // Forged, replayed, or unsigned while unsigned is refused
+    pub unknown: u64,       // Message ids we cannot check
+    pub dropped_bytes: u64,
+}
+
+struct Signing {
+    key: [u8; KEY_LEN],
+    link_id: u8,
+    allow_unsigned: bool,
+    timestamp: u64, // Ours: max(clock, last sent + 1, newest seen)
+    streams: HashMap<(u8, u8, u8), u64>,
+}
+
+// --- LINK ---
+
+/// One autopilot connection: our identity, the outgoing sequence, signing
+/// state and the streaming parser.
+pub struct Link {
+    system_id: u8,
+    component_id: u8,
+    seq: u8,
+    signing: Option<Signing>,
+    buf: Vec<u8>,
+    stats: LinkStats,
+}
+
+impl Link {
+    pub fn new(system_id: u8, component_id: u8) -> Self {
+        Self { system_id, component_id, seq: 0, signing: None, buf: Vec::new(), stats: LinkStats::default() }
+    }
+
+    /// `None` turns signing off. Stream timestamps restart with a new key.
+    pub fn set_signing(&mut self, key: Option<&[u8]>, link_id: u8, allow_unsigned: bool) -> Result<(), MavlinkError> {
+        self.signing = match key {
+            None => None,
+            Some(k) => Some(Signing {
+                key: k.try_into().map_err(|_| MavlinkError::InvalidKey)?,
+                link_id,
+                allow_unsigned,
+                timestamp: 0,
+                streams: HashMap::new(),
+            }),
+        };
+        Ok(())
+    }
+
+    pub fn stats(&self) -> LinkStats {
+        self.stats
+    }
+
+    pub fn encode(&mut self, message: &Message, now_us: u64) -> Vec<u8> {
+        // 1. Payload, trailing zeros cut (the first byte always stays)
+        let mut payload = message.serialize();
+        let keep = payload.iter().rposition(|&b| b != 0).map_or(1, |i| i + 1);
+        payload.truncate(keep);
+
+        // 2. Header + payload + CRC
+        let id = message.id();
+        let (crc_extra, _) = messages::spec(id).expect("every Message has a spec");
+        let incompat = if self.signing.is_some() { INCOMPAT_SIGNED } else { 0 };
+        let mut frame = Vec::with_capacity(HEADER_LEN + payload.len() + 2 + SIGNATURE_LEN);
+        frame.extend_from_slice(&[STX, payload.len() as u8, incompat, 0, self.seq, self.system_id, self.component_id]);
+        frame.extend_from_slice(&id.to_le_bytes()[..3]);
+        frame.extend_from_slice(&payload);
+        let crc = checksum(&frame[1..], crc_extra);
+        frame.extend_from_slice(&crc.to_le_bytes());
+        self.seq = self.seq.wrapping_add(1);
+
+        // 3. Signature
+        if let Some(s) = self.signing.as_mut() {
+            s.timestamp = clock(now_us).max(s.timestamp + 1);
+            frame.push(s.link_id);
+            frame.extend_from_slice(&s.timestamp.to_le_bytes()[..6]);
+            let sig = signature(&s.key, &frame);
+            frame.extend_from_slice(&sig);
+        }
+        frame
+    }
+
+    pub fn feed(&mut self, data: &[u8], now_us: u64, out: &mut Vec<Received>) {
+        for &b in data {
+            self.push(b, now_us, out);
+        }
+    }
+
+    fn push(&mut self, b: u8, now_us: u64, out: &mut Vec<Received>) {
+        if self.buf.is_empty() {
+            if b == STX {
+                self.buf.push(b);
+            } else {
+                self.stats.dropped_bytes += 1;
+            }
+            return;
+        }
+        self.buf.push(b);
+        if self.buf.len() == 3 && self.buf[2] & !INCOMPAT_SIGNED != 0 {
+            // Incompatibility flags we do not understand: must not parse
+            return self.rescan(now_us, out);
+        }
+        if self.buf.len() >= HEADER_LEN && self.buf.len() == self.frame_len() {
+            self.finish(now_us, out);
+        }
+    }
+
+    fn frame_len(&self) -> usize {
+        let signed = self.buf[2] & INCOMPAT_SIGNED != 0;
+        HEADER_LEN + self.buf[1] as usize + 2 + if signed { SIGNATURE_LEN } else { 0 }
+    }
+
+    fn finish(&mut self, now_us: u64, out: &mut Vec<Received>) {
+        let f = &self.buf;
+        let len = f[1] as usize;
+        let id = u32::from_le_bytes([f[7], f[8], f[9], 0]);
+
+        // 1. Known message and CRC: anything else may be a false start
+        let Some((crc_extra, full_len)) = messages::spec(id) else {
+            self.stats.unknown += 1;
+            return self.rescan(now_us, out);
+        };
+        let crc_at = HEADER_LEN + len;
+        if checksum(&f[1..crc_at], crc_extra) != u16::from_le_bytes([f[crc_at], f[crc_at + 1]]) {
+            self.stats.bad_crc += 1;
+            return self.rescan(now_us, out);
+        }
+
+        // 2. Signature (the frame is well formed: no rescan on failure)
+        let frame = std::mem::take(&mut self.buf);
+        if !self.verify(&frame, now_us) {
+            self.stats.bad_signature += 1;
+            return;
+        }
+
+        // 3. Payload, zero-extended (longer ones carry extensions we skip)
+        let mut payload = frame[HEADER_LEN..crc_at].to_vec();
+        payload.resize(full_len.max(len), 0);
+        if let Some(message) = Message::parse(id, &payload) {
+            self.stats.frames += 1;
+            out.push(Received { system_id: frame[5], component_id: frame[6], message });
+        }
+    }
+
+    fn verify(&mut self, frame: &[u8], now_us: u64) -> bool {
+        let signed = frame[2] & INCOMPAT_SIGNED != 0;
+        let Some(s) = self.signing.as_mut() else { return true };
+        if !signed {
+            return s.allow_unsigned;
+        }
+
+        let block = &frame[frame.len() - SIGNATURE_LEN..];
+        let link_id = block[0];
+        let mut ts = [0u8; 8];
+        ts[..6].copy_from_slice(&block[1..7]);
+        let timestamp = u64::from_le_bytes(ts);
+
+        if !bool::from(signature(&s.key, &frame[..frame.len() - 6]).ct_eq(&block[7..])) {
+            return false;
+        }
+
+        // Replay: per stream strictly increasing, new streams not too old
+        s.timestamp = s.timestamp.max(clock(now_us));
+        let stream = (frame[5], frame[6], link_id);
+        let fresh = match s.streams.get(&stream) {
+            Some(&last) => timestamp > last,
+            None => timestamp + STREAM_LAG >= s.timestamp,
+        };
+        if fresh {
+            s.streams.insert(stream, timestamp);
+            s.timestamp = s.timestamp.max(timestamp);
+        }
+        fresh
+    }
+
+    // A false start: drop the STX and re-parse what followed it
+    fn rescan(&mut self, now_us: u64, out: &mut Vec<Received>) {
+        let rest = std::mem::take(&mut self.buf);
+        self.stats.dropped_bytes += 1;
+        for &b in &rest[1..] {
+            self.push(b, now_us, out);
+        }
+    }
+}
+
+/// The ResourceArc payload.
+pub struct MavlinkLink {
+    link: Mutex<Link>,
+}
+
+impl std::panic::RefUnwindSafe for MavlinkLink {}
+
+impl MavlinkLink {
+    pub fn new(system_id: u8, component_id: u8) -> Self {
+        Self { link: Mutex::new(Link::new(system_id, component_id)) }
+    }
+
+    pub fn set_signing(&self, key: Option<&[u8]>, link_id: u8, allow_unsigned: bool) -> Result<(), MavlinkError> {
+        self.link.lock().unwrap().set_signing(key, link_id, allow_unsigned)
+    }
+
+    pub fn encode(&self, message: &Message, now_us: u64) -> Vec<u8> {
+        self.link.lock().unwrap().encode(message, now_us)
+    }
+
+    pub fn feed(&self, data: &[u8], now_us: u64) -> Vec<Received> {
+        let mut out = Vec::new();
+        self.link.lock().unwrap().feed(data, now_us, &mut out);
+        out
+    }
+
+    pub fn stats(&self) -> LinkStats {
+        self.link.lock().unwrap().stats()
+    }
+}
+
+fn checksum(bytes: &[u8], crc_extra: u8) -> u16 {
+    let crc = bytes.iter().fold(0xFFFF, |crc, &b| crc16_mcrf4xx(crc, b));
+    crc16_mcrf4xx(crc, crc_extra)
+}
+
+fn signature(key: &[u8; KEY_LEN], signed: &[u8]) -> [u8; 6] {
+    let digest = Sha256::new().chain_update(key).chain_update(signed).finalize();
+    digest[..6].try_into().unwrap()
+}
+
+// Unix µs -> 10 µs units since 2015-01-01
+fn clock(now_us: u64) -> u64 {
+    now_us.saturating_sub(EPOCH_2015_US) / 10
+}
+
+#[cfg(test)]
+mod tests {
+    use super::messages::*;
+    use super::*;
+
+    // Built from the spec by fixtures/mavlink/make_tlogs.py, not by this encoder
+    const FLIGHT: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mavlink/flight.tlog"));
+    const SIGNED: &[u8] = include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/mavlink/signed.tlog"));
+    const KEY: [u8; KEY_LEN] = {
+        let mut k = [0; KEY_LEN];
+        let mut i = 0;
+        while i < KEY_LEN {
+            k[i] = i as u8;
+            i += 1;
+        }
+        k
+    };
+
+    // tlog records: timestamp_us u64 (big-endian) | one frame (v2, or v1 0xFE)
+    fn records(tlog: &[u8]) -> Vec<(u64, Vec<u8>)> {
+        let mut out = Vec::new();
+        let mut rest = tlog;
+        while !rest.is_empty() {
+            let t = u64::from_be_bytes(rest[..8].try_into().unwrap());
+            let f = &rest[8..];
+            let len = match f[0] {
+                STX => HEADER_LEN + f[1] as usize + 2 + if f[2] & INCOMPAT_SIGNED != 0 { SIGNATURE_LEN } else { 0 },
+                _ => 8 + f[1] as usize,
+            };
+            out.push((t, f[..len].to_vec()));
+            rest = &f[len..];
+        }
+        out
+    }
+
+    fn replay(link: &mut Link, records: &[(u64, Vec<u8>)]) -> Vec<(u8, u8, Message)> {
+        let mut out = Vec::new();
+        for (t, frame) in records {
+            link.feed(frame, *t, &mut out);
+        }
+        out.into_iter().map(|r| (r.system_id, r.component_id, r.message)).collect()
+    }
+
+    fn heartbeat() -> Message {
+        Message::Heartbeat(Heartbeat { custom_mode: 4, mav_type: 2, autopilot: 3, base_mode: 217, system_status: 4, mavlink_version: 3 })
+    }
+
+    fn attitude() -> Message {
+        Message::Attitude(Attitude { time_boot_ms: 123_456, roll: 0.25, pitch: -0.125, yaw: 1.5, rollspeed: 0.0, pitchspeed: 0.0, yawspeed: -0.5 })
+    }
+
+    fn arm_ack() -> Message {
+        Message::CommandAck(CommandAck { command: 400, result: 0 })
+    }
+
+    // The autopilot's frames in flight.tlog, in order (the GCS heartbeat and the v1 frame aside)
+    fn flight() -> Vec<Message> {
+        vec![
+            heartbeat(),
+            Message::SysStatus(SysStatus {
+                sensors_present: 0x0120FC2F,
+                sensors_enabled: 0x0120FC2F,
+                sensors_health: 0x0120FC2F,
+                load: 250,
+                voltage_battery: 16_380,
+                current_battery: 1_234,
+                drop_rate_comm: 0,
+                errors_comm: 0,
+                errors_count: (0, 0, 0, 0),
+                battery_remaining: 76,
+            }),
+            attitude(),
+            Message::GlobalPositionInt(GlobalPositionInt {
+                time_boot_ms: 123_500,
+                lat: 473_977_418,
+                lon: 85_455_938,
+                alt: 488_000,
+                relative_alt: 10_000,
+                vx: 150,
+                vy: -75,
+                vz: 0,
+                hdg: 9_000,
+            }),
+            arm_ack(),
+            Message::Attitude(Attitude { time_boot_ms: 123_600, roll: 0.0, pitch: 0.0, yaw: 0.0, rollspeed: 0.0, pitchspeed: 0.0, yawspeed: 0.0 }),
+        ]
+    }
+
+    fn signing(allow_unsigned: bool) -> Link {
+        let mut link = Link::new(255, 190);
+        link.set_signing(Some(&KEY), 0, allow_unsigned).unwrap();
+        link
+    }
+
+    #[test]
+    fn flight_tlog_replays() {
+        let mut link = Link::new(255, 190);
+        let got = replay(&mut link, &records(FLIGHT));
+
+        let gcs = Message::Heartbeat(Heartbeat { custom_mode: 0, mav_type: 6, autopilot: 8, base_mode: 0, system_status: 0, mavlink_version: 3 });
+        let mut expected: Vec<_> = flight().into_iter().map(|m| (1, 1, m)).collect();
+        expected.insert(1, (255, 190, gcs));
+        assert_eq!(got, expected);
+
+        let stats = link.stats();
+        assert_eq!((stats.frames, stats.bad_crc, stats.unknown, stats.bad_signature), (7, 0, 0, 0));
+        assert_eq!(stats.dropped_bytes, 17); // The v1 frame
+    }
+
+    #[test]
+    fn flight_tlog_replays_in_any_chunking() {
+        let stream: Vec<u8> = records(FLIGHT).into_iter().flat_map(|(_, f)| f).collect();
+        let (mut whole, mut out) = (Vec::new(), Vec::new());
+        Link::new(255, 190).feed(&stream, 0, &mut whole);
+
+        for size in 1..=stream.len() {
+            let mut link = Link::new(255, 190);
+            out.clear();
+            for chunk in stream.chunks(size) {
+                link.feed(chunk, 0, &mut out);
+            }
+            assert_eq!(out.iter().map(|r| &r.message).collect::<Vec<_>>(), whole.iter().map(|r| &r.message).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn encoder_reproduces_the_tlog() {
+        let autopilot: Vec<_> = records(FLIGHT).into_iter().filter(|(_, f)| f[0] == STX && f[5] == 1).collect();
+        let mut link = Link::new(1, 1);
+        for ((t, frame), message) in autopilot.iter().zip(flight()) {
+            assert_eq!(&link.encode(&message, *t), frame, "{message:?}");
+        }
+    }
+
+    #[test]
+    fn encoder_signs_like_the_tlog() {
+        let mut link = Link::new(1, 1);
+        link.set_signing(Some(&KEY), 1, false).unwrap();
+        for ((t, frame), message) in records(SIGNED).iter().zip([heartbeat(), attitude(), arm_ack()]) {
+            assert_eq!(&link.encode(&message, *t), frame, "{message:?}");
+        }
+    }
+
+    #[test]
+    fn signed_tlog_verifies() {
+        let mut link = signing(false);
+        let got = replay(&mut link, &records(SIGNED));
+        assert_eq!(got, vec![(1, 1, heartbeat()), (1, 1, attitude()), (1, 1, arm_ack())]);
+        assert_eq!(link.stats().bad_signature, 0);
+    }
+
+    #[test]
+    fn bad_signatures_are_refused() {
+        let signed = records(SIGNED);
+
+        // Signature bytes forged
+        let mut forged = signed.clone();
+        let last = forged[0].1.len() - 1;
+        forged[0].1[last] ^= 0x01;
+        let mut link = signing(false);
+        assert_eq!(replay(&mut link, &forged).len(), 2);
+        assert_eq!(link.stats().bad_signature, 1);
+
+        // Timestamp altered (signed, so the signature no longer matches)
+        let mut shifted = signed.clone();
+        let ts_at = shifted[0].1.len() - SIGNATURE_LEN + 1;
+        shifted[0].1[ts_at] ^= 0x01;
+        let mut link = signing(false);
+        assert_eq!(replay(&mut link, &shifted).len(), 2);
+        assert_eq!(link.stats().bad_signature, 1);
+
+        // Another key
+        let mut stranger = Link::new(255, 190);
+        stranger.set_signing(Some(&[0xAA; KEY_LEN]), 0, true).unwrap();
+        assert!(replay(&mut stranger, &signed).is_empty());
+        assert_eq!(stranger.stats().bad_signature, 3);
+    }
+
+    #[test]
+    fn stale_and_replayed_timestamps_are_refused() {
+        let signed = records(SIGNED);
+
+        // Replayed, and out of order within a stream
+        let mut link = signing(false);
+        let again = [signed[0].clone(), signed[0].clone(), signed[2].clone(), signed[1].clone()];
+        assert_eq!(replay(&mut link, &again), vec![(1, 1, heartbeat()), (1, 1, arm_ack())]);
+        assert_eq!(link.stats().bad_signature, 2);
+
+        // A capture replayed later: a new stream more than a minute behind our clock
+        let late = |lag_us: u64| signed.iter().map(|(t, f)| (t + lag_us, f.clone())).collect::<Vec<_>>();
+        assert!(replay(&mut signing(false), &late(61_000_000)).is_empty());
+        assert_eq!(replay(&mut signing(false), &late(59_000_000)).len(), 3);
+    }
+
+    #[test]
+    fn unsigned_frames_follow_allow_unsigned() {
+        let flight = records(FLIGHT);
+
+        let mut strict = signing(false);
+        assert!(replay(&mut strict, &flight).is_empty());
+        assert_eq!(strict.stats().bad_signature, 7);
+
+        let mut lenient = signing(true);
+        assert_eq!(replay(&mut lenient, &flight).len(), 7);
+        assert_eq!(lenient.stats().bad_signature, 0);
+
+        // No key at all: signed frames pass unverified
+        assert_eq!(replay(&mut Link::new(255, 190), &records(SIGNED)).len(), 3);
+    }
+}
@@ -0,0 +1,13 @@
+// native/swarm_native/src/serial/mod.rs
+
+//! THE SPINE (Serial Protocols)
+//!
+//! Byte-level protocols spoken over the UARTs to the flight controller and
+//! the radio. Parsers are streaming: UART reads arrive in arbitrary
+//! fragments, so each parser keeps its partial frame between feeds and
+//! resynchronises on the next start byte after garbage or a bad checksum.
+
+pub mod crc;     // CRC8 DVB-S2 (MSP v2, CRSF), CRC16 MCRF4XX (MAVLink)
+pub mod crsf;    // Crossfire / ExpressLRS receiver link
+pub mod mavlink; // MAVLink v2 with signing
+pub mod msp;     // MultiWii Serial Protocol v1 / v2
@@ -0,0 +1,506 @@
+// native/swarm_native/src/serial/msp.rs
+
+//! MSP (MultiWii Serial Protocol), v1 and v2, as spoken by Betaflight / iNav.
+//!
+//! * v1: `$M<dir> | size u8 | cmd u8 | payload | xor(size, cmd, payload)`
+//! * v2: `$X<dir> | flag u8 | cmd u16 | size u16 | payload | crc8_dvb_s2(flag..payload)`
+//!
+//! `<dir>` is `<` (request), `>` (response) or `!` (error). Multi-byte
+//! fields are little-endian. v1 jumbo frames (size 255) are not supported.
+//!
+//! Responses to the commands below decode into typed structs; any other
+//! valid frame is handed over raw (`%SwarmBrain.Hardware.Msp.Frame{}`).
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use rustler::{NifMap, NifStruct, NifUnitEnum, NifUntaggedEnum};
+
+use crate::codec::Bytes;
+use crate::serial::crc::{crc8_dvb_s2, crc8_dvb_s2_slice};
+
+pub const MSP_IDENT: u16 = 100;
+pub const MSP_STATUS: u16 = 101;
+pub const MSP_RAW_IMU: u16 = 102;
+pub const MSP_ATTITUDE: u16 = 108;
+pub const MSP_ANALOG: u16 = 110;
+pub const MSP_SET_RAW_RC: u16 = 200;
+
+// Larger v2 frames are treated as line noise (FC buffers are far smaller)
+pub const MAX_PAYLOAD: usize = 4096;
+
+// Betaflight's MAX_SUPPORTED_RC_CHANNEL_COUNT
+pub const MAX_RC_CHANNELS: usize = 18;
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MspVersion {
+    V1,
+    V2,
+}
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Request,  // '<'
+    Response, // '>'
+    Error,    // '!'
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum MspError {
+    InvalidCommand,  // v1 commands are 0..=254
+    TooLarge,        // Payload beyond the version's size field
+    InvalidChannels, // SET_RAW_RC takes 1..=18 channels
+}
+
+// --- MESSAGES ---
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.Ident"]
+pub struct Ident {
+    pub version: u8,
+    pub multitype: u8,
+    pub msp_version: u8,
+    pub capability: u32,
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.Status"]
+pub struct Status {
+    pub cycle_time_us: u16,
+    pub i2c_errors: u16,
+    pub sensors: u16,    // Bitmask: acc, baro, mag, gps, sonar...
+    pub mode_flags: u32, // Active boxes (first 32)
+    pub profile: u8,
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.RawImu"]
+pub struct RawImu {
+    pub acc: (i16, i16, i16), // Raw sensor units (FC specific scale)
+    pub gyro: (i16, i16, i16),
+    pub mag: (i16, i16, i16),
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.Attitude"]
+pub struct Attitude {
+    pub roll: f32,  // Degrees
+    pub pitch: f32, // Degrees
+    pub yaw: f32,   // Heading, degrees
+}
+
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.Analog"]
+pub struct Analog {
+    pub voltage: f32,  // Volts
+    pub mah_drawn: u16,
+    pub rssi: u16,     // 0..1023
+    pub amperage: f32, // Amps
+}
+
+/// Any other valid frame, undecoded.
+#[derive(NifStruct, Clone, Debug, PartialEq)]
+#[module = "SwarmBrain.Hardware.Msp.Frame"]
+pub struct Frame {
+    pub version: MspVersion,
+    pub direction: Direction,
+    pub flag: u8, // v2 only (0 on v1)
+    pub cmd: u16,
+    pub payload: Bytes,
+}
+
+/// Encoded as the inner struct.
+#[derive(NifUntaggedEnum, Clone, Debug, PartialEq)]
+pub enum Message {
+    Ident(Ident),
+    Status(Status),
+    RawImu(RawImu),
+    Attitude(Attitude),
+    Analog(Analog),
+    Frame(Frame),
+}
+
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct ParserStats {
+    pub frames: u64,
+    pub bad_checksums: u64,
+    pub dropped_bytes: u64, // Bytes outside valid frames (noise, broken or corrupt frames)
+}
+
+// --- STREAMING PARSER ---
+
+/// Bytes of the frame in progress, starting at '$'.
+#[derive(Default)]
+pub struct Parser {
+    buf: Vec<u8>,
+    replay: VecDeque<u8>, // Bytes of a rejected frame, scanned again
+    stats: ParserStats,
+}
+
+impl Parser {
+    pub fn feed(&mut self, data: &[u8], out: &mut Vec<Message>) {
+        for &b in data {
+            self.push(b, out);
+            while let Some(b) = self.replay.pop_front() {
+                self.push(b, out);
+            }
+        }
+    }
+
+    pub fn stats(&self) -> ParserStats {
+        self.stats
+    }
+
+    fn push(&mut self, b: u8, out: &mut Vec<Message>) {
+        match self.buf.len() {
+            0 if b == b'$' => self.buf.push(b),
+            0 => self.stats.dropped_bytes += 1,
+            1 if matches!(b, b'M' | b'X') => self.buf.push(b),
+            2 if matches!(b, b'<' | b'>' | b'!') => self.buf.push(b),
+            1 | 2 => self.resync(b),
+            _ => {
+                self.buf.push(b);
+                match self.frame_len() {
+                    Some(len) if len > MAX_PAYLOAD + 9 => self.rescan(),
+                    Some(len) if self.buf.len() == len => self.finish(out),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    // Header + payload + checksum, once the size field is in
+    fn frame_len(&self) -> Option<usize> {
+        let b = &self.buf;
+        match b[1] {
+            b'M' if b.len() >= 5 => Some(5 + b[3] as usize + 1),
+            b'X' if b.len() >= 8 => Some(8 + u16::from_le_bytes([b[6], b[7]]) as usize + 1),
+            _ => None,
+        }
+    }
+
+    fn finish(&mut self, out: &mut Vec<Message>) {
+        let (body, checksum) = (&self.buf[3..self.buf.len() - 1], self.buf[self.buf.len() - 1]);
+        let valid = match self.buf[1] {
+            b'M' => body.iter().fold(0, |acc, b| acc ^ b) == checksum,
+            _ => crc8_dvb_s2_slice(body) == checksum,
+        };
+        if !valid {
+            self.stats.bad_checksums += 1;
+            self.rescan();
+            return;
+        }
+        self.stats.frames += 1;
+        let frame = std::mem::take(&mut self.buf);
+        let body = &frame[3..frame.len() - 1];
+
+        let direction = match frame[2] {
+            b'<' => Direction::Request,
+            b'>' => Direction::Response,
+            _ => Direction::Error,
+        };
+        let raw = match frame[1] {
+            b'M' => Frame { version: MspVersion::V1, direction, flag: 0, cmd: body[1] as u16, payload: Bytes(body[2..].to_vec()) },
+            _ => Frame {
+                version: MspVersion::V2,
+                direction,
+                flag: body[0],
+                cmd: u16::from_le_bytes([body[1], body[2]]),
+                payload: Bytes(body[5..].to_vec()),
+            },
+        };
+        out.push(decode(raw));
+    }
+
+    // A broken header: the bytes so far are noise, but `b` may open the next frame
+    fn resync(&mut self, b: u8) {
+        self.resync_after_drop();
+        if b == b'$' {
+            self.buf.push(b);
+        }
+    }
+
+    fn resync_after_drop(&mut self) {
+        self.stats.dropped_bytes += self.buf.len() as u64;
+        self.buf.clear();
+    }
+
+    // Not a frame after all (bad checksum, impossible size): a real frame
+    // may start inside it, so scan again from the next '$' after ours,
+    // ahead of any bytes still queued for replay
+    fn rescan(&mut self) {
+        let next = self.buf[1..].iter().position(|&b| b == b'$').map_or(self.buf.len(), |i| i + 1);
+        self.stats.dropped_bytes += next as u64;
+        for &b in self.buf[next..].iter().rev() {
+            self.replay.push_front(b);
+        }
+        self.buf.clear();
+    }
+}
+
+/// One parser per UART (the ResourceArc payload).
+#[derive(Default)]
+pub struct MspStream {
+    parser: Mutex<Parser>,
+}
+
+impl std::panic::RefUnwindSafe for MspStream {}
+
+impl MspStream {
+    pub fn feed(&self, data: &[u8]) -> Vec<Message> {
+        let mut out = Vec::new();
+        self.parser.lock().unwrap().feed(data, &mut out);
+        out
+    }
+
+    pub fn stats(&self) -> ParserStats {
+        self.parser.lock().unwrap().stats()
+    }
+}
+
+// --- DECODING ---
+
+fn decode(frame: Frame) -> Message {
+    if frame.direction != Direction::Response {
+        return Message::Frame(frame);
+    }
+    let p = &frame.payload.0;
+    let u16_at = |i: usize| u16::from_le_bytes([p[i], p[i + 1]]);
+    let i16_at = |i: usize| i16::from_le_bytes([p[i], p[i + 1]]);
+    let u32_at = |i: usize| u32::from_le_bytes([p[i], p[i + 1], p[i + 2], p[i + 3]]);
+
+    // Firmwares append fields over time: only a minimum length is required
+    match (frame.cmd, p.len()) {
+        (MSP_IDENT, 7..) => Message::Ident(Ident { version: p[0], multitype: p[1], msp_version: p[2], capability: u32_at(3) }),
+        (MSP_STATUS, 11..) => Message::Status(Status {
+            cycle_time_us: u16_at(0),
+            i2c_errors: u16_at(2),
+            sensors: u16_at(4),
+            mode_flags: u32_at(6),
+            profile: p[10],
+        }),
+        (MSP_RAW_IMU, 18..) => Message::RawImu(RawImu {
+            acc: (i16_at(0), i16_at(2), i16_at(4)),
+            gyro: (i16_at(6), i16_at(8), i16_at(10)),
+            mag: (i16_at(12), i16_at(14), i16_at(16)),
+        }),
+        (MSP_ATTITUDE, 6..) => Message::Attitude(Attitude {
+            roll: i16_at(0) as f32 / 10.0, // Decidegrees
+            pitch: i16_at(2) as f32 / 10.0,
+            yaw: i16_at(4) as f32,
+        }),
+        (MSP_ANALOG, 7..) => Message::Analog(Analog {
+            // Betaflight appends a 0.01 V reading after the legacy 0.1 V byte
+            voltage: if p.len() >= 9 { u16_at(7) as f32 / 100.0 } else { p[0] as f32 / 10.0 },
+            mah_drawn: u16_at(1),
+            rssi: u16_at(3),
+            amperage: i16_at(5) as f32 / 100.0,
+        }),
+        _ => Message::Frame(frame),
+    }
+}
+
+// --- ENCODING ---
+
+/// A request frame (`<`) for `cmd`.
+pub fn encode(version: MspVersion, cmd: u16, payload: &[u8]) -> Result<Vec<u8>, MspError> {
+    match version {
+        MspVersion::V1 => {
+            let cmd = u8::try_from(cmd).ok().filter(|&c| c != 255).ok_or(MspError::InvalidCommand)?;
+            let size = u8::try_from(payload.len()).ok().filter(|&s| s != 255).ok_or(MspError::TooLarge)?;
+
+            let mut out = Vec::with_capacity(6 + payload.len());
+            out.extend_from_slice(&[b'$', b'M', b'<', size, cmd]);
+            out.extend_from_slice(payload);
+            out.push(out[3..].iter().fold(0, |acc, b| acc ^ b));
+            Ok(out)
+        }
+        MspVersion::V2 => {
+            if payload.len() > MAX_PAYLOAD {
+                return Err(MspError::TooLarge);
+            }
+            let mut out = Vec::with_capacity(9 + payload.len());
+            out.extend_from_slice(&[b'$', b'X', b'<', 0]);
+            out.extend_from_slice(&cmd.to_le_bytes());
+            out.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+            out.extend_from_slice(payload);
+            out.push(out[3..].iter().fold(0, |crc, &b| crc8_dvb_s2(crc, b)));
+            Ok(out)
+        }
+    }
+}
+
+/// MSP_SET_RAW_RC: channel values in µs (AETR order on Betaflight).
+pub fn set_raw_rc(version: MspVersion, channels: &[u16]) -> Result<Vec<u8>, MspError> {
+    if channels.is_empty() || channels.len() > MAX_RC_CHANNELS {
+        return Err(MspError::InvalidChannels);
+    }
+    let payload: Vec<u8> = channels.iter().flat_map(|c| c.to_le_bytes()).collect();
+    encode(version, MSP_SET_RAW_RC, &payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(chunks: &[&[u8]]) -> (Vec<Message>, Parser) {
+        let (mut parser, mut out) = (Parser::default(), Vec::new());
+        for chunk in chunks {
+            parser.feed(chunk, &mut out);
+        }
+        (out, parser)
+    }
+
+    // As the FC would answer: same layout, '>' (not covered by the checksum)
+    fn response(version: MspVersion, cmd: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = encode(version, cmd, payload).unwrap();
+        frame[2] = b'>';
+        frame
+    }
+
+    fn raw(version: MspVersion, direction: Direction, cmd: u16, payload: &[u8]) -> Message {
+        let flag = 0;
+        Message::Frame(Frame { version, direction, flag, cmd, payload: Bytes(payload.to_vec()) })
+    }
+
+    // Any encodable frame: (version, cmd, payload)
+    fn frame() -> impl Strategy<Value = (MspVersion, u16, Vec<u8>)> {
+        prop_oneof![
+            (0..255u16, prop::collection::vec(any::<u8>(), 0..255)).prop_map(|(c, p)| (MspVersion::V1, c, p)),
+            (any::<u16>(), prop::collection::vec(any::<u8>(), 0..600)).prop_map(|(c, p)| (MspVersion::V2, c, p)),
+        ]
+    }
+
+    // Cut points for splitting a stream into reads
+    fn cuts(stream: &[u8], mut points: Vec<usize>) -> Vec<&[u8]> {
+        points.iter_mut().for_each(|p| *p %= stream.len() + 1);
+        points.sort_unstable();
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        for p in points.into_iter().chain([stream.len()]) {
+            chunks.push(&stream[start..p]);
+            start = p;
+        }
+        chunks
+    }
+
+    #[test]
+    fn requests_round_trip() {
+        for (version, cmd) in [(MspVersion::V1, MSP_ATTITUDE), (MspVersion::V2, 0x1f01)] {
+            let frame = encode(version, cmd, &[1, 2, 3]).unwrap();
+            assert_eq!(parse(&[&frame]).0, vec![raw(version, Direction::Request, cmd, &[1, 2, 3])]);
+        }
+
+        let rc = set_raw_rc(MspVersion::V1, &[1500, 1500, 1000, 1500]).unwrap();
+        let payload = [0xdc, 0x05, 0xdc, 0x05, 0xe8, 0x03, 0xdc, 0x05];
+        assert_eq!(parse(&[&rc]).0, vec![raw(MspVersion::V1, Direction::Request, MSP_SET_RAW_RC, &payload)]);
+    }
+
+    #[test]
+    fn responses_decode_into_messages() {
+        let attitude = response(MspVersion::V1, MSP_ATTITUDE, &[0x0f, 0x00, 0xf1, 0xff, 0x5a, 0x00]);
+        let analog = response(MspVersion::V2, MSP_ANALOG, &[0x7e, 0x10, 0x00, 0xff, 0x03, 0xf4, 0x01, 0xd2, 0x04]);
+        let mut error = response(MspVersion::V1, MSP_IDENT, &[]);
+        error[2] = b'!';
+
+        let (out, parser) = parse(&[&attitude, &analog, &error]);
+        assert_eq!(
+            out,
+            vec![
+                Message::Attitude(Attitude { roll: 1.5, pitch: -1.5, yaw: 90.0 }),
+                Message::Analog(Analog { voltage: 12.34, mah_drawn: 16, rssi: 1023, amperage: 5.0 }),
+                raw(MspVersion::V1, Direction::Error, MSP_IDENT, &[]),
+            ]
+        );
+        assert_eq!(parser.stats().frames, 3);
+    }
+
+    #[test]
+    fn encoder_refuses_what_the_wire_cannot_carry() {
+        assert_eq!(encode(MspVersion::V1, 255, &[]), Err(MspError::InvalidCommand));
+        assert_eq!(encode(MspVersion::V1, 256, &[]), Err(MspError::InvalidCommand));
+        assert_eq!(encode(MspVersion::V1, 1, &[0; 255]), Err(MspError::TooLarge));
+        assert_eq!(encode(MspVersion::V2, 1, &[0; MAX_PAYLOAD + 1]), Err(MspError::TooLarge));
+        assert_eq!(set_raw_rc(MspVersion::V1, &[]), Err(MspError::InvalidChannels));
+        assert_eq!(set_raw_rc(MspVersion::V1, &[1500; MAX_RC_CHANNELS + 1]), Err(MspError::InvalidChannels));
+    }
+
+    #[test]
+    fn bad_checksum_rescans_for_a_frame_inside() {
+        let good = encode(MspVersion::V1, MSP_IDENT, &[]).unwrap();
+
+        // A cut-off frame whose size field swallows the next, good one
+        let mut stream = vec![b'$', b'M', b'>', 12, MSP_ATTITUDE as u8, 0x01];
+        stream.extend_from_slice(&good);
+        stream.extend_from_slice(&[0; 8]);
+
+        let (out, parser) = parse(&[&stream]);
+        assert_eq!(out, vec![raw(MspVersion::V1, Direction::Request, MSP_IDENT, &[])]);
+        assert_eq!(parser.stats().bad_checksums, 1);
+        assert_eq!(parser.stats().frames, 1);
+
+        // A corrupted frame carrying a good one in its payload
+        let mut outer = encode(MspVersion::V2, 0x1000, &good).unwrap();
+        *outer.last_mut().unwrap() ^= 0xff;
+        outer.extend_from_slice(&good);
+        assert_eq!(parse(&[&outer]).0.len(), 2);
+    }
+
+    #[test]
+    fn oversize_frames_rescan_too() {
+        let good = encode(MspVersion::V2, 7, &[9]).unwrap();
+        let mut stream = vec![b'$', b'X', b'>', 0, 1, 0, 0xff, 0xff];
+        stream.extend_from_slice(&good);
+        assert_eq!(parse(&[&stream]).0, vec![raw(MspVersion::V2, Direction::Request, 7, &[9])]);
+    }
+
+    proptest! {
+        #[test]
+        fn frames_survive_any_chunking(
+            frames in prop::collection::vec(frame(), 1..8),
+            noise in prop::collection::vec(prop::collection::vec(any::<u8>().prop_filter("no '$'", |b| *b != b'$'), 0..16), 8),
+            points in prop::collection::vec(any::<usize>(), 0..32),
+        ) {
+            let mut stream = Vec::new();
+            let mut expected = Vec::new();
+            for ((version, cmd, payload), noise) in frames.iter().zip(&noise) {
+                stream.extend_from_slice(noise);
+                stream.extend_from_slice(&encode(*version, *cmd, payload).unwrap());
+                expected.push(raw(*version, Direction::Request, *cmd, payload));
+            }
+
+            let (whole, _) = parse(&[&stream]);
+            prop_assert_eq!(&whole, &expected);
+            let (chunked, _) = parse(&cuts(&stream, points));
+            prop_assert_eq!(chunked, expected);
+        }
+
+        #[test]
+        fn garbage_never_panics_and_chunking_never_matters(
+            parts in prop::collection::vec(prop_oneof![
+                prop::collection::vec(any::<u8>(), 0..64),
+                frame().prop_map(|(v, c, p)| encode(v, c, &p).unwrap()),
+                (frame(), any::<prop::sample::Index>(), any::<u8>()).prop_map(|((v, c, p), i, x)| {
+                    let mut f = encode(v, c, &p).unwrap();
+                    let i = i.index(f.len());
+                    f[i] ^= x.max(1);
+                    f
+                }),
+                (frame(), any::<prop::sample::Index>()).prop_map(|((v, c, p), i)| {
+                    let f = encode(v, c, &p).unwrap();
+                    f[..i.index(f.len())].to_vec()
+                }),
+            ], 0..16),
+            points in prop::collection::vec(any::<usize>(), 0..32),
+        ) {
+            let stream = parts.concat();
+            let (whole, a) = parse(&[&stream]);
+            let (chunked, b) = parse(&cuts(&stream, points));
+            prop_assert_eq!(whole, chunked);
+            prop_assert_eq!(a.stats().frames, b.stats().frames);
+            prop_assert_eq!(a.stats().bad_checksums, b.stats().bad_checksums);
+            prop_assert_eq!(a.stats().dropped_bytes, b.stats().dropped_bytes);
+        }
+    }
+}
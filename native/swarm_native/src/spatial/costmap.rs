@@ -0,0 +1,351 @@
+// native/swarm_native/src/spatial/costmap.rs
+
+//! THE TERRAIN (Navigation Costmap)
+//!
+//! A dense local copy of one rectangle of the Spatial Memory, classified for
+//! navigation. Planners search this snapshot, never the live DashMap:
+//! lookups are array reads and the world cannot shift mid-search.
+//!
+//! Each cell is FREE, UNKNOWN or BLOCKED (its status is in the caller's
+//! obstacle list). Blocked cells are inflated by a disc of `inflation`
+//! cells, and inflated cells are as impassable as the obstacle itself,
+//! except for escaping: a drone already inside the inflation zone may cross
+//! the inflated cells connected to its own position to get out.
+//!
+//! Step cost = length x (1 + unknown_penalty x fraction of unknown cells),
+//! never below the length, so distance heuristics stay admissible.
+
+use std::collections::VecDeque;
+
+use crate::spatial::grid::{GridConfig, SpatialMap, STATUS_UNKNOWN};
+use crate::spatial::query::Bounds;
+use crate::spatial::raycast;
+
+pub const FREE: u8 = 0;
+pub const UNKNOWN: u8 = 1;
+pub const BLOCKED: u8 = 2;
+
+pub type Point = (i32, i32);
+
+// Clearance is a few cells in practice. The disc is (2r + 1)^2 offsets
+// walked per obstacle, and the per-cell cover counter is a u16, so an
+// unchecked radius from the BEAM could stall a scheduler or overflow it.
+pub const MAX_INFLATION: u32 = 64;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum CostmapError {
+    TooLarge,         // Area above `max_cells`
+    InvalidInflation, // Above MAX_INFLATION
+}
+
+// 8-connected moves: (dx, dy, length)
+pub const MOVES: [(i32, i32, f32); 8] = [
+    (1, 0, 1.0),
+    (-1, 0, 1.0),
+    (0, 1, 1.0),
+    (0, -1, 1.0),
+    (1, 1, std::f32::consts::SQRT_2),
+    (1, -1, std::f32::consts::SQRT_2),
+    (-1, 1, std::f32::consts::SQRT_2),
+    (-1, -1, std::f32::consts::SQRT_2),
+];
+
+pub struct Costmap {
+    pub bounds: Bounds,
+    w: usize,
+    h: usize,
+    class: Vec<u8>,       // Raw class per cell (row-major)
+    inflated: Vec<u16>,   // Number of BLOCKED cells whose disc covers this one
+    escape: Vec<bool>,    // Inflated cells the start may cross
+    escape_list: Vec<usize>,
+    disc: Vec<(i32, i32)>, // Inflation offsets (including the center)
+    obstacles: Vec<u32>,
+    unknown_penalty: f32,
+}
+
+impl Costmap {
+    /// Snapshots `bounds`. Fails if it exceeds `max_cells` or `inflation`
+    /// exceeds MAX_INFLATION.
+    pub fn build(
+        map: &SpatialMap,
+        bounds: Bounds,
+        obstacles: &[u32],
+        inflation: u32,
+        unknown_penalty: f32,
+        max_cells: usize,
+        now_us: u64,
+    ) -> Result<Self, CostmapError> {
+        if inflation > MAX_INFLATION {
+            return Err(CostmapError::InvalidInflation);
+        }
+        if bounds.area() > max_cells as u64 {
+            return Err(CostmapError::TooLarge);
+        }
+
+        let (w, h) = (bounds.width() as usize, bounds.height() as usize);
+        // A disc wider than the box covers it all from anywhere: same result, fewer offsets
+        let r = inflation.min((w + h) as u32) as i32;
+        let disc = (-r..=r)
+            .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+            .filter(|&(dx, dy)| dx * dx + dy * dy <= r * r)
+            .collect();
+
+        let mut cm = Self {
+            bounds,
+            w,
+            h,
+            class: Vec::new(),
+            inflated: vec![0; w * h],
+            escape: vec![false; w * h],
+            escape_list: Vec::new(),
+            disc,
+            obstacles: obstacles.to_vec(),
+            unknown_penalty: unknown_penalty.max(0.0),
+        };
+
+        // 1. Never-observed cells are UNKNOWN, then overlay the stored ones
+        cm.class = vec![cm.classify(STATUS_UNKNOWN); w * h];
+        for (x, y, status, _) in map.cells_in_bounds(bounds, None, now_us) {
+            if let Some(i) = cm.index((x, y)) {
+                cm.class[i] = cm.classify(status);
+            }
+        }
+
+        // 2. Inflate
+        for i in 0..w * h {
+            if cm.class[i] == BLOCKED {
+                cm.spread(i, 1);
+            }
+        }
+
+        Ok(cm)
+    }
+
+    pub fn classify(&self, status: u32) -> u8 {
+        if self.obstacles.contains(&status) {
+            BLOCKED
+        } else if status == STATUS_UNKNOWN {
+            UNKNOWN
+        } else {
+            FREE
+        }
+    }
+
+    /// Classifies a live cell of `map` (for incremental updates).
+    pub fn classify_cell(&self, map: &SpatialMap, cfg: &GridConfig, p: Point, now_us: u64) -> u8 {
+        let status = map.cells.get(&p).map_or(STATUS_UNKNOWN, |c| c.status_at(cfg, now_us));
+        self.classify(status)
+    }
+
+    pub fn size(&self) -> usize {
+        self.w * self.h
+    }
+
+    pub fn index(&self, (x, y): Point) -> Option<usize> {
+        if !self.bounds.contains(x, y) {
+            return None;
+        }
+        let (dx, dy) = ((x as i64 - self.bounds.x_min as i64) as usize, (y as i64 - self.bounds.y_min as i64) as usize);
+        Some(dy * self.w + dx)
+    }
+
+    pub fn point(&self, i: usize) -> Point {
+        (self.bounds.x_min + (i % self.w) as i32, self.bounds.y_min + (i / self.w) as i32)
+    }
+
+    pub fn class(&self, i: usize) -> u8 {
+        self.class[i]
+    }
+
+    pub fn passable(&self, i: usize) -> bool {
+        self.class[i] != BLOCKED && (self.inflated[i] == 0 || self.escape[i])
+    }
+
+    fn passable_at(&self, p: Point) -> bool {
+        self.index(p).is_some_and(|i| self.passable(i))
+    }
+
+    fn spread(&mut self, i: usize, delta: i32) {
+        let (x, y) = self.point(i);
+        for k in 0..self.disc.len() {
+            let (dx, dy) = self.disc[k];
+            if let Some(j) = self.index((x.saturating_add(dx), y.saturating_add(dy))) {
+                self.inflated[j] = (self.inflated[j] as i32 + delta) as u16;
+            }
+        }
+    }
+
+    /// Changes a cell's raw class. Returns every cell whose cost may have
+    /// changed (the cell and its inflation disc), for incremental planners.
+    pub fn update(&mut self, i: usize, class: u8) -> Vec<usize> {
+        let old = self.class[i];
+        if old == class {
+            return Vec::new();
+        }
+
+        self.class[i] = class;
+        if old == BLOCKED {
+            self.spread(i, -1);
+        }
+        if class == BLOCKED {
+            self.spread(i, 1);
+        }
+
+        let (x, y) = self.point(i);
+        self.disc
+            .iter()
+            .filter_map(|&(dx, dy)| self.index((x.saturating_add(dx), y.saturating_add(dy))))
+            .collect()
+    }
+
+    /// Marks the inflated cells connected to `start` as crossable.
+    /// Returns every cell whose escape flag may have changed.
+    pub fn set_escape(&mut self, start: usize) -> Vec<usize> {
+        let old = std::mem::take(&mut self.escape_list);
+        for &i in &old {
+            self.escape[i] = false;
+        }
+
+        let mut list = Vec::new();
+        if self.class[start] != BLOCKED && self.inflated[start] > 0 {
+            let mut queue = VecDeque::from([start]);
+            self.escape[start] = true;
+            while let Some(i) = queue.pop_front() {
+                list.push(i);
+                let (x, y) = self.point(i);
+                for &(dx, dy, _) in &MOVES[..4] {
+                    if let Some(j) = self.index((x.saturating_add(dx), y.saturating_add(dy))) {
+                        if !self.escape[j] && self.class[j] != BLOCKED && self.inflated[j] > 0 {
+                            self.escape[j] = true;
+                            queue.push_back(j);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut changed: Vec<usize> = old.into_iter().chain(list.iter().copied()).collect();
+        changed.sort_unstable();
+        changed.dedup();
+        self.escape_list = list;
+        changed
+    }
+
+    fn step_factor(&self, a: usize, b: usize) -> f32 {
+        let unknown = (self.class[a] == UNKNOWN) as u8 + (self.class[b] == UNKNOWN) as u8;
+        1.0 + self.unknown_penalty * unknown as f32 / 2.0
+    }
+
+    /// Cost of one 8-connected move, INFINITY if it is not allowed.
+    /// Diagonals may not cut the corner of an impassable cell.
+    pub fn edge(&self, a: usize, b: usize) -> f32 {
+        if !self.passable(a) || !self.passable(b) {
+            return f32::INFINITY;
+        }
+        let ((ax, ay), (bx, by)) = (self.point(a), self.point(b));
+        let (dx, dy) = (bx - ax, by - ay);
+        if dx.abs() > 1 || dy.abs() > 1 || (dx == 0 && dy == 0) {
+            return f32::INFINITY;
+        }
+        if dx != 0 && dy != 0 && !(self.passable_at((ax.saturating_add(dx), ay)) && self.passable_at((ax, ay.saturating_add(dy)))) {
+            return f32::INFINITY;
+        }
+
+        let len = if dx != 0 && dy != 0 { std::f32::consts::SQRT_2 } else { 1.0 };
+        len * self.step_factor(a, b)
+    }
+
+    /// In-bounds 8-neighbours of `i`.
+    pub fn neighbors(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let (x, y) = self.point(i);
+        MOVES.iter().filter_map(move |&(dx, dy, _)| self.index((x.saturating_add(dx), y.saturating_add(dy))))
+    }
+
+    /// Straight-line cost between two cell centers, `None` without line of sight.
+    pub fn line(&self, a: usize, b: usize) -> Option<f32> {
+        let ((ax, ay), (bx, by)) = (self.point(a), self.point(b));
+        let cells = raycast::traverse(
+            ax as f32 + 0.5,
+            ay as f32 + 0.5,
+            bx as f32 + 0.5,
+            by as f32 + 0.5,
+            1.0,
+            self.w + self.h + 2,
+        );
+
+        let mut unknown = 0;
+        for &p in &cells {
+            let i = self.index(p)?;
+            if !self.passable(i) {
+                return None;
+            }
+            unknown += (self.class[i] == UNKNOWN) as usize;
+        }
+        if cells.last() != Some(&(bx, by)) {
+            return None; // Truncated walk
+        }
+
+        Some(self.euclid(a, b) * (1.0 + self.unknown_penalty * unknown as f32 / cells.len() as f32))
+    }
+
+    /// Octile distance: admissible for 8-connected moves.
+    pub fn octile(&self, a: usize, b: usize) -> f32 {
+        let ((ax, ay), (bx, by)) = (self.point(a), self.point(b));
+        let (dx, dy) = (((bx - ax) as f32).abs(), ((by - ay) as f32).abs());
+        dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy)
+    }
+
+    /// Euclidean distance: admissible for any-angle moves.
+    pub fn euclid(&self, a: usize, b: usize) -> f32 {
+        let ((ax, ay), (bx, by)) = (self.point(a), self.point(b));
+        (((bx - ax) as f32).powi(2) + ((by - ay) as f32).powi(2)).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::grid::STATUS_OCCUPIED;
+
+    fn build(map: &SpatialMap, bounds: Bounds, inflation: u32) -> Result<Costmap, CostmapError> {
+        Costmap::build(map, bounds, &[STATUS_OCCUPIED], inflation, 1.0, 1 << 20, 1)
+    }
+
+    #[test]
+    fn inflation_above_the_cap_is_rejected() {
+        let map = SpatialMap::new();
+        let bounds = Bounds::new(0, 0, 9, 9);
+        assert!(build(&map, bounds, MAX_INFLATION).is_ok());
+        assert_eq!(build(&map, bounds, MAX_INFLATION + 1).err(), Some(CostmapError::InvalidInflation));
+        assert_eq!(build(&map, bounds, u32::MAX).err(), Some(CostmapError::InvalidInflation));
+    }
+
+    #[test]
+    fn oversized_box_is_rejected() {
+        let map = SpatialMap::new();
+        let bounds = Bounds::new(0, 0, 1023, 1024);
+        let result = Costmap::build(&map, bounds, &[STATUS_OCCUPIED], 1, 1.0, 1 << 20, 1);
+        assert_eq!(result.err(), Some(CostmapError::TooLarge));
+    }
+
+    #[test]
+    fn obstacles_inflate_by_a_disc() {
+        let map = SpatialMap::new();
+        map.set_status(5, 5, STATUS_OCCUPIED, 1);
+        let cm = build(&map, Bounds::new(0, 0, 10, 10), 2).unwrap();
+
+        let at = |p| cm.passable(cm.index(p).unwrap());
+        assert!(!at((5, 5)) && !at((7, 5)) && !at((5, 3)) && !at((6, 6)));
+        assert!(at((8, 5)) && at((7, 7)), "outside the radius-2 disc");
+        assert_eq!(cm.class(cm.index((5, 5)).unwrap()), BLOCKED);
+        assert_eq!(cm.class(cm.index((0, 0)).unwrap()), UNKNOWN);
+    }
+
+    #[test]
+    fn inflation_wider_than_the_box_blocks_it_all() {
+        let map = SpatialMap::new();
+        map.set_status(0, 0, STATUS_OCCUPIED, 1);
+        let cm = build(&map, Bounds::new(0, 0, 3, 3), MAX_INFLATION).unwrap();
+        assert!((0..cm.size()).all(|i| !cm.passable(i)));
+    }
+}
@@ -0,0 +1,254 @@
+// native/swarm_native/src/spatial/crdt.rs
+
+//! THE HIVE MAP (Delta-State CRDT)
+//!
+//! Every cell is a last-writer-wins register. A write is stamped with a
+//! Hybrid Logical Clock: (wall_us, counter, node). Stamps are totally
+//! ordered, so whichever write carries the larger stamp wins on every drone,
+//! in any merge order, any number of times: the maps converge.
+//!
+//! Deltas are whole cell states changed since a local map version, so a
+//! drone ships only what moved since its last broadcast. A merged remote
+//! cell keeps its origin's stamp and `updated_us` (only a new local write
+//! re-stamps it); it just gets a fresh local version, which is the change
+//! feed cursor, not its age. So it is re-exported once, still carrying the
+//! origin stamp (gossip reaches drones that are not direct neighbours), and
+//! then dies out, because equal stamps never win.
+//!
+//! Wire format: `[magic "SWSD"][format u32][frame]` (framing: persist.rs).
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use rustler::NifMap;
+use serde::{Deserialize, Serialize};
+
+use crate::spatial::grid::{Cell, SpatialMap};
+use crate::spatial::persist::{self, PersistError, Record};
+
+const DELTA_MAGIC: [u8; 4] = *b"SWSD";
+
+// Remote stamps further ahead of our wall clock are refused, otherwise one
+// drone with a broken clock would win every cell for the rest of the flight.
+const MAX_DRIFT_US: u64 = 5_000_000;
+
+// Upper bound on an accepted delta (~40 bytes per cell)
+const MAX_DELTA_BYTES: usize = 64 * 1024 * 1024;
+
+/// Hybrid Logical Clock timestamp. Field order is the comparison order.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Hlc {
+    pub wall_us: u64, // Max physical time seen
+    pub counter: u32, // Ties within the same wall_us
+    pub node: u64,    // Final tie-break between drones
+}
+
+/// The local HLC. Every cell write takes a tick; every merged stamp is
+/// observed, so a local write always outranks what it builds on.
+pub struct Clock {
+    last: Mutex<(u64, u32)>,
+    node: AtomicU64,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Self { last: Mutex::new((0, 0)), node: AtomicU64::new(0) }
+    }
+
+    pub fn node(&self) -> u64 {
+        self.node.load(Ordering::Relaxed)
+    }
+
+    pub fn set_node(&self, node: u64) {
+        self.node.store(node, Ordering::Relaxed);
+    }
+
+    /// Stamp for a local write.
+    pub fn tick(&self, now_us: u64) -> Hlc {
+        let mut last = self.last.lock().unwrap();
+        *last = if now_us > last.0 { (now_us, 0) } else { (last.0, last.1 + 1) };
+        Hlc { wall_us: last.0, counter: last.1, node: self.node() }
+    }
+
+    /// Moves the clock past a remote stamp.
+    pub fn observe(&self, remote: Hlc) {
+        let mut last = self.last.lock().unwrap();
+        if (remote.wall_us, remote.counter) > *last {
+            *last = (remote.wall_us, remote.counter);
+        }
+    }
+}
+
+/// One cell as shipped between drones (the local `version` stays home).
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct Delta {
+    pub x: i32,
+    pub y: i32,
+    pub log_odds: f32,
+    pub tag: u32,
+    pub updated_us: u64,
+    pub stamp: Hlc,
+}
+
+/// Outcome of a merge.
+#[derive(NifMap, Clone, Copy, Debug, Default)]
+pub struct MergeStats {
+    pub applied: u64,  // Remote state won
+    pub stale: u64,    // Local state was newer (or identical)
+    pub rejected: u64, // Stamp too far in the future
+}
+
+pub fn encode(deltas: &[Delta]) -> Result<Vec<u8>, PersistError> {
+    let payload = bincode::serialize(deltas).map_err(|_| PersistError::Corrupt)?;
+    let mut bytes = Vec::with_capacity(16 + payload.len());
+    persist::write_header(&mut bytes, DELTA_MAGIC)?;
+    bytes.extend_from_slice(&persist::encode_frame(&payload));
+    Ok(bytes)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Vec<Delta>, PersistError> {
+    if persist::check_header(bytes, DELTA_MAGIC)? != persist::FORMAT_VERSION {
+        return Err(PersistError::Unsupported); // Deltas were never written in v1
+    }
+    let body = &bytes[persist::HEADER_LEN..];
+    match persist::decode_frame(body, MAX_DELTA_BYTES) {
+        Some((payload, len)) if len == body.len() => bincode::deserialize(payload).map_err(|_| PersistError::Corrupt),
+        _ => Err(PersistError::Corrupt),
+    }
+}
+
+impl SpatialMap {
+    /// This drone's tie-break identity (e.g. a hash of `Node.self()`).
+    pub fn set_node_id(&self, node: u64) {
+        self.clock.set_node(node);
+    }
+
+    /// Cells changed after local version `since`, at most `limit`.
+    /// Returns (cursor, encoded delta); same cursor contract as `changes_since`.
+    pub fn export_delta(&self, since: u64, limit: usize) -> Result<(u64, Vec<u8>), PersistError> {
+        let (cursor, cells) = self.changed_cells(since, limit);
+        let deltas: Vec<Delta> = cells
+            .into_iter()
+            .map(|((x, y), c)| Delta { x, y, log_odds: c.log_odds, tag: c.tag, updated_us: c.updated_us, stamp: c.stamp })
+            .collect();
+
+        Ok((cursor, encode(&deltas)?))
+    }
+
+    /// Joins a peer's delta into the map (LWW per cell on the HLC stamp).
+    pub fn merge_delta(&self, bytes: &[u8], now_us: u64) -> Result<MergeStats, PersistError> {
+        let deltas = decode(bytes)?;
+        let mut stats = MergeStats::default();
+        let mut records = Vec::new();
+        let journaling = self.is_journaling();
+
+        for d in &deltas {
+            if d.stamp.wall_us > now_us.saturating_add(MAX_DRIFT_US) || !d.log_odds.is_finite() {
+                stats.rejected += 1;
+                continue;
+            }
+            self.clock.observe(d.stamp);
+
+            let mut cell = self.cells.entry((d.x, d.y)).or_default();
+            if d.stamp <= cell.stamp {
+                stats.stale += 1;
+                continue;
+            }
+
+            // The origin's stamp, not a local tick: re-exports must not outrank it
+            *cell = Cell {
+                log_odds: d.log_odds,
+                tag: d.tag,
+                updated_us: d.updated_us,
+                version: self.next_version(), // Under the shard lock (change feed only)
+                stamp: d.stamp,
+            };
+            stats.applied += 1;
+
+            if journaling {
+                records.push(Record::Cell { x: d.x, y: d.y, cell: *cell });
+            }
+        }
+
+        if !records.is_empty() {
+            self.journal_append(&records);
+        }
+
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drone(node: u64) -> SpatialMap {
+        let map = SpatialMap::new();
+        map.set_node_id(node);
+        map
+    }
+
+    #[test]
+    fn merged_cells_keep_the_origin_stamp() {
+        let (a, b) = (drone(1), drone(2));
+        a.set_status(4, 2, 7, 1_000);
+        let origin = *a.cells.get(&(4, 2)).unwrap();
+
+        let (_, delta) = a.export_delta(0, 100).unwrap();
+        assert_eq!(b.merge_delta(&delta, 2_000).unwrap().applied, 1);
+        let merged = *b.cells.get(&(4, 2)).unwrap();
+        assert_eq!((merged.stamp, merged.updated_us, merged.tag), (origin.stamp, 1_000, 7));
+        assert_eq!(merged.stamp.node, 1);
+
+        // Gossiped on with the origin stamp, and stale when it comes back
+        let (_, relay) = b.export_delta(0, 100).unwrap();
+        assert_eq!(decode(&relay).unwrap()[0].stamp, origin.stamp);
+        let back = a.merge_delta(&relay, 2_000).unwrap();
+        assert_eq!((back.applied, back.stale), (0, 1));
+    }
+
+    #[test]
+    fn a_local_write_outranks_what_it_merged() {
+        let (a, b) = (drone(1), drone(2));
+        a.set_status(0, 0, 7, 5_000);
+        let (_, delta) = a.export_delta(0, 100).unwrap();
+        b.merge_delta(&delta, 1_000).unwrap(); // b's wall clock is behind
+
+        b.set_status(0, 0, 9, 1_000);
+        let rewritten = *b.cells.get(&(0, 0)).unwrap();
+        assert!(rewritten.stamp > a.cells.get(&(0, 0)).unwrap().stamp);
+
+        let (_, delta) = b.export_delta(0, 100).unwrap();
+        a.merge_delta(&delta, 5_000).unwrap();
+        assert_eq!(a.status(0, 0, 5_000), 9);
+    }
+
+    #[test]
+    fn merges_converge_in_any_order() {
+        let (a, b, c) = (drone(1), drone(2), drone(3));
+        a.set_status(0, 0, 1, 10);
+        b.set_status(0, 0, 2, 10); // Same wall time: node id breaks the tie
+        let (_, da) = a.export_delta(0, 100).unwrap();
+        let (_, db) = b.export_delta(0, 100).unwrap();
+
+        for (first, second) in [(&da, &db), (&db, &da)] {
+            let map = drone(9);
+            map.merge_delta(first, 10).unwrap();
+            map.merge_delta(second, 10).unwrap();
+            map.merge_delta(first, 10).unwrap();
+            assert_eq!(map.status(0, 0, 10), 2);
+        }
+        c.merge_delta(&db, 10).unwrap();
+        assert_eq!(c.cells.get(&(0, 0)).unwrap().stamp.node, 2);
+    }
+
+    #[test]
+    fn future_stamps_are_rejected() {
+        let (a, b) = (drone(1), drone(2));
+        a.set_status(0, 0, 7, 10 * MAX_DRIFT_US);
+        let (_, delta) = a.export_delta(0, 100).unwrap();
+        let stats = b.merge_delta(&delta, 0).unwrap();
+        assert_eq!((stats.applied, stats.rejected), (0, 1));
+        assert!(b.cells.is_empty());
+    }
+}
@@ -0,0 +1,397 @@
+// native/swarm_native/src/spatial/frontier.rs
+
+//! THE SCOUT (Frontier Exploration)
+//!
+//! A frontier cell is a free, passable cell with an unknown 4-neighbour:
+//! the edge of what the swarm has seen. Frontier cells are clustered
+//! (8-connected), and each cluster offers one goal, its cell nearest to
+//! the centroid.
+//!
+//! Score = gain - distance_weight x travel cost, where gain is the number of
+//! unknown cells within `sensor_range` of the goal and travel cost comes
+//! from a Dijkstra sweep over the same Costmap the planners use
+//! (unreachable clusters are dropped).
+//!
+//! Allocation is a greedy auction: the best (drone, cluster) pair wins, the
+//! unknown cells it will see are claimed, and the gain of every other
+//! cluster is recounted without them. Drones end up on distinct goals that
+//! do not look at the same unknown space. The result only depends on the map
+//! and the positions, so drones sharing a map compute the same allocation.
+
+use std::collections::VecDeque;
+
+use rustler::{NifMap, NifStruct};
+
+use crate::spatial::costmap::{Costmap, CostmapError, Point, FREE, MOVES, UNKNOWN};
+use crate::spatial::grid::{SpatialMap, MAX_REGION_CELLS};
+use crate::spatial::planner;
+use crate::spatial::query::Bounds;
+
+/// Mirrors `%SwarmBrain.Spatial.FrontierOptions{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Spatial.FrontierOptions"]
+pub struct FrontierOptions {
+    pub radius: u32,          // Search box half-size around each drone, in cells
+    pub min_size: u32,        // Smaller clusters are ignored (speckle)
+    pub sensor_range: u32,    // Gain radius around a goal, in cells
+    pub distance_weight: f32, // Gain cells one unit of travel cost is worth
+    pub obstacles: Vec<u32>,  // Same meaning as in PlanOptions (never include 0 here)
+    pub inflation: u32,
+    pub unknown_penalty: f32,
+}
+
+/// One exploration goal.
+#[derive(NifMap, Clone, Debug)]
+pub struct Frontier {
+    pub target: (i32, i32),   // The cell to fly to
+    pub centroid: (f32, f32), // Of the cluster, in cells
+    pub size: u32,            // Frontier cells in the cluster
+    pub gain: u32,            // Unknown cells within sensor_range of the target
+    pub distance: f32,        // Travel cost from the drone
+    pub score: f32,
+}
+
+struct Cluster {
+    target: usize,
+    centroid: (f32, f32),
+    size: u32,
+}
+
+/// Scored frontiers reachable from `origin`, best first. Fails if the
+/// search box exceeds MAX_REGION_CELLS or `inflation` exceeds MAX_INFLATION.
+pub fn find(map: &SpatialMap, origin: Point, opts: &FrontierOptions, now_us: u64) -> Result<Vec<Frontier>, CostmapError> {
+    let mut cm = build(map, Bounds::new(origin.0, origin.1, origin.0, origin.1).grow(opts.radius), opts, now_us)?;
+    let clusters = detect(&cm, opts.min_size);
+    let dist = sweep(&mut cm, origin);
+    let disc = disc(opts.sensor_range);
+    let claimed = vec![false; cm.size()];
+
+    let mut out: Vec<Frontier> = clusters
+        .iter()
+        .filter_map(|c| frontier(&cm, c, &dist, gain(&cm, c.target, &disc, &claimed), opts))
+        .collect();
+    out.sort_by(|a, b| b.score.total_cmp(&a.score));
+    Ok(out)
+}
+
+/// One goal per drone (same order as `drones`), `None` for a drone left
+/// without a reachable cluster. Fails like `find`, for the box around all
+/// the drones.
+pub fn allocate(
+    map: &SpatialMap,
+    drones: &[Point],
+    opts: &FrontierOptions,
+    now_us: u64,
+) -> Result<Vec<Option<Frontier>>, CostmapError> {
+    let Some(&(x0, y0)) = drones.first() else {
+        return Ok(Vec::new());
+    };
+
+    // 1. One costmap covering every drone's search box
+    let bounds = drones
+        .iter()
+        .fold(Bounds::new(x0, y0, x0, y0), |b, &(x, y)| {
+            Bounds::new(b.x_min.min(x), b.y_min.min(y), b.x_max.max(x), b.y_max.max(y))
+        })
+        .grow(opts.radius);
+    let mut cm = build(map, bounds, opts, now_us)?;
+    let clusters = detect(&cm, opts.min_size);
+    let dists: Vec<Vec<f32>> = drones.iter().map(|&p| sweep(&mut cm, p)).collect();
+
+    // 2. Greedy auction over (drone, cluster) pairs
+    let disc = disc(opts.sensor_range);
+    let mut claimed = vec![false; cm.size()];
+    let mut gains: Vec<u32> = clusters.iter().map(|c| gain(&cm, c.target, &disc, &claimed)).collect();
+    let mut taken = vec![false; clusters.len()];
+    let mut out: Vec<Option<Frontier>> = vec![None; drones.len()];
+
+    loop {
+        let mut best: Option<(usize, usize, Frontier)> = None;
+        for (d, dist) in dists.iter().enumerate() {
+            if out[d].is_some() {
+                continue;
+            }
+            for (k, c) in clusters.iter().enumerate() {
+                if taken[k] {
+                    continue;
+                }
+                if let Some(f) = frontier(&cm, c, dist, gains[k], opts) {
+                    if best.as_ref().is_none_or(|(_, _, b)| f.score > b.score) {
+                        best = Some((d, k, f));
+                    }
+                }
+            }
+        }
+
+        let Some((d, k, f)) = best else {
+            break; // Every drone served, or nothing reachable is left
+        };
+        out[d] = Some(f);
+        taken[k] = true;
+
+        // 3. What this drone will see no longer counts for the others
+        let (tx, ty) = cm.point(clusters[k].target);
+        for &(dx, dy) in &disc {
+            if let Some(i) = cm.index((tx.saturating_add(dx), ty.saturating_add(dy))) {
+                claimed[i] = true;
+            }
+        }
+        let reach = 2 * opts.sensor_range as i64;
+        for (j, c) in clusters.iter().enumerate() {
+            let (cx, cy) = cm.point(c.target);
+            if !taken[j] && (cx as i64 - tx as i64).abs() <= reach && (cy as i64 - ty as i64).abs() <= reach {
+                gains[j] = gain(&cm, c.target, &disc, &claimed);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn build(map: &SpatialMap, bounds: Bounds, opts: &FrontierOptions, now_us: u64) -> Result<Costmap, CostmapError> {
+    Costmap::build(map, bounds, &opts.obstacles, opts.inflation, opts.unknown_penalty, MAX_REGION_CELLS, now_us)
+}
+
+// Travel costs from a drone (escaping its own inflation zone, like the planners).
+fn sweep(cm: &mut Costmap, from: Point) -> Vec<f32> {
+    match cm.index(from) {
+        Some(s) => {
+            cm.set_escape(s);
+            planner::distances(cm, s)
+        }
+        None => vec![f32::INFINITY; cm.size()],
+    }
+}
+
+/// Frontier cells, clustered 8-connected; clusters below `min_size` are dropped.
+fn detect(cm: &Costmap, min_size: u32) -> Vec<Cluster> {
+    let is_frontier: Vec<bool> = (0..cm.size())
+        .map(|i| {
+            let (x, y) = cm.point(i);
+            cm.class(i) == FREE
+                && cm.passable(i)
+                && MOVES[..4].iter().any(|&(dx, dy, _)| {
+                    cm.index((x.saturating_add(dx), y.saturating_add(dy))).is_some_and(|j| cm.class(j) == UNKNOWN)
+                })
+        })
+        .collect();
+
+    let mut seen = vec![false; cm.size()];
+    let mut clusters = Vec::new();
+
+    for i in 0..cm.size() {
+        if !is_frontier[i] || seen[i] {
+            continue;
+        }
+
+        // 1. Flood the cluster
+        let mut cells = Vec::new();
+        let mut queue = VecDeque::from([i]);
+        seen[i] = true;
+        while let Some(u) = queue.pop_front() {
+            cells.push(u);
+            for v in cm.neighbors(u) {
+                if is_frontier[v] && !seen[v] {
+                    seen[v] = true;
+                    queue.push_back(v);
+                }
+            }
+        }
+        if cells.len() < min_size as usize {
+            continue;
+        }
+
+        // 2. Centroid, and the member cell nearest to it (clusters can be curved)
+        let n = cells.len() as f32;
+        let (sx, sy) = cells.iter().fold((0.0f32, 0.0f32), |(sx, sy), &c| {
+            let (x, y) = cm.point(c);
+            (sx + x as f32, sy + y as f32)
+        });
+        let centroid = (sx / n, sy / n);
+        let target = cells
+            .iter()
+            .copied()
+            .min_by(|&a, &b| dist2(cm.point(a), centroid).total_cmp(&dist2(cm.point(b), centroid)))
+            .unwrap_or(i);
+
+        clusters.push(Cluster { target, centroid, size: cells.len() as u32 });
+    }
+
+    clusters
+}
+
+fn dist2((x, y): Point, (cx, cy): (f32, f32)) -> f32 {
+    (x as f32 - cx).powi(2) + (y as f32 - cy).powi(2)
+}
+
+fn disc(r: u32) -> Vec<(i32, i32)> {
+    let r = r.min(1024) as i32;
+    (-r..=r)
+        .flat_map(|dy| (-r..=r).map(move |dx| (dx, dy)))
+        .filter(|&(dx, dy)| dx * dx + dy * dy <= r * r)
+        .collect()
+}
+
+// Unknown, unclaimed cells in the sensor disc around `target`
+fn gain(cm: &Costmap, target: usize, disc: &[(i32, i32)], claimed: &[bool]) -> u32 {
+    let (x, y) = cm.point(target);
+    disc.iter()
+        .filter_map(|&(dx, dy)| cm.index((x.saturating_add(dx), y.saturating_add(dy))))
+        .filter(|&i| cm.class(i) == UNKNOWN && !claimed[i])
+        .count() as u32
+}
+
+// Scores a cluster for one drone; `None` if unreachable or nothing is left to see.
+fn frontier(cm: &Costmap, c: &Cluster, dist: &[f32], gain: u32, opts: &FrontierOptions) -> Option<Frontier> {
+    let distance = dist[c.target];
+    if !distance.is_finite() || gain == 0 {
+        return None;
+    }
+    Some(Frontier {
+        target: cm.point(c.target),
+        centroid: c.centroid,
+        size: c.size,
+        gain,
+        distance,
+        score: gain as f32 - opts.distance_weight * distance,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spatial::costmap::MAX_INFLATION;
+    use crate::spatial::grid::{STATUS_FREE, STATUS_OCCUPIED};
+
+    fn opts() -> FrontierOptions {
+        FrontierOptions {
+            radius: 20,
+            min_size: 2,
+            sensor_range: 3,
+            distance_weight: 0.1,
+            obstacles: vec![STATUS_OCCUPIED],
+            inflation: 0,
+            unknown_penalty: 1.0,
+        }
+    }
+
+    // A known free rectangle inside unknown space: frontiers on every edge
+    fn room(x0: i32, y0: i32, x1: i32, y1: i32) -> SpatialMap {
+        let map = SpatialMap::new();
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                map.set_status(x, y, STATUS_FREE, 1);
+            }
+        }
+        map
+    }
+
+    #[test]
+    fn invalid_inflation_is_rejected() {
+        let map = room(0, 0, 4, 4);
+        let opts = FrontierOptions { inflation: MAX_INFLATION + 1, ..opts() };
+        assert_eq!(find(&map, (2, 2), &opts, 1).err(), Some(CostmapError::InvalidInflation));
+        assert_eq!(allocate(&map, &[(2, 2)], &opts, 1).err(), Some(CostmapError::InvalidInflation));
+    }
+
+    #[test]
+    fn oversized_search_box_is_rejected() {
+        let map = room(0, 0, 4, 4);
+        let opts = FrontierOptions { radius: 4096, ..opts() };
+        assert_eq!(find(&map, (2, 2), &opts, 1).err(), Some(CostmapError::TooLarge));
+    }
+
+    #[test]
+    fn finds_reachable_frontiers_best_first() {
+        let map = room(0, 0, 10, 2);
+        let found = find(&map, (1, 1), &opts(), 1).unwrap();
+        assert!(!found.is_empty());
+        assert!(found.windows(2).all(|w| w[0].score >= w[1].score));
+        for f in &found {
+            assert!(f.gain > 0 && f.distance.is_finite());
+            assert_eq!(map.status(f.target.0, f.target.1, 1), STATUS_FREE);
+        }
+    }
+
+    #[test]
+    fn walled_off_frontiers_are_dropped() {
+        // Two rooms; the wall column between them is known and occupied
+        // (taller than the search box, so nothing goes around it through unknown space)
+        let map = room(0, 0, 10, 4);
+        for y in -30..=30 {
+            map.set_status(5, y, STATUS_OCCUPIED, 1);
+        }
+        let found = find(&map, (1, 2), &opts(), 1).unwrap();
+        assert!(found.iter().all(|f| f.target.0 < 5), "{found:?}");
+    }
+
+    #[test]
+    fn auction_gives_drones_distinct_goals() {
+        // Walled room with three gaps (left, right, bottom): three clusters
+        let map = room(0, 0, 20, 20);
+        for k in -1..=21 {
+            for (x, y) in [(-1, k), (21, k), (k, -1), (k, 21)] {
+                let gap = ((x == -1 || x == 21) && (9..=11).contains(&y)) || (y == -1 && (9..=11).contains(&x));
+                if !gap {
+                    map.set_status(x, y, STATUS_OCCUPIED, 1);
+                }
+            }
+        }
+        let drones = [(2, 10), (18, 10), (10, 2)];
+        let goals = allocate(&map, &drones, &opts(), 1).unwrap();
+        assert_eq!(goals.len(), 3);
+
+        let targets: Vec<_> = goals.iter().map(|g| g.as_ref().expect("one goal per drone").target).collect();
+        for (i, a) in targets.iter().enumerate() {
+            assert!(targets[i + 1..].iter().all(|b| b != a), "shared goal {a:?}");
+        }
+
+        // Each drone takes the frontier on its own side of the room
+        assert!(targets[0].0 < 10 && targets[1].0 > 10 && targets[2].1 < 10, "{targets:?}");
+    }
+
+    #[test]
+    fn a_cluster_goes_to_one_drone_only() {
+        let map = room(0, 0, 3, 0); // A single frontier cluster
+        let goals = allocate(&map, &[(0, 0), (3, 0)], &opts(), 1).unwrap();
+        assert_eq!(goals.iter().filter(|g| g.is_some()).count(), 1);
+    }
+
+    #[test]
+    fn auction_claims_what_the_winner_will_see() {
+        // Two gaps in the left wall, close enough to look at the same unknown cells
+        let map = room(0, 0, 10, 10);
+        for y in -1..=11 {
+            if !matches!(y, 2 | 3 | 7 | 8) {
+                map.set_status(-1, y, STATUS_OCCUPIED, 1);
+            }
+            map.set_status(11, y, STATUS_OCCUPIED, 1);
+        }
+        for x in 0..=10 {
+            map.set_status(x, -1, STATUS_OCCUPIED, 1);
+            map.set_status(x, 11, STATUS_OCCUPIED, 1);
+        }
+
+        let opts = FrontierOptions { sensor_range: 5, ..opts() };
+        let alone = find(&map, (5, 5), &opts, 1).unwrap();
+        assert_eq!(alone.len(), 2);
+        let goals = allocate(&map, &[(5, 5), (5, 5)], &opts, 1).unwrap();
+        let (first, second) = (goals[0].as_ref().unwrap(), goals[1].as_ref().unwrap());
+        assert_ne!(first.target, second.target);
+
+        let full = |t| alone.iter().find(|f| f.target == t).unwrap().gain;
+        assert_eq!(first.gain, full(first.target));
+        assert!(second.gain < full(second.target), "overlap must be claimed");
+    }
+
+    #[test]
+    fn auction_is_deterministic() {
+        let map = room(-5, -5, 15, 12);
+        let drones = [(0, 0), (10, 10), (5, -3), (12, 0)];
+        let a = allocate(&map, &drones, &opts(), 1).unwrap();
+        let b = allocate(&map, &drones, &opts(), 1).unwrap();
+        let targets = |g: &[Option<Frontier>]| g.iter().map(|f| f.as_ref().map(|f| f.target)).collect::<Vec<_>>();
+        assert_eq!(targets(&a), targets(&b));
+        assert!(allocate(&map, &[], &opts(), 1).unwrap().is_empty());
+    }
+}
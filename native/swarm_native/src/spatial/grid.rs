@@ -0,0 +1,383 @@
+// native/swarm_native/src/spatial/grid.rs
+
+//! THE HIPPOCAMPUS (Occupancy Grid)
+//!
+//! Sparse occupancy grid keyed by integer cell coordinates. Each cell holds
+//! log-odds evidence that it is occupied, the time of its last update, and
+//! an optional explicit status tag (the legacy `update_spatial_state` path).
+//!
+//! Status of a cell, as seen by every reader:
+//! * a non-zero tag wins (explicit knowledge, e.g. "target seen here"),
+//! * otherwise the decayed log-odds is classified FREE / OCCUPIED / UNKNOWN.
+//!
+//! Evidence fades with `half_life_s`: log-odds decays toward 0 (unknown)
+//! lazily, on read and before every update, so no sweeper thread is needed.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, RwLock};
+
+use dashmap::DashMap;
+use rustler::{NifMap, NifUnitEnum};
+use serde::{Deserialize, Serialize};
+
+use crate::spatial::crdt::{Clock, Hlc};
+use crate::spatial::persist::{Journal, Record};
+use crate::spatial::raycast;
+
+// Reserved status values. Anything else is an application tag.
+pub const STATUS_UNKNOWN: u32 = 0;
+pub const STATUS_FREE: u32 = 1;
+pub const STATUS_OCCUPIED: u32 = 2;
+
+// Safety cap on a single ray (a 0.1m grid and a 400m lidar return is 4000 cells)
+const MAX_RAY_CELLS: usize = 4096;
+
+// Safety cap on a region export (16 MB of f32)
+pub const MAX_REGION_CELLS: usize = 4 * 1024 * 1024;
+
+/// Map-wide tuning. Log-odds: l = ln(p / (1 - p)).
+#[derive(NifMap, Serialize, Deserialize, Clone, Copy, Debug)]
+pub struct GridConfig {
+    pub cell_size: f32,          // Meters per cell edge
+    pub hit: f32,                // Log-odds added by an "occupied" observation
+    pub miss: f32,               // Log-odds added by a "free" observation (negative)
+    pub clamp_min: f32,          // Saturation, keeps cells revisable
+    pub clamp_max: f32,
+    pub occupied_threshold: f32, // l >= this -> OCCUPIED
+    pub free_threshold: f32,     // l <= this -> FREE
+    pub half_life_s: f32,        // Evidence half-life. 0.0 disables decay.
+}
+
+impl Default for GridConfig {
+    fn default() -> Self {
+        Self {
+            cell_size: 0.5,
+            hit: 0.85,  // p = 0.70
+            miss: -0.4, // p = 0.40
+            clamp_min: -2.0,
+            clamp_max: 3.5,
+            occupied_threshold: 0.6,
+            free_threshold: -0.3,
+            half_life_s: 60.0,
+        }
+    }
+}
+
+impl GridConfig {
+    /// Rejects configurations that would make the grid meaningless.
+    pub fn is_valid(&self) -> bool {
+        self.cell_size > 0.0
+            && self.clamp_min < self.clamp_max
+            && self.free_threshold < self.occupied_threshold
+            && self.half_life_s >= 0.0
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default)]
+pub struct Cell {
+    pub log_odds: f32,   // As of 'updated_us'
+    pub tag: u32,        // Explicit status (0 = none, derive from evidence)
+    pub updated_us: u64, // Last write
+    pub version: u64,    // Local map version of the last write
+    pub stamp: Hlc,      // Swarm-wide order of the last write (crdt.rs)
+}
+
+impl Cell {
+    /// Log-odds decayed from 'updated_us' to 'now_us'.
+    pub fn log_odds_at(&self, cfg: &GridConfig, now_us: u64) -> f32 {
+        if cfg.half_life_s <= 0.0 || now_us <= self.updated_us {
+            return self.log_odds;
+        }
+        let age_s = (now_us - self.updated_us) as f32 / 1_000_000.0;
+        self.log_odds * 0.5f32.powf(age_s / cfg.half_life_s)
+    }
+
+    pub fn status_at(&self, cfg: &GridConfig, now_us: u64) -> u32 {
+        if self.tag != STATUS_UNKNOWN {
+            return self.tag;
+        }
+        classify(self.log_odds_at(cfg, now_us), cfg)
+    }
+}
+
+pub fn classify(log_odds: f32, cfg: &GridConfig) -> u32 {
+    if log_odds >= cfg.occupied_threshold {
+        STATUS_OCCUPIED
+    } else if log_odds <= cfg.free_threshold {
+        STATUS_FREE
+    } else {
+        STATUS_UNKNOWN
+    }
+}
+
+/// What `region` exports per cell.
+#[derive(NifUnitEnum, Clone, Copy, Debug)]
+pub enum Layer {
+    Probability, // f32, 0.0..1.0 (0.5 = unknown)
+    LogOdds,     // f32
+    Status,      // u32
+}
+
+/// One range observation: absolute bearing (radians, world frame),
+/// distance (meters), and whether it ended on an obstacle.
+pub type Ray = (f32, f32, bool);
+
+/// The Spatial Memory (shared through `SwarmState`).
+pub struct SpatialMap {
+    pub cells: DashMap<(i32, i32), Cell>,
+    pub(super) config: RwLock<GridConfig>,
+    pub(super) version: AtomicU64, // Bumped on every cell write
+
+    // Stamps writes for the swarm merge (spatial/crdt.rs)
+    pub(super) clock: Clock,
+
+    // Crash log (spatial/persist.rs). The flag spares writers the lock when detached.
+    pub(super) journal: Mutex<Option<Journal>>,
+    pub(super) journaling: AtomicBool,
+    pub(super) saving: Mutex<()>, // Serializes snapshots with journal attach/detach
+}
+
+impl SpatialMap {
+    pub fn new() -> Self {
+        Self {
+            cells: DashMap::new(),
+            config: RwLock::new(GridConfig::default()),
+            version: AtomicU64::new(0),
+            clock: Clock::new(),
+            journal: Mutex::new(None),
+            journaling: AtomicBool::new(false),
+            saving: Mutex::new(()),
+        }
+    }
+
+    pub fn config(&self) -> GridConfig {
+        *self.config.read().unwrap()
+    }
+
+    /// NOTE: Changing 'cell_size' does not resample existing cells;
+    /// configure before the first observation.
+    pub fn set_config(&self, cfg: GridConfig) -> bool {
+        if !cfg.is_valid() {
+            return false;
+        }
+        *self.config.write().unwrap() = cfg;
+        self.journal_append(&[Record::Config(cfg)]);
+        true
+    }
+
+    /// Current map version (the version of the newest write).
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+
+    pub(super) fn next_version(&self) -> u64 {
+        self.version.fetch_add(1, Ordering::AcqRel) + 1
+    }
+
+    /// False once a journal write failed (the journal is then dropped).
+    pub fn is_journaling(&self) -> bool {
+        self.journaling.load(Ordering::Acquire)
+    }
+
+    // --- LEGACY PATH (explicit status) ---
+
+    /// Sets (or with STATUS_UNKNOWN, clears) the explicit status tag.
+    pub fn set_status(&self, x: i32, y: i32, status: u32, now_us: u64) {
+        let cfg = self.config();
+        let mut cell = self.cells.entry((x, y)).or_default();
+        let version = self.next_version(); // Under the shard lock (see query::changes_since)
+
+        cell.log_odds = cell.log_odds_at(&cfg, now_us);
+        cell.tag = status;
+        cell.updated_us = now_us;
+        cell.version = version;
+        cell.stamp = self.clock.tick(now_us);
+
+        // Journal after the shard lock is released (see persist::save_snapshot)
+        let record = Record::Cell { x, y, cell: *cell };
+        drop(cell);
+        if self.is_journaling() {
+            self.journal_append(&[record]);
+        }
+    }
+
+    pub fn status(&self, x: i32, y: i32, now_us: u64) -> u32 {
+        let cfg = self.config();
+        self.cells
+            .get(&(x, y))
+            .map_or(STATUS_UNKNOWN, |c| c.status_at(&cfg, now_us))
+    }
+
+    // --- OCCUPANCY PATH (evidence) ---
+
+    /// Adds `delta` log-odds to a cell (after decaying it to `now_us`).
+    /// Returns the cell as written.
+    fn observe(&self, cfg: &GridConfig, x: i32, y: i32, delta: f32, now_us: u64) -> Cell {
+        let mut cell = self.cells.entry((x, y)).or_default();
+        let version = self.next_version(); // Under the shard lock (see query::changes_since)
+
+        cell.log_odds = (cell.log_odds_at(cfg, now_us) + delta).clamp(cfg.clamp_min, cfg.clamp_max);
+        cell.updated_us = now_us;
+        cell.version = version;
+        cell.stamp = self.clock.tick(now_us);
+        *cell
+    }
+
+    /// Integrates range observations taken from world position (ox, oy).
+    /// Every cell a ray crosses gets a miss; its final cell gets a hit if the
+    /// ray ended on an obstacle (otherwise it was max range, and is free too).
+    /// A ray cut short by `MAX_RAY_CELLS` never reached its obstacle, so it
+    /// only clears. Non-finite origins or endpoints are ignored.
+    /// Returns the number of cell updates.
+    pub fn cast_rays(&self, ox: f32, oy: f32, rays: &[Ray], now_us: u64) -> usize {
+        if !(ox.is_finite() && oy.is_finite()) {
+            return 0;
+        }
+
+        let cfg = self.config();
+        let journaling = self.is_journaling();
+        let mut records = Vec::new();
+        let mut updates = 0;
+
+        for &(bearing, range, hit) in rays {
+            if !(range.is_finite() && range >= 0.0 && bearing.is_finite()) {
+                continue;
+            }
+
+            let (ex, ey) = (ox + range * bearing.cos(), oy + range * bearing.sin());
+            if !(ex.is_finite() && ey.is_finite()) {
+                continue;
+            }
+            let cells = raycast::traverse(ox, oy, ex, ey, cfg.cell_size, MAX_RAY_CELLS);
+            let end = raycast::cell_of(ex, ey, cfg.cell_size);
+            let last = cells.len().saturating_sub(1);
+
+            for (i, &(x, y)) in cells.iter().enumerate() {
+                let delta = if i == last && hit && (x, y) == end { cfg.hit } else { cfg.miss };
+                let cell = self.observe(&cfg, x, y, delta, now_us);
+                if journaling {
+                    records.push(Record::Cell { x, y, cell });
+                }
+            }
+            updates += cells.len();
+        }
+
+        // One journal write per scan, not per cell
+        if !records.is_empty() {
+            self.journal_append(&records);
+        }
+
+        updates
+    }
+
+    // --- BULK EXPORT ---
+
+    /// Dense row-major (y outer, x inner) export of the rectangle starting
+    /// at cell (x0, y0), `w` x `h` cells, 4 native-endian bytes per cell.
+    /// Matches `Nx.from_binary(bin, type) |> Nx.reshape({h, w})`.
+    /// Returns `None` if the rectangle is empty or too large.
+    pub fn region(&self, x0: i32, y0: i32, w: u32, h: u32, layer: Layer, now_us: u64) -> Option<Vec<u8>> {
+        let count = (w as usize).checked_mul(h as usize)?;
+        if count == 0 || count > MAX_REGION_CELLS {
+            return None;
+        }
+
+        let cfg = self.config();
+        let mut out = Vec::with_capacity(count * 4);
+
+        for dy in 0..h as i64 {
+            for dx in 0..w as i64 {
+                let key = ((x0 as i64 + dx) as i32, (y0 as i64 + dy) as i32);
+                let cell = self.cells.get(&key).map(|c| *c);
+
+                let bytes = match layer {
+                    Layer::Status => cell.map_or(STATUS_UNKNOWN, |c| c.status_at(&cfg, now_us)).to_ne_bytes(),
+                    Layer::LogOdds => cell.map_or(0.0, |c| c.log_odds_at(&cfg, now_us)).to_ne_bytes(),
+                    Layer::Probability => {
+                        let l = cell.map_or(0.0, |c| c.log_odds_at(&cfg, now_us));
+                        (1.0 - 1.0 / (1.0 + l.exp())).to_ne_bytes()
+                    }
+                };
+                out.extend_from_slice(&bytes);
+            }
+        }
+
+        Some(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map() -> SpatialMap {
+        let map = SpatialMap::new();
+        map.set_config(GridConfig { cell_size: 1.0, half_life_s: 0.0, ..GridConfig::default() });
+        map
+    }
+
+    fn log_odds(map: &SpatialMap, x: i32, y: i32) -> f32 {
+        map.cells.get(&(x, y)).map_or(0.0, |c| c.log_odds)
+    }
+
+    #[test]
+    fn hit_marks_only_the_endpoint() {
+        let map = map();
+        let cfg = map.config();
+        assert_eq!(map.cast_rays(0.5, 0.5, &[(0.0, 3.0, true)], 1), 4);
+        for x in 0..3 {
+            assert_eq!(log_odds(&map, x, 0), cfg.miss);
+        }
+        assert_eq!(log_odds(&map, 3, 0), cfg.hit);
+    }
+
+    #[test]
+    fn max_range_ray_only_clears() {
+        let map = map();
+        map.cast_rays(0.5, 0.5, &[(0.0, 3.0, false)], 1);
+        assert_eq!(log_odds(&map, 3, 0), map.config().miss);
+    }
+
+    #[test]
+    fn truncated_ray_marks_no_phantom_obstacle() {
+        let map = map();
+        let range = MAX_RAY_CELLS as f32 + 100.0;
+        assert_eq!(map.cast_rays(0.5, 0.5, &[(0.0, range, true)], 1), MAX_RAY_CELLS);
+        assert!(map.cells.iter().all(|c| c.log_odds < 0.0), "a cut-short ray must not hit");
+    }
+
+    #[test]
+    fn non_finite_rays_and_origins_are_ignored() {
+        let map = map();
+        let rays = [(f32::NAN, 1.0, true), (0.0, f32::INFINITY, true), (0.0, -1.0, true)];
+        assert_eq!(map.cast_rays(0.5, 0.5, &rays, 1), 0);
+        assert_eq!(map.cast_rays(f32::MAX, 0.5, &[(0.0, f32::MAX, true)], 1), 0); // Endpoint overflows
+        assert_eq!(map.cast_rays(f32::NAN, 0.5, &[(0.0, 1.0, true)], 1), 0);
+        assert_eq!(map.cast_rays(0.5, f32::INFINITY, &[(0.0, 1.0, true)], 1), 0);
+        assert!(map.cells.is_empty());
+    }
+
+    #[test]
+    fn evidence_clamps_and_classifies() {
+        let map = map();
+        let cfg = map.config();
+        for t in 0..20 {
+            map.cast_rays(0.5, 0.5, &[(0.0, 2.0, true)], t);
+        }
+        assert_eq!(log_odds(&map, 2, 0), cfg.clamp_max);
+        assert_eq!(log_odds(&map, 0, 0), cfg.clamp_min);
+        assert_eq!(map.status(2, 0, 20), STATUS_OCCUPIED);
+        assert_eq!(map.status(1, 0, 20), STATUS_FREE);
+        assert_eq!(map.status(9, 9, 20), STATUS_UNKNOWN);
+    }
+
+    #[test]
+    fn explicit_tag_wins_over_evidence() {
+        let map = map();
+        map.cast_rays(0.5, 0.5, &[(0.0, 2.0, false)], 1);
+        map.set_status(1, 0, 7, 2);
+        assert_eq!(map.status(1, 0, 2), 7);
+        map.set_status(1, 0, STATUS_UNKNOWN, 3);
+        assert_eq!(map.status(1, 0, 3), STATUS_FREE);
+    }
+}
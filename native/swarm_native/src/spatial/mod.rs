@@ -0,0 +1,20 @@
+// native/swarm_native/src/spatial/mod.rs
+
+pub mod costmap; // Dense navigation snapshot (obstacles, inflation, line of sight)
+pub mod crdt;    // Swarm merge (HLC-stamped LWW cells, deltas)
+pub mod frontier; // Exploration goals (clusters, gain, multi-drone allocation)
+pub mod grid;    // The Occupancy Grid (log-odds cells, decay, config)
+pub mod persist; // Snapshots + append-only journal
+pub mod planner; // A*, Theta*, D* Lite
+pub mod query;   // Region, radius, nearest, counts, change feed
+pub mod raycast; // Grid traversal for range observations
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Wall clock in µs since the UNIX epoch (cell timestamps are comparable across drones).
+pub fn now_us() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or(0)
+}
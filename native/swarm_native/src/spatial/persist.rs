@@ -0,0 +1,628 @@
+// native/swarm_native/src/spatial/persist.rs
+
+//! THE LONG-TERM MEMORY (Snapshots + Journal)
+//!
+//! Two files keep the Spatial Memory alive across restarts:
+//!
+//! * Snapshot: the whole map, written atomically (tmp file, fsync, rename,
+//!   fsync of the directory).
+//!   `[magic "SWSM"][format u32][frame]`
+//! * Journal: every cell write since the last snapshot, appended and flushed
+//!   to the OS per operation, so a crash of the BEAM loses nothing and a
+//!   power cut loses at most what the kernel had not yet written back.
+//!   `[magic "SWSJ"][format u32][frame][frame]...`
+//!
+//! A frame is `[len u32][crc32 u32][bincode payload]`, little-endian.
+//! A torn or corrupt journal tail (crash mid-append) is cut off on open.
+//!
+//! Formats: v1 cells had no HLC stamp; v2 adds it (crdt.rs). v1 files are
+//! still read (stamped with their `updated_us`, node 0), and a v1 journal is
+//! rewritten as v2 on open.
+//!
+//! Boot order: `load_snapshot`, then `attach_journal` (replays it), then
+//! start observing. `save_snapshot` compacts the attached journal.
+//! Restored cells move the HLC past their stamps, so a write after a restart
+//! still outranks what it overwrites.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+use serde::{Deserialize, Serialize};
+
+use crate::spatial::crdt::Hlc;
+use crate::spatial::grid::{Cell, GridConfig, SpatialMap};
+
+const SNAPSHOT_MAGIC: [u8; 4] = *b"SWSM";
+const JOURNAL_MAGIC: [u8; 4] = *b"SWSJ";
+pub const FORMAT_VERSION: u32 = 2;
+
+pub(super) const HEADER_LEN: usize = 8;
+const FRAME_HEADER_LEN: usize = 8;
+
+// A journal record is a few dozen bytes; anything bigger is garbage.
+const MAX_RECORD_LEN: usize = 4096;
+
+/// One journal entry. Cell records carry the full cell as written, so
+/// replaying them is idempotent.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug)]
+pub enum Record {
+    Cell { x: i32, y: i32, cell: Cell },
+    Config(GridConfig),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    config: GridConfig,
+    version: u64,
+    cells: Vec<((i32, i32), Cell)>,
+}
+
+// --- FORMAT V1 (read-only) ---
+
+#[derive(Deserialize, Clone, Copy)]
+struct CellV1 {
+    log_odds: f32,
+    tag: u32,
+    updated_us: u64,
+    version: u64,
+}
+
+impl From<CellV1> for Cell {
+    fn from(c: CellV1) -> Self {
+        let stamp = Hlc { wall_us: c.updated_us, counter: 0, node: 0 };
+        Cell { log_odds: c.log_odds, tag: c.tag, updated_us: c.updated_us, version: c.version, stamp }
+    }
+}
+
+#[derive(Deserialize)]
+enum RecordV1 {
+    Cell { x: i32, y: i32, cell: CellV1 },
+    Config(GridConfig),
+}
+
+impl From<RecordV1> for Record {
+    fn from(r: RecordV1) -> Self {
+        match r {
+            RecordV1::Cell { x, y, cell } => Record::Cell { x, y, cell: cell.into() },
+            RecordV1::Config(cfg) => Record::Config(cfg),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SnapshotV1 {
+    config: GridConfig,
+    version: u64,
+    cells: Vec<((i32, i32), CellV1)>,
+}
+
+fn decode_record(payload: &[u8], format: u32) -> Option<Record> {
+    match format {
+        1 => bincode::deserialize::<RecordV1>(payload).ok().map(Record::from),
+        _ => bincode::deserialize::<Record>(payload).ok(),
+    }
+}
+
+fn decode_snapshot(payload: &[u8], format: u32) -> Option<Snapshot> {
+    match format {
+        1 => bincode::deserialize::<SnapshotV1>(payload).ok().map(|s| Snapshot {
+            config: s.config,
+            version: s.version,
+            cells: s.cells.into_iter().map(|(k, c)| (k, c.into())).collect(),
+        }),
+        _ => bincode::deserialize::<Snapshot>(payload).ok(),
+    }
+}
+
+/// Why a snapshot or journal was rejected.
+#[derive(Debug)]
+pub enum PersistError {
+    Io,
+    Corrupt,     // Bad magic, length or checksum
+    Unsupported, // Written by a newer format
+    Busy,        // A journal is attached (load) or already attached (attach)
+}
+
+impl From<io::Error> for PersistError {
+    fn from(_: io::Error) -> Self {
+        PersistError::Io
+    }
+}
+
+// --- FRAMING ---
+
+pub(super) fn write_header(out: &mut impl Write, magic: [u8; 4]) -> io::Result<()> {
+    out.write_all(&magic)?;
+    out.write_all(&FORMAT_VERSION.to_le_bytes())
+}
+
+/// Returns the format version of a file (or message) with this magic.
+pub(super) fn check_header(bytes: &[u8], magic: [u8; 4]) -> Result<u32, PersistError> {
+    if bytes.len() < HEADER_LEN || bytes[0..4] != magic {
+        return Err(PersistError::Corrupt);
+    }
+    let format = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    if format == 0 || format > FORMAT_VERSION {
+        return Err(PersistError::Unsupported);
+    }
+    Ok(format)
+}
+
+pub(super) fn encode_frame(payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(payload).to_le_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Decodes the frame at the start of `bytes`: (payload, frame length).
+/// `None` if it is truncated, oversized or fails its checksum.
+pub(super) fn decode_frame(bytes: &[u8], max_len: usize) -> Option<(&[u8], usize)> {
+    if bytes.len() < FRAME_HEADER_LEN {
+        return None;
+    }
+    let len = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    let crc = u32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    if len > max_len || bytes.len() < FRAME_HEADER_LEN + len {
+        return None;
+    }
+
+    let payload = &bytes[FRAME_HEADER_LEN..FRAME_HEADER_LEN + len];
+    (crc32fast::hash(payload) == crc).then_some((payload, FRAME_HEADER_LEN + len))
+}
+
+// --- JOURNAL ---
+
+/// The open append-only log.
+pub struct Journal {
+    file: BufWriter<File>,
+    path: PathBuf,
+    len: u64, // Bytes written, buffered or not
+}
+
+impl Journal {
+    /// Opens (or creates) the journal and returns every intact record in it.
+    /// A damaged tail is truncated so new appends follow the last good frame.
+    fn open(path: &Path) -> Result<(Self, Vec<Record>), PersistError> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+
+        // 1. Fresh file: stamp the header
+        if bytes.is_empty() {
+            write_header(&mut file, JOURNAL_MAGIC)?;
+            file.sync_data()?;
+            let journal = Self { file: BufWriter::new(file), path: path.to_owned(), len: HEADER_LEN as u64 };
+            return Ok((journal, Vec::new()));
+        }
+        let format = check_header(&bytes, JOURNAL_MAGIC)?;
+
+        // 2. Collect frames until the first bad one
+        let mut records = Vec::new();
+        let mut offset = HEADER_LEN;
+        while let Some((payload, len)) = decode_frame(&bytes[offset..], MAX_RECORD_LEN) {
+            match decode_record(payload, format) {
+                Some(record) => records.push(record),
+                None => break,
+            }
+            offset += len;
+        }
+
+        // 3. Old format: rewrite in the current one (appends must not mix formats)
+        if format != FORMAT_VERSION {
+            let mut upgraded = Vec::new();
+            write_header(&mut upgraded, JOURNAL_MAGIC)?;
+            for record in &records {
+                let payload = bincode::serialize(record).map_err(|_| PersistError::Corrupt)?;
+                upgraded.extend_from_slice(&encode_frame(&payload));
+            }
+            write_atomic(path, &upgraded)?;
+            return Ok((Self::reopen(path)?, records));
+        }
+
+        // 4. Cut the torn tail, continue from there
+        if offset < bytes.len() {
+            file.set_len(offset as u64)?;
+        }
+        file.seek(SeekFrom::Start(offset as u64))?;
+
+        Ok((Self { file: BufWriter::new(file), path: path.to_owned(), len: offset as u64 }, records))
+    }
+
+    /// Opens an intact journal for appending at its end.
+    fn reopen(path: &Path) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+        let len = file.seek(SeekFrom::End(0))?;
+        Ok(Self { file: BufWriter::new(file), path: path.to_owned(), len })
+    }
+
+    fn append(&mut self, records: &[Record]) -> io::Result<()> {
+        for record in records {
+            let payload = bincode::serialize(record).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let frame = encode_frame(&payload);
+            self.file.write_all(&frame)?;
+            self.len += frame.len() as u64;
+        }
+        self.file.flush() // Hand it to the OS; fsync is reserved for snapshots
+    }
+
+    /// Drops the records before byte `mark` (they are in the snapshot now)
+    /// and keeps the ones appended since.
+    fn compact(&mut self, mark: u64) -> io::Result<()> {
+        self.file.flush()?;
+        let file = self.file.get_mut();
+
+        // 1. Nothing newer: truncate in place
+        if mark >= self.len {
+            file.set_len(HEADER_LEN as u64)?;
+            file.seek(SeekFrom::Start(HEADER_LEN as u64))?;
+            self.len = HEADER_LEN as u64;
+            return file.sync_data();
+        }
+
+        // 2. Keep the tail; swapped in atomically, so a crash loses neither half
+        let mut bytes = Vec::new();
+        write_header(&mut bytes, JOURNAL_MAGIC)?;
+        file.seek(SeekFrom::Start(mark))?;
+        file.read_to_end(&mut bytes)?;
+        write_atomic(&self.path, &bytes)?;
+        *self = Self::reopen(&self.path)?;
+        Ok(())
+    }
+}
+
+// --- SNAPSHOT ---
+
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+
+    let mut file = File::create(&tmp)?;
+    file.write_all(bytes)?;
+    file.sync_all()?;
+    fs::rename(&tmp, path)?;
+
+    // The rename lives in the directory: until that is synced too, a power
+    // cut can bring back the old file (or no file at all).
+    sync_dir(path)
+}
+
+#[cfg(unix)]
+fn sync_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_dir(_path: &Path) -> io::Result<()> {
+    Ok(()) // Directories cannot be opened as files here; rename is already durable
+}
+
+impl SpatialMap {
+    /// Writes the whole map to `path` and compacts the attached journal.
+    /// Returns the number of cells saved.
+    pub fn save_snapshot(&self, path: &Path) -> Result<usize, PersistError> {
+        // One save at a time, and no attach/detach: `mark` must keep pointing
+        // into the journal it was taken from.
+        let _saving = self.saving.lock().unwrap();
+
+        // Copied under the journal lock: a write that lands after its cell was
+        // copied is blocked on it, so it journals past `mark` and survives
+        // compaction. Writers release their shard lock before journaling, so no deadlock.
+        let (snapshot, mark) = {
+            let journal = self.journal.lock().unwrap();
+            let snapshot = Snapshot {
+                config: self.config(),
+                version: self.version(),
+                cells: self.cells.iter().map(|e| (*e.key(), *e.value())).collect(),
+            };
+            (snapshot, journal.as_ref().map(|j| j.len))
+        };
+
+        // Written and synced unlocked: writers keep journaling meanwhile
+        let payload = bincode::serialize(&snapshot).map_err(|_| PersistError::Corrupt)?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + FRAME_HEADER_LEN + payload.len());
+        write_header(&mut bytes, SNAPSHOT_MAGIC)?;
+        bytes.extend_from_slice(&encode_frame(&payload));
+        write_atomic(path, &bytes)?;
+
+        if let Some(mark) = mark {
+            if let Some(journal) = self.journal.lock().unwrap().as_mut() {
+                journal.compact(mark)?;
+            }
+        }
+
+        Ok(snapshot.cells.len())
+    }
+
+    /// Replaces the map with the snapshot at `path`.
+    /// Refused while a journal is attached (it would no longer match the map).
+    /// Returns the number of cells loaded.
+    pub fn load_snapshot(&self, path: &Path) -> Result<usize, PersistError> {
+        let journal = self.journal.lock().unwrap();
+        if journal.is_some() {
+            return Err(PersistError::Busy);
+        }
+
+        let bytes = fs::read(path)?;
+        let format = check_header(&bytes, SNAPSHOT_MAGIC)?;
+
+        let body = &bytes[HEADER_LEN..];
+        let (payload, len) = decode_frame(body, body.len()).ok_or(PersistError::Corrupt)?;
+        if len != body.len() {
+            return Err(PersistError::Corrupt);
+        }
+        let snapshot = decode_snapshot(payload, format).ok_or(PersistError::Corrupt)?;
+        if !snapshot.config.is_valid() {
+            return Err(PersistError::Corrupt);
+        }
+
+        *self.config.write().unwrap() = snapshot.config;
+        self.cells.clear();
+        for &(key, cell) in &snapshot.cells {
+            self.clock.observe(cell.stamp);
+            self.cells.insert(key, cell);
+        }
+
+        // Never move the counter backwards: change-feed cursors must stay valid.
+        let newest = snapshot.cells.iter().map(|(_, c)| c.version).max().unwrap_or(0);
+        self.version.fetch_max(newest.max(snapshot.version), Ordering::AcqRel);
+
+        Ok(snapshot.cells.len())
+    }
+
+    /// Opens the journal at `path`, replays it onto the map and starts
+    /// logging every write to it. Returns the number of records replayed.
+    pub fn attach_journal(&self, path: &Path) -> Result<usize, PersistError> {
+        let _saving = self.saving.lock().unwrap();
+        let mut slot = self.journal.lock().unwrap();
+        if slot.is_some() {
+            return Err(PersistError::Busy);
+        }
+
+        let (journal, records) = Journal::open(path)?;
+        for record in &records {
+            self.replay(record);
+        }
+
+        *slot = Some(journal);
+        self.journaling.store(true, Ordering::Release);
+        Ok(records.len())
+    }
+
+    /// Flushes and closes the journal. Later writes are no longer logged.
+    pub fn detach_journal(&self) {
+        let _saving = self.saving.lock().unwrap();
+        let mut slot = self.journal.lock().unwrap();
+        self.journaling.store(false, Ordering::Release);
+        if let Some(mut journal) = slot.take() {
+            let _ = journal.file.flush();
+        }
+    }
+
+    /// Version-guarded: a record never overwrites a newer write (records can
+    /// land in the journal slightly out of order across threads).
+    fn replay(&self, record: &Record) {
+        match *record {
+            Record::Config(cfg) => {
+                if cfg.is_valid() {
+                    *self.config.write().unwrap() = cfg;
+                }
+            }
+            Record::Cell { x, y, cell } => {
+                self.clock.observe(cell.stamp);
+                let mut slot = self.cells.entry((x, y)).or_default();
+                if cell.version > slot.version {
+                    *slot = cell;
+                }
+                drop(slot);
+                self.version.fetch_max(cell.version, Ordering::AcqRel);
+            }
+        }
+    }
+
+    /// Appends to the journal if one is attached. A failing disk must not
+    /// take the map down with it: on error the journal is dropped.
+    pub(super) fn journal_append(&self, records: &[Record]) {
+        let mut slot = self.journal.lock().unwrap();
+        if let Some(journal) = slot.as_mut() {
+            if journal.append(records).is_err() {
+                *slot = None;
+                self.journaling.store(false, Ordering::Release);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    // The v1 layout, as an old build wrote it
+    #[derive(Serialize)]
+    struct CellV1Out {
+        log_odds: f32,
+        tag: u32,
+        updated_us: u64,
+        version: u64,
+    }
+
+    #[derive(Serialize)]
+    enum RecordV1Out {
+        Cell { x: i32, y: i32, cell: CellV1Out },
+        #[allow(dead_code)] // Keeps the variant index of `Config` in sync
+        Config(GridConfig),
+    }
+
+    #[derive(Serialize)]
+    struct SnapshotV1Out {
+        config: GridConfig,
+        version: u64,
+        cells: Vec<((i32, i32), CellV1Out)>,
+    }
+
+    fn header_v1(magic: [u8; 4]) -> Vec<u8> {
+        let mut bytes = magic.to_vec();
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes
+    }
+
+    fn cell_v1(tag: u32, version: u64) -> CellV1Out {
+        CellV1Out { log_odds: 1.5, tag, updated_us: 1_000 * version, version }
+    }
+
+    #[test]
+    fn v1_journal_is_replayed_and_rewritten_as_v2() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.journal");
+
+        let mut bytes = header_v1(JOURNAL_MAGIC);
+        for (x, version) in [(1, 1), (2, 2)] {
+            let record = RecordV1Out::Cell { x, y: 0, cell: cell_v1(7, version) };
+            bytes.extend_from_slice(&encode_frame(&bincode::serialize(&record).unwrap()));
+        }
+        fs::write(&path, &bytes).unwrap();
+
+        let map = SpatialMap::new();
+        assert_eq!(map.attach_journal(&path).unwrap(), 2);
+        let cell = *map.cells.get(&(2, 0)).unwrap();
+        assert_eq!((cell.tag, cell.version, cell.log_odds), (7, 2, 1.5));
+        assert_eq!(cell.stamp, Hlc { wall_us: 2_000, counter: 0, node: 0 });
+
+        // Rewritten in place, and new appends follow in the same format
+        map.set_status(3, 0, 9, 5_000);
+        map.detach_journal();
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(check_header(&bytes, JOURNAL_MAGIC).unwrap(), FORMAT_VERSION);
+
+        let reopened = SpatialMap::new();
+        assert_eq!(reopened.attach_journal(&path).unwrap(), 3);
+        assert_eq!(reopened.cells.get(&(3, 0)).unwrap().tag, 9);
+        assert_eq!(reopened.cells.get(&(1, 0)).unwrap().stamp.wall_us, 1_000);
+    }
+
+    #[test]
+    fn v1_snapshot_loads_with_derived_stamps() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.snapshot");
+
+        let snapshot = SnapshotV1Out { config: GridConfig::default(), version: 4, cells: vec![((5, 6), cell_v1(3, 4))] };
+        let mut bytes = header_v1(SNAPSHOT_MAGIC);
+        bytes.extend_from_slice(&encode_frame(&bincode::serialize(&snapshot).unwrap()));
+        fs::write(&path, &bytes).unwrap();
+
+        let map = SpatialMap::new();
+        assert_eq!(map.load_snapshot(&path).unwrap(), 1);
+        let cell = *map.cells.get(&(5, 6)).unwrap();
+        assert_eq!((cell.tag, cell.stamp.wall_us, cell.stamp.node), (3, 4_000, 0));
+        assert_eq!(map.version(), 4);
+    }
+
+    #[test]
+    fn torn_journal_tail_is_cut_and_appends_continue() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.journal");
+
+        let map = SpatialMap::new();
+        map.attach_journal(&path).unwrap();
+        map.set_status(1, 1, 4, 1);
+        map.set_status(2, 2, 5, 2);
+        map.detach_journal();
+        let intact = fs::read(&path).unwrap().len();
+
+        // A crash mid-append: half a frame, then a frame with a bad checksum
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        let mut frame = encode_frame(&bincode::serialize(&Record::Config(GridConfig::default())).unwrap());
+        file.write_all(&frame[..frame.len() / 2]).unwrap();
+        drop(file);
+
+        let map = SpatialMap::new();
+        assert_eq!(map.attach_journal(&path).unwrap(), 2);
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, intact);
+        map.set_status(3, 3, 6, 3);
+        map.detach_journal();
+
+        frame[FRAME_HEADER_LEN] ^= 0xFF;
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(&frame).unwrap();
+        drop(file);
+
+        let map = SpatialMap::new();
+        assert_eq!(map.attach_journal(&path).unwrap(), 3);
+        assert_eq!(map.status(3, 3, 3), 6);
+    }
+
+    #[test]
+    fn snapshot_round_trips_and_compacts_the_journal() {
+        let dir = tempfile::tempdir().unwrap();
+        let (snapshot, journal) = (dir.path().join("map.snapshot"), dir.path().join("map.journal"));
+
+        let map = SpatialMap::new();
+        map.attach_journal(&journal).unwrap();
+        map.set_status(1, 2, 8, 1);
+        assert_eq!(map.save_snapshot(&snapshot).unwrap(), 1);
+        assert_eq!(fs::metadata(&journal).unwrap().len() as usize, HEADER_LEN);
+        assert!(!dir.path().join("map.snapshot.tmp").exists());
+        map.detach_journal();
+
+        let restored = SpatialMap::new();
+        assert_eq!(restored.load_snapshot(&snapshot).unwrap(), 1);
+        assert_eq!(restored.status(1, 2, 1), 8);
+        assert_eq!(restored.version(), map.version());
+    }
+
+    #[test]
+    fn compaction_keeps_records_past_the_mark() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("map.journal");
+        let cell = |x| Record::Cell { x, y: 0, cell: Cell::default() };
+
+        // Record 2 lands while the snapshot (which holds record 1) is being synced
+        let (mut journal, _) = Journal::open(&path).unwrap();
+        journal.append(&[cell(1)]).unwrap();
+        let mark = journal.len;
+        journal.append(&[cell(2)]).unwrap();
+        journal.compact(mark).unwrap();
+        journal.append(&[cell(3)]).unwrap();
+        drop(journal);
+
+        let (_, records) = Journal::open(&path).unwrap();
+        let xs: Vec<i32> = records.iter().filter_map(|r| if let Record::Cell { x, .. } = r { Some(*x) } else { None }).collect();
+        assert_eq!(xs, [2, 3]);
+    }
+
+    #[test]
+    fn restored_stamps_advance_the_clock() {
+        let dir = tempfile::tempdir().unwrap();
+        let (snapshot, journal) = (dir.path().join("map.snapshot"), dir.path().join("map.journal"));
+
+        // Written by a clock far ahead of the one that restores it
+        let map = SpatialMap::new();
+        map.set_status(1, 1, 4, 9_000_000);
+        map.save_snapshot(&snapshot).unwrap();
+        map.attach_journal(&journal).unwrap();
+        map.set_status(2, 2, 5, 9_500_000);
+        map.detach_journal();
+
+        let from_snapshot = SpatialMap::new();
+        from_snapshot.load_snapshot(&snapshot).unwrap();
+        from_snapshot.set_status(1, 1, 6, 1);
+        let restored = map.cells.get(&(1, 1)).unwrap().stamp;
+        assert!(from_snapshot.cells.get(&(1, 1)).unwrap().stamp > restored);
+
+        let from_journal = SpatialMap::new();
+        from_journal.attach_journal(&journal).unwrap();
+        from_journal.set_status(2, 2, 7, 1);
+        let replayed = map.cells.get(&(2, 2)).unwrap().stamp;
+        assert!(from_journal.cells.get(&(2, 2)).unwrap().stamp > replayed);
+    }
+}
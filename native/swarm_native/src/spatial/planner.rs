@@ -0,0 +1,499 @@
+// native/swarm_native/src/spatial/planner.rs
+
+//! THE NAVIGATOR (Path Planning)
+//!
+//! Turns the Spatial Memory into flyable paths, in cell coordinates.
+//!
+//! * A*: 8-connected grid search, then line-of-sight smoothing.
+//! * Theta*: A* that checks line of sight to the grandparent on every
+//!   relaxation, so paths come out any-angle without post-processing.
+//! * D* Lite (`Replanner`): searches backwards from the goal and keeps its
+//!   search tree between calls. When the drone moves or the map changes
+//!   (read from the map's change feed), only the affected part is repaired.
+//!
+//! All of them plan over a `Costmap` snapshot of the bounding box of start
+//! and goal plus `margin` cells: the map is unbounded, the search is not.
+
+use std::cmp::Ordering as CmpOrdering;
+use std::collections::{BinaryHeap, HashSet};
+use std::sync::{Arc, Mutex};
+
+use rustler::{NifStruct, NifUnitEnum};
+
+use crate::spatial::costmap::{Costmap, CostmapError, Point, BLOCKED};
+use crate::spatial::grid::{SpatialMap, MAX_REGION_CELLS};
+use crate::spatial::query::Bounds;
+
+const NONE: usize = usize::MAX;
+
+#[derive(NifUnitEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Algorithm {
+    AStar,
+    ThetaStar,
+}
+
+/// Mirrors `%SwarmBrain.Spatial.PlanOptions{}` (defaults live on the Elixir side).
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Spatial.PlanOptions"]
+pub struct PlanOptions {
+    pub algorithm: Algorithm, // Ignored by the Replanner (always D* Lite)
+    pub obstacles: Vec<u32>,  // Statuses treated as walls (add 0 to avoid unexplored space)
+    pub inflation: u32,       // Clearance radius in cells
+    pub unknown_penalty: f32, // Extra cost per unit length through unknown cells
+    pub margin: u32,          // Search box padding around start and goal, in cells
+    pub max_expansions: u32,  // Search budget
+    pub smooth: bool,         // Line-of-sight shortcutting of the final path
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PlanError {
+    StartBlocked,
+    GoalBlocked,
+    NoPath,
+    SearchLimit,
+    TooLarge,   // Search box above MAX_REGION_CELLS
+    OutOfBounds, // Replanner start left its search box
+    InvalidInflation, // Above costmap::MAX_INFLATION
+}
+
+impl From<CostmapError> for PlanError {
+    fn from(e: CostmapError) -> Self {
+        match e {
+            CostmapError::TooLarge => PlanError::TooLarge,
+            CostmapError::InvalidInflation => PlanError::InvalidInflation,
+        }
+    }
+}
+
+// --- SHARED PIECES ---
+
+fn search_box(start: Point, goal: Point, margin: u32) -> Bounds {
+    Bounds::new(start.0, start.1, goal.0, goal.1).grow(margin)
+}
+
+fn prepare(map: &SpatialMap, start: Point, goal: Point, opts: &PlanOptions, now_us: u64) -> Result<(Costmap, usize, usize), PlanError> {
+    let mut cm = Costmap::build(
+        map,
+        search_box(start, goal, opts.margin),
+        &opts.obstacles,
+        opts.inflation,
+        opts.unknown_penalty,
+        MAX_REGION_CELLS,
+        now_us,
+    )?;
+
+    let (s, g) = (cm.index(start).ok_or(PlanError::TooLarge)?, cm.index(goal).ok_or(PlanError::TooLarge)?);
+    cm.set_escape(s);
+    check_ends(&cm, s, g)?;
+    Ok((cm, s, g))
+}
+
+fn check_ends(cm: &Costmap, s: usize, g: usize) -> Result<(), PlanError> {
+    if cm.class(s) == BLOCKED {
+        return Err(PlanError::StartBlocked);
+    }
+    if !cm.passable(g) {
+        return Err(PlanError::GoalBlocked);
+    }
+    Ok(())
+}
+
+/// Min-heap entry, ordered by `key` lexicographically.
+/// A* stores (f, -g) so ties go to deeper nodes; D* Lite its (k1, k2).
+#[derive(Clone, Copy)]
+struct Open {
+    key: (f32, f32),
+    node: usize,
+}
+
+impl PartialEq for Open {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == CmpOrdering::Equal
+    }
+}
+
+impl Eq for Open {}
+
+impl PartialOrd for Open {
+    fn partial_cmp(&self, other: &Self) -> Option<CmpOrdering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Open {
+    fn cmp(&self, other: &Self) -> CmpOrdering {
+        other
+            .key
+            .0
+            .total_cmp(&self.key.0)
+            .then_with(|| other.key.1.total_cmp(&self.key.1))
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+fn key_lt(a: (f32, f32), b: (f32, f32)) -> bool {
+    a.0 < b.0 || (a.0 == b.0 && a.1 < b.1)
+}
+
+// D* Lite termination test. Summed f32 costs differ in the last ulp along
+// equivalent paths, so every key whose k1 ties with `b` within rounding is
+// still expanded (k2 ignored, following the heap order): a node left behind
+// on such a tie keeps a stale g that the path walk falls into. Expanding
+// extra nodes never breaks D* Lite.
+fn key_before(a: (f32, f32), b: (f32, f32)) -> bool {
+    if !b.0.is_finite() {
+        return key_lt(a, b);
+    }
+    a.0 <= b.0 + 1e-4 * b.0.abs().max(1.0)
+}
+
+/// Greedy line-of-sight shortcutting. A shortcut is only taken when it is
+/// no more expensive than the stretch of path it replaces (so it never
+/// trades a detour around unknown space for a line straight through it).
+fn smooth(cm: &Costmap, path: &[usize]) -> Vec<usize> {
+    if path.len() < 3 {
+        return path.to_vec();
+    }
+
+    // Cumulative cost along the raw path
+    let mut cum = vec![0.0f32; path.len()];
+    for k in 1..path.len() {
+        let step = cm.line(path[k - 1], path[k]).unwrap_or(f32::INFINITY);
+        cum[k] = cum[k - 1] + step;
+    }
+
+    let mut out = vec![path[0]];
+    let mut anchor = 0;
+    while anchor < path.len() - 1 {
+        let mut next = anchor + 1;
+        for j in anchor + 2..path.len() {
+            match cm.line(path[anchor], path[j]) {
+                Some(c) if c <= cum[j] - cum[anchor] + 1e-3 => next = j,
+                _ => break,
+            }
+        }
+        out.push(path[next]);
+        anchor = next;
+    }
+    out
+}
+
+fn finish(cm: &Costmap, path: Vec<usize>, opts: &PlanOptions) -> Vec<Point> {
+    let path = if opts.smooth { smooth(cm, &path) } else { path };
+    path.into_iter().map(|i| cm.point(i)).collect()
+}
+
+// --- A* / THETA* (one-shot) ---
+
+/// Plans from `start` to `goal`. Returns the waypoints, start and goal included.
+pub fn plan(map: &SpatialMap, start: Point, goal: Point, opts: &PlanOptions, now_us: u64) -> Result<Vec<Point>, PlanError> {
+    let (cm, s, g) = prepare(map, start, goal, opts, now_us)?;
+    let path = search(&cm, s, g, opts.max_expansions, opts.algorithm == Algorithm::ThetaStar)?;
+    Ok(finish(&cm, path, opts))
+}
+
+fn search(cm: &Costmap, s: usize, goal: usize, max_expansions: u32, any_angle: bool) -> Result<Vec<usize>, PlanError> {
+    let h = |i: usize| if any_angle { cm.euclid(i, goal) } else { cm.octile(i, goal) };
+
+    let mut g = vec![f32::INFINITY; cm.size()];
+    let mut parent = vec![NONE; cm.size()];
+    let mut closed = vec![false; cm.size()];
+    let mut open = BinaryHeap::new();
+
+    g[s] = 0.0;
+    parent[s] = s;
+    open.push(Open { key: (h(s), 0.0), node: s });
+
+    let mut expansions = 0;
+    while let Some(Open { node: u, .. }) = open.pop() {
+        if closed[u] {
+            continue; // Stale entry
+        }
+        if u == goal {
+            return Ok(trace(&parent, s, goal));
+        }
+        closed[u] = true;
+
+        expansions += 1;
+        if expansions > max_expansions {
+            return Err(PlanError::SearchLimit);
+        }
+
+        for v in cm.neighbors(u) {
+            if closed[v] {
+                continue;
+            }
+            let step = cm.edge(u, v);
+            if !step.is_finite() {
+                continue;
+            }
+
+            // Theta*: skip u entirely if the grandparent sees v
+            let p = parent[u];
+            let (from, cost) = match (any_angle && p != u).then(|| cm.line(p, v)).flatten() {
+                Some(c) => (p, g[p] + c),
+                None => (u, g[u] + step),
+            };
+
+            if cost < g[v] {
+                g[v] = cost;
+                parent[v] = from;
+                open.push(Open { key: (cost + h(v), -cost), node: v });
+            }
+        }
+    }
+
+    Err(PlanError::NoPath)
+}
+
+/// Travel cost from `s` to every cell (Dijkstra), INFINITY where unreachable.
+pub fn distances(cm: &Costmap, s: usize) -> Vec<f32> {
+    let mut dist = vec![f32::INFINITY; cm.size()];
+    let mut open = BinaryHeap::new();
+
+    dist[s] = 0.0;
+    open.push(Open { key: (0.0, 0.0), node: s });
+
+    while let Some(Open { key: (d, _), node: u }) = open.pop() {
+        if d > dist[u] {
+            continue; // Stale entry
+        }
+        for v in cm.neighbors(u) {
+            let cost = d + cm.edge(u, v);
+            if cost < dist[v] {
+                dist[v] = cost;
+                open.push(Open { key: (cost, 0.0), node: v });
+            }
+        }
+    }
+
+    dist
+}
+
+fn trace(parent: &[usize], s: usize, goal: usize) -> Vec<usize> {
+    let mut path = vec![goal];
+    let mut cur = goal;
+    while cur != s {
+        cur = parent[cur];
+        path.push(cur);
+    }
+    path.reverse();
+    path
+}
+
+// --- D* LITE (incremental) ---
+
+/// The Replanner (the ResourceArc payload).
+pub struct Replanner {
+    map: Arc<SpatialMap>,
+    state: Mutex<DStarLite>,
+}
+
+impl std::panic::RefUnwindSafe for Replanner {}
+
+struct DStarLite {
+    cm: Costmap,
+    opts: PlanOptions,
+    start: usize,
+    last: usize, // Start at the last key-modifier update
+    goal: usize,
+    km: f32,
+    g: Vec<f32>,
+    rhs: Vec<f32>,
+    queued: Vec<Option<(f32, f32)>>, // Current key of queued nodes (heap entries are lazy)
+    open: BinaryHeap<Open>,
+    version: u64, // Map version the costmap reflects
+}
+
+impl Replanner {
+    /// Builds the costmap and runs the first search.
+    /// Returns the planner and its first path.
+    pub fn new(map: Arc<SpatialMap>, start: Point, goal: Point, opts: PlanOptions, now_us: u64) -> Result<(Self, Vec<Point>), PlanError> {
+        // Read the version first: writes racing the snapshot are re-applied on the next replan.
+        let version = map.version();
+        let (cm, s, g) = prepare(&map, start, goal, &opts, now_us)?;
+        let n = cm.size();
+
+        let mut d = DStarLite {
+            cm,
+            opts,
+            start: s,
+            last: s,
+            goal: g,
+            km: 0.0,
+            g: vec![f32::INFINITY; n],
+            rhs: vec![f32::INFINITY; n],
+            queued: vec![None; n],
+            open: BinaryHeap::new(),
+            version,
+        };
+        d.rhs[g] = 0.0;
+        let key = d.key(g);
+        d.push(g, key);
+
+        d.compute()?;
+        let path = d.path()?;
+        Ok((Self { map, state: Mutex::new(d) }, path))
+    }
+
+    /// Moves the start to `start`, folds in every map change since the last
+    /// call, repairs the search and returns the new path.
+    /// NOTE: Only written cells are picked up. A cell whose evidence merely
+    /// decayed into another class is seen once it is observed again.
+    pub fn replan(&self, start: Point, now_us: u64) -> Result<Vec<Point>, PlanError> {
+        let mut d = self.state.lock().unwrap();
+        let s = d.cm.index(start).ok_or(PlanError::OutOfBounds)?;
+
+        // 1. The drone moved: heuristics shrink by the distance travelled
+        let last = d.last;
+        d.km += d.cm.octile(last, s);
+        d.last = s;
+        d.start = s;
+
+        // 2. Map changes since the last call (change feed), then the escape zone
+        let mut touched = HashSet::new();
+        let cfg = self.map.config();
+        let (cursor, cells) = self.map.changed_cells(d.version, usize::MAX);
+        d.version = cursor;
+        for (p, _) in cells {
+            if let Some(i) = d.cm.index(p) {
+                let class = d.cm.classify_cell(&self.map, &cfg, p, now_us);
+                touched.extend(d.cm.update(i, class));
+            }
+        }
+        touched.extend(d.cm.set_escape(s));
+
+        // 3. Every edge into or out of a touched cell may have changed
+        let mut dirty: HashSet<usize> = HashSet::new();
+        for &i in &touched {
+            dirty.insert(i);
+            dirty.extend(d.cm.neighbors(i));
+        }
+        for u in dirty {
+            if u != d.goal {
+                d.rhs[u] = d.best_successor(u);
+            }
+            d.update_vertex(u);
+        }
+
+        // 4. Only now bail out: the search state above must stay in sync with the costmap
+        check_ends(&d.cm, s, d.goal)?;
+        d.compute()?;
+        d.path()
+    }
+}
+
+impl DStarLite {
+    fn key(&self, u: usize) -> (f32, f32) {
+        let m = self.g[u].min(self.rhs[u]);
+        (m + self.cm.octile(self.start, u) + self.km, m)
+    }
+
+    fn push(&mut self, u: usize, key: (f32, f32)) {
+        self.queued[u] = Some(key);
+        self.open.push(Open { key, node: u });
+    }
+
+    /// The smallest valid queue entry (stale ones are discarded).
+    fn top(&mut self) -> Option<Open> {
+        while let Some(&top) = self.open.peek() {
+            if self.queued[top.node] == Some(top.key) {
+                return Some(top);
+            }
+            self.open.pop();
+        }
+        None
+    }
+
+    fn best_successor(&self, u: usize) -> f32 {
+        self.cm
+            .neighbors(u)
+            .map(|v| self.cm.edge(u, v) + self.g[v])
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    fn update_vertex(&mut self, u: usize) {
+        if self.g[u] != self.rhs[u] {
+            let key = self.key(u);
+            self.push(u, key);
+        } else {
+            self.queued[u] = None;
+        }
+    }
+
+    fn compute(&mut self) -> Result<(), PlanError> {
+        let mut expansions = 0;
+
+        while let Some(top) = self.top() {
+            let start_key = self.key(self.start);
+            if !key_before(top.key, start_key) && self.rhs[self.start] == self.g[self.start] {
+                break;
+            }
+
+            expansions += 1;
+            if expansions > self.opts.max_expansions {
+                return Err(PlanError::SearchLimit);
+            }
+
+            let u = top.node;
+            let fresh = self.key(u);
+            self.open.pop();
+            self.queued[u] = None;
+
+            if key_lt(top.key, fresh) {
+                // Key outdated by km: requeue
+                self.push(u, fresh);
+            } else if self.g[u] > self.rhs[u] {
+                // Overconsistent: settle it, relax predecessors
+                self.g[u] = self.rhs[u];
+                let neighbors: Vec<usize> = self.cm.neighbors(u).collect();
+                for s in neighbors {
+                    if s != self.goal {
+                        self.rhs[s] = self.rhs[s].min(self.cm.edge(s, u) + self.g[u]);
+                    }
+                    self.update_vertex(s);
+                }
+            } else {
+                // Underconsistent: raise it, recompute everything that used it
+                self.g[u] = f32::INFINITY;
+                let neighbors: Vec<usize> = self.cm.neighbors(u).chain(std::iter::once(u)).collect();
+                for s in neighbors {
+                    if s != self.goal {
+                        self.rhs[s] = self.best_successor(s);
+                    }
+                    self.update_vertex(s);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Follows the cheapest successors from the start down to the goal.
+    fn path(&self) -> Result<Vec<Point>, PlanError> {
+        if !self.g[self.start].is_finite() && !self.rhs[self.start].is_finite() {
+            return Err(PlanError::NoPath);
+        }
+
+        let mut path = vec![self.start];
+        let mut cur = self.start;
+        while cur != self.goal {
+            let next = self
+                .cm
+                .neighbors(cur)
+                .map(|v| (self.cm.edge(cur, v) + self.g[v], v))
+                .filter(|(c, _)| c.is_finite())
+                .min_by(|a, b| a.0.total_cmp(&b.0))
+                .map(|(_, v)| v)
+                .ok_or(PlanError::NoPath)?;
+
+            path.push(next);
+            cur = next;
+            if path.len() > self.cm.size() {
+                return Err(PlanError::NoPath); // Defensive: never loop
+            }
+        }
+
+        Ok(finish(&self.cm, path, &self.opts))
+    }
+}
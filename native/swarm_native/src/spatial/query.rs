@@ -0,0 +1,287 @@
+// native/swarm_native/src/spatial/query.rs
+
+//! THE RECALL (Spatial Queries)
+//!
+//! Read-only questions over the occupancy grid. All coordinates and radii
+//! are in CELLS (the same integer keys as `get_spatial_state`).
+//!
+//! Only cells that have ever been written exist in the map. Listing and
+//! counting therefore cover known territory; "unknown" cells that were never
+//! observed are implicit (see `nearest`, which does search them).
+
+use std::collections::HashMap;
+
+use crate::spatial::grid::{Cell, GridConfig, SpatialMap, STATUS_UNKNOWN};
+
+// Widest ring search for never-observed (UNKNOWN) cells, ~4M lookups worst case.
+const RING_SEARCH_MAX_RADIUS: i32 = 1024;
+
+/// A cell as reported to Elixir: {x, y, status, version}.
+pub type CellReport = (i32, i32, u32, u64);
+
+/// A stored cell with its key.
+pub type CellEntry = ((i32, i32), Cell);
+
+/// Inclusive cell rectangle.
+#[derive(Clone, Copy, Debug)]
+pub struct Bounds {
+    pub x_min: i32,
+    pub y_min: i32,
+    pub x_max: i32,
+    pub y_max: i32,
+}
+
+impl Bounds {
+    pub fn new(x0: i32, y0: i32, x1: i32, y1: i32) -> Self {
+        Self { x_min: x0.min(x1), y_min: y0.min(y1), x_max: x0.max(x1), y_max: y0.max(y1) }
+    }
+
+    pub fn around(x: i32, y: i32, radius: i32) -> Self {
+        Self::new(x.saturating_sub(radius), y.saturating_sub(radius), x.saturating_add(radius), y.saturating_add(radius))
+    }
+
+    /// The same rectangle with `margin` extra cells on every side.
+    pub fn grow(&self, margin: u32) -> Self {
+        let m = margin.min(i32::MAX as u32) as i32;
+        Self::new(self.x_min.saturating_sub(m), self.y_min.saturating_sub(m), self.x_max.saturating_add(m), self.y_max.saturating_add(m))
+    }
+
+    pub fn width(&self) -> u64 {
+        (self.x_max as i64 - self.x_min as i64 + 1) as u64
+    }
+
+    pub fn height(&self) -> u64 {
+        (self.y_max as i64 - self.y_min as i64 + 1) as u64
+    }
+
+    pub fn area(&self) -> u64 {
+        self.width() * self.height()
+    }
+
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x_min && x <= self.x_max && y >= self.y_min && y <= self.y_max
+    }
+}
+
+fn dist2(ax: i32, ay: i32, bx: i32, by: i32) -> i64 {
+    let (dx, dy) = (ax as i64 - bx as i64, ay as i64 - by as i64);
+    dx * dx + dy * dy
+}
+
+impl SpatialMap {
+    /// Known cells inside `bounds`, optionally only those with `status`.
+    /// Sorted by (y, x) so the output is deterministic.
+    pub fn cells_in_bounds(&self, bounds: Bounds, status: Option<u32>, now_us: u64) -> Vec<CellReport> {
+        let cfg = self.config();
+        self.collect_in_bounds(&cfg, bounds, now_us, |x, y, s| {
+            bounds.contains(x, y) && status.is_none_or(|want| s == want)
+        })
+    }
+
+    /// Known cells whose centers lie within `radius` of (x, y).
+    pub fn cells_in_radius(&self, x: i32, y: i32, radius: u32, status: Option<u32>, now_us: u64) -> Vec<CellReport> {
+        let cfg = self.config();
+        let r = radius.min(i32::MAX as u32) as i32;
+        let r2 = (r as i64) * (r as i64);
+        self.collect_in_bounds(&cfg, Bounds::around(x, y, r), now_us, |cx, cy, s| {
+            dist2(cx, cy, x, y) <= r2 && status.is_none_or(|want| s == want)
+        })
+    }
+
+    fn collect_in_bounds<F>(&self, cfg: &GridConfig, bounds: Bounds, now_us: u64, keep: F) -> Vec<CellReport>
+    where
+        F: Fn(i32, i32, u32) -> bool,
+    {
+        let mut out = Vec::new();
+
+        // Pick the cheaper walk: the rectangle's keys, or every stored cell.
+        if bounds.area() <= self.cells.len() as u64 {
+            for y in bounds.y_min..=bounds.y_max {
+                for x in bounds.x_min..=bounds.x_max {
+                    if let Some(cell) = self.cells.get(&(x, y)) {
+                        let s = cell.status_at(cfg, now_us);
+                        if keep(x, y, s) {
+                            out.push((x, y, s, cell.version));
+                        }
+                    }
+                }
+            }
+        } else {
+            for entry in self.cells.iter() {
+                let (x, y) = *entry.key();
+                let s = entry.status_at(cfg, now_us);
+                if bounds.contains(x, y) && keep(x, y, s) {
+                    out.push((x, y, s, entry.version));
+                }
+            }
+            out.sort_unstable_by_key(|&(x, y, _, _)| (y, x));
+        }
+
+        out
+    }
+
+    /// Nearest cell to (x, y) with `status`, within `max_radius` cells.
+    /// Works for STATUS_UNKNOWN too (never-observed cells count as unknown).
+    /// Ties are broken by (y, x). Returns (x, y, distance_in_cells).
+    pub fn nearest(&self, x: i32, y: i32, status: u32, max_radius: u32, now_us: u64) -> Option<(i32, i32, f32)> {
+        let cfg = self.config();
+        let max_r = max_radius.min(i32::MAX as u32) as i32;
+        let max_r2 = (max_r as i64) * (max_r as i64);
+
+        let better = |best: Option<(i64, i32, i32)>, d2: i64, cx: i32, cy: i32| match best {
+            Some((bd, bx, by)) => (d2, cy, cx) < (bd, by, bx),
+            None => true,
+        };
+
+        let mut best: Option<(i64, i32, i32)> = None;
+
+        // Tagged or observed statuses only exist as stored cells: if the map
+        // is smaller than the search disc, scanning it is the cheaper path.
+        let disc = (2 * max_r as u64 + 1).pow(2);
+        if (status != STATUS_UNKNOWN && (self.cells.len() as u64) < disc) || max_r > RING_SEARCH_MAX_RADIUS {
+            if status == STATUS_UNKNOWN {
+                // An unbounded unknown search would walk forever; clamp it.
+                return self.nearest(x, y, status, RING_SEARCH_MAX_RADIUS as u32, now_us);
+            }
+            for entry in self.cells.iter() {
+                let (cx, cy) = *entry.key();
+                let d2 = dist2(cx, cy, x, y);
+                if d2 <= max_r2 && entry.status_at(&cfg, now_us) == status && better(best, d2, cx, cy) {
+                    best = Some((d2, cx, cy));
+                }
+            }
+        } else {
+            // Expanding square rings. Euclidean distance >= ring index, so once
+            // the ring index passes the best distance nothing closer remains.
+            for r in 0..=max_r {
+                if let Some((bd, _, _)) = best {
+                    if (r as i64) * (r as i64) > bd {
+                        break;
+                    }
+                }
+                for (cx, cy) in ring(x, y, r) {
+                    let d2 = dist2(cx, cy, x, y);
+                    if d2 <= max_r2 && self.status_with(&cfg, cx, cy, now_us) == status && better(best, d2, cx, cy) {
+                        best = Some((d2, cx, cy));
+                    }
+                }
+            }
+        }
+
+        best.map(|(d2, cx, cy)| (cx, cy, (d2 as f32).sqrt()))
+    }
+
+    fn status_with(&self, cfg: &GridConfig, x: i32, y: i32, now_us: u64) -> u32 {
+        self.cells.get(&(x, y)).map_or(STATUS_UNKNOWN, |c| c.status_at(cfg, now_us))
+    }
+
+    /// Histogram of known cells by status, optionally within `bounds`.
+    pub fn count_by_status(&self, bounds: Option<Bounds>, now_us: u64) -> HashMap<u32, u64> {
+        let cfg = self.config();
+        let mut counts = HashMap::new();
+
+        for entry in self.cells.iter() {
+            let (x, y) = *entry.key();
+            if bounds.is_none_or(|b| b.contains(x, y)) {
+                *counts.entry(entry.status_at(&cfg, now_us)).or_insert(0) += 1;
+            }
+        }
+
+        counts
+    }
+
+    /// Cells written after `since`, oldest first, at most `limit` of them.
+    /// Returns the version to pass as `since` next time (the last one
+    /// returned, or the current map version once caught up).
+    pub fn changes_since(&self, since: u64, limit: usize, now_us: u64) -> (u64, Vec<CellReport>) {
+        let cfg = self.config();
+        let (cursor, cells) = self.changed_cells(since, limit);
+        let changed = cells
+            .into_iter()
+            .map(|((x, y), c)| (x, y, c.status_at(&cfg, now_us), c.version))
+            .collect();
+
+        (cursor, changed)
+    }
+
+    /// The raw change feed behind `changes_since` and `export_delta`.
+    ///
+    /// Versions are assigned under the cell's shard lock, so every write up
+    /// to `head` is either done or blocks our scan of its shard: nothing at
+    /// or below the returned cursor can be missed. Newer writes wait for the
+    /// next call.
+    pub fn changed_cells(&self, since: u64, limit: usize) -> (u64, Vec<CellEntry>) {
+        let head = self.version();
+
+        let mut changed: Vec<CellEntry> = self
+            .cells
+            .iter()
+            .filter(|e| e.version > since && e.version <= head)
+            .map(|e| (*e.key(), *e.value()))
+            .collect();
+
+        changed.sort_unstable_by_key(|(_, c)| c.version);
+
+        if changed.len() > limit {
+            changed.truncate(limit);
+            let cursor = changed.last().map_or(since, |(_, c)| c.version);
+            (cursor, changed)
+        } else {
+            (head.max(since), changed)
+        }
+    }
+}
+
+/// The cells at Chebyshev distance exactly `r` from (x, y). Cells past the
+/// edge of the i32 grid do not exist and are left out.
+fn ring(x: i32, y: i32, r: i32) -> Vec<(i32, i32)> {
+    if r == 0 {
+        return vec![(x, y)];
+    }
+
+    let mut cells = Vec::with_capacity(8 * r as usize);
+    let mut push = |cx: Option<i32>, cy: Option<i32>| {
+        if let (Some(cx), Some(cy)) = (cx, cy) {
+            cells.push((cx, cy));
+        }
+    };
+    for d in -r..=r {
+        push(x.checked_add(d), y.checked_sub(r)); // Top edge
+        push(x.checked_add(d), y.checked_add(r)); // Bottom edge
+    }
+    for d in -r + 1..r {
+        push(x.checked_sub(r), y.checked_add(d)); // Left edge
+        push(x.checked_add(r), y.checked_add(d)); // Right edge
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ring_has_the_full_perimeter() {
+        let cells = ring(0, 0, 2);
+        assert_eq!(cells.len(), 16);
+        assert!(cells.iter().all(|&(x, y)| x.abs().max(y.abs()) == 2));
+    }
+
+    #[test]
+    fn ring_at_the_grid_edge_drops_missing_cells() {
+        let cells = ring(i32::MAX, i32::MIN, 1);
+        assert_eq!(cells.len(), 3);
+        assert!(cells.contains(&(i32::MAX - 1, i32::MIN)));
+        assert!(cells.contains(&(i32::MAX - 1, i32::MIN + 1)));
+        assert!(cells.contains(&(i32::MAX, i32::MIN + 1)));
+    }
+
+    #[test]
+    fn nearest_unknown_from_a_corner() {
+        // Unknown searches always walk rings
+        let map = SpatialMap::new();
+        map.set_status(i32::MAX, i32::MAX, 9, 1);
+        let (x, y, d) = map.nearest(i32::MAX, i32::MAX, STATUS_UNKNOWN, 8, 1).unwrap();
+        assert_eq!((x, y, d), (i32::MAX, i32::MAX - 1, 1.0));
+    }
+}
@@ -0,0 +1,110 @@
+// native/swarm_native/src/spatial/raycast.rs
+
+//! THE WHISKER (Grid Ray Traversal)
+//!
+//! Amanatides & Woo voxel traversal: visits every cell a segment passes
+//! through, in order, with no gaps and no duplicates (unlike Bresenham,
+//! which can skip corner-clipped cells).
+
+/// The cell containing world point (x, y).
+#[inline]
+pub fn cell_of(x: f32, y: f32, cell_size: f32) -> (i32, i32) {
+    ((x / cell_size).floor() as i32, (y / cell_size).floor() as i32)
+}
+
+/// Returns the cells crossed by the segment (x0, y0) -> (x1, y1), in world
+/// units, starting with the origin cell and ending with the endpoint cell.
+/// Stops early after `max_cells`, so callers must not assume the last cell
+/// is the endpoint's. Coordinates must be finite.
+pub fn traverse(x0: f32, y0: f32, x1: f32, y1: f32, cell_size: f32, max_cells: usize) -> Vec<(i32, i32)> {
+    let (mut cx, mut cy) = cell_of(x0, y0, cell_size);
+    let end = cell_of(x1, y1, cell_size);
+
+    let (dx, dy) = (x1 - x0, y1 - y0);
+
+    // Per axis: direction, parametric distance (t in 0..1) to the first
+    // boundary, and the t needed to cross one whole cell.
+    let axis = |d: f32, origin: f32, c: i32| -> (i32, f32, f32) {
+        if d > 0.0 {
+            (1, ((c + 1) as f32 * cell_size - origin) / d, cell_size / d)
+        } else if d < 0.0 {
+            (-1, (c as f32 * cell_size - origin) / d, -cell_size / d)
+        } else {
+            (0, f32::INFINITY, f32::INFINITY)
+        }
+    };
+    let (step_x, mut t_max_x, t_delta_x) = axis(dx, x0, cx);
+    let (step_y, mut t_max_y, t_delta_y) = axis(dy, y0, cy);
+
+    // The walk takes exactly this many steps per axis. Counting them (rather
+    // than trusting the accumulated t) keeps float drift from overshooting
+    // or missing the endpoint cell.
+    // (i64: far-apart cells saturate to opposite ends of i32)
+    let mut left_x = (end.0 as i64 - cx as i64).abs();
+    let mut left_y = (end.1 as i64 - cy as i64).abs();
+
+    let mut cells = Vec::new();
+    while cells.len() < max_cells {
+        cells.push((cx, cy));
+
+        if left_x == 0 && left_y == 0 {
+            break;
+        }
+
+        if left_y == 0 || (left_x > 0 && t_max_x < t_max_y) {
+            left_x -= 1;
+            cx += step_x;
+            t_max_x += t_delta_x;
+        } else {
+            left_y -= 1;
+            cy += step_y;
+            t_max_y += t_delta_y;
+        }
+    }
+
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visits_every_cell_from_origin_to_endpoint() {
+        let cells = traverse(0.5, 0.5, 3.5, 0.5, 1.0, 100);
+        assert_eq!(cells, vec![(0, 0), (1, 0), (2, 0), (3, 0)]);
+
+        let cells = traverse(0.5, 0.5, -1.5, -1.5, 1.0, 100);
+        assert_eq!(cells.first(), Some(&(0, 0)));
+        assert_eq!(cells.last(), Some(&(-2, -2)));
+    }
+
+    #[test]
+    fn steps_are_four_connected_without_duplicates() {
+        let cells = traverse(0.2, 0.7, 7.9, 3.1, 0.5, 1000);
+        assert_eq!(cells.last(), Some(&cell_of(7.9, 3.1, 0.5)));
+        for pair in cells.windows(2) {
+            let (a, b) = (pair[0], pair[1]);
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1, "{a:?} -> {b:?}");
+        }
+    }
+
+    #[test]
+    fn same_cell_is_one_cell() {
+        assert_eq!(traverse(0.1, 0.1, 0.4, 0.3, 1.0, 100), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn stops_at_max_cells() {
+        let cells = traverse(0.5, 0.5, 100.5, 0.5, 1.0, 10);
+        assert_eq!(cells.len(), 10);
+        assert_eq!(cells.last(), Some(&(9, 0)));
+    }
+
+    #[test]
+    fn saturated_cells_do_not_overflow() {
+        let cells = traverse(-1e30, 0.0, 1e30, 0.0, 1.0, 16);
+        assert_eq!(cells.len(), 16);
+        assert_eq!(cells[0], (i32::MIN, 0));
+    }
+}
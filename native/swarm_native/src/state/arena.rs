@@ -3,8 +3,11 @@
 use std::sync::{Arc, Mutex, RwLock};
 use std::sync::atomic::{AtomicU32, AtomicUsize};
 use std::process::Child;
-use dashmap::DashMap; // <--- Critical for legacy support
 use crate::types::Kinematics;
+use crate::spatial::grid::SpatialMap;
+use crate::bus::Bus;
+use crate::recorder::Recorder;
+use crate::vision::replay::Replay;
 
 // Constants for Pre-Allocation
 pub const FRAME_WIDTH: usize = 640;
@@ -43,9 +46,18 @@ pub struct SwarmState {
     // 5. The Insect Eye (Math Path - Optical Flow Grid)
     pub flow_grid: Arc<RwLock<[f32; 200]>>, 
 
-    // 6. The Spatial Memory (Legacy/Spatial Path)
+    // 6. The Spatial Memory (Occupancy Grid + legacy status tags)
     // Kept here so NIFs can access it via the main resource handle.
-    pub spatial_memory: Arc<DashMap<(i32, i32), u32>>, 
+    pub spatial_memory: Arc<SpatialMap>,
+
+    // 7. The Nerve (optional zenoh bus, fed by the heartbeat)
+    pub bus: Arc<RwLock<Option<Arc<Bus>>>>,
+
+    // 8. The Black Box (optional flight recorder, fed by the heartbeat)
+    pub recorder: Arc<RwLock<Option<Arc<Recorder>>>>,
+
+    // 9. The Tape (optional replay feeding the heartbeat instead of FFmpeg)
+    pub replay: Arc<RwLock<Option<Arc<Replay>>>>,
 }
 
 impl SwarmState {
@@ -66,7 +78,10 @@ impl SwarmState {
             child_process: Arc::new(Mutex::new(None)),
             running: Arc::new(AtomicU32::new(1)),
             flow_grid: Arc::new(RwLock::new([0.0; 200])),
-            spatial_memory: Arc::new(DashMap::new()), // Initialize the storage
+            spatial_memory: Arc::new(SpatialMap::new()), // Initialize the storage
+            bus: Arc::new(RwLock::new(None)), // Off until start_zenoh
+            recorder: Arc::new(RwLock::new(None)), // Off until start_recorder
+            replay: Arc::new(RwLock::new(None)), // Off until start_replay
         }
     }
 }
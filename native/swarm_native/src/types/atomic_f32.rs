@@ -41,6 +41,66 @@ impl AtomicF32 {
         let bit_cast = val.to_bits();
         self.storage.store(bit_cast, Ordering::Release);
     }
+
+    // --- EXPLICIT ORDERING API ---
+    // Same contract as the std integer atomics, applied to the bit pattern.
+
+    #[inline(always)]
+    pub fn load_with(&self, order: Ordering) -> f32 {
+        f32::from_bits(self.storage.load(order))
+    }
+
+    #[inline(always)]
+    pub fn store_with(&self, val: f32, order: Ordering) {
+        self.storage.store(val.to_bits(), order);
+    }
+
+    /// Stores `val`, returning the previous value.
+    #[inline(always)]
+    pub fn swap(&self, val: f32, order: Ordering) -> f32 {
+        f32::from_bits(self.storage.swap(val.to_bits(), order))
+    }
+
+    /// Stores `new` if the current value is `current`.
+    ///
+    /// NOTE: Comparison is BITWISE, not IEEE. `0.0` does not match `-0.0`,
+    /// and a NaN matches itself only with the identical payload.
+    #[inline(always)]
+    pub fn compare_exchange(
+        &self,
+        current: f32,
+        new: f32,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<f32, f32> {
+        self.storage
+            .compare_exchange(current.to_bits(), new.to_bits(), success, failure)
+            .map(f32::from_bits)
+            .map_err(f32::from_bits)
+    }
+
+    /// Read-Modify-Write via a CAS loop. `f` may run several times under
+    /// contention; returning `None` aborts. Returns the previous value.
+    #[inline(always)]
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, mut f: F) -> Result<f32, f32>
+    where
+        F: FnMut(f32) -> Option<f32>,
+    {
+        self.storage
+            .fetch_update(set_order, fetch_order, |bits| f(f32::from_bits(bits)).map(f32::to_bits))
+            .map(f32::from_bits)
+            .map_err(f32::from_bits)
+    }
+
+    /// Atomic `+=`. Returns the previous value.
+    /// Replaces racy `x.store(x.load() + d)` sequences.
+    #[inline(always)]
+    pub fn fetch_add(&self, val: f32, order: Ordering) -> f32 {
+        // The closure never returns None, so this cannot fail
+        match self.fetch_update(order, Ordering::Relaxed, |x| Some(x + val)) {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
 }
 
 // Allow default initialization (0.0)
@@ -48,4 +108,20 @@ impl Default for AtomicF32 {
     fn default() -> Self {
         Self::new(0.0)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::stress::{hammer, ADDS, THREADS};
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_fetch_add_loses_nothing() {
+        let sum = Arc::new(AtomicF32::new(0.0));
+        let shared = sum.clone();
+        hammer(move || {
+            shared.fetch_add(1.0, Ordering::AcqRel);
+        });
+        assert_eq!(sum.load(), (THREADS * ADDS) as f32);
+    }
+}
@@ -0,0 +1,102 @@
+// native/swarm_native/src/types/atomic_f64.rs
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The double-precision sibling of `AtomicF32` (same cache-line isolation,
+/// same API). For accumulators that outgrow f32, e.g. integrated position
+/// or timestamps in seconds over a long flight.
+#[repr(align(64))]
+pub struct AtomicF64 {
+    storage: AtomicU64,
+}
+
+impl AtomicF64 {
+    pub fn new(val: f64) -> Self {
+        Self {
+            storage: AtomicU64::new(val.to_bits()),
+        }
+    }
+
+    /// Acquire load.
+    #[inline(always)]
+    pub fn load(&self) -> f64 {
+        f64::from_bits(self.storage.load(Ordering::Acquire))
+    }
+
+    /// Release store.
+    #[inline(always)]
+    pub fn store(&self, val: f64) {
+        self.storage.store(val.to_bits(), Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn load_with(&self, order: Ordering) -> f64 {
+        f64::from_bits(self.storage.load(order))
+    }
+
+    #[inline(always)]
+    pub fn store_with(&self, val: f64, order: Ordering) {
+        self.storage.store(val.to_bits(), order);
+    }
+
+    #[inline(always)]
+    pub fn swap(&self, val: f64, order: Ordering) -> f64 {
+        f64::from_bits(self.storage.swap(val.to_bits(), order))
+    }
+
+    /// Bitwise comparison (see `AtomicF32::compare_exchange`).
+    #[inline(always)]
+    pub fn compare_exchange(
+        &self,
+        current: f64,
+        new: f64,
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<f64, f64> {
+        self.storage
+            .compare_exchange(current.to_bits(), new.to_bits(), success, failure)
+            .map(f64::from_bits)
+            .map_err(f64::from_bits)
+    }
+
+    #[inline(always)]
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, mut f: F) -> Result<f64, f64>
+    where
+        F: FnMut(f64) -> Option<f64>,
+    {
+        self.storage
+            .fetch_update(set_order, fetch_order, |bits| f(f64::from_bits(bits)).map(f64::to_bits))
+            .map(f64::from_bits)
+            .map_err(f64::from_bits)
+    }
+
+    #[inline(always)]
+    pub fn fetch_add(&self, val: f64, order: Ordering) -> f64 {
+        match self.fetch_update(order, Ordering::Relaxed, |x| Some(x + val)) {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+}
+
+impl Default for AtomicF64 {
+    fn default() -> Self {
+        Self::new(0.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::stress::{hammer, ADDS, THREADS};
+    use std::sync::Arc;
+
+    #[test]
+    fn concurrent_fetch_add_loses_nothing() {
+        let sum = Arc::new(AtomicF64::new(0.0));
+        let shared = sum.clone();
+        hammer(move || {
+            shared.fetch_add(0.5, Ordering::AcqRel);
+        });
+        assert_eq!(sum.load(), (THREADS * ADDS) as f64 * 0.5);
+    }
+}
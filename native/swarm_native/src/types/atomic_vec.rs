@@ -0,0 +1,323 @@
+// native/swarm_native/src/types/atomic_vec.rs
+
+//! Atomic vectors: several floats that must always be read and written together.
+//!
+//! * `AtomicVec2` packs two f32 into one `AtomicU64`. Every operation is a
+//!   single hardware atomic, so it gets the full explicit-ordering API.
+//! * `AtomicVec4` needs 128 bits, which has no stable atomic type, so it is
+//!   a seqlock. Readers are lock-free; writers take turns on the sequence.
+//!   It takes the same `Ordering` arguments, but the protocol already needs
+//!   Acquire reads and Release writes: weaker orderings act as those, and
+//!   only `SeqCst` adds anything.
+
+use std::sync::atomic::{fence, AtomicU32, AtomicU64, Ordering};
+
+#[inline(always)]
+fn pack(v: [f32; 2]) -> u64 {
+    (v[0].to_bits() as u64) | ((v[1].to_bits() as u64) << 32)
+}
+
+#[inline(always)]
+fn unpack(bits: u64) -> [f32; 2] {
+    [f32::from_bits(bits as u32), f32::from_bits((bits >> 32) as u32)]
+}
+
+/// Two floats in one 64-bit atomic, e.g. a (vx, vy) velocity pair.
+#[repr(align(64))]
+pub struct AtomicVec2 {
+    storage: AtomicU64,
+}
+
+impl AtomicVec2 {
+    pub fn new(val: [f32; 2]) -> Self {
+        Self {
+            storage: AtomicU64::new(pack(val)),
+        }
+    }
+
+    /// Acquire load.
+    #[inline(always)]
+    pub fn load(&self) -> [f32; 2] {
+        unpack(self.storage.load(Ordering::Acquire))
+    }
+
+    /// Release store.
+    #[inline(always)]
+    pub fn store(&self, val: [f32; 2]) {
+        self.storage.store(pack(val), Ordering::Release);
+    }
+
+    #[inline(always)]
+    pub fn load_with(&self, order: Ordering) -> [f32; 2] {
+        unpack(self.storage.load(order))
+    }
+
+    #[inline(always)]
+    pub fn store_with(&self, val: [f32; 2], order: Ordering) {
+        self.storage.store(pack(val), order);
+    }
+
+    #[inline(always)]
+    pub fn swap(&self, val: [f32; 2], order: Ordering) -> [f32; 2] {
+        unpack(self.storage.swap(pack(val), order))
+    }
+
+    /// Bitwise comparison of both lanes (see `AtomicF32::compare_exchange`).
+    #[inline(always)]
+    pub fn compare_exchange(
+        &self,
+        current: [f32; 2],
+        new: [f32; 2],
+        success: Ordering,
+        failure: Ordering,
+    ) -> Result<[f32; 2], [f32; 2]> {
+        self.storage
+            .compare_exchange(pack(current), pack(new), success, failure)
+            .map(unpack)
+            .map_err(unpack)
+    }
+
+    #[inline(always)]
+    pub fn fetch_update<F>(
+        &self,
+        set_order: Ordering,
+        fetch_order: Ordering,
+        mut f: F,
+    ) -> Result<[f32; 2], [f32; 2]>
+    where
+        F: FnMut([f32; 2]) -> Option<[f32; 2]>,
+    {
+        self.storage
+            .fetch_update(set_order, fetch_order, |bits| f(unpack(bits)).map(pack))
+            .map(unpack)
+            .map_err(unpack)
+    }
+
+    /// Lane-wise `+=` of both components as one atomic step.
+    #[inline(always)]
+    pub fn fetch_add(&self, delta: [f32; 2], order: Ordering) -> [f32; 2] {
+        match self.fetch_update(order, Ordering::Relaxed, |v| Some([v[0] + delta[0], v[1] + delta[1]])) {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+}
+
+impl Default for AtomicVec2 {
+    fn default() -> Self {
+        Self::new([0.0; 2])
+    }
+}
+
+/// Four floats behind a seqlock, e.g. (vx, vy, px, py) or a quaternion.
+///
+/// `seq` is odd while a writer holds it. Writers claim it with a CAS, so any
+/// number of threads may write; readers retry if a write overlapped their copy.
+#[repr(align(64))]
+pub struct AtomicVec4 {
+    seq: AtomicU64,
+    lanes: [AtomicU32; 4],
+}
+
+impl AtomicVec4 {
+    pub fn new(val: [f32; 4]) -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            lanes: val.map(|x| AtomicU32::new(x.to_bits())),
+        }
+    }
+
+    /// Acquire load.
+    #[inline(always)]
+    pub fn load(&self) -> [f32; 4] {
+        self.load_with(Ordering::Acquire)
+    }
+
+    /// Release store.
+    #[inline(always)]
+    pub fn store(&self, val: [f32; 4]) {
+        self.store_with(val, Ordering::Release);
+    }
+
+    /// Consistent read of all four lanes. Panics on `Release` / `AcqRel`,
+    /// like `AtomicU64::load`.
+    pub fn load_with(&self, order: Ordering) -> [f32; 4] {
+        assert!(!matches!(order, Ordering::Release | Ordering::AcqRel), "there is no such thing as a release load");
+        loop {
+            let before = self.seq.load(acquire_part(order));
+            if before & 1 == 0 {
+                let val = self.read_lanes();
+
+                // Keep the lane loads from drifting past the re-check
+                fence(Ordering::Acquire);
+                if self.seq.load(Ordering::Relaxed) == before {
+                    return val;
+                }
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    /// Panics on `Acquire` / `AcqRel`, like `AtomicU64::store`.
+    pub fn store_with(&self, val: [f32; 4], order: Ordering) {
+        assert!(!matches!(order, Ordering::Acquire | Ordering::AcqRel), "there is no such thing as an acquire store");
+        let seq = self.lock(order);
+        self.write_lanes(val);
+        self.unlock(seq, order);
+    }
+
+    pub fn swap(&self, val: [f32; 4], order: Ordering) -> [f32; 4] {
+        let seq = self.lock(order);
+        let prev = self.read_lanes();
+        self.write_lanes(val);
+        self.unlock(seq, order);
+        prev
+    }
+
+    /// Read-Modify-Write under the writer lock. `f` runs exactly once;
+    /// returning `None` leaves the value untouched (and releases with
+    /// `fetch_order`). Returns the previous value.
+    pub fn fetch_update<F>(&self, set_order: Ordering, fetch_order: Ordering, f: F) -> Result<[f32; 4], [f32; 4]>
+    where
+        F: FnOnce([f32; 4]) -> Option<[f32; 4]>,
+    {
+        let seq = self.lock(stronger(set_order, fetch_order));
+        let prev = self.read_lanes();
+        let result = match f(prev) {
+            Some(next) => {
+                self.write_lanes(next);
+                Ok(prev)
+            }
+            None => Err(prev),
+        };
+        self.unlock(seq, if result.is_ok() { set_order } else { fetch_order });
+        result
+    }
+
+    /// Lane-wise `+=` as one atomic step. Returns the previous value.
+    pub fn fetch_add(&self, delta: [f32; 4], order: Ordering) -> [f32; 4] {
+        let add = |v: [f32; 4]| Some([v[0] + delta[0], v[1] + delta[1], v[2] + delta[2], v[3] + delta[3]]);
+        match self.fetch_update(order, Ordering::Relaxed, add) {
+            Ok(prev) | Err(prev) => prev,
+        }
+    }
+
+    // --- SEQLOCK INTERNALS ---
+
+    /// Claims the writer slot (even -> odd). Returns the even value claimed.
+    fn lock(&self, order: Ordering) -> u64 {
+        loop {
+            let seq = self.seq.load(Ordering::Relaxed);
+            if seq & 1 == 0
+                && self
+                    .seq
+                    .compare_exchange_weak(seq, seq.wrapping_add(1), acquire_part(order), Ordering::Relaxed)
+                    .is_ok()
+            {
+                // The odd marker must be visible before any lane store
+                fence(Ordering::Release);
+                return seq;
+            }
+            std::hint::spin_loop();
+        }
+    }
+
+    fn unlock(&self, seq: u64, order: Ordering) {
+        self.seq.store(seq.wrapping_add(2), release_part(order));
+    }
+
+    #[inline(always)]
+    fn read_lanes(&self) -> [f32; 4] {
+        [0, 1, 2, 3].map(|i| f32::from_bits(self.lanes[i].load(Ordering::Relaxed)))
+    }
+
+    #[inline(always)]
+    fn write_lanes(&self, val: [f32; 4]) {
+        for (lane, x) in self.lanes.iter().zip(val) {
+            lane.store(x.to_bits(), Ordering::Relaxed);
+        }
+    }
+}
+
+// The seqlock needs Acquire to claim / read and Release to publish, so the
+// caller's ordering can only strengthen those (SeqCst), never weaken them.
+#[inline(always)]
+fn acquire_part(order: Ordering) -> Ordering {
+    if order == Ordering::SeqCst { Ordering::SeqCst } else { Ordering::Acquire }
+}
+
+#[inline(always)]
+fn release_part(order: Ordering) -> Ordering {
+    if order == Ordering::SeqCst { Ordering::SeqCst } else { Ordering::Release }
+}
+
+#[inline(always)]
+fn stronger(a: Ordering, b: Ordering) -> Ordering {
+    if b == Ordering::SeqCst { b } else { a }
+}
+
+impl Default for AtomicVec4 {
+    fn default() -> Self {
+        Self::new([0.0; 4])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::stress::{hammer, ADDS, THREADS};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn vec2_concurrent_fetch_add_loses_nothing() {
+        let v = Arc::new(AtomicVec2::default());
+        let shared = v.clone();
+        hammer(move || {
+            shared.fetch_add([1.0, -2.0], Ordering::AcqRel);
+        });
+        let n = (THREADS * ADDS) as f32;
+        assert_eq!(v.load(), [n, -2.0 * n]);
+    }
+
+    #[test]
+    fn vec4_concurrent_fetch_add_loses_nothing() {
+        let v = Arc::new(AtomicVec4::default());
+        let shared = v.clone();
+        hammer(move || {
+            shared.fetch_add([1.0, 2.0, 3.0, 4.0], Ordering::AcqRel);
+        });
+        let n = (THREADS * ADDS) as f32;
+        assert_eq!(v.load(), [n, 2.0 * n, 3.0 * n, 4.0 * n]);
+    }
+
+    #[test]
+    fn vec4_reads_stay_consistent_under_fetch_add() {
+        let v = Arc::new(AtomicVec4::default());
+        let shared = v.clone();
+        let reader = thread::spawn(move || {
+            let mut last = 0.0;
+            loop {
+                let [a, b, c, d] = shared.load_with(Ordering::SeqCst);
+                assert_eq!([b, c, d], [2.0 * a, 3.0 * a, 4.0 * a], "torn read");
+                assert!(a >= last, "went backwards");
+                last = a;
+                if a == (THREADS * ADDS) as f32 {
+                    break;
+                }
+            }
+        });
+        let writer = v.clone();
+        hammer(move || {
+            writer.fetch_add([1.0, 2.0, 3.0, 4.0], Ordering::SeqCst);
+        });
+        reader.join().unwrap();
+    }
+
+    #[test]
+    fn vec4_fetch_update_none_leaves_value() {
+        let v = AtomicVec4::new([1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(v.fetch_update(Ordering::AcqRel, Ordering::Acquire, |_| None), Err([1.0, 2.0, 3.0, 4.0]));
+        assert_eq!(v.swap([0.0; 4], Ordering::Relaxed), [1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(v.load_with(Ordering::Relaxed), [0.0; 4]);
+    }
+}
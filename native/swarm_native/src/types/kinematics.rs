@@ -0,0 +1,159 @@
+// native/swarm_native/src/types/kinematics.rs
+
+use std::sync::atomic::{fence, AtomicU64, Ordering};
+
+use rustler::NifMap;
+
+use super::AtomicF32;
+
+/// The Physiological State of the Drone.
+///
+/// # ARCHITECTURAL TACTIC: Seqlock
+/// The camera thread publishes all four fields as one update. Readers never
+/// block it: they copy the fields and retry if a publish overlapped the copy.
+/// `seq` is odd while a publish is in flight and advances by 2 per update,
+/// so a reader that sees the same even value before and after its copy holds
+/// a consistent frame (no `vx` from frame N with `px` from frame N+1).
+///
+/// Single writer: only the camera heartbeat may call `publish`.
+pub struct Kinematics {
+    seq: AtomicU64,
+    vx: AtomicF32,           // Velocity X
+    vy: AtomicF32,           // Velocity Y
+    px: AtomicF32,           // Position X (Integrated)
+    py: AtomicF32,           // Position Y (Integrated)
+    timestamp_us: AtomicU64, // Capture time of the frame (µs since UNIX epoch)
+}
+
+/// One consistent reading of `Kinematics`.
+/// Encoded to Elixir as `%{vx, vy, px, py, timestamp_us, seq}`.
+#[derive(NifMap, Clone, Copy, Debug, Default, PartialEq)]
+pub struct KinematicsSnapshot {
+    pub vx: f32,
+    pub vy: f32,
+    pub px: f32,
+    pub py: f32,
+    pub timestamp_us: u64,
+    pub seq: u64, // Number of publishes so far (0 = boot state)
+}
+
+impl Kinematics {
+    /// Publish a new update (Camera Writing).
+    /// `timestamp_us` is the frame's capture time: the wall clock when live,
+    /// the recorded time on replay.
+    pub fn publish(&self, vx: f32, vy: f32, px: f32, py: f32, timestamp_us: u64) {
+        // 1. Mark the write in progress (odd).
+        // The Release fence keeps the field stores below from being
+        // reordered before the odd marker becomes visible.
+        let seq = self.seq.load(Ordering::Relaxed);
+        self.seq.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        // 2. Payload
+        self.vx.store(vx);
+        self.vy.store(vy);
+        self.px.store(px);
+        self.py.store(py);
+        self.timestamp_us.store(timestamp_us, Ordering::Relaxed);
+
+        // 3. Seal (even). Release publishes the payload with it.
+        self.seq.store(seq.wrapping_add(2), Ordering::Release);
+    }
+
+    /// Read a consistent snapshot (Elixir Reading). Lock-free; spins only
+    /// while a publish is overlapping, which lasts a handful of stores.
+    pub fn snapshot(&self) -> KinematicsSnapshot {
+        loop {
+            let before = self.seq.load(Ordering::Acquire);
+            if before & 1 == 1 {
+                std::hint::spin_loop();
+                continue;
+            }
+
+            let snap = KinematicsSnapshot {
+                vx: self.vx.load(),
+                vy: self.vy.load(),
+                px: self.px.load(),
+                py: self.py.load(),
+                timestamp_us: self.timestamp_us.load(Ordering::Relaxed),
+                seq: before / 2,
+            };
+
+            // The Acquire fence keeps the payload loads above from drifting
+            // past the re-check of the sequence.
+            fence(Ordering::Acquire);
+            if self.seq.load(Ordering::Relaxed) == before {
+                return snap;
+            }
+        }
+    }
+}
+
+// Zero-initialization for boot
+impl Default for Kinematics {
+    fn default() -> Self {
+        Self {
+            seq: AtomicU64::new(0),
+            vx: AtomicF32::new(0.0),
+            vy: AtomicF32::new(0.0),
+            px: AtomicF32::new(0.0),
+            py: AtomicF32::new(0.0),
+            timestamp_us: AtomicU64::new(0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::{Arc, Barrier};
+    use std::thread;
+
+    // Every field of update n is derived from n, so a torn read shows up as a
+    // snapshot whose fields disagree. Values stay exact in f32 (< 2^24).
+    const UPDATES: u64 = 2_000_000;
+
+    #[test]
+    fn concurrent_snapshots_are_never_torn() {
+        let kin = Arc::new(Kinematics::default());
+        let done = Arc::new(AtomicBool::new(false));
+        let start = Arc::new(Barrier::new(5)); // Readers are spinning before the first publish
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let (kin, done, start) = (kin.clone(), done.clone(), start.clone());
+                thread::spawn(move || {
+                    start.wait();
+                    let mut last_seq = 0;
+                    let mut reads = 0u64;
+                    loop {
+                        let finished = done.load(Ordering::Acquire);
+                        let s = kin.snapshot();
+                        let n = s.timestamp_us as f32;
+                        assert_eq!((s.vx, s.vy, s.px, s.py), (n, 2.0 * n, 3.0 * n, 4.0 * n), "torn: {s:?}");
+                        assert_eq!(s.seq, s.timestamp_us, "seq out of step: {s:?}");
+                        assert!(s.seq >= last_seq, "went backwards: {s:?}");
+                        last_seq = s.seq;
+                        reads += 1;
+                        if finished {
+                            break reads;
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        start.wait();
+        for n in 1..=UPDATES {
+            let v = n as f32;
+            kin.publish(v, 2.0 * v, 3.0 * v, 4.0 * v, n);
+        }
+        done.store(true, Ordering::Release);
+
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(kin.snapshot().seq, UPDATES);
+    }
+}
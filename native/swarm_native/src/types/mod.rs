@@ -1,31 +1,39 @@
 // native/swarm_native/src/types/mod.rs
 
+// The atomic toolbox is exported (`swarm_native::types`) through the rlib
+// crate type, so primitives without an in-crate consumer are not dead.
 pub mod atomic_f32;
+pub mod atomic_f64;
+pub mod atomic_vec;
+pub mod kinematics;
 
-// Re-export the primitive for easier access
+// Re-export the primitives for easier access
 pub use atomic_f32::AtomicF32;
+pub use atomic_f64::AtomicF64;
+pub use atomic_vec::{AtomicVec2, AtomicVec4};
+pub use kinematics::{Kinematics, KinematicsSnapshot};
 
-/// The Physiological State of the Drone.
-///
-/// This struct aggregates the atomic telemetry.
-/// Because each `AtomicF32` is aligned to 64 bytes (cache line isolation),
-/// this struct guarantees no "False Sharing" between the Camera Thread (Writer)
-/// and the NIF Thread (Reader).
-pub struct Kinematics {
-    pub vx: AtomicF32, // Velocity X
-    pub vy: AtomicF32, // Velocity Y
-    pub px: AtomicF32, // Position X (Integrated)
-    pub py: AtomicF32, // Position Y (Integrated)
-}
+// Shared stress harness for the primitives' fetch_add tests
+#[cfg(test)]
+mod stress {
+    use std::sync::Arc;
+    use std::thread;
+
+    // Whole numbers below 2^24 add exactly in f32, so any lost update shows
+    pub const THREADS: usize = 4;
+    pub const ADDS: usize = 50_000;
 
-// Zero-initialization for boot
-impl Default for Kinematics {
-    fn default() -> Self {
-        Self {
-            vx: AtomicF32::new(0.0),
-            vy: AtomicF32::new(0.0),
-            px: AtomicF32::new(0.0),
-            py: AtomicF32::new(0.0),
+    /// Runs `add` ADDS times on each of THREADS threads and waits for them.
+    pub fn hammer(add: impl Fn() + Send + Sync + 'static) {
+        let add = Arc::new(add);
+        let workers: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let add = add.clone();
+                thread::spawn(move || (0..ADDS).for_each(|_| add()))
+            })
+            .collect();
+        for worker in workers {
+            worker.join().unwrap();
         }
     }
-}
\ No newline at end of file
+}
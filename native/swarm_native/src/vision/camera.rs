@@ -4,15 +4,15 @@ use std::io::Read;
 use std::process::{Command, Stdio};
 use std::sync::atomic::Ordering;
 use std::thread;
-use std::time::Instant;
 
+use crate::spatial;
 use crate::state::arena::{SwarmState, FRAME_SIZE, FRAME_WIDTH, FRAME_HEIGHT};
-use crate::vision::math; 
+use crate::vision::{detector, math};
 
 // [CORRECT] Taking state by value (SwarmState), not reference or Arc wrapper
 pub fn spawn_heartbeat(state: SwarmState, width: u32, height: u32) {
     // OPTIMIZATION: Removed the redundant .clone() lines here.
-    // Since we move 'state' into the thread below, we can access 
+    // Since we move 'state' into the thread below, we can access
     // state.memory, state.physiology, etc. directly inside the loop.
 
     thread::spawn(move || {
@@ -20,7 +20,7 @@ pub fn spawn_heartbeat(state: SwarmState, width: u32, height: u32) {
         let child = Command::new("ffmpeg")
             .args(&[
                 "-f", "v4l2", "-framerate", "30", "-video_size", &format!("{}x{}", width, height),
-                "-i", "/dev/video1", 
+                "-i", "/dev/video1",
                 "-f", "rawvideo", "-pix_fmt", "rgb24", "-"
             ])
             .stdout(Stdio::piped())
@@ -41,59 +41,143 @@ pub fn spawn_heartbeat(state: SwarmState, width: u32, height: u32) {
             lock.as_mut().unwrap().stdout.take().unwrap()
         };
 
-        let mut last_time = Instant::now();
-        let mut current_w_idx = 0;
-        let mut local_grid = [0.0f32; 200];
-
-        // Thread-local previous frame buffer (The Evolutionary Step)
-        let mut prev_frame = vec![0u8; FRAME_SIZE];
+        let mut lung = Lung::new(&state);
 
         // 4. The Iron Lung Loop
         // [CLEANUP] Access state directly instead of using the old _ref variables
         while state.running.load(Ordering::Acquire) == 1 {
-            
-            // Triple Buffer Logic
-            let r_idx = state.memory.ready_idx.load(Ordering::Acquire);
-            let next_w_idx = (current_w_idx + 1) % 3;
-            let final_w_idx = if next_w_idx == r_idx { (next_w_idx + 1) % 3 } else { next_w_idx };
-
-            let slot = &state.memory.slots[final_w_idx];
-
-            {
-                let mut data = slot.data.lock().unwrap();
-                
-                if stdout.read_exact(&mut data).is_err() { break; }
-
-                let (dx, dy) = math::calculate_optical_flow(
-                    &data, 
-                    &prev_frame, 
-                    FRAME_WIDTH, 
-                    FRAME_HEIGHT, 
-                    &mut local_grid
-                );
-                
-                let dt = last_time.elapsed().as_secs_f32();
-                last_time = Instant::now();
-                
-                // Direct access to physiology
-                let vx = state.physiology.vx.load() * 0.7 + dx * 0.3;
-                let vy = state.physiology.vy.load() * 0.7 + dy * 0.3;
-                
-                state.physiology.vx.store(vx);
-                state.physiology.vy.store(vy);
-                state.physiology.px.store(state.physiology.px.load() + vx * dt);
-                state.physiology.py.store(state.physiology.py.load() + vy * dt);
-
-                if let Ok(mut g) = state.flow_grid.write() {
-                    g.copy_from_slice(&local_grid);
+            // Stamped as the frame lands: the timestamp drives the integration
+            let breathed = lung.breathe(&state, |data| {
+                stdout.read_exact(data).ok()?;
+                Some(spatial::now_us())
+            });
+            if !breathed { break; }
+        }
+    });
+}
+
+/// Kinematics and the flow grid as recorded for a frame, to start a replay
+/// from where the recording starts instead of from boot.
+pub struct Primer {
+    pub kinematics: (f32, f32, f32, f32), // vx, vy, px, py
+    pub flow: [f32; 200],
+}
+
+/// The heartbeat's per-frame state. Live frames (FFmpeg) and replayed ones
+/// (vision/replay.rs) go through the same `breathe`, so a replay computes
+/// exactly what the flight did.
+pub struct Lung {
+    current_w_idx: usize,
+    local_grid: [f32; 200],
+
+    // Thread-local previous frame buffer (The Evolutionary Step)
+    prev_frame: Vec<u8>,
+
+    // Thread-local kinematics. This thread is the only writer, so it
+    // integrates here and publishes whole updates (no read-back races).
+    vx: f32,
+    vy: f32,
+    px: f32,
+    py: f32,
+    last_us: Option<u64>, // Capture time of the previous frame
+}
+
+impl Lung {
+    pub fn new(state: &SwarmState) -> Self {
+        let boot = state.physiology.snapshot();
+        Self {
+            current_w_idx: 0,
+            local_grid: [0.0; 200],
+            prev_frame: vec![0u8; FRAME_SIZE],
+            vx: boot.vx,
+            vy: boot.vy,
+            px: boot.px,
+            py: boot.py,
+            last_us: None,
+        }
+    }
+
+    /// One frame: `fill` writes the pixels into the slot and returns their
+    /// capture time (µs), or `None` when the source is exhausted.
+    pub fn breathe(&mut self, state: &SwarmState, fill: impl FnOnce(&mut [u8]) -> Option<u64>) -> bool {
+        self.step(state, fill, None)
+    }
+
+    /// A frame whose kinematics and flow are taken from the recording
+    /// instead of computed: the next `breathe` then continues the flight.
+    pub fn prime(&mut self, state: &SwarmState, fill: impl FnOnce(&mut [u8]) -> Option<u64>, primer: &Primer) -> bool {
+        self.step(state, fill, Some(primer))
+    }
+
+    fn step(&mut self, state: &SwarmState, fill: impl FnOnce(&mut [u8]) -> Option<u64>, primer: Option<&Primer>) -> bool {
+        // Triple Buffer Logic
+        let r_idx = state.memory.ready_idx.load(Ordering::Acquire);
+        let next_w_idx = (self.current_w_idx + 1) % 3;
+        let final_w_idx = if next_w_idx == r_idx { (next_w_idx + 1) % 3 } else { next_w_idx };
+
+        let slot = &state.memory.slots[final_w_idx];
+
+        {
+            let mut data = slot.data.lock().unwrap();
+
+            let Some(ts_us) = fill(data.as_mut_slice()) else { return false };
+
+            match primer {
+                None => {
+                    let (dx, dy) = math::calculate_optical_flow(
+                        &data,
+                        &self.prev_frame,
+                        FRAME_WIDTH,
+                        FRAME_HEIGHT,
+                        &mut self.local_grid
+                    );
+
+                    // Frame to frame, from capture times (0 on the first frame)
+                    let dt = self.last_us.map_or(0.0, |last| ts_us.saturating_sub(last) as f32 / 1_000_000.0);
+
+                    self.vx = self.vx * 0.7 + dx * 0.3;
+                    self.vy = self.vy * 0.7 + dy * 0.3;
+                    self.px += self.vx * dt;
+                    self.py += self.vy * dt;
+                }
+                Some(p) => {
+                    (self.vx, self.vy, self.px, self.py) = p.kinematics;
+                    self.local_grid = p.flow;
+                }
+            }
+            self.last_us = Some(ts_us);
+
+            // One atomic publish (Seqlock) so readers never see a torn frame
+            state.physiology.publish(self.vx, self.vy, self.px, self.py, ts_us);
+
+            if let Ok(mut g) = state.flow_grid.write() {
+                g.copy_from_slice(&self.local_grid);
+            }
+
+            // Zenoh streams + black box (motion is only computed when someone consumes it)
+            let bus = state.bus.read().ok().and_then(|b| b.clone());
+            let recorder = state.recorder.read().ok().and_then(|r| r.clone());
+            if bus.is_some() || recorder.is_some() {
+                let motion = if recorder.is_some() || bus.as_ref().is_some_and(|b| b.wants_motion()) {
+                    detector::calculate_motion_bbox(&data, &self.prev_frame, FRAME_WIDTH, FRAME_HEIGHT, 10)
+                } else {
+                    None
+                };
+                let kin = state.physiology.snapshot();
+                if let Some(bus) = bus {
+                    bus.on_frame(&data, &self.local_grid, &kin, motion);
+                }
+                if let Some(recorder) = recorder {
+                    recorder.on_frame(&data, &self.local_grid, &kin, motion);
                 }
-                
-                prev_frame.copy_from_slice(&data);
             }
 
-            state.memory.ready_idx.store(final_w_idx, Ordering::Release);
-            state.memory.write_idx.store(final_w_idx, Ordering::Release);
-            current_w_idx = final_w_idx;
+            self.prev_frame.copy_from_slice(&data);
         }
-    });
-}
\ No newline at end of file
+
+        state.memory.ready_idx.store(final_w_idx, Ordering::Release);
+        state.memory.write_idx.store(final_w_idx, Ordering::Release);
+        self.current_w_idx = final_w_idx;
+        true
+    }
+}
@@ -0,0 +1,100 @@
+// native/swarm_native/src/vision/encode.rs
+
+//! THE LENS (Frame Compression)
+//!
+//! Raw frames are 900 KB of RGB24. Everything that keeps or ships a frame
+//! (the black box, the ground station, snapshots and motion crops for the
+//! prompt builder) goes through here first, and replayed frames come back
+//! through `decode`.
+
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::{CompressionType, FilterType, PngEncoder};
+use image::{ExtendedColorType, ImageEncoder};
+
+use crate::recorder::FrameFormat;
+
+/// Nearest-neighbour downscale of an RGB24 frame by `scale` (1 = copy).
+/// Returns (pixels, width, height).
+pub fn downscale(rgb: &[u8], width: usize, height: usize, scale: usize) -> (Vec<u8>, usize, usize) {
+    let scale = scale.max(1);
+    let (w, h) = (width / scale, height / scale);
+
+    let mut out = Vec::with_capacity(w * h * 3);
+    for y in 0..h {
+        let row = y * scale * width;
+        for x in 0..w {
+            let i = (row + x * scale) * 3;
+            out.extend_from_slice(&rgb[i..i + 3]);
+        }
+    }
+    (out, w, h)
+}
+
+/// The `x, y, w, h` rectangle of an RGB24 image, clipped to it.
+/// Returns (pixels, width, height); empty if nothing is left.
+pub fn crop(rgb: &[u8], width: usize, height: usize, x: usize, y: usize, w: usize, h: usize) -> (Vec<u8>, usize, usize) {
+    let w = w.min(width.saturating_sub(x));
+    let h = h.min(height.saturating_sub(y));
+
+    let mut out = Vec::with_capacity(w * h * 3);
+    for row in y..y + h {
+        let i = (row * width + x) * 3;
+        out.extend_from_slice(&rgb[i..i + w * 3]);
+    }
+    (out, w, h)
+}
+
+/// An RGB24 image in `format` (raw is passed through).
+/// `None` if it is empty or does not match the dimensions.
+pub fn image(rgb: Vec<u8>, width: usize, height: usize, format: FrameFormat, quality: u8) -> Option<Vec<u8>> {
+    match format {
+        FrameFormat::Jpeg => jpeg(&rgb, width, height, quality),
+        FrameFormat::Png => png(&rgb, width, height),
+        FrameFormat::Raw => (width > 0 && height > 0 && rgb.len() == width * height * 3).then_some(rgb),
+    }
+}
+
+/// Baseline JPEG of an RGB24 image. `quality` is clamped to 1..=100.
+/// `None` if the buffer does not match the dimensions.
+pub fn jpeg(rgb: &[u8], width: usize, height: usize, quality: u8) -> Option<Vec<u8>> {
+    if width == 0 || height == 0 || rgb.len() != width * height * 3 {
+        return None;
+    }
+    let mut out = Vec::new();
+    JpegEncoder::new_with_quality(&mut out, quality.clamp(1, 100))
+        .encode(rgb, width as u32, height as u32, ExtendedColorType::Rgb8)
+        .ok()?;
+    Some(out)
+}
+
+/// Lossless PNG of an RGB24 image, favouring speed over size.
+/// `None` if the buffer does not match the dimensions.
+pub fn png(rgb: &[u8], width: usize, height: usize) -> Option<Vec<u8>> {
+    if width == 0 || height == 0 || rgb.len() != width * height * 3 {
+        return None;
+    }
+    let mut out = Vec::new();
+    PngEncoder::new_with_quality(&mut out, CompressionType::Fast, FilterType::Sub)
+        .write_image(rgb, width as u32, height as u32, ExtendedColorType::Rgb8)
+        .ok()?;
+    Some(out)
+}
+
+/// A JPEG or PNG back to RGB24: (pixels, width, height).
+pub fn decode(data: &[u8]) -> Option<(Vec<u8>, usize, usize)> {
+    let rgb = image::load_from_memory(data).ok()?.to_rgb8();
+    let (w, h) = (rgb.width() as usize, rgb.height() as usize);
+    Some((rgb.into_raw(), w, h))
+}
+
+/// Nearest-neighbour resize of an RGB24 image into `out` (`out_w` x `out_h`).
+pub fn resize_into(rgb: &[u8], width: usize, height: usize, out: &mut [u8], out_w: usize, out_h: usize) {
+    for y in 0..out_h {
+        let src_row = (y * height / out_h) * width;
+        for x in 0..out_w {
+            let i = (src_row + x * width / out_w) * 3;
+            let o = (y * out_w + x) * 3;
+            out[o..o + 3].copy_from_slice(&rgb[i..i + 3]);
+        }
+    }
+}
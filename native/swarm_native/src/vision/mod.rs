@@ -2,4 +2,6 @@
 
 pub mod camera;   // The FFmpeg Heartbeat
 pub mod math;     // The Optical Flow Logic
-pub mod detector; // The Motion Watchdog
\ No newline at end of file
+pub mod detector; // The Motion Watchdog
+pub mod encode;   // JPEG / PNG snapshots and crops (black box, replay, export)
+pub mod replay;   // The Heartbeat from a recording
@@ -0,0 +1,276 @@
+// native/swarm_native/src/vision/replay.rs
+
+//! THE IRON LUNG ON TAPE (Deterministic Replay)
+//!
+//! Drives the heartbeat from a black box recording instead of FFmpeg: the
+//! recorded frames go through the same `Lung` as live ones, stamped with
+//! their capture times, so `get_fused_state`, `detect_change`, the flow
+//! grid, the bus and the recorder see what they saw in flight.
+//!
+//! 1. The first frame is primed with the kinematics and flow grid recorded
+//!    with it, so integration continues the flight instead of restarting
+//!    from rest. From the second frame on everything is recomputed, and
+//!    `detect_change` (which compares against two frames back) matches
+//!    from the third.
+//! 2. Exact only when every frame was recorded full size and lossless
+//!    (`frame_every: 1`, `frame_scale: 1`, `frame_format: :png | :raw`).
+//!    Anything else still replays, upscaled and with approximate flow.
+//! 3. Pacing: `speed` 1.0 is real time, above 1.0 accelerated, 0.0 steps
+//!    one frame per `step` credit. Pacing never changes the results: the
+//!    integration runs on recorded timestamps, not wall time.
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use rustler::{NifMap, NifStruct};
+
+use crate::recorder::reader::Recording;
+use crate::recorder::{FrameFormat, Record};
+use crate::state::arena::{SwarmState, FRAME_HEIGHT, FRAME_SIZE, FRAME_WIDTH};
+use crate::vision::camera::{Lung, Primer};
+use crate::vision::encode;
+
+// Entries read per batch (a batch of raw frames is ~4 MB each)
+const BATCH: usize = 64;
+
+#[derive(NifStruct, Clone, Debug)]
+#[module = "SwarmBrain.Replay.Config"]
+pub struct ReplayConfig {
+    pub dir: String,
+    pub from_us: u64, // Inclusive range of capture times
+    pub to_us: u64,
+    pub speed: f64, // 1.0 real time, >1.0 accelerated, 0.0 step-by-step
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io,
+    Empty, // Nothing recorded in the range
+    InvalidConfig,
+}
+
+#[derive(NifMap, Clone, Copy, Debug)]
+pub struct ReplayStatus {
+    pub frames: u64,      // Frames replayed so far
+    pub position_us: u64, // Capture time of the last one
+    pub finished: bool,
+    pub speed: f64,
+}
+
+fn valid_speed(speed: f64) -> bool {
+    speed.is_finite() && speed >= 0.0
+}
+
+struct Pace {
+    speed: f64,
+    credits: u64,                   // Frames granted while stepping
+    anchor: Option<(Instant, u64)>, // Wall time <-> capture time, while timed
+}
+
+/// A running replay. The thread holds the SwarmState; this is the control.
+pub struct Replay {
+    pace: Mutex<Pace>,
+    wake: Condvar,
+    stop: AtomicBool,
+    finished: AtomicBool,
+    frames: AtomicU64,
+    position_us: AtomicU64,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Replay {
+    /// Opens the recording and starts feeding `state` from it.
+    pub fn start(state: SwarmState, cfg: &ReplayConfig) -> Result<Arc<Self>, ReplayError> {
+        if !valid_speed(cfg.speed) || cfg.from_us > cfg.to_us {
+            return Err(ReplayError::InvalidConfig);
+        }
+        let recording = Recording::open(Path::new(&cfg.dir)).map_err(|_| ReplayError::Io)?;
+        let info = recording.info();
+        if info.records == 0 || info.first_us > cfg.to_us || info.last_us < cfg.from_us {
+            return Err(ReplayError::Empty);
+        }
+        recording.seek(cfg.from_us, cfg.to_us);
+
+        let replay = Arc::new(Self {
+            pace: Mutex::new(Pace { speed: cfg.speed, credits: 0, anchor: None }),
+            wake: Condvar::new(),
+            stop: AtomicBool::new(false),
+            finished: AtomicBool::new(false),
+            frames: AtomicU64::new(0),
+            position_us: AtomicU64::new(0),
+            thread: Mutex::new(None),
+        });
+
+        let control = replay.clone();
+        let handle = thread::spawn(move || {
+            control.run(&state, &recording);
+            control.finished.store(true, Ordering::Release);
+        });
+        *replay.thread.lock().unwrap() = Some(handle);
+        Ok(replay)
+    }
+
+    /// Grants `n` more frames while stepping (speed 0.0).
+    pub fn step(&self, n: u64) {
+        let mut pace = self.pace.lock().unwrap();
+        pace.credits = pace.credits.saturating_add(n);
+        self.wake.notify_all();
+    }
+
+    pub fn set_speed(&self, speed: f64) -> bool {
+        if !valid_speed(speed) {
+            return false;
+        }
+        let mut pace = self.pace.lock().unwrap();
+        pace.speed = speed;
+        pace.anchor = None; // Re-anchored on the next frame
+        self.wake.notify_all();
+        true
+    }
+
+    pub fn status(&self) -> ReplayStatus {
+        ReplayStatus {
+            frames: self.frames.load(Ordering::Relaxed),
+            position_us: self.position_us.load(Ordering::Relaxed),
+            finished: self.finished.load(Ordering::Acquire),
+            speed: self.pace.lock().unwrap().speed,
+        }
+    }
+
+    /// Stops the thread and waits for it (at most one frame).
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Release);
+        {
+            let _pace = self.pace.lock().unwrap();
+            self.wake.notify_all();
+        }
+        if let Some(handle) = self.thread.lock().unwrap().take() {
+            let _ = handle.join();
+        }
+    }
+
+    fn run(&self, state: &SwarmState, recording: &Recording) {
+        let mut lung = Lung::new(state);
+        let mut primed = false;
+        let mut frame = vec![0u8; FRAME_SIZE];
+
+        // What was recorded with the frame being assembled (same timestamp)
+        let mut kinematics: Option<(u64, (f32, f32, f32, f32))> = None;
+        let mut flow: Option<(u64, [f32; 200])> = None;
+
+        loop {
+            let entries = recording.next(BATCH);
+            if entries.is_empty() {
+                return;
+            }
+            for entry in entries {
+                let ts_us = entry.ts_us;
+                match entry.record {
+                    Record::Kinematics { vx, vy, px, py, .. } => kinematics = Some((ts_us, (vx, vy, px, py))),
+                    Record::Flow(grid) => flow = decode_flow(&grid.0).map(|g| (ts_us, g)),
+                    Record::Frame { width, height, format, data } => {
+                        // 1. Pixels, at heartbeat size
+                        if !decode_frame(&data.0, width as usize, height as usize, format, &mut frame) {
+                            continue;
+                        }
+
+                        // 2. Its turn (pacing or step credit)
+                        if !self.wait_turn(ts_us) || state.running.load(Ordering::Acquire) != 1 {
+                            return;
+                        }
+
+                        // 3. Through the lung, stamped with the capture time
+                        let fill = |slot: &mut [u8]| {
+                            slot.copy_from_slice(&frame);
+                            Some(ts_us)
+                        };
+                        match kinematics.filter(|(t, _)| !primed && *t == ts_us) {
+                            Some((_, kinematics)) => {
+                                let flow = flow.filter(|(t, _)| *t == ts_us).map_or([0.0; 200], |(_, g)| g);
+                                lung.prime(state, fill, &Primer { kinematics, flow });
+                            }
+                            None => {
+                                lung.breathe(state, fill);
+                            }
+                        }
+                        primed = true;
+
+                        self.frames.fetch_add(1, Ordering::Relaxed);
+                        self.position_us.store(ts_us, Ordering::Relaxed);
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Blocks until the frame at `ts_us` is due. `false` once stopped.
+    fn wait_turn(&self, ts_us: u64) -> bool {
+        let mut pace = self.pace.lock().unwrap();
+        loop {
+            if self.stop.load(Ordering::Acquire) {
+                return false;
+            }
+
+            // 1. Stepping: one credit per frame
+            if pace.speed <= 0.0 {
+                if pace.credits > 0 {
+                    pace.credits -= 1;
+                    return true;
+                }
+                pace = self.wake.wait(pace).unwrap();
+                continue;
+            }
+
+            // 2. Timed: recorded spacing divided by speed
+            let speed = pace.speed;
+            let (at, at_us) = *pace.anchor.get_or_insert((Instant::now(), ts_us));
+            let due = at + Duration::from_secs_f64(ts_us.saturating_sub(at_us) as f64 / 1_000_000.0 / speed);
+            let now = Instant::now();
+            if now >= due {
+                return true;
+            }
+            pace = self.wake.wait_timeout(pace, due - now).unwrap().0;
+        }
+    }
+}
+
+/// A recorded frame into `out` (FRAME_WIDTH x FRAME_HEIGHT RGB24).
+/// Smaller recordings are scaled up. `false` if it does not decode.
+fn decode_frame(data: &[u8], width: usize, height: usize, format: FrameFormat, out: &mut [u8]) -> bool {
+    let decoded;
+    let (rgb, w, h) = match format {
+        FrameFormat::Raw if data.len() == width * height * 3 => (data, width, height),
+        FrameFormat::Raw => return false,
+        FrameFormat::Jpeg | FrameFormat::Png => match encode::decode(data) {
+            Some(d) => {
+                decoded = d;
+                (decoded.0.as_slice(), decoded.1, decoded.2)
+            }
+            None => return false,
+        },
+    };
+    if w == 0 || h == 0 {
+        return false;
+    }
+    if (w, h) == (FRAME_WIDTH, FRAME_HEIGHT) {
+        out.copy_from_slice(rgb);
+    } else {
+        encode::resize_into(rgb, w, h, out, FRAME_WIDTH, FRAME_HEIGHT);
+    }
+    true
+}
+
+fn decode_flow(bytes: &[u8]) -> Option<[f32; 200]> {
+    if bytes.len() != 200 * 4 {
+        return None;
+    }
+    let mut grid = [0.0f32; 200];
+    for (v, b) in grid.iter_mut().zip(bytes.chunks_exact(4)) {
+        *v = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+    }
+    Some(grid)
+}
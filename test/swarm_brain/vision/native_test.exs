@@ -0,0 +1,26 @@
+defmodule SwarmBrain.Vision.NativeTest do
+  use ExUnit.Case, async: true
+
+  alias SwarmBrain.Vision.Native
+
+  # Mirrors GridConfig::default() in native/swarm_native/src/spatial/grid.rs
+  @grid %{
+    cell_size: 0.5,
+    hit: 0.85,
+    miss: -0.4,
+    clamp_min: -2.0,
+    clamp_max: 3.5,
+    occupied_threshold: 0.6,
+    free_threshold: -0.3,
+    half_life_s: 60.0
+  }
+
+  test "configure_spatial_map returns a bare :ok" do
+    assert Native.configure_spatial_map(Native.init_state(), @grid) === :ok
+  end
+
+  test "configure_spatial_map rejects an invalid grid" do
+    assert Native.configure_spatial_map(Native.init_state(), %{@grid | cell_size: 0.0}) ===
+             {:error, :invalid_config}
+  end
+end